@@ -0,0 +1,65 @@
+use anyhow::bail;
+use clap::{Args, ValueEnum};
+use ic_ledger_types::{AccountIdentifier, Subaccount};
+use icp::context::Context;
+
+use crate::commands::args::{ContactOr, FlexibleAccountId, with_subaccount};
+use crate::commands::parsers::parse_subaccount;
+
+/// The account format to convert to
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub(crate) enum AccountFormat {
+    /// ICRC-1 textual account
+    #[default]
+    Icrc1,
+    /// ICP ledger account identifier
+    Ledger,
+}
+
+/// Convert a principal or account to another account format
+///
+/// An ICP ledger account identifier is a hash of the owner and subaccount,
+/// so it cannot be converted back into an ICRC-1 account.
+#[derive(Debug, Args)]
+pub(crate) struct ConvertArgs {
+    /// A principal, ICRC-1 account or ICP ledger account identifier,
+    /// or `@name` of a contact or identity
+    pub(crate) account: ContactOr<FlexibleAccountId>,
+
+    /// The subaccount of the account (only if the account is a principal)
+    #[arg(long, value_parser = parse_subaccount)]
+    pub(crate) subaccount: Option<[u8; 32]>,
+
+    /// The account format to convert to
+    #[arg(long, default_value = "icrc1")]
+    pub(crate) to: AccountFormat,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ConvertArgs) -> Result<(), anyhow::Error> {
+    let account = match args.account.resolve(ctx).await? {
+        FlexibleAccountId::Icrc1(account) => {
+            with_subaccount(account, args.subaccount, "--subaccount")?
+        }
+        FlexibleAccountId::IcpLedger(account_id) => {
+            if args.subaccount.is_some() {
+                bail!("Cannot use --subaccount with an ICP ledger account identifier.");
+            }
+            match args.to {
+                AccountFormat::Icrc1 => bail!(
+                    "An ICP ledger account identifier cannot be converted to an ICRC-1 account: it does not contain the owner"
+                ),
+                AccountFormat::Ledger => println!("{account_id}"),
+            }
+            return Ok(());
+        }
+    };
+
+    match args.to {
+        AccountFormat::Icrc1 => println!("{account}"),
+        AccountFormat::Ledger => {
+            let subaccount = Subaccount(account.subaccount.unwrap_or([0; 32]));
+            println!("{}", AccountIdentifier::new(&account.owner, &subaccount));
+        }
+    }
+    Ok(())
+}
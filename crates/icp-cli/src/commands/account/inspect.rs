@@ -0,0 +1,80 @@
+use std::io::stdout;
+
+use clap::Args;
+use ic_ledger_types::{AccountIdentifier, Subaccount};
+use icp::context::Context;
+use serde::Serialize;
+
+use crate::commands::args::{ContactOr, FlexibleAccountId};
+
+/// Validate an account and show it in every account format
+///
+/// An ICRC-1 account is shown with its owner, subaccount and ICP ledger
+/// account identifier. An ICP ledger account identifier is only checked
+/// against its checksum, since the owner cannot be recovered from it.
+#[derive(Debug, Args)]
+pub(crate) struct InspectArgs {
+    /// A principal, ICRC-1 account or ICP ledger account identifier,
+    /// or `@name` of a contact or identity
+    pub(crate) account: ContactOr<FlexibleAccountId>,
+
+    /// Output command results as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &InspectArgs) -> Result<(), anyhow::Error> {
+    let inspected = match args.account.resolve(ctx).await? {
+        FlexibleAccountId::Icrc1(account) => {
+            let subaccount = account.subaccount.unwrap_or([0; 32]);
+            JsonAccount {
+                format: "icrc1",
+                owner: Some(account.owner.to_string()),
+                subaccount: Some(hex::encode(subaccount)),
+                icrc1: Some(account.to_string()),
+                ledger: AccountIdentifier::new(&account.owner, &Subaccount(subaccount)).to_hex(),
+            }
+        }
+        FlexibleAccountId::IcpLedger(account_id) => JsonAccount {
+            format: "ledger",
+            owner: None,
+            subaccount: None,
+            icrc1: None,
+            ledger: account_id.to_hex(),
+        },
+    };
+
+    if args.json {
+        serde_json::to_writer(stdout(), &inspected)?;
+        return Ok(());
+    }
+
+    match (&inspected.owner, &inspected.subaccount, &inspected.icrc1) {
+        (Some(owner), Some(subaccount), Some(icrc1)) => {
+            println!("Owner:       {owner}");
+            println!("Subaccount:  {subaccount}");
+            println!("ICRC-1:      {icrc1}");
+            println!("ICP ledger:  {}", inspected.ledger);
+        }
+        _ => {
+            println!("ICP ledger:  {} (checksum valid)", inspected.ledger);
+            println!(
+                "The owner and subaccount cannot be recovered from an ICP ledger account identifier"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonAccount {
+    /// The format the account was given in, `icrc1` or `ledger`
+    format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subaccount: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icrc1: Option<String>,
+    ledger: String,
+}
@@ -0,0 +1,19 @@
+use clap::Subcommand;
+
+pub(crate) mod convert;
+pub(crate) mod inspect;
+pub(crate) mod subaccount;
+
+/// Convert and validate principals, accounts and subaccounts
+///
+/// Accounts are written as a principal, an ICRC-1 textual account
+/// (`<principal>-<checksum>.<subaccount>`) or an ICP ledger account
+/// identifier (64 hex characters). Subaccounts are written as hex, as
+/// `index:<n>` or as `principal:<principal>`; every command that takes an
+/// account or subaccount accepts the same forms.
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    Convert(convert::ConvertArgs),
+    Inspect(inspect::InspectArgs),
+    Subaccount(subaccount::SubaccountArgs),
+}
@@ -0,0 +1,22 @@
+use clap::Args;
+use icp::context::Context;
+
+use crate::commands::parsers::parse_subaccount;
+
+/// Print a subaccount as 64 hex characters
+///
+/// The subaccount can be given as hex (left-padded to 32 bytes), as
+/// `index:<n>` for the subaccount numbered `n`, or as `principal:<principal>`
+/// for the subaccount derived from a principal, as used to top up canisters
+/// through the cycles minting canister.
+#[derive(Debug, Args)]
+pub(crate) struct SubaccountArgs {
+    /// The subaccount
+    #[arg(value_parser = parse_subaccount)]
+    pub(crate) subaccount: [u8; 32],
+}
+
+pub(crate) async fn exec(_: &Context, args: &SubaccountArgs) -> Result<(), anyhow::Error> {
+    println!("{}", hex::encode(args.subaccount));
+    Ok(())
+}
@@ -6,12 +6,15 @@ use candid::Principal;
 use clap::{Args, ValueHint};
 use clap_complete::ArgValueCandidates;
 use ic_ledger_types::AccountIdentifier;
-use icp::context::{CanisterSelection, EnvironmentSelection, NetworkSelection};
+use icp::context::{CanisterSelection, Context, EnvironmentSelection, NetworkSelection};
+use icp::fs::lock::LockError;
 use icp::identity::IdentitySelection;
+use icp::identity::contacts::{ResolveContactError, resolve_contact};
 use icp::manifest::ArgsFormat;
 use icp::prelude::PathBuf;
 use icp::{InitArgs, fs};
 use icrc_ledger_types::icrc1::account::Account;
+use snafu::Snafu;
 
 use crate::options::{EnvironmentOpt, IdentityOpt, NetworkOpt};
 
@@ -184,21 +187,24 @@ impl FromStr for FlexibleAccountId {
         // Try parsing as ICP ledger account (hex string)
         if let Ok(bytes) = hex::decode(s) {
             if bytes.len() == 32 {
-                let mut array = [0u8; 32];
-                array.copy_from_slice(&bytes);
-                return Ok(FlexibleAccountId::IcpLedger(
-                    AccountIdentifier::from_slice(&array).unwrap(),
-                ));
+                return AccountIdentifier::from_slice(&bytes)
+                    .map(FlexibleAccountId::IcpLedger)
+                    .map_err(|_| {
+                        format!(
+                            "Invalid ICP ledger account identifier {s}: checksum does not match"
+                        )
+                    });
             } else {
                 return Err(format!("Invalid ICP ledger account hex string: {s}"));
             }
         }
         // Try parsing as ICRC1 account
-        if let Ok(account) = s.parse::<Account>() {
-            return Ok(FlexibleAccountId::Icrc1(account));
+        match s.parse::<Account>() {
+            Ok(account) => Ok(FlexibleAccountId::Icrc1(account)),
+            // The textual form of an account with a subaccount, so the reason is worth showing
+            Err(e) if s.contains('.') => Err(format!("Invalid ICRC-1 account {s}: {e}")),
+            Err(_) => Err(format!("Invalid principal / account identifier: {s}")),
         }
-
-        Err(format!("Invalid principal / account identifier: {s}"))
     }
 }
 
@@ -211,6 +217,102 @@ impl Display for FlexibleAccountId {
     }
 }
 
+/// Sets the subaccount given with `flag` on `account`. Fails if `account`,
+/// written in ICRC-1 textual form, already names a subaccount.
+pub(crate) fn with_subaccount(
+    mut account: Account,
+    subaccount: Option<[u8; 32]>,
+    flag: &str,
+) -> Result<Account, anyhow::Error> {
+    if let Some(subaccount) = subaccount {
+        if account.subaccount.is_some() {
+            bail!(
+                "Cannot use {flag} with an account that has a subaccount. Use a plain principal if you want to specify a subaccount."
+            );
+        }
+        account.subaccount = Some(subaccount);
+    }
+    Ok(account)
+}
+
+/// A principal or account given directly, or as `@name` of a contact or
+/// identity, which is looked up when the command runs.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ContactOr<T> {
+    Value(T),
+    Contact(String),
+}
+
+impl<T: FromStr> FromStr for ContactOr<T>
+where
+    T::Err: Display,
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = s.strip_prefix('@') {
+            return Ok(Self::Contact(name.to_string()));
+        }
+        s.parse()
+            .map(Self::Value)
+            .map_err(|e: T::Err| e.to_string())
+    }
+}
+
+impl<T: FromStr + Clone> ContactOr<T>
+where
+    T::Err: Display,
+{
+    /// The value, looking up a contact's address if needed.
+    pub(crate) async fn resolve(&self, ctx: &Context) -> Result<T, ResolveContactOrError> {
+        match self {
+            Self::Value(value) => Ok(value.clone()),
+            Self::Contact(name) => {
+                let address = ctx
+                    .dirs
+                    .identity()?
+                    .with_read(async |dirs| resolve_contact(dirs, name))
+                    .await??;
+                address
+                    .parse()
+                    .map_err(|e: T::Err| ResolveContactOrError::UnusableContact {
+                        name: name.clone(),
+                        message: e.to_string(),
+                        address,
+                    })
+            }
+        }
+    }
+
+    /// Resolves every value in `values`.
+    pub(crate) async fn resolve_all(
+        values: &[Self],
+        ctx: &Context,
+    ) -> Result<Vec<T>, ResolveContactOrError> {
+        let mut resolved = Vec::with_capacity(values.len());
+        for value in values {
+            resolved.push(value.resolve(ctx).await?);
+        }
+        Ok(resolved)
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub(crate) enum ResolveContactOrError {
+    #[snafu(transparent)]
+    LockIdentityDir { source: LockError },
+
+    #[snafu(transparent)]
+    ResolveContact { source: ResolveContactError },
+
+    #[snafu(display("contact `{name}` is `{address}`, which cannot be used here: {message}"))]
+    UnusableContact {
+        name: String,
+        address: String,
+        message: String,
+    },
+}
+
 /// Grouped flags for specifying canister install arguments, shared by `canister install`, and `deploy`.
 #[derive(Args, Clone, Debug, Default)]
 pub(crate) struct ArgsOpt {
@@ -301,6 +403,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flexible_account_ids() {
+        let ledger = "4f3d4b40cdb852732601fccf8bd24dffe44957a647cb867913e982d98cf85676";
+        assert!(matches!(
+            ledger.parse::<FlexibleAccountId>(),
+            Ok(FlexibleAccountId::IcpLedger(_))
+        ));
+        // The first 4 bytes are a checksum of the rest
+        let corrupted = format!("00{}", &ledger[2..]);
+        assert!(
+            corrupted
+                .parse::<FlexibleAccountId>()
+                .unwrap_err()
+                .contains("checksum does not match")
+        );
+
+        let icrc1 = "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae-6cc627i.1";
+        assert_eq!(
+            icrc1.parse::<FlexibleAccountId>().unwrap().to_string(),
+            icrc1
+        );
+        let bad_checksum =
+            "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae-aaaaaaa.1";
+        assert!(
+            bad_checksum
+                .parse::<FlexibleAccountId>()
+                .unwrap_err()
+                .contains("invalid checksum")
+        );
+        assert!("not-an-account".parse::<FlexibleAccountId>().is_err());
+    }
+
+    #[test]
+    fn subaccount_is_set_once() {
+        let owner = Principal::anonymous();
+        let account = with_subaccount(owner.into(), Some([1; 32]), "--subaccount").unwrap();
+        assert_eq!(account.subaccount, Some([1; 32]));
+        assert!(with_subaccount(account, Some([2; 32]), "--subaccount").is_err());
+        assert_eq!(
+            with_subaccount(owner.into(), None, "--subaccount")
+                .unwrap()
+                .subaccount,
+            None
+        );
+    }
+
     #[test]
     fn canister_by_principal() {
         let cid = "ntyui-iatoh-pfi3f-27wnk-vgdqt-mq3cl-ld7jh-743kl-sde6i-tbm7g-tqe";
@@ -6,7 +6,7 @@ use icp::context::{Context, EnvironmentSelection};
 use tracing::info;
 
 use crate::{
-    operations::build::build_many_with_progress_bar,
+    operations::{build::build_many_with_progress_bar, remote::resolve_canister_downloads},
     options::{EnvironmentOpt, arg_struct_change_help},
 };
 
@@ -54,6 +54,18 @@ pub(crate) async fn exec(ctx: &Context, args: &BuildArgs) -> Result<(), anyhow::
             .map(|name| ctx.get_canister_and_path_for_env(name, &environment_selection)),
     )
     .await?;
+
+    // Offline, report everything missing from the package cache at once
+    if ctx.offline {
+        resolve_canister_downloads(
+            ctx,
+            canisters_to_build
+                .iter()
+                .map(|(path, c)| (path.as_path(), c)),
+        )
+        .await?;
+    }
+
     // Build the selected canisters
     info!("Building canisters:");
 
@@ -0,0 +1,17 @@
+use clap::Args;
+use icp::{context::Context, package::clear_cache};
+
+/// Remove everything from the package cache
+///
+/// Anything needed afterwards is downloaded again on next use, unless offline.
+#[derive(Debug, Args)]
+pub(crate) struct ClearArgs;
+
+pub(crate) async fn exec(ctx: &Context, _: &ClearArgs) -> Result<(), anyhow::Error> {
+    ctx.dirs
+        .package_cache()?
+        .with_write(async |pkg| clear_cache(pkg))
+        .await??;
+    println!("Cleared the package cache");
+    Ok(())
+}
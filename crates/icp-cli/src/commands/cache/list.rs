@@ -0,0 +1,73 @@
+use std::io::stdout;
+
+use clap::Args;
+use icp::{
+    context::Context,
+    package::{CacheKind, list_cache_entries},
+};
+use serde::Serialize;
+
+use super::{format_last_used, format_size};
+
+/// List the entries in the package cache
+#[derive(Debug, Args)]
+pub(crate) struct ListArgs {
+    /// Only list entries of this kind
+    #[arg(long, value_enum)]
+    pub(crate) kind: Option<CacheKind>,
+
+    /// Output command results as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ListArgs) -> Result<(), anyhow::Error> {
+    let entries = ctx
+        .dirs
+        .package_cache()?
+        .with_read(async |pkg| list_cache_entries(pkg))
+        .await??;
+    let entries = entries
+        .into_iter()
+        .filter(|e| args.kind.is_none_or(|kind| e.kind == kind));
+
+    if args.json {
+        let entries: Vec<_> = entries
+            .map(|e| JsonCacheEntry {
+                kind: e.kind,
+                last_used: format_last_used(&e),
+                key: e.key,
+                path: e.path.to_string(),
+                size: e.size,
+            })
+            .collect();
+        serde_json::to_writer(stdout(), &entries)?;
+        return Ok(());
+    }
+
+    for e in entries {
+        println!(
+            "{:<8} {:<16} {:>10}  last used {}",
+            e.kind,
+            abbreviate(&e.key),
+            format_size(e.size),
+            format_last_used(&e)
+        );
+    }
+
+    Ok(())
+}
+
+/// Shorten sha256 keys for display; launcher versions are left as they are.
+fn abbreviate(key: &str) -> &str {
+    key.get(..16).unwrap_or(key)
+}
+
+#[derive(Serialize)]
+struct JsonCacheEntry {
+    kind: CacheKind,
+    key: String,
+    path: String,
+    size: u64,
+    last_used: String,
+}
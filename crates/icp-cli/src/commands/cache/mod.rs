@@ -0,0 +1,57 @@
+use std::time::UNIX_EPOCH;
+
+use clap::Subcommand;
+use icp::package::CacheEntry;
+
+use crate::operations::misc::format_timestamp;
+
+pub(crate) mod clear;
+pub(crate) mod list;
+pub(crate) mod prune;
+pub(crate) mod size;
+
+/// Inspect and clean the package cache of launchers, recipes, wasms and plugins
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    Clear(clear::ClearArgs),
+    List(list::ListArgs),
+    Prune(prune::PruneArgs),
+    Size(size::SizeArgs),
+}
+
+/// Format a size in bytes with a binary unit, e.g. `12.3 MiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+fn format_last_used(entry: &CacheEntry) -> String {
+    let nanos = entry
+        .last_used
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    format_timestamp(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
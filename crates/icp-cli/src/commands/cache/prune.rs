@@ -0,0 +1,73 @@
+use std::time::{Duration, SystemTime};
+
+use clap::Args;
+use icp::{
+    context::Context,
+    package::{CacheKind, list_cache_entries, remove_cache_entries},
+    parsers::DurationAmount,
+};
+
+use super::format_size;
+
+/// Remove package cache entries that have not been used recently
+///
+/// Entries are aged by when they were last read. An entry that is removed but
+/// still needed is downloaded again on next use, unless offline.
+#[derive(Debug, Args)]
+#[command(after_long_help = "\
+Examples:
+
+    # Remove everything not used in the last 30 days
+    icp cache prune
+
+    # Remove network launchers not used in the last week
+    icp cache prune --older-than 7d --kind launcher
+")]
+pub(crate) struct PruneArgs {
+    /// Remove entries not used for at least this long (s, m, h, d or w suffix)
+    #[arg(long, default_value = "30d")]
+    pub(crate) older_than: DurationAmount,
+
+    /// Only remove entries of this kind; can be repeated
+    #[arg(long, value_enum)]
+    pub(crate) kind: Vec<CacheKind>,
+
+    /// List what would be removed without removing anything
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &PruneArgs) -> Result<(), anyhow::Error> {
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(args.older_than.get()))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let stale = ctx
+        .dirs
+        .package_cache()?
+        .with_write(async |pkg| {
+            let stale: Vec<_> = list_cache_entries(pkg.read())?
+                .into_iter()
+                .filter(|e| args.kind.is_empty() || args.kind.contains(&e.kind))
+                .filter(|e| e.last_used < cutoff)
+                .collect();
+            if !args.dry_run {
+                remove_cache_entries(pkg, &stale)?;
+            }
+            anyhow::Ok(stale)
+        })
+        .await??;
+
+    for e in &stale {
+        println!("{} {} ({})", e.kind, e.key, format_size(e.size));
+    }
+    let freed: u64 = stale.iter().map(|e| e.size).sum();
+    let verb = if args.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    println!("{verb} {} entries, {}", stale.len(), format_size(freed));
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+
+use clap::Args;
+use icp::{context::Context, package::list_cache_entries};
+
+use super::format_size;
+
+/// Show how much disk space the package cache uses, by kind of entry
+#[derive(Debug, Args)]
+pub(crate) struct SizeArgs;
+
+pub(crate) async fn exec(ctx: &Context, _: &SizeArgs) -> Result<(), anyhow::Error> {
+    let entries = ctx
+        .dirs
+        .package_cache()?
+        .with_read(async |pkg| list_cache_entries(pkg))
+        .await??;
+
+    let mut by_kind = BTreeMap::new();
+    for e in &entries {
+        let (count, size) = by_kind.entry(e.kind).or_insert((0, 0));
+        *count += 1;
+        *size += e.size;
+    }
+
+    for (kind, (count, size)) in &by_kind {
+        println!("{kind:<8} {count:>5} entries {:>10}", format_size(*size));
+    }
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    println!(
+        "{:<8} {:>5} entries {:>10}",
+        "total",
+        entries.len(),
+        format_size(total)
+    );
+
+    Ok(())
+}
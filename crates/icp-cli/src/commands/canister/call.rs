@@ -171,6 +171,9 @@ pub(crate) async fn exec(ctx: &Context, args: &CallArgs) -> Result<(), anyhow::E
         }) => {
             unreachable!("load_args rejects bin format for inline values")
         }
+        Some(icp::InitArgs::Secret { .. }) => {
+            unreachable!("load_args never reads args from a secret")
+        }
     };
 
     let arg_bytes = match (&declared_method, resolved_args) {
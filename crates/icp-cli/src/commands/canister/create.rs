@@ -71,9 +71,10 @@ pub(crate) struct CreateArgs {
     #[command(flatten)]
     pub(crate) cmd_args: args::OptionalCanisterCommandArgs,
 
-    /// One or more controllers for the canister. Repeat `--controller` to specify multiple.
+    /// One or more controllers for the canister, as principals or `@name` of a contact or
+    /// identity. Repeat `--controller` to specify multiple.
     #[arg(long)]
-    pub(crate) controller: Vec<Principal>,
+    pub(crate) controller: Vec<args::ContactOr<Principal>>,
 
     // Resource-related settings and thresholds for the new canister.
     #[command(flatten)]
@@ -125,6 +126,7 @@ pub(crate) struct CreateArgs {
 impl CreateArgs {
     /// Merge CLI settings with manifest defaults. Returns the merged `CanisterSettings`
     /// and any controller canister names that could not be resolved (not yet created).
+    /// `controllers` are the resolved `--controller` flags.
     ///
     /// `caller` is always injected into manifest-derived controller lists so the active identity
     /// retains access after creation (ic00 `controllers: Some([])` would lock out the caller).
@@ -133,11 +135,12 @@ impl CreateArgs {
         default: &Canister,
         ids: &IdMapping,
         caller: Principal,
+        controllers: Vec<Principal>,
     ) -> (MgmtCanisterSettings, Vec<String>) {
         // CLI --controller flags take precedence over manifest controllers.
         let (controllers, unresolved): (Option<Vec<Principal>>, Vec<String>) =
-            if !self.controller.is_empty() {
-                (Some(self.controller.clone()), vec![])
+            if !controllers.is_empty() {
+                (Some(controllers), vec![])
             } else if let Some(crefs) = &default.settings.controllers {
                 let (mut resolved, unresolved) = resolve_controllers(crefs, ids);
                 // The active identity must always be included so it retains access.
@@ -236,17 +239,18 @@ impl CreateArgs {
         }
     }
 
-    pub(crate) fn canister_settings(&self) -> MgmtCanisterSettings {
+    /// The CLI settings, with `controllers` the resolved `--controller` flags.
+    pub(crate) fn canister_settings(&self, controllers: Vec<Principal>) -> MgmtCanisterSettings {
         MgmtCanisterSettings {
             freezing_threshold: self
                 .settings
                 .freezing_threshold
                 .clone()
                 .map(|d| Nat::from(d.get())),
-            controllers: if self.controller.is_empty() {
+            controllers: if controllers.is_empty() {
                 None
             } else {
-                Some(self.controller.clone())
+                Some(controllers)
             },
             reserved_cycles_limit: self
                 .settings
@@ -296,7 +300,8 @@ async fn create_canister(ctx: &Context, args: &CreateArgs) -> Result<(), anyhow:
     let create_operation =
         CreateOperation::new(agent, args.create_target(), args.funding(), vec![]);
 
-    let canister_settings = args.canister_settings();
+    let controllers = args::ContactOr::resolve_all(&args.controller, ctx).await?;
+    let canister_settings = args.canister_settings(controllers);
 
     let id = create_operation.create(&canister_settings).await?;
 
@@ -344,13 +349,12 @@ async fn create_project_canister(ctx: &Context, args: &CreateArgs) -> Result<(),
         return Ok(());
     }
 
-    let identity = ctx.get_identity(&selections.identity, None).await?;
-    let caller = identity
-        .sender()
-        .map_err(|e| anyhow!("failed to get caller principal: {e}"))?;
     let agent = ctx
         .get_agent_for_env(&selections.identity, &selections.environment)
         .await?;
+    let caller = agent
+        .get_principal()
+        .map_err(|e| anyhow!("failed to get caller principal: {e}"))?;
     let ids = ctx
         .ids_by_environment(&selections.environment)
         .await
@@ -363,8 +367,9 @@ async fn create_project_canister(ctx: &Context, args: &CreateArgs) -> Result<(),
         ids.values().copied().collect(),
     );
 
+    let controllers = args::ContactOr::resolve_all(&args.controller, ctx).await?;
     let (canister_settings, unresolved) =
-        args.canister_settings_with_default(&canister_info, &ids, caller);
+        args.canister_settings_with_default(&canister_info, &ids, caller, controllers);
     for name in &unresolved {
         warn!(
             "Controller canister '{name}' for '{canister}' has not been created yet; \
@@ -4,12 +4,18 @@ use ic_management_canister_types::{CanisterIdRecord, DefiniteCanisterSettings, L
 use icp::context::Context;
 use std::fmt::Write;
 
-use crate::{commands::args::CanisterCommandArgs, operations::proxy_management};
+use crate::{
+    commands::args::CanisterCommandArgs,
+    operations::{proxy_management, settings},
+};
 
 /// Show the settings of a canister.
 ///
 /// Queries the canister_status endpoint of the management canister and
 /// displays only the settings fields. Requires the caller to be a controller.
+///
+/// The values of environment variables the project reads from a secret are
+/// masked.
 #[derive(Debug, Args)]
 pub(crate) struct ShowArgs {
     #[command(flatten)]
@@ -43,13 +49,23 @@ pub(crate) async fn exec(ctx: &Context, args: &ShowArgs) -> Result<(), anyhow::E
         )
         .await?;
 
-    let result = proxy_management::canister_status(
+    let mut result = proxy_management::canister_status(
         &agent,
         args.proxy,
         CanisterIdRecord { canister_id: cid },
     )
     .await?;
 
+    let secrets = settings::secret_names(
+        ctx,
+        &selections.canister,
+        cid,
+        &selections.environment,
+        &selections.network,
+    )
+    .await;
+    settings::redact_secrets(&mut result.settings.environment_variables, &secrets);
+
     let output = if args.json_format {
         serde_json::to_string(&result.settings).expect("Serializing settings to json failed")
     } else {
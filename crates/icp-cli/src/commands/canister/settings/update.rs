@@ -2,7 +2,6 @@ use anyhow::bail;
 use candid::Nat;
 use clap::{ArgAction, Args};
 use dialoguer::Confirm;
-use ic_agent::Identity;
 use ic_agent::export::Principal;
 use ic_management_canister_types::{
     CanisterIdRecord, CanisterSettings, CanisterStatusResult, EnvironmentVariable, LogVisibility,
@@ -14,19 +13,24 @@ use icp::parsers::{CyclesAmount, DurationAmount, MemoryAmount};
 use std::collections::{HashMap, HashSet};
 use tracing::warn;
 
-use crate::{commands::args, operations::proxy_management};
+use crate::{
+    commands::args::{self, ContactOr, ResolveContactOrError},
+    operations::proxy_management,
+};
 
 #[derive(Clone, Debug, Default, Args)]
 pub(crate) struct ControllerOpt {
-    /// Add one or more principals to the canister's controller list.
+    /// Add one or more principals, or `@name` of contacts or identities, to the canister's
+    /// controller list.
     #[arg(long, action = ArgAction::Append)]
-    add_controller: Option<Vec<Principal>>,
+    add_controller: Option<Vec<ContactOr<Principal>>>,
 
-    /// Remove one or more principals from the canister's controller list.
+    /// Remove one or more principals, or `@name` of contacts or identities, from the
+    /// canister's controller list.
     ///
     /// Warning: Removing yourself will cause you to lose control of the canister.
     #[arg(long, action = ArgAction::Append)]
-    remove_controller: Option<Vec<Principal>>,
+    remove_controller: Option<Vec<ContactOr<Principal>>>,
 
     /// Remove all controllers.
     ///
@@ -57,17 +61,17 @@ pub(crate) struct LogVisibilityOpt {
     )]
     log_visibility: Option<LogVisibility>,
 
-    /// Add a principal to the allowed log viewers list
+    /// Add a principal, or `@name` of a contact or identity, to the allowed log viewers list
     #[arg(long, action = ArgAction::Append, conflicts_with("set_log_viewer"))]
-    add_log_viewer: Option<Vec<Principal>>,
+    add_log_viewer: Option<Vec<ContactOr<Principal>>>,
 
-    /// Remove a principal from the allowed log viewers list
+    /// Remove a principal, or `@name` of a contact or identity, from the allowed log viewers list
     #[arg(long, action = ArgAction::Append, conflicts_with("set_log_viewer"))]
-    remove_log_viewer: Option<Vec<Principal>>,
+    remove_log_viewer: Option<Vec<ContactOr<Principal>>>,
 
     /// Replace the allowed log viewers list with the specified principals
     #[arg(long, action = ArgAction::Append)]
-    set_log_viewer: Option<Vec<Principal>>,
+    set_log_viewer: Option<Vec<ContactOr<Principal>>>,
 }
 
 impl LogVisibilityOpt {
@@ -152,11 +156,6 @@ pub(crate) struct UpdateArgs {
 
 pub(crate) async fn exec(ctx: &Context, args: &UpdateArgs) -> Result<(), anyhow::Error> {
     let selections = args.cmd_args.selections();
-    let identity = ctx.get_identity(&selections.identity, None).await?;
-    let caller_principal = identity
-        .sender()
-        .map_err(|e| anyhow::anyhow!("failed to get caller principal: {e}"))?;
-
     let agent = ctx
         .get_agent(
             &selections.identity,
@@ -164,6 +163,9 @@ pub(crate) async fn exec(ctx: &Context, args: &UpdateArgs) -> Result<(), anyhow:
             &selections.environment,
         )
         .await?;
+    let caller_principal = agent
+        .get_principal()
+        .map_err(|e| anyhow::anyhow!("failed to get caller principal: {e}"))?;
     let cid = ctx
         .get_canister_id(
             &selections.canister,
@@ -199,7 +201,7 @@ pub(crate) async fn exec(ctx: &Context, args: &UpdateArgs) -> Result<(), anyhow:
     // Handle controllers.
     let mut controllers: Option<Vec<Principal>> = None;
     if let Some(controllers_opt) = &args.controllers {
-        controllers = get_controllers(controllers_opt, current_status.as_ref());
+        controllers = get_controllers(ctx, controllers_opt, current_status.as_ref()).await?;
 
         // Check if the effective controller is being removed from the controller list.
         // When --proxy is set, the proxy canister is the one making management calls and
@@ -235,7 +237,8 @@ pub(crate) async fn exec(ctx: &Context, args: &UpdateArgs) -> Result<(), anyhow:
     // Handle log visibility.
     let mut log_visibility: Option<LogVisibility> = None;
     if let Some(log_visibility_opt) = args.log_visibility.clone() {
-        log_visibility = get_log_visibility(&log_visibility_opt, current_status.as_ref());
+        log_visibility =
+            get_log_visibility(ctx, &log_visibility_opt, current_status.as_ref()).await?;
     }
 
     // Handle environment variables.
@@ -374,10 +377,15 @@ fn require_current_settings(args: &UpdateArgs) -> bool {
     false
 }
 
-fn get_controllers(
+async fn get_controllers(
+    ctx: &Context,
     controllers: &ControllerOpt,
     current_status: Option<&CanisterStatusResult>,
-) -> Option<Vec<Principal>> {
+) -> Result<Option<Vec<Principal>>, ResolveContactOrError> {
+    let to_be_added = match &controllers.add_controller {
+        Some(added) => Some(ContactOr::resolve_all(added, ctx).await?),
+        None => None,
+    };
     if controllers.require_current_settings() {
         let mut current_controllers: HashSet<Principal> = current_status
             .as_ref()
@@ -388,34 +396,39 @@ fn get_controllers(
             .into_iter()
             .collect();
 
-        if let Some(to_be_added) = controllers.add_controller.as_ref() {
+        if let Some(to_be_added) = to_be_added {
             current_controllers.extend(to_be_added);
         }
         if let Some(to_be_removed) = controllers.remove_controller.as_ref() {
-            for controller in to_be_removed {
-                current_controllers.remove(controller);
+            for controller in ContactOr::resolve_all(to_be_removed, ctx).await? {
+                current_controllers.remove(&controller);
             }
         }
 
-        Some(current_controllers.into_iter().collect::<Vec<Principal>>())
+        Ok(Some(
+            current_controllers.into_iter().collect::<Vec<Principal>>(),
+        ))
     } else if controllers.remove_all_controllers {
-        Some(controllers.add_controller.clone().unwrap_or_default())
+        Ok(Some(to_be_added.unwrap_or_default()))
     } else {
-        None
+        Ok(None)
     }
 }
 
-fn get_log_visibility(
+async fn get_log_visibility(
+    ctx: &Context,
     log_visibility: &LogVisibilityOpt,
     current_status: Option<&CanisterStatusResult>,
-) -> Option<LogVisibility> {
+) -> Result<Option<LogVisibility>, ResolveContactOrError> {
     if let Some(log_visibility) = log_visibility.log_visibility.as_ref() {
-        return Some(log_visibility.clone());
+        return Ok(Some(log_visibility.clone()));
     }
 
     if let Some(viewer) = log_visibility.set_log_viewer.as_ref() {
         // TODO(VZ): Warn for switching from public to viewers.
-        return Some(LogVisibility::AllowedViewers(viewer.clone()));
+        return Ok(Some(LogVisibility::AllowedViewers(
+            ContactOr::resolve_all(viewer, ctx).await?,
+        )));
     }
 
     let mut log_viewers: Vec<Principal> = match current_status {
@@ -428,23 +441,23 @@ fn get_log_visibility(
 
     if let Some(to_be_added) = log_visibility.add_log_viewer.as_ref() {
         // TODO(VZ): Warn for switching from public to viewers.
-        for principal in to_be_added {
-            if !log_viewers.iter().any(|x| x == principal) {
-                log_viewers.push(*principal);
+        for principal in ContactOr::resolve_all(to_be_added, ctx).await? {
+            if !log_viewers.contains(&principal) {
+                log_viewers.push(principal);
             }
         }
     }
 
     if let Some(removed) = log_visibility.remove_log_viewer.as_ref() {
         // TODO(VZ): Warn for removing from if log visibility is public and controllers.
-        for principal in removed {
-            if let Some(idx) = log_viewers.iter().position(|x| x == principal) {
+        for principal in ContactOr::resolve_all(removed, ctx).await? {
+            if let Some(idx) = log_viewers.iter().position(|x| *x == principal) {
                 log_viewers.swap_remove(idx);
             }
         }
     }
 
-    Some(LogVisibility::AllowedViewers(log_viewers))
+    Ok(Some(LogVisibility::AllowedViewers(log_viewers)))
 }
 
 fn get_environment_variables(
@@ -15,7 +15,7 @@ use tracing::debug;
 
 use crate::{
     commands::args,
-    operations::{proxy::UpdateOrProxyError, proxy_management},
+    operations::{proxy::UpdateOrProxyError, proxy_management, settings},
     options,
 };
 
@@ -29,6 +29,9 @@ const E_NOT_A_CONTROLLER: &str = "IC0512";
 /// By default this queries the status endpoint of the management canister.
 /// If the caller is not a controller, falls back on fetching public
 /// information from the state tree.
+///
+/// The values of environment variables the project reads from a secret are
+/// masked.
 #[derive(Debug, Args)]
 #[command(after_long_help = "\
 Examples:
@@ -254,7 +257,24 @@ pub(crate) async fn exec(ctx: &Context, args: &StatusArgs) -> Result<(), anyhow:
                 )
                 .await
                 {
-                    Ok(result) => {
+                    Ok(mut result) => {
+                        let canister = match maybe_name {
+                            Some(name) => CanisterSelection::Named(name.clone()),
+                            None => CanisterSelection::Principal(*cid),
+                        };
+                        let secrets = settings::secret_names(
+                            ctx,
+                            &canister,
+                            *cid,
+                            &selections.environment,
+                            &selections.network,
+                        )
+                        .await;
+                        settings::redact_secrets(
+                            &mut result.settings.environment_variables,
+                            &secrets,
+                        );
+
                         let status = SerializableCanisterStatusResult::from(
                             cid.to_owned(),
                             maybe_name.clone(),
@@ -0,0 +1,28 @@
+use clap::Args;
+use icp::context::Context;
+use icp::identity::contacts::add_contact;
+use tracing::info;
+
+use crate::commands::args::FlexibleAccountId;
+
+/// Add a contact
+#[derive(Debug, Args)]
+pub(crate) struct AddArgs {
+    /// Name of the contact
+    name: String,
+
+    /// A principal, ICRC-1 account or ICP ledger account identifier
+    address: FlexibleAccountId,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &AddArgs) -> Result<(), anyhow::Error> {
+    let dirs = ctx.dirs.identity()?;
+    let address = args.address.to_string();
+
+    dirs.with_write(async |dirs| {
+        add_contact(dirs, &args.name, &address)?;
+        info!("Added contact `{}` for {address}", args.name);
+        Ok(())
+    })
+    .await?
+}
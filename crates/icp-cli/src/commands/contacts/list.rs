@@ -0,0 +1,48 @@
+use std::{collections::BTreeMap, io::stdout};
+
+use clap::Args;
+use icp::context::Context;
+use icp::identity::contacts::ContactList;
+use serde::Serialize;
+
+/// List the contacts
+#[derive(Debug, Args)]
+pub(crate) struct ListArgs {
+    /// Output command results as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ListArgs) -> Result<(), anyhow::Error> {
+    let dirs = ctx.dirs.identity()?.into_read().await?;
+
+    let list = ContactList::load_from(dirs.as_ref())?;
+
+    if args.json {
+        serde_json::to_writer(
+            stdout(),
+            &JsonContactList {
+                contacts: list.contacts,
+            },
+        )?;
+        return Ok(());
+    }
+
+    let longest_name_length = list
+        .contacts
+        .keys()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0);
+
+    for (name, address) in &list.contacts {
+        println!("{name: <longest_name_length$} {address}");
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonContactList {
+    contacts: BTreeMap<String, String>,
+}
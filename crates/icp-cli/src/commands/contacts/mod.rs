@@ -0,0 +1,17 @@
+use clap::Subcommand;
+
+pub(crate) mod add;
+pub(crate) mod list;
+pub(crate) mod remove;
+
+/// Manage named principals and accounts that can be used as `@name`
+///
+/// Any argument that takes a principal or account also accepts `@name`,
+/// which resolves to the contact `name`, or if there is none, to the
+/// principal of identity `name`.
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    Add(add::AddArgs),
+    List(list::ListArgs),
+    Remove(remove::RemoveArgs),
+}
@@ -0,0 +1,22 @@
+use clap::Args;
+use icp::context::Context;
+use icp::identity::contacts::remove_contact;
+use tracing::info;
+
+/// Remove a contact
+#[derive(Debug, Args)]
+pub(crate) struct RemoveArgs {
+    /// Name of the contact
+    name: String,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &RemoveArgs) -> Result<(), anyhow::Error> {
+    let dirs = ctx.dirs.identity()?;
+
+    dirs.with_write(async |dirs| {
+        remove_contact(dirs, &args.name)?;
+        info!("Removed contact `{}`", args.name);
+        Ok(())
+    })
+    .await?
+}
@@ -1,6 +1,5 @@
 use std::io::stdout;
 
-use anyhow::ensure;
 use clap::Args;
 use icp::context::Context;
 use icp::parsers::CyclesAmount;
@@ -8,7 +7,7 @@ use icp_canister_interfaces::cycles_ledger::{CYCLES_LEDGER_BLOCK_FEE, CYCLES_LED
 use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
 
-use crate::commands::args::TokenCommandArgs;
+use crate::commands::args::{ContactOr, TokenCommandArgs, with_subaccount};
 use crate::commands::parsers::parse_subaccount;
 use crate::operations::token::transfer::icrc1_transfer;
 
@@ -19,8 +18,9 @@ pub(crate) struct TransferArgs {
     /// Supports suffixes: k (thousand), m (million), b (billion), t (trillion).
     pub(crate) amount: CyclesAmount,
 
-    /// The receiver of the cycles transfer
-    pub(crate) receiver: Account,
+    /// The receiver of the cycles transfer: a principal, an ICRC1 account ID,
+    /// or `@name` of a contact or identity
+    pub(crate) receiver: ContactOr<Account>,
 
     /// The subaccount to transfer to (only if the receiver is a principal)
     #[arg(long, value_parser = parse_subaccount)]
@@ -43,14 +43,11 @@ pub(crate) struct TransferArgs {
 }
 
 pub(crate) async fn exec(ctx: &Context, args: &TransferArgs) -> Result<(), anyhow::Error> {
-    ensure!(
-        !(args.to_subaccount.is_some() && args.receiver.subaccount.is_some()),
-        "Cannot use both --subaccount with an account ID. Use a plain principal if you want to change the subaccount."
-    );
-    let mut receiver = args.receiver;
-    if let Some(subaccount) = args.to_subaccount {
-        receiver.subaccount = Some(subaccount);
-    }
+    let receiver = with_subaccount(
+        args.receiver.resolve(ctx).await?,
+        args.to_subaccount,
+        "--to-subaccount",
+    )?;
     let selections = args.token_command_args.selections();
 
     // Agent
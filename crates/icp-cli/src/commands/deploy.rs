@@ -1,10 +1,10 @@
-use anyhow::{anyhow, bail};
+use anyhow::{Context as _, anyhow, bail};
 use candid::Principal;
 use clap::Args;
 use clap_complete::ArgValueCandidates;
 use futures::{StreamExt, future::try_join_all, stream::FuturesOrdered};
 use ic_agent::{Agent, AgentError};
-use ic_management_canister_types::{CanisterId, CanisterIdRecord};
+use ic_management_canister_types::{CanisterId, CanisterIdRecord, CanisterSettings};
 use icp::parsers::CyclesAmount;
 use icp::{
     context::{CanisterSelection, Context, EnvironmentSelection},
@@ -20,14 +20,19 @@ use tracing::info;
 
 use crate::options::EnvironmentOpt;
 use crate::{
-    commands::{args::ArgsOpt, canister::create},
+    commands::{
+        args::{self, ArgsOpt},
+        canister::create,
+    },
     operations::{
         binding_env_vars::set_binding_env_vars_many,
         build::build_many_with_progress_bar,
         candid_compat::check_candid_compatibility_many,
         create::{CreateFunding, CreateOperation, CreateTarget},
         install::{install_many, resolve_install_mode_and_status},
+        promote::verify_artifact,
         proxy_management,
+        remote::resolve_canister_downloads,
         settings::{sync_controller_dependents, sync_settings_many},
         sync::sync_many,
     },
@@ -49,6 +54,20 @@ using --args or --args-file:
 
     # Pass raw bytes
     icp deploy my_canister --args-file ./args.bin --args-format bin
+
+To ship exactly what another environment runs, promote its builds with
+--from-environment. The canisters are not built: their local builds are
+verified against the modules installed in that environment and installed
+as they are:
+
+    icp build -e staging
+    icp deploy --from-environment staging -e production
+
+Without local builds, --fetch-from-snapshot downloads the installed modules
+from snapshots of the canisters in that environment instead. Each running
+canister is stopped while its snapshot is taken:
+
+    icp deploy --from-environment staging -e production --fetch-from-snapshot
 ")]
 pub(crate) struct DeployArgs {
     /// Canister names
@@ -68,8 +87,9 @@ pub(crate) struct DeployArgs {
     pub(crate) proxy: Option<Principal>,
 
     /// One or more controllers for the canisters being deployed. Repeat `--controller` to specify multiple.
+    /// Accepts a principal or `@name` of a contact or identity.
     #[arg(long)]
-    pub(crate) controller: Vec<Principal>,
+    pub(crate) controller: Vec<args::ContactOr<Principal>>,
 
     /// Cycles to fund canister creation.
     /// Supports suffixes: k (thousand), m (million), b (billion), t (trillion).
@@ -94,6 +114,18 @@ pub(crate) struct DeployArgs {
     #[arg(long)]
     pub(crate) json: bool,
 
+    /// Promote the canisters of another environment instead of building them.
+    /// The local build of each canister must be the exact module installed in
+    /// that environment.
+    #[arg(long, value_name = "ENVIRONMENT", add = ArgValueCandidates::new(crate::complete::environments))]
+    pub(crate) from_environment: Option<String>,
+
+    /// When a local build is missing or differs from the module installed in
+    /// the environment promoted from, download that module from a snapshot of
+    /// the canister. A running canister is stopped while the snapshot is taken.
+    #[arg(long, requires = "from_environment")]
+    pub(crate) fetch_from_snapshot: bool,
+
     /// Arguments to pass to the canister on install.
     /// Only valid when deploying a single canister. Takes priority over `init_args` in the manifest.
     #[command(flatten)]
@@ -141,6 +173,8 @@ pub(crate) async fn exec(ctx: &Context, args: &DeployArgs) -> Result<(), anyhow:
         anyhow::bail!("--args and --args-file can only be used when deploying a single canister");
     }
 
+    let controllers = args::ContactOr::resolve_all(&args.controller, ctx).await?;
+
     // A member-scoped deploy targets only the sub-project's own canisters, but
     // those canisters are wired to their dependencies' ids — and the dependency
     // canisters are outside the scope, so they are not (re)deployed here. If any
@@ -172,25 +206,66 @@ pub(crate) async fn exec(ctx: &Context, args: &DeployArgs) -> Result<(), anyhow:
         }
     }
 
-    let canisters_to_build = try_join_all(
+    let canisters = try_join_all(
         cnames
             .iter()
             .map(|name| ctx.get_canister_and_path_for_env(name, &environment_selection)),
     )
     .await?;
 
-    // Build the selected canisters
-    info!("Building canisters:");
+    // Offline, report everything missing from the package cache at once
+    if ctx.offline {
+        resolve_canister_downloads(ctx, canisters.iter().map(|(path, c)| (path.as_path(), c)))
+            .await?;
+    }
 
-    build_many_with_progress_bar(
-        canisters_to_build,
-        environment_selection.name(),
-        ctx.builder.clone(),
-        ctx.artifacts.clone(),
-        &ctx.dirs.package_cache()?,
-        ctx.debug,
-    )
-    .await?;
+    if let Some(source) = &args.from_environment {
+        // Verify the builds to promote instead of building
+        if source == environment_selection.name() {
+            bail!("cannot promote environment '{source}' to itself");
+        }
+        let source_selection = EnvironmentSelection::Named(source.clone());
+        let cids = try_join_all(cnames.iter().map(|name| {
+            let source_selection = &source_selection;
+            async move {
+                ctx.get_canister_id_for_env(
+                    &CanisterSelection::Named(name.clone()),
+                    source_selection,
+                )
+                .await
+            }
+        }))
+        .await?;
+        let source_agent = ctx
+            .get_agent_for_env(&identity_selection, &source_selection)
+            .await?;
+
+        info!("Verifying builds against environment '{source}'");
+        try_join_all(cnames.iter().zip(cids).map(|(name, cid)| {
+            verify_artifact(
+                &source_agent,
+                source,
+                name,
+                cid,
+                &*ctx.artifacts,
+                args.fetch_from_snapshot,
+            )
+        }))
+        .await?;
+    } else {
+        // Build the selected canisters
+        info!("Building canisters:");
+
+        build_many_with_progress_bar(
+            canisters,
+            environment_selection.name(),
+            ctx.builder.clone(),
+            ctx.artifacts.clone(),
+            &ctx.dirs.package_cache()?,
+            ctx.debug,
+        )
+        .await?;
+    }
 
     // Ensure the selected canisters exist, creating any that are missing.
     let env = ctx
@@ -237,10 +312,16 @@ pub(crate) async fn exec(ctx: &Context, args: &DeployArgs) -> Result<(), anyhow:
             pb.set_message("Creating...");
             let create_op = create_operation.clone();
             let (_, canister_info) = env.get_canister_info(name).map_err(|e| anyhow!(e))?;
+            let mut settings: CanisterSettings = canister_info.settings.into();
+            // CLI --controller flags take precedence over manifest controllers,
+            // which are otherwise applied when settings are synced
+            if !controllers.is_empty() {
+                settings.controllers = Some(controllers.clone());
+            }
             futs.push_back(async move {
                 ProgressManager::execute_with_custom_progress(
                     &pb,
-                    create_op.create(&canister_info.settings.into()),
+                    create_op.create(&settings),
                     || "Created successfully".to_string(),
                     |err: &_| err.to_string(),
                     |_| false,
@@ -364,12 +445,13 @@ pub(crate) async fn exec(ctx: &Context, args: &DeployArgs) -> Result<(), anyhow:
             // CLI --args/--args-file take priority over manifest init_args
             let init_args_bytes = if args.args_opt.is_some() {
                 args.args_opt.resolve_bytes()?
+            } else if let Some(ia) = &canister_info.init_args {
+                let ia = ia.resolve_secret().await.with_context(|| {
+                    format!("failed to read the init args secret of canister '{name}'")
+                })?;
+                Some(ia.to_bytes()?)
             } else {
-                canister_info
-                    .init_args
-                    .as_ref()
-                    .map(|ia| ia.to_bytes())
-                    .transpose()?
+                None
             };
 
             Ok::<_, anyhow::Error>((name.clone(), cid, mode, status, init_args_bytes))
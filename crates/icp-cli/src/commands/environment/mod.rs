@@ -1,9 +1,13 @@
 use clap::Subcommand;
 
 pub(crate) mod list;
+pub(crate) mod promote;
+pub(crate) mod show;
 
 /// Show information about the current project environments
 #[derive(Subcommand, Debug)]
 pub(crate) enum Command {
     List(list::ListArgs),
+    Promote(promote::PromoteArgs),
+    Show(show::ShowArgs),
 }
@@ -0,0 +1,75 @@
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+use icp::context::Context;
+use icp::parsers::CyclesAmount;
+
+use crate::commands::{
+    args::ArgsOpt,
+    canister::create,
+    deploy::{self, DeployArgs, DeployEnvironmentOpt},
+};
+use crate::options::{EnvironmentOpt, IdentityOpt};
+
+/// Deploy the exact modules one environment runs to another
+///
+/// Shorthand for `icp deploy --from-environment <FROM> -e <TO>`: the canisters
+/// are not built, their local builds are verified against the modules installed
+/// in <FROM> and installed in <TO> as they are.
+#[derive(Args, Debug)]
+#[command(after_long_help = "\
+Examples:
+
+    icp build -e staging
+    icp environment promote staging production
+    icp environment promote staging production backend --fetch-from-snapshot
+")]
+pub(crate) struct PromoteArgs {
+    /// The environment to promote
+    #[arg(add = ArgValueCandidates::new(crate::complete::environments))]
+    pub(crate) from: String,
+
+    /// The environment to deploy to
+    #[arg(add = ArgValueCandidates::new(crate::complete::environments))]
+    pub(crate) to: String,
+
+    /// Canister names. By default, every canister of the environment
+    #[arg(add = ArgValueCandidates::new(crate::complete::canisters))]
+    pub(crate) names: Vec<String>,
+
+    /// When a local build is missing or differs from the module installed in
+    /// <FROM>, download that module from a snapshot of the canister. A running
+    /// canister is stopped while the snapshot is taken.
+    #[arg(long)]
+    pub(crate) fetch_from_snapshot: bool,
+
+    /// If any canisters do not exist in <TO>, error instead of creating them.
+    #[arg(long)]
+    pub(crate) no_create: bool,
+
+    /// Skip confirmation prompts, including the Candid interface compatibility check.
+    #[arg(long, short)]
+    pub(crate) yes: bool,
+
+    #[command(flatten)]
+    pub(crate) identity: IdentityOpt,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &PromoteArgs) -> Result<(), anyhow::Error> {
+    let deploy = DeployArgs {
+        names: args.names.clone(),
+        mode: "auto".to_string(),
+        subnet: None,
+        proxy: None,
+        controller: vec![],
+        cycles: CyclesAmount::from(create::DEFAULT_CANISTER_CYCLES),
+        no_create: args.no_create,
+        yes: args.yes,
+        identity: args.identity.clone(),
+        environment: DeployEnvironmentOpt(EnvironmentOpt::named(args.to.clone())),
+        json: false,
+        from_environment: Some(args.from.clone()),
+        fetch_from_snapshot: args.fetch_from_snapshot,
+        args_opt: ArgsOpt::default(),
+    };
+    deploy::exec(ctx, &deploy).await
+}
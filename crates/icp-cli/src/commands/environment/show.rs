@@ -0,0 +1,66 @@
+use anyhow::{Context as _, bail};
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+use icp::{
+    context::Context,
+    manifest::{CanisterSelection, EnvironmentManifest},
+};
+
+/// Show how an environment is configured
+///
+/// Prints the environment as the project declares it. With `--resolved`, prints
+/// its effective configuration instead, with every environment it `extends`
+/// merged in.
+#[derive(Args, Debug)]
+#[command(after_long_help = "\
+Examples:
+
+    icp environment show staging
+    icp environment show prod-eu --resolved
+")]
+pub(crate) struct ShowArgs {
+    /// The environment to show
+    #[arg(add = ArgValueCandidates::new(crate::complete::environments))]
+    pub(crate) name: String,
+
+    /// Show the effective configuration, with the environments it extends merged in
+    #[arg(long)]
+    pub(crate) resolved: bool,
+
+    /// Output the configuration as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ShowArgs) -> Result<(), anyhow::Error> {
+    let p = ctx.project.load().await.context("failed to load project")?;
+
+    let manifest = match p.declared_environments.get(&args.name) {
+        Some(d) if args.resolved => d.resolved.clone(),
+        Some(d) => d.declared.clone(),
+        None => {
+            // The implicit `local` and `ic` environments only set their network
+            let Some(env) = p.environments.get(&args.name) else {
+                bail!("environment '{}' not found in the project", args.name);
+            };
+            EnvironmentManifest {
+                name: env.name.clone(),
+                extends: None,
+                network: Some(env.network.name.clone()),
+                identity: None,
+                canisters: CanisterSelection::Everything,
+                settings: None,
+                init_args: None,
+            }
+        }
+    };
+
+    match args.json {
+        true => {
+            serde_json::to_writer_pretty(std::io::stdout(), &manifest)?;
+            println!();
+        }
+        false => print!("{}", serde_yaml::to_string(&manifest)?),
+    }
+    Ok(())
+}
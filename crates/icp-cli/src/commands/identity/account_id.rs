@@ -4,6 +4,7 @@ use ic_ledger_types::{AccountIdentifier, Subaccount};
 use icp::context::Context;
 use icrc_ledger_types::icrc1::account::Account;
 
+use crate::commands::args::ContactOr;
 use crate::commands::parsers::parse_subaccount;
 use crate::options::IdentityOpt;
 
@@ -23,9 +24,10 @@ pub(crate) struct AccountIdArgs {
     #[command(flatten)]
     pub(crate) identity: IdentityOpt,
 
-    /// Convert this Principal instead of the current identity's Principal
+    /// Convert this Principal, or `@name` of a contact or identity, instead of the
+    /// current identity's Principal
     #[arg(long = "of-principal", conflicts_with = "identity")]
-    pub(crate) of_principal: Option<Principal>,
+    pub(crate) of_principal: Option<ContactOr<Principal>>,
 
     /// Specify a subaccount
     #[arg(long, value_parser = parse_subaccount)]
@@ -38,7 +40,7 @@ pub(crate) struct AccountIdArgs {
 
 pub(crate) async fn exec(ctx: &Context, args: &AccountIdArgs) -> Result<(), anyhow::Error> {
     let principal = if let Some(p) = &args.of_principal {
-        *p
+        p.resolve(ctx).await?
     } else {
         let id = ctx
             .get_identity(&args.identity.clone().into(), None)
@@ -0,0 +1,45 @@
+use anyhow::Context as _;
+use clap::{Args, ValueHint};
+use icp::context::Context;
+use icp::identity::backup::backup_identities;
+use icp::prelude::*;
+use tracing::info;
+
+use crate::commands::identity::read_new_password;
+
+/// Back up every identity into one password-encrypted file
+///
+/// The backup holds each identity's key, including keys kept in the keyring,
+/// its delegation chain, and the default identity. Keys stored with a password
+/// stay encrypted with that password. Restore it with `icp identity restore`.
+#[derive(Debug, Args)]
+pub(crate) struct BackupArgs {
+    /// The file to write the backup to
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    out: PathBuf,
+
+    /// Read the backup password from a file instead of prompting
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    password_file: Option<PathBuf>,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &BackupArgs) -> Result<(), anyhow::Error> {
+    let password = read_new_password(
+        args.password_file.as_deref(),
+        "Enter password to encrypt the backup",
+    )?;
+
+    let backup = ctx
+        .dirs
+        .identity()?
+        .with_read(async |dirs| backup_identities(dirs, &password))
+        .await??;
+
+    icp::fs::write_private(&args.out, backup.archive.as_bytes())
+        .context("failed to write the backup")?;
+    let count = backup.identities.len();
+    let noun = if count == 1 { "identity" } else { "identities" };
+    info!("Backed up {count} {noun} to {}", args.out);
+
+    Ok(())
+}
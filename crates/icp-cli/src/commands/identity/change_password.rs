@@ -0,0 +1,44 @@
+use clap::{Args, ValueHint};
+use clap_complete::ArgValueCandidates;
+use icp::context::Context;
+use icp::identity::key::change_identity_password;
+use icp::prelude::*;
+use tracing::info;
+
+use crate::commands::identity::{current_password, read_new_password};
+
+/// Change the password of an identity stored with a password
+#[derive(Debug, Args)]
+pub(crate) struct ChangePasswordArgs {
+    /// Name of the identity
+    #[arg(add = ArgValueCandidates::new(crate::complete::identity_names))]
+    name: String,
+
+    /// Read the current password from a file instead of prompting
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    password_file: Option<PathBuf>,
+
+    /// Read the new password from a file instead of prompting
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    new_password_file: Option<PathBuf>,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ChangePasswordArgs) -> Result<(), anyhow::Error> {
+    let new_password = read_new_password(
+        args.new_password_file.as_deref(),
+        "Enter new password to encrypt identity",
+    )?;
+
+    ctx.dirs
+        .identity()?
+        .with_write(async |dirs| {
+            change_identity_password(dirs, &args.name, new_password, || {
+                current_password(&args.name, args.password_file.as_deref())
+            })
+        })
+        .await??;
+
+    info!("Changed the password of identity `{}`", args.name);
+
+    Ok(())
+}
@@ -16,7 +16,10 @@ use icp::{
 use pem::Pem;
 use snafu::{OptionExt, ResultExt, Snafu};
 
-use crate::options::IdentityOpt;
+use crate::{
+    commands::args::{ContactOr, ResolveContactOrError},
+    options::IdentityOpt,
+};
 
 /// Sign a delegation from the selected identity to a target key
 #[derive(Debug, Args)]
@@ -29,9 +32,10 @@ pub(crate) struct SignArgs {
     #[arg(long)]
     duration: DurationArg,
 
-    /// Canister principals to restrict the delegation to (comma-separated)
+    /// Canister principals, or `@name` of contacts, to restrict the delegation to
+    /// (comma-separated)
     #[arg(long, value_delimiter = ',')]
-    canisters: Option<Vec<Principal>>,
+    canisters: Option<Vec<ContactOr<Principal>>>,
 
     #[command(flatten)]
     identity: IdentityOpt,
@@ -53,10 +57,19 @@ pub(crate) async fn exec(ctx: &Context, args: &SignArgs) -> Result<(), SignError
         .as_nanos() as u64;
     let expiration = now_nanos.saturating_add(args.duration.as_nanos());
 
+    let targets = match &args.canisters {
+        Some(canisters) => Some(
+            ContactOr::resolve_all(canisters, ctx)
+                .await
+                .context(ResolveCanistersSnafu)?,
+        ),
+        None => None,
+    };
+
     let delegation = AgentDelegation {
         pubkey: target_pubkey.clone(),
         expiration,
-        targets: args.canisters.clone(),
+        targets: targets.clone(),
         permissions: None,
     };
 
@@ -92,8 +105,7 @@ pub(crate) async fn exec(ctx: &Context, args: &SignArgs) -> Result<(), SignError
         delegation: WireDelegation {
             pubkey: hex::encode(&target_pubkey),
             expiration: format!("{expiration:x}"),
-            targets: args
-                .canisters
+            targets: targets
                 .as_ref()
                 .map(|ts| ts.iter().map(|p| hex::encode(p.as_slice())).collect()),
         },
@@ -190,4 +202,7 @@ pub(crate) enum SignError {
 
     #[snafu(display("failed to serialize delegation chain"))]
     Serialize { source: serde_json::Error },
+
+    #[snafu(display("failed to resolve --canisters"))]
+    ResolveCanisters { source: ResolveContactOrError },
 }
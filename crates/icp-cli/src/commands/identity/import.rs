@@ -4,8 +4,12 @@ use dialoguer::Password;
 use elliptic_curve::zeroize::Zeroizing;
 use icp::identity::{
     delegation::DelegationChain,
+    dfx::{DfxIdentityStore, DfxKey, LocateDfxError, ReadDfxIdentityError},
     key::{CreateFormat, CreateIdentityError, IdentityKey, create_identity},
-    manifest::IdentityKeyAlgorithm,
+    manifest::{
+        IdentityDefaults, IdentityKeyAlgorithm, IdentityList, LoadIdentityManifestError,
+        WriteIdentityManifestError,
+    },
     seed::derive_key_from_seed_slip10,
 };
 use icp::{
@@ -33,10 +37,13 @@ use crate::commands::identity::StorageMode;
 #[command(
     group(ArgGroup::new("import-from").required(true)),
     group(ArgGroup::new("seed").required(false)),
+    group(ArgGroup::new("encrypted-key").args(["from_pem", "from_dfx"])),
 )]
 pub(crate) struct ImportArgs {
-    /// Name for the imported identity
-    name: String,
+    /// Name for the imported identity. With `--from-dfx`, the dfx identity to import,
+    /// which keeps its name
+    #[arg(required_unless_present = "all")]
+    name: Option<String>,
 
     /// Where to store the private key
     #[arg(long, value_enum, default_value_t)]
@@ -46,6 +53,15 @@ pub(crate) struct ImportArgs {
     #[arg(long, value_name = "FILE", group = "import-from", value_hint = ValueHint::FilePath)]
     from_pem: Option<PathBuf>,
 
+    /// Import from dfx's identity store (`~/.config/dfx`), whether the key is stored in
+    /// plaintext, encrypted or in the keyring
+    #[arg(long, group = "import-from")]
+    from_dfx: bool,
+
+    /// Import every dfx identity, skipping those whose name is already taken
+    #[arg(long, requires = "from_dfx", conflicts_with = "name")]
+    all: bool,
+
     /// Read seed phrase interactively from the terminal
     #[arg(long, group = "import-from", group = "seed")]
     read_seed_phrase: bool,
@@ -54,8 +70,9 @@ pub(crate) struct ImportArgs {
     #[arg(long, value_name = "FILE", group = "import-from", group = "seed", value_hint = ValueHint::FilePath)]
     from_seed_file: Option<PathBuf>,
 
-    /// Read the PEM decryption password from a file instead of prompting
-    #[arg(long, value_name = "FILE", requires = "from_pem", value_hint = ValueHint::FilePath)]
+    /// Read the PEM decryption password, or the password of encrypted dfx identities, from a
+    /// file instead of prompting
+    #[arg(long, value_name = "FILE", requires = "encrypted-key", value_hint = ValueHint::FilePath)]
     decryption_password_from_file: Option<PathBuf>,
 
     /// Read the storage password from a file instead of prompting (for --storage password)
@@ -73,7 +90,7 @@ pub(crate) struct ImportArgs {
     /// Attach a signed delegation chain JSON (same format as `icp identity delegation sign`).
     ///
     /// For security, in most cases it is better to use `icp identity delegation` instead.
-    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    #[arg(long, value_name = "FILE", conflicts_with = "all", value_hint = ValueHint::FilePath)]
     delegation: Option<PathBuf>,
 }
 
@@ -105,10 +122,33 @@ pub(crate) async fn exec(ctx: &Context, args: &ImportArgs) -> Result<(), anyhow:
         .map(json::load::<DelegationChain>)
         .transpose()?;
 
+    if args.from_dfx {
+        import_from_dfx(
+            ctx,
+            args.name.as_deref(),
+            args.decryption_password_from_file.as_deref(),
+            args.assert_key_type.clone(),
+            format,
+            chain.as_ref(),
+        )
+        .await?;
+
+        if matches!(args.storage, StorageMode::Plaintext) {
+            warn!(
+                "These identities are stored in plaintext and are not secure. Do not use them for anything of significant value."
+            );
+        }
+        return Ok(());
+    }
+
+    let name = args
+        .name
+        .as_deref()
+        .expect("name is required without --all");
     if let Some(from_pem) = &args.from_pem {
         import_from_pem(
             ctx,
-            &args.name,
+            name,
             from_pem,
             args.decryption_password_from_file.as_deref(),
             args.assert_key_type.clone(),
@@ -120,7 +160,7 @@ pub(crate) async fn exec(ctx: &Context, args: &ImportArgs) -> Result<(), anyhow:
         let phrase = read_to_string(path).context(ReadSeedFileSnafu)?;
         import_from_seed_phrase(
             ctx,
-            &args.name,
+            name,
             &phrase,
             args.seed_curve.clone(),
             format,
@@ -135,7 +175,7 @@ pub(crate) async fn exec(ctx: &Context, args: &ImportArgs) -> Result<(), anyhow:
             .context(ReadSeedPhraseFromTerminalSnafu)?;
         import_from_seed_phrase(
             ctx,
-            &args.name,
+            name,
             &phrase,
             args.seed_curve.clone(),
             format,
@@ -146,7 +186,7 @@ pub(crate) async fn exec(ctx: &Context, args: &ImportArgs) -> Result<(), anyhow:
         unreachable!();
     }
 
-    info!("Identity `{}` created", args.name);
+    info!("Identity `{name}` created");
 
     if matches!(args.storage, StorageMode::Plaintext) {
         warn!(
@@ -170,7 +210,24 @@ async fn import_from_pem(
     // - if SEC1, the key algorithm can be embedded, separate, or missing
     // - if PKCS#8, the key may or may not be encrypted
     let pem = read_to_string(path).context(ReadFileSnafu)?;
-    let sections = pem::parse_many(&pem).context(BadPemFileSnafu { path })?;
+    let key = parse_pem_key(&pem, path, decryption_password_file, known_key_type)?;
+
+    ctx.dirs
+        .identity()?
+        .with_write(async move |dirs| create_identity(dirs, name, key, format, delegation))
+        .await??;
+
+    Ok(())
+}
+
+/// Parses the private key in `pem`, read from `path`.
+fn parse_pem_key(
+    pem: &str,
+    path: &Path,
+    decryption_password_file: Option<&Path>,
+    known_key_type: Option<IdentityKeyAlgorithm>,
+) -> Result<IdentityKey, LoadKeyError> {
+    let sections = pem::parse_many(pem).context(BadPemFileSnafu { path })?;
     let section = match sections
         .iter()
         .filter(|s| {
@@ -215,12 +272,7 @@ async fn import_from_pem(
         _ => unreachable!(),
     };
 
-    ctx.dirs
-        .identity()?
-        .with_write(async move |dirs| create_identity(dirs, name, key, format, delegation))
-        .await??;
-
-    Ok(())
+    Ok(key)
 }
 
 fn import_pkcs8(
@@ -425,6 +477,142 @@ async fn import_from_seed_phrase(
     Ok(())
 }
 
+/// Imports dfx identity `name`, or every dfx identity if `name` is `None`.
+/// When importing every identity, those whose name is taken or that are held in an HSM
+/// are skipped, and dfx's default identity becomes the default if none was chosen.
+async fn import_from_dfx(
+    ctx: &Context,
+    name: Option<&str>,
+    password_file: Option<&Path>,
+    known_key_type: Option<IdentityKeyAlgorithm>,
+    format: CreateFormat,
+    delegation: Option<&DelegationChain>,
+) -> Result<(), ImportDfxError> {
+    let store = DfxIdentityStore::locate()?;
+    let dirs = ctx.dirs.identity()?;
+
+    let Some(name) = name else {
+        let existing = dirs
+            .with_read(async |dirs| IdentityList::load_from(dirs))
+            .await??;
+        let mut imported = Vec::new();
+        for name in store.identities()? {
+            if existing.identities.contains_key(&name) {
+                warn!("Skipping dfx identity `{name}`: an identity with that name already exists");
+                continue;
+            }
+            let key = match read_dfx_key(&store, &name, password_file, known_key_type.clone()) {
+                Ok(key) => key,
+                Err(err @ ImportDfxError::DfxHsmIdentity { .. }) => {
+                    warn!("Skipping {err}");
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            let format = format.clone();
+            dirs.with_write(async |dirs| create_identity(dirs, &name, key, format, None))
+                .await??;
+            info!("Imported dfx identity `{name}`");
+            imported.push(name);
+        }
+
+        if let Some(dfx_default) = store.default_identity()?
+            && imported.contains(&dfx_default)
+        {
+            dirs.with_write(async |dirs| -> Result<(), ImportDfxError> {
+                let mut defaults = IdentityDefaults::load_from(dirs.read())?;
+                if defaults.default == "anonymous" {
+                    defaults.default = dfx_default.clone();
+                    defaults.write_to(dirs)?;
+                    info!("Set default identity to `{dfx_default}`");
+                }
+                Ok(())
+            })
+            .await??;
+        }
+
+        let noun = if imported.len() == 1 {
+            "identity"
+        } else {
+            "identities"
+        };
+        info!("Imported {} {noun} from dfx", imported.len());
+        return Ok(());
+    };
+
+    let key = read_dfx_key(&store, name, password_file, known_key_type)?;
+    dirs.with_write(async |dirs| create_identity(dirs, name, key, format, delegation))
+        .await??;
+    info!("Identity `{name}` created from dfx identity `{name}`");
+
+    Ok(())
+}
+
+fn read_dfx_key(
+    store: &DfxIdentityStore,
+    name: &str,
+    password_file: Option<&Path>,
+    known_key_type: Option<IdentityKeyAlgorithm>,
+) -> Result<IdentityKey, ImportDfxError> {
+    let key = store.read_key(name, || match password_file {
+        Some(path) => read_to_string(path)
+            .map(|s| s.trim().to_string())
+            .map_err(|e| e.to_string()),
+        None => Password::new()
+            .with_prompt(format!("Enter the dfx password for identity `{name}`"))
+            .interact()
+            .map_err(|e| e.to_string()),
+    })?;
+    match key {
+        DfxKey::Pem { pem, origin } => Ok(parse_pem_key(
+            &pem,
+            Path::new(&origin),
+            None,
+            known_key_type,
+        )?),
+        DfxKey::Hsm { module, key_id } => DfxHsmIdentitySnafu {
+            name,
+            module,
+            key_id,
+        }
+        .fail(),
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub(crate) enum ImportDfxError {
+    #[snafu(transparent)]
+    LocateDfx { source: LocateDfxError },
+
+    #[snafu(transparent)]
+    ReadDfxIdentity { source: ReadDfxIdentityError },
+
+    #[snafu(transparent)]
+    LoadKey { source: LoadKeyError },
+
+    #[snafu(display(
+        "dfx identity `{name}` is held in an HSM; link it with \
+        `icp identity link hsm {name} --pkcs11-module {module} --key-id {key_id}`"
+    ))]
+    DfxHsmIdentity {
+        name: String,
+        module: PathBuf,
+        key_id: String,
+    },
+
+    #[snafu(transparent)]
+    CreateIdentity { source: CreateIdentityError },
+
+    #[snafu(transparent)]
+    LoadIdentityManifest { source: LoadIdentityManifestError },
+
+    #[snafu(transparent)]
+    WriteIdentityManifest { source: WriteIdentityManifestError },
+
+    #[snafu(transparent)]
+    LockIdentityDir { source: icp::fs::lock::LockError },
+}
+
 #[derive(Debug, Snafu)]
 pub(crate) enum LoadKeyError {
     #[snafu(display("unknown PEM formats: expected {}; found {} in file `{path}`", expected.join(", "), found.join(", ")))]
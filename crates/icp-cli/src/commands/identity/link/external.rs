@@ -0,0 +1,79 @@
+use clap::Args;
+use icp::{
+    context::Context,
+    identity::{
+        key::link_external_identity,
+        manifest::{IdentityKeyAlgorithm, IdentityList},
+    },
+};
+use snafu::{ResultExt, Snafu, ensure};
+use tracing::info;
+
+/// Link a key held by an external signer to a new identity
+///
+/// The signer is an executable that `icp` runs for each signature, passing a
+/// JSON request on stdin and reading a JSON response from stdout. See the
+/// identities guide for the protocol.
+#[derive(Debug, Args)]
+pub(crate) struct ExternalArgs {
+    /// Name for the linked identity
+    name: String,
+
+    /// The signer command, with its arguments (e.g. "kms-signer --key-id prod")
+    #[arg(long)]
+    command: String,
+
+    /// The algorithm of the signer's key
+    #[arg(long, value_enum, default_value_t = IdentityKeyAlgorithm::Secp256k1)]
+    algorithm: IdentityKeyAlgorithm,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ExternalArgs) -> Result<(), ExternalError> {
+    ctx.dirs
+        .identity()?
+        .with_read(async |dirs| -> Result<(), ExternalError> {
+            let list = IdentityList::load_from(dirs).context(LoadIdentityListSnafu)?;
+            ensure!(
+                !list.identities.contains_key(&args.name),
+                NameTakenSnafu { name: &args.name }
+            );
+            Ok(())
+        })
+        .await??;
+
+    ctx.dirs
+        .identity()?
+        .with_write(async |dirs| {
+            link_external_identity(
+                dirs,
+                &args.name,
+                args.command.clone(),
+                args.algorithm.clone(),
+            )
+        })
+        .await?
+        .context(LinkExternalSnafu)?;
+
+    info!("Identity `{}` linked to external signer", args.name);
+
+    Ok(())
+}
+
+#[derive(Debug, Snafu)]
+pub(crate) enum ExternalError {
+    #[snafu(display("identity `{name}` already exists"))]
+    NameTaken { name: String },
+
+    #[snafu(display("failed to load identity list"))]
+    LoadIdentityList {
+        source: icp::identity::manifest::LoadIdentityManifestError,
+    },
+
+    #[snafu(transparent)]
+    LockIdentityDir { source: icp::fs::lock::LockError },
+
+    #[snafu(display("failed to link external identity"))]
+    LinkExternal {
+        source: icp::identity::key::LinkExternalIdentityError,
+    },
+}
@@ -1,11 +1,15 @@
 use clap::Subcommand;
 
+pub(crate) mod external;
 pub(crate) mod hsm;
+pub(crate) mod ssh_agent;
 pub(crate) mod web;
 
 /// Link an external key to a new identity
 #[derive(Debug, Subcommand)]
 pub(crate) enum Command {
+    External(external::ExternalArgs),
     Hsm(hsm::HsmArgs),
+    SshAgent(ssh_agent::SshAgentArgs),
     Web(web::WebArgs),
 }
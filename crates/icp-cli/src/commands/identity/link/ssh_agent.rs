@@ -0,0 +1,64 @@
+use clap::Args;
+use icp::{
+    context::Context,
+    identity::{key::link_ssh_agent_identity, manifest::IdentityList},
+};
+use snafu::{ResultExt, Snafu, ensure};
+use tracing::info;
+
+/// Link an Ed25519 key held by an ssh-agent to a new identity
+///
+/// The agent is reached through $SSH_AUTH_SOCK, both now and whenever the
+/// identity signs. Only the key's fingerprint and principal are stored.
+#[derive(Debug, Args)]
+pub(crate) struct SshAgentArgs {
+    /// Name for the linked identity
+    name: String,
+
+    /// The key to link, by fingerprint (as shown by `ssh-add -l`) or comment
+    #[arg(long)]
+    key: String,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &SshAgentArgs) -> Result<(), SshAgentError> {
+    ctx.dirs
+        .identity()?
+        .with_read(async |dirs| -> Result<(), SshAgentError> {
+            let list = IdentityList::load_from(dirs).context(LoadIdentityListSnafu)?;
+            ensure!(
+                !list.identities.contains_key(&args.name),
+                NameTakenSnafu { name: &args.name }
+            );
+            Ok(())
+        })
+        .await??;
+
+    ctx.dirs
+        .identity()?
+        .with_write(async |dirs| link_ssh_agent_identity(dirs, &args.name, &args.key))
+        .await?
+        .context(LinkSshAgentSnafu)?;
+
+    info!("Identity `{}` linked to ssh-agent key", args.name);
+
+    Ok(())
+}
+
+#[derive(Debug, Snafu)]
+pub(crate) enum SshAgentError {
+    #[snafu(display("identity `{name}` already exists"))]
+    NameTaken { name: String },
+
+    #[snafu(display("failed to load identity list"))]
+    LoadIdentityList {
+        source: icp::identity::manifest::LoadIdentityManifestError,
+    },
+
+    #[snafu(transparent)]
+    LockIdentityDir { source: icp::fs::lock::LockError },
+
+    #[snafu(display("failed to link ssh-agent identity"))]
+    LinkSshAgent {
+        source: icp::identity::key::LinkSshAgentIdentityError,
+    },
+}
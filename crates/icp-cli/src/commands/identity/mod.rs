@@ -1,6 +1,12 @@
+use anyhow::Context as _;
 use clap::{Subcommand, ValueEnum};
+use dialoguer::Password;
+use elliptic_curve::zeroize::Zeroizing;
+use icp::{identity::key::validate_password, prelude::*};
 
 pub(crate) mod account_id;
+pub(crate) mod backup;
+pub(crate) mod change_password;
 pub(crate) mod default;
 pub(crate) mod delegation;
 pub(crate) mod delete;
@@ -12,11 +18,16 @@ pub(crate) mod new;
 pub(crate) mod principal;
 pub(crate) mod reauth;
 pub(crate) mod rename;
+pub(crate) mod restore;
+pub(crate) mod rotate;
+pub(crate) mod storage;
 
 /// Manage your identities
 #[derive(Debug, Subcommand)]
 pub(crate) enum Command {
     AccountId(account_id::AccountIdArgs),
+    Backup(backup::BackupArgs),
+    ChangePassword(change_password::ChangePasswordArgs),
     Default(default::DefaultArgs),
     #[command(subcommand)]
     Delegation(delegation::Command),
@@ -30,6 +41,9 @@ pub(crate) enum Command {
     Principal(principal::PrincipalArgs),
     Reauth(reauth::ReauthArgs),
     Rename(rename::RenameArgs),
+    Restore(restore::RestoreArgs),
+    Rotate(rotate::RotateArgs),
+    Storage(storage::StorageArgs),
 }
 
 #[derive(Debug, Clone, ValueEnum, Default)]
@@ -39,3 +53,40 @@ enum StorageMode {
     Keyring,
     Password,
 }
+
+/// Reads the current password of identity `name` from `file`, or prompts for it.
+fn current_password(name: &str, file: Option<&Path>) -> Result<String, String> {
+    if let Some(path) = file {
+        icp::fs::read_to_string(path)
+            .context("failed to read password file")
+            .map(|s| s.trim().to_string())
+            .map_err(|e| e.to_string())
+    } else {
+        Password::new()
+            .with_prompt(format!("Enter the current password for identity `{name}`"))
+            .interact()
+            .context("failed to read password from terminal")
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Reads a new password from `file`, or prompts for it twice with `prompt`.
+fn read_new_password(
+    file: Option<&Path>,
+    prompt: &str,
+) -> Result<Zeroizing<String>, anyhow::Error> {
+    let password = if let Some(path) = file {
+        icp::fs::read_to_string(path)
+            .context("failed to read new password file")?
+            .trim()
+            .to_string()
+    } else {
+        Password::new()
+            .with_prompt(prompt)
+            .with_confirmation("Confirm password", "Passwords do not match")
+            .interact()
+            .context("failed to read password from terminal")?
+    };
+    validate_password(&password).map_err(anyhow::Error::msg)?;
+    Ok(Zeroizing::new(password))
+}
@@ -0,0 +1,61 @@
+use anyhow::Context as _;
+use clap::{Args, ValueHint};
+use dialoguer::Password;
+use icp::context::Context;
+use icp::identity::backup::{OnConflict, RestoreOutcome, restore_identities};
+use icp::prelude::*;
+use tracing::info;
+
+/// Restore identities from a file written by `icp identity backup`
+///
+/// Identities are added alongside the existing ones. If no default identity is
+/// set, the backup's default becomes the default.
+#[derive(Debug, Args)]
+pub(crate) struct RestoreArgs {
+    /// The backup file
+    #[arg(value_hint = ValueHint::FilePath)]
+    file: PathBuf,
+
+    /// Read the backup password from a file instead of prompting
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    password_file: Option<PathBuf>,
+
+    /// What to do with an identity whose name is already taken
+    #[arg(long, value_enum, default_value_t)]
+    on_conflict: OnConflict,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &RestoreArgs) -> Result<(), anyhow::Error> {
+    let archive = icp::fs::read_to_string(&args.file).context("failed to read the backup")?;
+    let password = if let Some(path) = &args.password_file {
+        icp::fs::read_to_string(path)
+            .context("failed to read password file")?
+            .trim()
+            .to_string()
+    } else {
+        Password::new()
+            .with_prompt("Enter the password of the backup")
+            .interact()
+            .context("failed to read password from terminal")?
+    };
+
+    let outcomes = ctx
+        .dirs
+        .identity()?
+        .with_write(async |dirs| restore_identities(dirs, &archive, &password, args.on_conflict))
+        .await??;
+
+    for outcome in outcomes {
+        match outcome {
+            RestoreOutcome::Restored { name } => info!("Restored identity `{name}`"),
+            RestoreOutcome::Renamed { name, to } => {
+                info!("Restored identity `{name}` as `{to}`, as the name is taken")
+            }
+            RestoreOutcome::Skipped { name } => {
+                info!("Skipped identity `{name}`, as the name is taken")
+            }
+        }
+    }
+
+    Ok(())
+}
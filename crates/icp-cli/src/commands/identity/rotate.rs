@@ -0,0 +1,84 @@
+use anyhow::{Context as _, anyhow, bail};
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+use icp::context::{Context, EnvironmentSelection};
+use icp::identity::IdentitySelection;
+use tracing::info;
+
+use crate::{
+    operations::rotate::{RotationProgress, rotate_controller},
+    options::EnvironmentOpt,
+};
+
+/// Replace an identity as controller of the canisters in an environment
+///
+/// For each canister in the environment that OLD controls, adds the --to
+/// identity as a controller, checks that it can call canister_status, then
+/// removes OLD. Progress is recorded in the project; if the rotation is
+/// interrupted, run the same command again to continue it.
+#[derive(Debug, Args)]
+pub(crate) struct RotateArgs {
+    /// The identity to remove from the controllers
+    #[arg(add = ArgValueCandidates::new(crate::complete::identity_names))]
+    old: String,
+
+    /// The identity to make a controller in its place
+    #[arg(long, add = ArgValueCandidates::new(crate::complete::identity_names))]
+    to: String,
+
+    #[command(flatten)]
+    environment: EnvironmentOpt,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &RotateArgs) -> Result<(), anyhow::Error> {
+    let environment: EnvironmentSelection = args.environment.clone().into();
+    let p = ctx.project.load().await?;
+    let env = ctx.get_environment(&environment).await?;
+
+    let old_agent = ctx
+        .get_agent_for_env(&IdentitySelection::Named(args.old.clone()), &environment)
+        .await?;
+    let new_agent = ctx
+        .get_agent_for_env(&IdentitySelection::Named(args.to.clone()), &environment)
+        .await?;
+    let old = old_agent.get_principal().map_err(|e| anyhow!(e))?;
+    let new = new_agent.get_principal().map_err(|e| anyhow!(e))?;
+    if old == new {
+        bail!(
+            "identities `{}` and `{}` have the same principal {old}",
+            args.old,
+            args.to
+        );
+    }
+
+    let ids = ctx.ids_by_environment(&environment).await?;
+    let path = RotationProgress::path(&p.data_dir(), &env.name);
+    let mut progress = RotationProgress::load_or_start(&path, old, new)?;
+
+    let mut rotated = 0;
+    for name in env.canisters.keys() {
+        // Canisters that were never created have no controllers
+        let Some(cid) = ids.get(name) else {
+            continue;
+        };
+
+        let done = rotate_controller(&old_agent, &new_agent, name, *cid, &mut progress, &path)
+            .await
+            .context("the rotation was interrupted; run the command again to continue it")?;
+        if done {
+            info!("Rotated the controller of canister '{name}' ({cid})");
+            rotated += 1;
+        }
+    }
+
+    if path.exists() {
+        icp::fs::remove_file(&path)?;
+    }
+
+    info!(
+        "Replaced `{}` with `{}` as controller of {rotated} canister(s) in environment '{}'",
+        args.old, args.to, env.name
+    );
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use clap::{Args, ValueHint};
+use clap_complete::ArgValueCandidates;
+use icp::context::Context;
+use icp::identity::key::{CreateFormat, change_identity_storage};
+use icp::prelude::*;
+use tracing::{info, warn};
+
+use crate::commands::identity::{StorageMode, current_password, read_new_password};
+
+/// Change where the private key of an identity is stored
+///
+/// The key is re-stored in place, keeping the identity's name and principal.
+/// This covers identities created with `icp identity new` or `import` as well
+/// as the session keys of delegation-based identities.
+#[derive(Debug, Args)]
+pub(crate) struct StorageArgs {
+    /// Name of the identity
+    #[arg(add = ArgValueCandidates::new(crate::complete::identity_names))]
+    name: String,
+
+    /// Where to store the private key from now on
+    #[arg(long, value_enum)]
+    to: StorageMode,
+
+    /// Read the current password from a file instead of prompting (only required for identities stored with a password)
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    password_file: Option<PathBuf>,
+
+    /// Read the new password from a file instead of prompting (for --to password)
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    new_password_file: Option<PathBuf>,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &StorageArgs) -> Result<(), anyhow::Error> {
+    let format = match args.to {
+        StorageMode::Plaintext => CreateFormat::Plaintext,
+        StorageMode::Keyring => CreateFormat::Keyring,
+        StorageMode::Password => CreateFormat::Pbes2 {
+            password: read_new_password(
+                args.new_password_file.as_deref(),
+                "Enter password to encrypt identity",
+            )?,
+        },
+    };
+
+    ctx.dirs
+        .identity()?
+        .with_write(async |dirs| {
+            change_identity_storage(dirs, &args.name, format, || {
+                current_password(&args.name, args.password_file.as_deref())
+            })
+        })
+        .await??;
+
+    if matches!(args.to, StorageMode::Plaintext) {
+        warn!(
+            "This identity is now stored in plaintext and is not secure. Do not use it for anything of significant value."
+        );
+    }
+    info!("Changed the storage of identity `{}`", args.name);
+
+    Ok(())
+}
@@ -1,9 +1,12 @@
 use clap::Subcommand;
 
+pub(crate) mod account;
 pub(crate) mod args;
 pub(crate) mod build;
+pub(crate) mod cache;
 pub(crate) mod canister;
 pub(crate) mod completions;
+pub(crate) mod contacts;
 pub(crate) mod cycles;
 pub(crate) mod deploy;
 pub(crate) mod environment;
@@ -12,18 +15,26 @@ pub(crate) mod network;
 pub(crate) mod new;
 pub(crate) mod parsers;
 pub(crate) mod project;
+pub(crate) mod recipe;
 pub(crate) mod settings;
 pub(crate) mod sync;
+pub(crate) mod telemetry;
 pub(crate) mod token;
 
 #[derive(Subcommand, Debug)]
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum Command {
+    #[command(subcommand)]
+    Account(account::Command),
     Build(build::BuildArgs),
     #[command(subcommand)]
+    Cache(cache::Command),
+    #[command(subcommand)]
     Canister(canister::Command),
     Completions(completions::CompletionsArgs),
     #[command(subcommand)]
+    Contacts(contacts::Command),
+    #[command(subcommand)]
     Cycles(cycles::Command),
     Deploy(deploy::DeployArgs),
     #[command(subcommand)]
@@ -35,7 +46,11 @@ pub(crate) enum Command {
     New(new::IcpGenerateArgs),
     #[command(subcommand)]
     Project(project::Command),
+    #[command(subcommand)]
+    Recipe(recipe::Command),
     Settings(settings::SettingsArgs),
     Sync(sync::SyncArgs),
+    #[command(subcommand)]
+    Telemetry(telemetry::Command),
     Token(token::Command),
 }
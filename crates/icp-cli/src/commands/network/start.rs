@@ -1,5 +1,3 @@
-use std::sync::{Arc, OnceLock};
-
 use anyhow::{Context as _, bail};
 use candid::Principal;
 use clap::Args;
@@ -7,22 +5,12 @@ use icp::network::ManagedMode;
 use icp::prelude::*;
 use icp::{
     identity::manifest::IdentityList,
-    network::{
-        Configuration,
-        managed::{
-            cache::{
-                check_launcher_update_available, download_launcher_version,
-                get_cached_launcher_version_if_fresh,
-            },
-            run::stop_network,
-        },
-        run_network,
-    },
+    network::{Configuration, managed::run::stop_network, run_network},
     settings::Settings,
 };
-use tracing::{debug, info, warn};
+use tracing::{debug, warn};
 
-use crate::progress::{ProgressManager, ProgressManagerSettings};
+use crate::operations::remote::resolve_launcher;
 
 use super::args::NetworkOrEnvironmentArgs;
 use icp::context::Context;
@@ -167,55 +155,12 @@ pub(crate) async fn exec(ctx: &Context, args: &StartArgs) -> Result<(), anyhow::
     let autocontainerize = cfg!(windows) || settings.autocontainerize;
 
     // Acquire network launcher path, downloading it if necessary
-    let debug = ctx.debug;
     let network_launcher_path = if let Ok(var) = std::env::var("ICP_CLI_NETWORK_LAUNCHER_PATH") {
         // The user is overriding the launcher
         debug!("Network launcher path overridden by ICP_CLI_NETWORK_LAUNCHER_PATH={var}");
         Some(PathBuf::from(var))
     } else if !autocontainerize && let ManagedMode::Launcher(managed_cfg) = &cfg.mode {
-        let version = managed_cfg.version.as_deref().unwrap_or("latest");
-        let client = reqwest::Client::new();
-        ctx.dirs
-            .package_cache()?
-            .with_write(async |pkg| {
-                // Resolve the declared version to a real version, if it's fresh
-                // A fresh version is one that is either specified exactly, or was last updated since icp-cli was updated
-                if let Some((resolved, path)) =
-                    get_cached_launcher_version_if_fresh(pkg.read(), version)?
-                {
-                    // The version has already been downloaded. Use it, but first, check for updates and nag if so
-                    if let Some(update) = check_launcher_update_available(pkg, &resolved, &client).await {
-                        info!("A newer network launcher version is available: {update} (current: {resolved}). Run `icp network update` to update.");
-                    }
-                    anyhow::Ok(Some(path))
-                } else {
-                    // The version is not fresh or not cached, download it
-                    debug!("Downloading icp-cli-network-launcher version `{version}`");
-                    let progress_manager =
-                        ProgressManager::new(ProgressManagerSettings { hidden: debug });
-                    let pb = progress_manager.create_independent_progress_bar();
-                    pb.set_message(format!("Downloading icp-cli-network-launcher {version}..."));
-                    let version_slot: Arc<OnceLock<String>> = Arc::new(OnceLock::new());
-                    let version_capture = version_slot.clone();
-                    let path = ProgressManager::execute_with_progress(
-                        &pb,
-                        async {
-                            let (ver, path) =
-                                download_launcher_version(pkg, version, &client).await?;
-                            let _ = version_capture.set(ver);
-                            anyhow::Ok(path)
-                        },
-                        move || {
-                            let ver = version_slot.get().map(String::as_str).unwrap();
-                            format!("Downloaded icp-cli-network-launcher {ver}")
-                        },
-                        |err| format!("Failed to download icp-cli-network-launcher: {err}"),
-                    )
-                    .await?;
-                    Ok(Some(path))
-                }
-            })
-            .await??
+        Some(resolve_launcher(ctx, &network.name, managed_cfg).await?)
     } else {
         None
     };
@@ -10,6 +10,10 @@ use crate::progress::{ProgressManager, ProgressManagerSettings};
 pub struct UpdateArgs {}
 
 pub async fn exec(ctx: &Context, _args: &UpdateArgs) -> Result<(), anyhow::Error> {
+    if ctx.offline {
+        anyhow::bail!("icp-cli-network-launcher cannot be updated in offline mode");
+    }
+
     let progress_manager = ProgressManager::new(ProgressManagerSettings { hidden: ctx.debug });
     let pb = progress_manager.create_independent_progress_bar();
     pb.set_message("Downloading latest icp-cli-network-launcher...".to_string());
@@ -1,4 +1,26 @@
+use candid::Principal;
+use ic_ledger_types::Subaccount;
+
+/// Parses a subaccount, given as:
+/// - hex, left-padded to 32 bytes
+/// - `index:<n>`, the subaccount numbered `n` (big-endian, like the hex form)
+/// - `principal:<principal>`, the subaccount derived from a principal, as the
+///   cycles minting canister uses to top up canisters
 pub(crate) fn parse_subaccount(input: &str) -> Result<[u8; 32], String> {
+    if let Some(index) = input.strip_prefix("index:") {
+        let index = index
+            .parse::<u64>()
+            .map_err(|e| format!("Invalid subaccount index '{index}': {e}"))?;
+        let mut bytes = [0; 32];
+        bytes[24..].copy_from_slice(&index.to_be_bytes());
+        return Ok(bytes);
+    }
+    if let Some(principal) = input.strip_prefix("principal:") {
+        let principal = Principal::from_text(principal)
+            .map_err(|e| format!("Invalid subaccount principal '{principal}': {e}"))?;
+        return Ok(Subaccount::from(principal).0);
+    }
+
     if input.len() > 64 {
         return Err(format!(
             "Subaccount cannot be longer than 64 hex characters: '{}'",
@@ -12,3 +34,27 @@ pub(crate) fn parse_subaccount(input: &str) -> Result<[u8; 32], String> {
         .try_into()
         .expect("Hex string should be 32 bytes after padding"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subaccount_forms() {
+        let mut five = [0; 32];
+        five[31] = 5;
+        assert_eq!(parse_subaccount("5"), Ok(five));
+        assert_eq!(parse_subaccount("index:5"), Ok(five));
+        assert_eq!(parse_subaccount(&"0".repeat(63)), Ok([0; 32]));
+
+        let principal = parse_subaccount("principal:aaaaa-aa").unwrap();
+        assert_eq!(principal, [0; 32]);
+        let principal = parse_subaccount("principal:rrkah-fqaaa-aaaaa-aaaaq-cai").unwrap();
+        assert_eq!(&principal[..11], &[10, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1]);
+
+        assert!(parse_subaccount("index:-1").is_err());
+        assert!(parse_subaccount("principal:nope").is_err());
+        assert!(parse_subaccount("xyz").is_err());
+        assert!(parse_subaccount(&"1".repeat(65)).is_err());
+    }
+}
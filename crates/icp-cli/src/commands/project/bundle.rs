@@ -1,9 +1,9 @@
-use anyhow::Context as _;
+use anyhow::{Context as _, bail};
 use clap::{Args, ValueHint};
 use icp::context::Context;
 use icp::prelude::*;
 
-use crate::operations::bundle::create_bundle;
+use crate::operations::{bundle::create_bundle, remote::resolve_canister_downloads};
 
 /// Bundle a project into a self-contained deployable archive.
 ///
@@ -28,7 +28,48 @@ pub(crate) struct BundleArgs {
 pub(crate) async fn exec(ctx: &Context, args: &BundleArgs) -> Result<(), anyhow::Error> {
     let project = ctx.project.load().await.context("failed to load project")?;
 
-    let canisters: Vec<_> = project.canisters.into_values().collect();
+    // Variables are resolved into the bundled manifests
+    if !project.secret_values.is_empty() {
+        bail!(
+            "the project references secret variables (`${{secret:NAME}}`), whose values \
+             a bundle would contain; bundle the project without them"
+        );
+    }
+
+    // Recipes render per environment, so take the steps rendered for the one
+    // the bundle is built for
+    let env = project
+        .environments
+        .get(&args.environment)
+        .with_context(|| {
+            format!(
+                "project does not contain an environment named '{}'",
+                args.environment
+            )
+        })?;
+    let canisters = project
+        .canisters
+        .iter()
+        .map(|(key, (path, canister))| {
+            let (_, rendered) = env.canisters.get(key).with_context(|| {
+                format!(
+                    "canister '{}' is not in environment '{}'; a bundle holds every \
+                     canister of the project, so bundle it for an environment that has them all",
+                    canister.name, args.environment
+                )
+            })?;
+            let mut canister = canister.clone();
+            canister.build = rendered.build.clone();
+            canister.sync = rendered.sync.clone();
+            Ok((path.clone(), canister))
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    // Offline, report everything missing from the package cache at once
+    if ctx.offline {
+        resolve_canister_downloads(ctx, canisters.iter().map(|(path, c)| (path.as_path(), c)))
+            .await?;
+    }
 
     create_bundle(
         &project.dir,
@@ -37,6 +78,8 @@ pub(crate) async fn exec(ctx: &Context, args: &BundleArgs) -> Result<(), anyhow:
         ctx.builder.clone(),
         ctx.artifacts.clone(),
         &ctx.dirs.package_cache()?,
+        ctx.lockfile.as_ref(),
+        ctx.offline,
         ctx.debug,
         &args.output,
     )
@@ -0,0 +1,70 @@
+use anyhow::{Context as _, bail};
+use clap::Args;
+use icp::{
+    context::Context,
+    fs::{json, write_string},
+    manifest::PROJECT_MANIFEST,
+    prelude::*,
+};
+use tracing::{info, warn};
+
+use crate::operations::import_dfx::{DfxCanisterIds, DfxJson, convert_dfx_json};
+
+/// Convert the dfx project in the current directory into an icp-cli project.
+///
+/// Writes an `icp.yaml` next to `dfx.json` with the project's canisters, networks and
+/// an environment for each persistent network. The canister IDs in `canister_ids.json`
+/// are recorded for the matching environments (`ic` for mainnet), so existing canisters
+/// keep being managed. IDs on local networks are not imported.
+#[derive(Args, Debug)]
+pub(crate) struct ImportDfxArgs;
+
+pub(crate) async fn exec(ctx: &Context, _: &ImportDfxArgs) -> Result<(), anyhow::Error> {
+    let dir = PathBuf::try_from(std::env::current_dir()?)?;
+    let dfx_json = dir.join("dfx.json");
+    if !dfx_json.exists() {
+        bail!("no dfx.json found in `{dir}`");
+    }
+    let icp_yaml = dir.join(PROJECT_MANIFEST);
+    if icp_yaml.exists() {
+        bail!("`{icp_yaml}` already exists");
+    }
+
+    let dfx: DfxJson = json::load(&dfx_json)?;
+    let converted = convert_dfx_json(dfx, dir.join("package.json").exists());
+    for warning in &converted.warnings {
+        warn!("{warning}");
+    }
+    write_string(&icp_yaml, &converted.yaml)?;
+    info!("Wrote `{icp_yaml}`");
+
+    let ids_path = dir.join("canister_ids.json");
+    if !ids_path.exists() {
+        return Ok(());
+    }
+    let ids: DfxCanisterIds = json::load(&ids_path)?;
+    let mut imported = 0;
+    for (canister, by_network) in ids {
+        if !converted.canisters.contains(&canister) {
+            warn!("Skipping the IDs of canister `{canister}`, which was not converted");
+            continue;
+        }
+        for (network, id) in by_network {
+            if !converted.environments.contains(&network) {
+                warn!(
+                    "Skipping the ID of canister `{canister}` on network `{network}`, which has no environment"
+                );
+                continue;
+            }
+            ctx.ids
+                .register(false, &network, &canister, id)
+                .with_context(|| {
+                    format!("failed to record the ID of canister `{canister}` in environment `{network}`")
+                })?;
+            imported += 1;
+        }
+    }
+    info!("Imported {imported} canister IDs from `{ids_path}`");
+
+    Ok(())
+}
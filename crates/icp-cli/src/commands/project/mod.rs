@@ -1,12 +1,18 @@
 use clap::Subcommand;
 
 pub(crate) mod bundle;
+pub(crate) mod import_dfx;
+pub(crate) mod prefetch;
 pub(crate) mod show;
+pub(crate) mod update_lock;
 
 /// Manage the current project
 #[derive(Debug, Subcommand)]
 pub(crate) enum Command {
     Show(show::ShowArgs),
+    ImportDfx(import_dfx::ImportDfxArgs),
+    Prefetch(prefetch::PrefetchArgs),
+    UpdateLock(update_lock::UpdateLockArgs),
     #[command(hide = true)]
     Bundle(bundle::BundleArgs),
 }
@@ -0,0 +1,59 @@
+use anyhow::{Context as _, bail};
+use clap::Args;
+use icp::{
+    context::Context,
+    network::{Configuration, Managed, ManagedMode},
+    settings::Settings,
+};
+
+use crate::operations::remote::{project_canisters, resolve_canister_downloads, resolve_launcher};
+
+/// Download everything the project needs into the package cache, so that it
+/// can be built, deployed and its networks started with `--offline`.
+///
+/// Fetches remote recipes, pre-built wasms, sync plugins and the launchers of
+/// managed networks (unless they run in Docker), recording floating references
+/// in icp.lock as `icp build` and `icp deploy` would.
+#[derive(Args, Debug)]
+pub(crate) struct PrefetchArgs;
+
+pub(crate) async fn exec(ctx: &Context, _: &PrefetchArgs) -> Result<(), anyhow::Error> {
+    if ctx.offline {
+        bail!(
+            "nothing can be prefetched in offline mode; pass `--offline=false` to go online \
+             despite the `offline` setting"
+        );
+    }
+
+    // Loading the project resolves its recipes
+    let p = ctx.project.load().await.context("failed to load project")?;
+
+    resolve_canister_downloads(ctx, project_canisters(&p)).await?;
+
+    // Containerized networks never run the native launcher, and an overridden
+    // one is never downloaded
+    let settings = ctx
+        .dirs
+        .settings()?
+        .with_read(async |dirs| Settings::load_from(dirs))
+        .await??;
+    if cfg!(windows)
+        || settings.autocontainerize
+        || std::env::var_os("ICP_CLI_NETWORK_LAUNCHER_PATH").is_some()
+    {
+        return Ok(());
+    }
+
+    for network in p.networks.values() {
+        if let Configuration::Managed {
+            managed: Managed {
+                mode: ManagedMode::Launcher(cfg),
+            },
+        } = &network.configuration
+        {
+            resolve_launcher(ctx, &network.name, cfg).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -13,6 +13,8 @@ use icp::context::Context;
 ///
 /// - processed recipes
 ///
+/// - resolved `${VAR}` references, with secret ones redacted
+///
 #[derive(Args, Debug)]
 pub(crate) struct ShowArgs;
 
@@ -22,7 +24,11 @@ pub(crate) async fn exec(ctx: &Context, _: &ShowArgs) -> Result<(), anyhow::Erro
     // Load the project manifest, which defines the canisters to be built.
     let p = ctx.project.load().await.context("failed to load project")?;
 
-    let yaml = serde_yaml::to_string(&p).expect("Serializing to yaml failed");
+    // The values of `${secret:NAME}` references are left out
+    let mut doc = serde_yaml::to_value(&p).expect("Serializing to yaml failed");
+    p.redact(&mut doc);
+
+    let yaml = serde_yaml::to_string(&doc).expect("Serializing to yaml failed");
     print!("{yaml}");
 
     Ok(())
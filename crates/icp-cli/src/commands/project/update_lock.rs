@@ -0,0 +1,65 @@
+use anyhow::{Context as _, bail};
+use clap::Args;
+use icp::{
+    context::Context,
+    network::{Configuration, Managed, ManagedMode, managed::cache::get_latest_launcher_version},
+};
+
+use crate::operations::remote::{project_canisters, resolve_canister_downloads};
+
+/// Re-resolve every floating reference and rewrite icp.lock.
+///
+/// Remote recipes, pre-built wasm URLs and sync plugins without a `sha256` in
+/// the manifest are fetched anew, and managed networks without a launcher
+/// `version` are pinned to the latest launcher release. Pins for references the
+/// project no longer uses are dropped.
+#[derive(Args, Debug)]
+pub(crate) struct UpdateLockArgs;
+
+pub(crate) async fn exec(ctx: &Context, _: &UpdateLockArgs) -> Result<(), anyhow::Error> {
+    if ctx.offline {
+        bail!(
+            "icp.lock cannot be updated in offline mode; pass `--offline=false` to go online \
+             despite the `offline` setting"
+        );
+    }
+
+    // Pins recorded from here on replace the lock file; loading the project
+    // resolves its recipes.
+    ctx.lockfile.begin_refresh();
+    let p = ctx.project.load().await.context("failed to load project")?;
+
+    resolve_canister_downloads(ctx, project_canisters(&p)).await?;
+
+    let client = reqwest::Client::new();
+    let mut latest_launcher = None;
+    for network in p.networks.values() {
+        let Configuration::Managed {
+            managed: Managed {
+                mode: ManagedMode::Launcher(cfg),
+            },
+        } = &network.configuration
+        else {
+            continue;
+        };
+        if cfg.version.is_some() {
+            continue;
+        }
+        if latest_launcher.is_none() {
+            latest_launcher = Some(get_latest_launcher_version(&client).await?);
+        }
+        let version = latest_launcher.as_deref().unwrap();
+        ctx.lockfile
+            .update(&mut |lock| lock.pin_launcher(&network.name, version))?;
+    }
+
+    let changes = ctx.lockfile.commit_refresh()?;
+    if changes.is_empty() {
+        println!("icp.lock is up to date");
+    }
+    for change in changes {
+        println!("{change}");
+    }
+
+    Ok(())
+}
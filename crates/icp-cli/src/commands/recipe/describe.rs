@@ -0,0 +1,97 @@
+use std::io::stdout;
+
+use anyhow::Context as _;
+use clap::Args;
+use icp::{
+    canister::recipe::{RecipeSchema, Resolve},
+    context::Context,
+    manifest::{Recipe, RecipeType},
+};
+
+use super::detached_fetcher;
+
+/// Show the configuration a recipe accepts
+///
+/// Lists the keys of the recipe's `configuration`, as declared by the schema in
+/// its template, with their types, defaults and descriptions.
+#[derive(Debug, Args)]
+#[command(after_long_help = "\
+Examples:
+
+    icp recipe describe @dfinity/rust@v3.0.0
+    icp recipe describe file://recipes/my-recipe.hbs
+")]
+pub(crate) struct DescribeArgs {
+    /// The recipe, as it would be written in a manifest's `recipe.type`
+    pub(crate) recipe: RecipeType,
+
+    /// Print the recipe's configuration schema as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &DescribeArgs) -> Result<(), anyhow::Error> {
+    let recipe = Recipe {
+        recipe_type: args.recipe.clone(),
+        configuration: Default::default(),
+        sha256: None,
+    };
+    let registries = ctx.project.registries().await?;
+    let fetched = detached_fetcher(ctx)?
+        .resolve(&recipe, &registries)
+        .await
+        .with_context(|| format!("failed to fetch recipe '{}'", args.recipe))?;
+    let schema = RecipeSchema::from_template(&fetched.template)
+        .with_context(|| format!("recipe '{}' declares an invalid schema", args.recipe))?;
+
+    if args.json {
+        serde_json::to_writer_pretty(
+            stdout(),
+            schema
+                .as_ref()
+                .map(RecipeSchema::as_json)
+                .unwrap_or(&serde_json::Value::Null),
+        )?;
+        println!();
+        return Ok(());
+    }
+
+    let Some(schema) = schema else {
+        println!(
+            "Recipe '{}' does not declare the configuration it accepts",
+            args.recipe
+        );
+        return Ok(());
+    };
+
+    println!("{}", args.recipe);
+    if let Some(description) = schema.description() {
+        println!("\n{description}");
+    }
+
+    let properties = schema.properties();
+    if properties.is_empty() {
+        println!("\nThis recipe takes no configuration");
+        return Ok(());
+    }
+    println!("\nConfiguration:");
+    let width = properties.iter().map(|p| p.name.len()).max().unwrap_or(0);
+    for p in properties {
+        let mut details = vec![];
+        if let Some(ty) = &p.ty {
+            details.push(ty.clone());
+        }
+        if p.required {
+            details.push("required".to_string());
+        }
+        if let Some(default) = &p.default {
+            details.push(format!("default: {default}"));
+        }
+        println!("  {:<width$}  {}", p.name, details.join(", "));
+        if let Some(description) = &p.description {
+            println!("  {:<width$}  {description}", "");
+        }
+    }
+
+    Ok(())
+}
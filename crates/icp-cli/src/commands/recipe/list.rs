@@ -0,0 +1,30 @@
+use clap::Args;
+use icp::context::Context;
+
+use super::{list_recipes, print_recipes};
+
+/// List the recipes published to the known registries
+///
+/// Reads the index of the built-in `dfinity` registry, of those declared in the
+/// user settings and, inside a project, of those declared in `icp.yaml`.
+#[derive(Debug, Args)]
+#[command(after_long_help = "\
+Examples:
+
+    icp recipe list
+    icp recipe list --registry acme
+")]
+pub(crate) struct ListArgs {
+    /// Only list the recipes of this registry
+    #[arg(long)]
+    pub(crate) registry: Option<String>,
+
+    /// Print the recipes as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ListArgs) -> Result<(), anyhow::Error> {
+    let recipes = list_recipes(ctx, args.registry.as_deref()).await?;
+    print_recipes(&recipes, args.json)
+}
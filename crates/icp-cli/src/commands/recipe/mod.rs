@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use anyhow::{Context as _, bail};
+use clap::Subcommand;
+use icp::{
+    canister::recipe::fetch::RecipeFetcher, context::Context, lockfile, registry::IndexedRecipe,
+};
+use serde::Serialize;
+
+pub(crate) mod describe;
+pub(crate) mod list;
+pub(crate) mod outdated;
+pub(crate) mod render;
+pub(crate) mod search;
+
+/// Inspect recipes
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    Describe(describe::DescribeArgs),
+    List(list::ListArgs),
+    Outdated(outdated::OutdatedArgs),
+    Render(render::RenderArgs),
+    Search(search::SearchArgs),
+}
+
+/// A fetcher for recipes named on the command line rather than in a project
+/// manifest. Nothing it resolves is pinned in icp.lock.
+fn detached_fetcher(ctx: &Context) -> Result<RecipeFetcher, anyhow::Error> {
+    Ok(RecipeFetcher {
+        http_client: reqwest::Client::new(),
+        pkg_cache: ctx.dirs.package_cache()?,
+        lockfile: Arc::new(lockfile::Detached::default()),
+        offline: ctx.offline,
+    })
+}
+
+/// A recipe listed in a registry's index.
+#[derive(Serialize)]
+struct ListedRecipe {
+    registry: String,
+
+    #[serde(flatten)]
+    recipe: IndexedRecipe,
+}
+
+impl ListedRecipe {
+    /// The reference to the latest version, or to the package if it has none.
+    fn reference(&self) -> String {
+        let package = format!("@{}/{}", self.registry, self.recipe.name);
+        match self.recipe.versions.last() {
+            Some(version) => format!("{package}@{version}"),
+            None => package,
+        }
+    }
+}
+
+/// The recipes of every registry known here, or of only `registry`.
+async fn list_recipes(
+    ctx: &Context,
+    registry: Option<&str>,
+) -> Result<Vec<ListedRecipe>, anyhow::Error> {
+    if ctx.offline {
+        bail!("registry indexes cannot be fetched in offline mode");
+    }
+    let registries = ctx.project.registries().await?;
+    let selected = match registry {
+        Some(name) => vec![registries.get(name)?],
+        None => registries.iter().collect(),
+    };
+
+    let client = reqwest::Client::new();
+    let mut listed = vec![];
+    for registry in selected {
+        let index = registry
+            .fetch_index(&client)
+            .await
+            .with_context(|| format!("failed to list the recipes of '{}'", registry.name))?;
+        listed.extend(index.recipes.into_iter().map(|recipe| ListedRecipe {
+            registry: registry.name.clone(),
+            recipe,
+        }));
+    }
+    Ok(listed)
+}
+
+fn print_recipes(recipes: &[ListedRecipe], json: bool) -> Result<(), anyhow::Error> {
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), recipes)?;
+        println!();
+        return Ok(());
+    }
+    let references: Vec<_> = recipes.iter().map(ListedRecipe::reference).collect();
+    let width = references.iter().map(String::len).max().unwrap_or(0);
+    for (recipe, reference) in recipes.iter().zip(&references) {
+        match &recipe.recipe.description {
+            Some(description) => println!("{reference:<width$}  {description}"),
+            None => println!("{reference}"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use anyhow::{Context as _, bail};
+use clap::Args;
+use icp::{
+    context::Context,
+    manifest::RecipeType,
+    registry::{RegistryIndex, parse_version, version_requirement},
+};
+use semver::VersionReq;
+use serde::Serialize;
+use tracing::warn;
+
+/// List canisters whose registry recipe has a newer version
+///
+/// Compares the version each canister's registry recipe uses, as written in
+/// the manifest or as its version requirement is pinned in icp.lock, with the
+/// versions its registry publishes. `compatible` is the newest version
+/// satisfying the requirement, or of the same major version as an exact
+/// version; `latest` is the newest stable version.
+#[derive(Debug, Args)]
+pub(crate) struct OutdatedArgs {
+    /// Output command results as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+#[derive(Serialize)]
+struct Outdated {
+    canister: String,
+    recipe: String,
+    current: String,
+    compatible: String,
+    latest: String,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &OutdatedArgs) -> Result<(), anyhow::Error> {
+    if ctx.offline {
+        bail!("registry indexes cannot be fetched in offline mode");
+    }
+    let p = ctx.project.load().await.context("failed to load project")?;
+    let lock = ctx.lockfile.load()?;
+
+    let client = reqwest::Client::new();
+    let mut indexes: HashMap<String, RegistryIndex> = HashMap::new();
+    let mut outdated = vec![];
+    for (cname, (_, canister)) in &p.canisters {
+        let Some(source) = &canister.recipe_source else {
+            continue;
+        };
+        let recipe_type = &source.recipe.recipe_type;
+        let RecipeType::Registry {
+            name: registry,
+            recipe: name,
+            version,
+        } = recipe_type
+        else {
+            continue;
+        };
+
+        let index = match indexes.get(registry) {
+            Some(index) => index,
+            None => {
+                let Ok(r) = p.registries.get(registry) else {
+                    // Declared by a dependency project, not the root
+                    warn!(
+                        "Skipping canister '{cname}': registry '{registry}' is not declared by the project"
+                    );
+                    continue;
+                };
+                let index = r.fetch_index(&client).await.with_context(|| {
+                    format!("failed to read the index of registry '{registry}'")
+                })?;
+                indexes.entry(registry.clone()).or_insert(index)
+            }
+        };
+        let Some(indexed) = index.recipe(name) else {
+            warn!(
+                "Skipping canister '{cname}': registry '{registry}' no longer lists recipe '{name}'"
+            );
+            continue;
+        };
+
+        // What the canister uses, and the requirement newer versions must meet
+        // to be a drop-in update
+        let reference = recipe_type.to_string();
+        let (current, compatible_req) = match version_requirement(version) {
+            Some(req) => {
+                let current = lock
+                    .recipe_version(&reference)
+                    .or_else(|| indexed.latest_matching(&req))
+                    .map(str::to_owned);
+                (current, req)
+            }
+            None => {
+                let Some(v) = parse_version(version) else {
+                    // Not a semver version: nothing to compare
+                    continue;
+                };
+                (Some(version.clone()), VersionReq::parse(&format!("^{v}"))?)
+            }
+        };
+        let Some(current) = current else {
+            continue;
+        };
+        let compatible = indexed.latest_matching(&compatible_req).unwrap_or(&current);
+        let latest = indexed.latest().unwrap_or(compatible);
+
+        let newer = |candidate: &str| parse_version(candidate) > parse_version(&current);
+        if newer(compatible) || newer(latest) {
+            outdated.push(Outdated {
+                canister: cname.clone(),
+                recipe: format!("@{registry}/{name}"),
+                compatible: compatible.to_owned(),
+                latest: latest.to_owned(),
+                current,
+            });
+        }
+    }
+
+    if args.json {
+        serde_json::to_writer(std::io::stdout(), &outdated)?;
+        return Ok(());
+    }
+
+    if outdated.is_empty() {
+        println!("All registry recipes are up to date");
+        return Ok(());
+    }
+    let width = |f: fn(&Outdated) -> &str, header: &str| {
+        outdated
+            .iter()
+            .map(|o| f(o).len())
+            .chain([header.len()])
+            .max()
+            .unwrap_or(0)
+    };
+    let cw = width(|o| &o.canister, "CANISTER");
+    let rw = width(|o| &o.recipe, "RECIPE");
+    let uw = width(|o| &o.current, "CURRENT");
+    let pw = width(|o| &o.compatible, "COMPATIBLE");
+    println!(
+        "{:<cw$}  {:<rw$}  {:<uw$}  {:<pw$}  LATEST",
+        "CANISTER", "RECIPE", "CURRENT", "COMPATIBLE"
+    );
+    for o in &outdated {
+        println!(
+            "{:<cw$}  {:<rw$}  {:<uw$}  {:<pw$}  {}",
+            o.canister, o.recipe, o.current, o.compatible, o.latest
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context as _, anyhow, bail};
+use clap::{ArgGroup, Args};
+use clap_complete::ArgValueCandidates;
+use icp::{
+    canister::{
+        ManifestSettings,
+        recipe::{RecipeContext, RecipeEnvironment, RenderedRecipe, Resolve, render_recipe},
+    },
+    context::{Context, EnvironmentSelection},
+    manifest::{BuildSteps, ManifestInitArgs, Recipe, RecipeType, SyncSteps},
+    prelude::*,
+};
+use serde::Serialize;
+
+use super::detached_fetcher;
+use crate::options::{EnvironmentOpt, arg_struct_change_help};
+
+/// Print what recipes render into
+///
+/// Renders the recipes of the given canisters for an environment and prints
+/// the resulting build and sync steps, plus the settings and init args the
+/// recipe provides as defaults, exactly as `icp build` and `icp deploy` use
+/// them.
+///
+/// With `--template`, renders a local template outside of any project instead,
+/// with the configuration given by `--config`.
+#[derive(Debug, Args)]
+#[command(
+    group(ArgGroup::new("target").required(true).args(["canisters", "all", "template"])),
+    after_long_help = "\
+Examples:
+
+    icp recipe render backend
+    icp recipe render --all --environment ic --json
+    icp recipe render --template ./recipe.hbs --config package=backend --config shrink=true
+"
+)]
+pub(crate) struct RenderArgs {
+    /// The canisters whose recipes to render
+    #[arg(add = ArgValueCandidates::new(crate::complete::canisters))]
+    pub(crate) canisters: Vec<String>,
+
+    /// Render the recipes of every canister in the environment
+    #[arg(long, conflicts_with = "canisters")]
+    pub(crate) all: bool,
+
+    /// Render this recipe template instead of a project's recipes
+    #[arg(long, conflicts_with_all = ["canisters", "all"])]
+    pub(crate) template: Option<PathBuf>,
+
+    /// A configuration value for `--template`, as KEY=VALUE. The value is parsed
+    /// as YAML, so `shrink=true` is a boolean and `size=3` a number.
+    #[arg(long = "config", value_name = "KEY=VALUE", requires = "template", value_parser = parse_config)]
+    pub(crate) config: Vec<(String, serde_yaml::Value)>,
+
+    /// The canister name `--template` is rendered for
+    #[arg(long, default_value = "canister", requires = "template")]
+    pub(crate) canister_name: String,
+
+    /// Print the rendered recipes as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+
+    #[command(flatten)]
+    pub(crate) environment: RenderEnvironmentOpt,
+}
+
+arg_struct_change_help!(
+    EnvironmentOpt => RenderEnvironmentOpt,
+    arg = "environment",
+    help = "Override the environment to render for. By default, the local environment is used, \
+            or none with `--template`"
+);
+
+fn parse_config(v: &str) -> Result<(String, serde_yaml::Value), String> {
+    let (key, value) = v
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{v}`"))?;
+    let value =
+        serde_yaml::from_str(value).unwrap_or_else(|_| serde_yaml::Value::String(value.to_owned()));
+    Ok((key.to_owned(), value))
+}
+
+/// A rendered recipe, leaving out the defaults it does not provide.
+#[derive(Serialize)]
+struct Output {
+    build: BuildSteps,
+    sync: SyncSteps,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    settings: Option<ManifestSettings>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    init_args: Option<ManifestInitArgs>,
+}
+
+impl From<RenderedRecipe> for Output {
+    fn from(r: RenderedRecipe) -> Self {
+        Self {
+            build: r.build,
+            sync: r.sync,
+            settings: (r.settings != ManifestSettings::default()).then_some(r.settings),
+            init_args: r.init_args,
+        }
+    }
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &RenderArgs) -> Result<(), anyhow::Error> {
+    let environment_selection: EnvironmentSelection = args.environment.0.clone().into();
+
+    if let Some(template) = &args.template {
+        let output = render_template(ctx, args, template, &environment_selection).await?;
+        return print(&output, args.json);
+    }
+
+    let env = ctx.get_environment(&environment_selection).await?;
+    let cnames = match args.all {
+        true => env.get_canister_names(),
+        false => args.canisters.clone(),
+    };
+
+    let mut rendered = BTreeMap::new();
+    for name in cnames {
+        let (_, canister) = env.get_canister_info(&name).map_err(|e| anyhow!(e))?;
+        let Some(source) = &canister.recipe_source else {
+            // `--all` skips canisters without a recipe
+            if !args.all {
+                bail!("canister '{name}' does not use a recipe");
+            }
+            continue;
+        };
+        let recipe_context = RecipeContext {
+            canister_name: canister.name.clone(),
+            project_root: source.project_root.clone(),
+            environment: Some(RecipeEnvironment::new(&env.name, &env.network)),
+        };
+        let output = render_recipe(&source.template, &source.recipe, &recipe_context)
+            .with_context(|| format!("failed to render the recipe of canister '{name}'"))?;
+        rendered.insert(name, Output::from(output));
+    }
+
+    print(&rendered, args.json)
+}
+
+async fn render_template(
+    ctx: &Context,
+    args: &RenderArgs,
+    template: &Path,
+    environment_selection: &EnvironmentSelection,
+) -> Result<Output, anyhow::Error> {
+    let cwd = PathBuf::try_from(std::env::current_dir()?)
+        .context("the current directory is not valid UTF-8")?;
+    let recipe = Recipe {
+        recipe_type: RecipeType::File(cwd.join(template).to_string()),
+        configuration: args.config.iter().cloned().collect::<HashMap<_, _>>(),
+        sha256: None,
+    };
+
+    // Outside an environment unless one is asked for
+    let environment = match environment_selection {
+        EnvironmentSelection::Default => None,
+        selection => {
+            let env = ctx.get_environment(selection).await?;
+            Some(RecipeEnvironment::new(&env.name, &env.network))
+        }
+    };
+
+    let fetched = detached_fetcher(ctx)?
+        .resolve(&recipe, &ctx.registries)
+        .await
+        .with_context(|| format!("failed to read recipe template '{template}'"))?;
+    let recipe_context = RecipeContext {
+        canister_name: args.canister_name.clone(),
+        project_root: cwd,
+        environment,
+    };
+    let rendered = render_recipe(&fetched.template, &recipe, &recipe_context)
+        .with_context(|| format!("failed to render recipe template '{template}'"))?;
+    Ok(rendered.into())
+}
+
+fn print<T: Serialize>(value: &T, json: bool) -> Result<(), anyhow::Error> {
+    match json {
+        true => {
+            serde_json::to_writer_pretty(std::io::stdout(), value)?;
+            println!();
+        }
+        false => print!("{}", serde_yaml::to_string(value)?),
+    }
+    Ok(())
+}
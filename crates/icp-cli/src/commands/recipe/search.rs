@@ -0,0 +1,41 @@
+use clap::Args;
+use icp::context::Context;
+
+use super::{list_recipes, print_recipes};
+
+/// Search the known registries for recipes
+///
+/// Matches the query, ignoring case, against the name and description of every
+/// recipe listed by `icp recipe list`.
+#[derive(Debug, Args)]
+#[command(after_long_help = "\
+Examples:
+
+    icp recipe search rust
+    icp recipe search asset --registry acme
+")]
+pub(crate) struct SearchArgs {
+    /// The text to look for
+    pub(crate) query: String,
+
+    /// Only search the recipes of this registry
+    #[arg(long)]
+    pub(crate) registry: Option<String>,
+
+    /// Print the matching recipes as JSON
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &SearchArgs) -> Result<(), anyhow::Error> {
+    let query = args.query.to_lowercase();
+    let mut recipes = list_recipes(ctx, args.registry.as_deref()).await?;
+    recipes.retain(|r| {
+        r.recipe.name.to_lowercase().contains(&query)
+            || r.recipe
+                .description
+                .as_ref()
+                .is_some_and(|d| d.to_lowercase().contains(&query))
+    });
+    print_recipes(&recipes, args.json)
+}
@@ -1,8 +1,12 @@
 use std::{fmt, str::FromStr};
 
+use anyhow::{Context as _, bail};
 use clap::{Args, Subcommand};
 use icp::{
     context::Context,
+    manifest::RegistryManifest,
+    prelude::*,
+    registry::Registries,
     settings::{Settings, UpdateCheck},
 };
 use tracing::{info, warn};
@@ -28,10 +32,17 @@ enum Setting {
     Autocontainerize(AutocontainerizeArgs),
     /// Enable or disable anonymous usage telemetry
     Telemetry(TelemetryArgs),
+    /// Keep telemetry on this machine, with the project of each command, and never send it
+    TelemetryLocalOnly(TelemetryLocalOnlyArgs),
     /// Enable or disable the CLI update check
     UpdateCheck(UpdateCheckArgs),
     /// Set the session length for password-protected PEM identities
     SessionLength(SessionLengthArgs),
+    /// Never download recipes, wasms, plugins or the network launcher
+    Offline(OfflineArgs),
+    /// Add, remove or list the recipe registries declared for every project
+    #[command(subcommand)]
+    Registry(RegistryCommand),
 }
 
 #[derive(Debug, Args)]
@@ -46,6 +57,52 @@ struct TelemetryArgs {
     value: Option<bool>,
 }
 
+#[derive(Debug, Args)]
+struct TelemetryLocalOnlyArgs {
+    /// Set to true or false. If omitted, prints the current value.
+    value: Option<bool>,
+}
+
+#[derive(Debug, Args)]
+struct OfflineArgs {
+    /// Set to true or false. If omitted, prints the current value.
+    value: Option<bool>,
+}
+
+#[derive(Debug, Subcommand)]
+enum RegistryCommand {
+    /// Declare a registry, replacing one of the same name
+    Add(RegistryAddArgs),
+    /// Remove a registry
+    Remove(RegistryRemoveArgs),
+    /// List the registries declared in the user settings
+    List,
+}
+
+#[derive(Debug, Args)]
+struct RegistryAddArgs {
+    /// The name recipes reference the registry by, as in `@<name>/<recipe>@<version>`
+    name: String,
+
+    /// The base URL the registry is served from
+    #[arg(long, conflicts_with = "path", required_unless_present = "path")]
+    url: Option<String>,
+
+    /// The local directory holding the registry
+    #[arg(long)]
+    path: Option<PathBuf>,
+
+    /// An environment variable holding the `Authorization` header to send to the registry
+    #[arg(long)]
+    auth_header_env: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct RegistryRemoveArgs {
+    /// The name of the registry
+    name: String,
+}
+
 #[derive(Debug, Args)]
 struct UpdateCheckArgs {
     /// Set to releases, betas, or disabled. If omitted, prints the current value.
@@ -111,8 +168,11 @@ pub(crate) async fn exec(ctx: &Context, args: &SettingsArgs) -> Result<(), anyho
     match &args.setting {
         Setting::Autocontainerize(sub_args) => exec_autocontainerize(ctx, sub_args).await,
         Setting::Telemetry(sub_args) => exec_telemetry(ctx, sub_args).await,
+        Setting::TelemetryLocalOnly(sub_args) => exec_telemetry_local_only(ctx, sub_args).await,
         Setting::UpdateCheck(sub_args) => exec_update_check(ctx, sub_args).await,
         Setting::SessionLength(sub_args) => exec_session_length(ctx, sub_args).await,
+        Setting::Offline(sub_args) => exec_offline(ctx, sub_args).await,
+        Setting::Registry(cmd) => exec_registry(ctx, cmd).await,
     }
 }
 
@@ -175,6 +235,34 @@ async fn exec_telemetry(ctx: &Context, args: &TelemetryArgs) -> Result<(), anyho
     }
 }
 
+async fn exec_telemetry_local_only(
+    ctx: &Context,
+    args: &TelemetryLocalOnlyArgs,
+) -> Result<(), anyhow::Error> {
+    let dirs = ctx.dirs.settings()?;
+
+    match args.value {
+        Some(value) => {
+            dirs.with_write(async |dirs| {
+                let mut settings = Settings::load_from(dirs.read())?;
+                settings.telemetry_local_only = value;
+                settings.write_to(dirs)?;
+                info!("Set telemetry-local-only to {value}");
+                Ok(())
+            })
+            .await?
+        }
+
+        None => {
+            let settings = dirs
+                .with_read(async |dirs| Settings::load_from(dirs))
+                .await??;
+            println!("{}", settings.telemetry_local_only);
+            Ok(())
+        }
+    }
+}
+
 async fn exec_update_check(ctx: &Context, args: &UpdateCheckArgs) -> Result<(), anyhow::Error> {
     let dirs = ctx.dirs.settings()?;
 
@@ -228,3 +316,93 @@ async fn exec_session_length(ctx: &Context, args: &SessionLengthArgs) -> Result<
         }
     }
 }
+
+async fn exec_offline(ctx: &Context, args: &OfflineArgs) -> Result<(), anyhow::Error> {
+    let dirs = ctx.dirs.settings()?;
+
+    match args.value {
+        Some(value) => {
+            dirs.with_write(async |dirs| {
+                let mut settings = Settings::load_from(dirs.read())?;
+                settings.offline = value;
+                settings.write_to(dirs)?;
+                info!("Set offline to {value}");
+                Ok(())
+            })
+            .await?
+        }
+
+        None => {
+            let settings = dirs
+                .with_read(async |dirs| Settings::load_from(dirs))
+                .await??;
+            println!("{}", settings.offline);
+            Ok(())
+        }
+    }
+}
+
+async fn exec_registry(ctx: &Context, cmd: &RegistryCommand) -> Result<(), anyhow::Error> {
+    let dirs = ctx.dirs.settings()?;
+
+    match cmd {
+        RegistryCommand::Add(args) => {
+            if args.name == icp::registry::DFINITY {
+                bail!("the `{}` registry is built in", args.name);
+            }
+            let path = match &args.path {
+                Some(path) => {
+                    let cwd = PathBuf::try_from(std::env::current_dir()?)
+                        .context("the current directory is not valid UTF-8")?;
+                    Some(cwd.join(path).to_string())
+                }
+                None => None,
+            };
+            let registry = RegistryManifest {
+                name: args.name.clone(),
+                url: args.url.clone(),
+                path,
+                auth_header_env: args.auth_header_env.clone(),
+            };
+            dirs.with_write(async |dirs| {
+                let mut settings = Settings::load_from(dirs.read())?;
+                settings.registries.retain(|r| r.name != registry.name);
+                settings.registries.push(registry);
+                Registries::from_settings(&settings.registries)?;
+                settings.write_to(dirs)?;
+                info!("Added registry '{}'", args.name);
+                Ok(())
+            })
+            .await?
+        }
+
+        RegistryCommand::Remove(args) => {
+            dirs.with_write(async |dirs| {
+                let mut settings = Settings::load_from(dirs.read())?;
+                let before = settings.registries.len();
+                settings.registries.retain(|r| r.name != args.name);
+                if settings.registries.len() == before {
+                    bail!("no registry named '{}' in the user settings", args.name);
+                }
+                settings.write_to(dirs)?;
+                info!("Removed registry '{}'", args.name);
+                Ok(())
+            })
+            .await?
+        }
+
+        RegistryCommand::List => {
+            let settings = dirs
+                .with_read(async |dirs| Settings::load_from(dirs))
+                .await??;
+            for r in &settings.registries {
+                let location = r.url.as_deref().or(r.path.as_deref()).unwrap_or_default();
+                match &r.auth_header_env {
+                    Some(variable) => println!("{}  {location}  (auth: ${variable})", r.name),
+                    None => println!("{}  {location}", r.name),
+                }
+            }
+            Ok(())
+        }
+    }
+}
@@ -10,7 +10,7 @@ use std::collections::BTreeMap;
 use tracing::info;
 
 use crate::{
-    operations::{proxy_management, sync::sync_many},
+    operations::{proxy_management, remote::resolve_canister_downloads, sync::sync_many},
     options::{EnvironmentOpt, IdentityOpt},
 };
 
@@ -85,6 +85,17 @@ pub(crate) async fn exec(ctx: &Context, args: &SyncArgs) -> Result<(), anyhow::E
         return Ok(());
     }
 
+    // Offline, report every plugin missing from the package cache at once
+    if ctx.offline {
+        resolve_canister_downloads(
+            ctx,
+            sync_canisters
+                .iter()
+                .map(|(_, path, c)| (path.as_path(), c)),
+        )
+        .await?;
+    }
+
     // icp sync is sync-only and does not manage lifecycle: unlike deploy it will
     // NOT start the canister for the user. Asset sync requires a Running canister,
     // so detect a non-Running canister up front and abort with an actionable error
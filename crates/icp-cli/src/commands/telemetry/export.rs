@@ -0,0 +1,39 @@
+use anyhow::Context as _;
+use clap::{Args, ValueHint};
+use icp::context::Context;
+use icp::prelude::*;
+use itertools::Itertools;
+use tracing::info;
+
+use super::{record_files, records_noun};
+use crate::telemetry::read_records;
+
+/// Write the telemetry records waiting to be sent to a file, one JSON record per line
+#[derive(Debug, Args)]
+pub(crate) struct ExportArgs {
+    /// The file to write the records to
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    out: PathBuf,
+
+    /// Export the records of local-only mode instead, which are never sent
+    #[arg(long)]
+    local: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ExportArgs) -> Result<(), anyhow::Error> {
+    let records = read_records(&record_files(ctx, args.local));
+
+    let mut contents = records.iter().map(|record| record.to_string()).join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    icp::fs::write_string(&args.out, &contents).context("failed to write the records")?;
+    info!(
+        "Exported {} telemetry {} to {}",
+        records.len(),
+        records_noun(records.len()),
+        args.out
+    );
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use clap::Subcommand;
+use icp::context::Context;
+use icp::prelude::*;
+
+use crate::telemetry::{local_file, pending_files};
+
+pub(crate) mod export;
+pub(crate) mod purge;
+pub(crate) mod show;
+
+/// Inspect, export and delete the telemetry recorded on this machine
+///
+/// By default these commands act on the records waiting to be sent. With
+/// `--local`, they act on the records of local-only mode
+/// (`icp settings telemetry-local-only true`), which are never sent.
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    Show(show::ShowArgs),
+    Export(export::ExportArgs),
+    Purge(purge::PurgeArgs),
+}
+
+/// The files holding the records `--local` selects.
+fn record_files(ctx: &Context, local: bool) -> Vec<PathBuf> {
+    let telemetry_dir = ctx.dirs.telemetry_data();
+    if local {
+        let path = local_file(&telemetry_dir);
+        if path.exists() { vec![path] } else { vec![] }
+    } else {
+        pending_files(&telemetry_dir)
+    }
+}
+
+fn records_noun(count: usize) -> &'static str {
+    if count == 1 { "record" } else { "records" }
+}
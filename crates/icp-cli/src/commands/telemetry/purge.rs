@@ -0,0 +1,29 @@
+use anyhow::Context as _;
+use clap::Args;
+use icp::context::Context;
+use tracing::info;
+
+use super::{record_files, records_noun};
+use crate::telemetry::read_records;
+
+/// Delete the telemetry records waiting to be sent
+///
+/// Telemetry stays enabled; to stop recording, run `icp settings telemetry false`.
+#[derive(Debug, Args)]
+pub(crate) struct PurgeArgs {
+    /// Delete the records of local-only mode instead
+    #[arg(long)]
+    local: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &PurgeArgs) -> Result<(), anyhow::Error> {
+    let files = record_files(ctx, args.local);
+    let count = read_records(&files).len();
+
+    for path in &files {
+        icp::fs::remove_file(path).context("failed to delete telemetry records")?;
+    }
+    info!("Deleted {count} telemetry {}", records_noun(count));
+
+    Ok(())
+}
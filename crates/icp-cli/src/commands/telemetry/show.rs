@@ -0,0 +1,113 @@
+use std::io::stdout;
+
+use clap::Args;
+use icp::context::Context;
+use itertools::Itertools;
+use serde_json::Value;
+
+use super::{record_files, records_noun};
+use crate::telemetry::read_records;
+
+/// Print the telemetry records waiting to be sent
+#[derive(Debug, Args)]
+pub(crate) struct ShowArgs {
+    /// Show the records of local-only mode instead, which are never sent
+    #[arg(long)]
+    pub(crate) local: bool,
+
+    /// Print the records as a JSON array, exactly as they are stored
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+pub(crate) async fn exec(ctx: &Context, args: &ShowArgs) -> Result<(), anyhow::Error> {
+    let records = read_records(&record_files(ctx, args.local));
+
+    if args.json {
+        serde_json::to_writer_pretty(stdout(), &records)?;
+        println!();
+        return Ok(());
+    }
+
+    for record in &records {
+        println!("{}", format_record(record));
+    }
+    let kind = if args.local { "local" } else { "pending" };
+    println!(
+        "{} {kind} telemetry {}",
+        records.len(),
+        records_noun(records.len())
+    );
+
+    Ok(())
+}
+
+/// One line per record: date, command, outcome, duration and arguments, then
+/// the project for local records.
+fn format_record(record: &Value) -> String {
+    let date = record["date"].as_str().unwrap_or("-");
+    let command = record["command"].as_str().unwrap_or("-");
+    let outcome = match record["success"].as_bool() {
+        Some(true) => "ok",
+        Some(false) => "failed",
+        None => "-",
+    };
+    let duration = record["duration_ms"]
+        .as_u64()
+        .map(|ms| format!("{:.2}s", ms as f64 / 1000.0))
+        .unwrap_or_else(|| "-".to_string());
+    let arguments = record["arguments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|argument| {
+            let name = argument["name"].as_str()?;
+            Some(match argument["value"].as_str() {
+                Some(value) => format!("{name}={value}"),
+                None => name.to_string(),
+            })
+        })
+        .join(" ");
+
+    let mut line = format!("{date}  {command: <20} {outcome: <6} {duration: >8}");
+    if !arguments.is_empty() {
+        line.push_str(&format!("  [{arguments}]"));
+    }
+    if let Some(project) = record["project"].as_str() {
+        line.push_str(&format!("  {project}"));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn formats_records() {
+        let record = json!({
+            "date": "2026-10-18",
+            "command": "deploy",
+            "arguments": [
+                { "name": "mode", "value": "install", "source": "command-line" },
+                { "name": "environment", "value": null, "source": "command-line" }
+            ],
+            "success": true,
+            "duration_ms": 4230,
+            "project": "/work/app"
+        });
+        assert_eq!(
+            format_record(&record),
+            "2026-10-18  deploy               ok        4.23s  [mode=install environment]  /work/app"
+        );
+
+        // Records from older versions may lack fields
+        let record = json!({ "command": "build", "success": false, "duration_ms": 42 });
+        assert_eq!(
+            format_record(&record),
+            "-  build                failed    0.04s"
+        );
+    }
+}
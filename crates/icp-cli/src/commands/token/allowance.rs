@@ -1,12 +1,11 @@
 use std::io::stdout;
 
-use candid::Principal;
 use clap::Args;
 use icp::context::Context;
 use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
 
-use crate::commands::args::TokenCommandArgs;
+use crate::commands::args::{ContactOr, TokenCommandArgs, with_subaccount};
 use crate::commands::parsers::parse_subaccount;
 use crate::commands::token::format_expiry;
 use crate::operations::token::allowance::get_allowance;
@@ -20,8 +19,9 @@ use crate::operations::token::allowance::get_allowance;
 #[derive(Args, Debug)]
 #[command(override_usage = "icp token [TOKEN|LEDGER_ID] allowance [OPTIONS] <SPENDER>")]
 pub(crate) struct AllowanceArgs {
-    /// Principal of the spender whose allowance to look up.
-    pub(crate) spender: Principal,
+    /// Principal or ICRC-1 account of the spender whose allowance to look up,
+    /// or `@name` of a contact or identity.
+    pub(crate) spender: ContactOr<Account>,
 
     /// The spender's subaccount, as a hex string (32 bytes, left-padded).
     /// Defaults to the default subaccount.
@@ -33,10 +33,10 @@ pub(crate) struct AllowanceArgs {
     #[arg(long, value_parser = parse_subaccount)]
     pub(crate) subaccount: Option<[u8; 32]>,
 
-    /// The allowance owner to look up, instead of the current identity.
-    /// Lets you inspect allowances granted by any principal.
+    /// The allowance owner to look up, as a principal or ICRC-1 account, instead of
+    /// the current identity. Lets you inspect allowances granted by any principal.
     #[arg(long)]
-    pub(crate) of_principal: Option<Principal>,
+    pub(crate) of_principal: Option<ContactOr<Account>>,
 
     #[command(flatten)]
     pub(crate) token_command_args: TokenCommandArgs,
@@ -71,17 +71,20 @@ pub(crate) async fn exec(
             &selections.environment,
         )
         .await?;
-    let owner = args
-        .of_principal
-        .unwrap_or_else(|| agent.get_principal().unwrap());
-
-    let spender = Account {
-        owner: args.spender,
-        subaccount: args.spender_subaccount,
+    let owner = match &args.of_principal {
+        Some(account) => account.resolve(ctx).await?,
+        None => agent.get_principal().unwrap().into(),
     };
+    let owner = with_subaccount(owner, args.subaccount, "--subaccount")?;
+
+    let spender = with_subaccount(
+        args.spender.resolve(ctx).await?,
+        args.spender_subaccount,
+        "--spender-subaccount",
+    )?;
 
     // Query the allowance from the ledger
-    let info = get_allowance(&agent, token, owner, args.subaccount, spender).await?;
+    let info = get_allowance(&agent, token, owner.owner, owner.subaccount, spender).await?;
 
     if args.json {
         serde_json::to_writer(
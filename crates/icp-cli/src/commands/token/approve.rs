@@ -2,7 +2,6 @@ use std::io::stdout;
 
 use anyhow::Context as _;
 use bigdecimal::BigDecimal;
-use candid::Principal;
 use clap::Args;
 use icp::context::Context;
 use icp::parsers::{DurationAmount, parse_token_amount};
@@ -10,7 +9,7 @@ use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
 use time::OffsetDateTime;
 
-use crate::commands::args::TokenCommandArgs;
+use crate::commands::args::{ContactOr, TokenCommandArgs, with_subaccount};
 use crate::commands::parsers::parse_subaccount;
 use crate::commands::token::format_expiry;
 use crate::operations::token::approve::approve;
@@ -30,8 +29,9 @@ pub(crate) struct ApproveArgs {
     #[arg(value_parser = parse_token_amount)]
     pub(crate) amount: BigDecimal,
 
-    /// Principal of the spender being granted the allowance.
-    pub(crate) spender: Principal,
+    /// Principal or ICRC-1 account of the spender being granted the allowance,
+    /// or `@name` of a contact or identity.
+    pub(crate) spender: ContactOr<Account>,
 
     /// The spender's subaccount, as a hex string (32 bytes, left-padded).
     /// Defaults to the default subaccount.
@@ -82,10 +82,11 @@ pub(crate) async fn exec(
         )
         .await?;
 
-    let spender = Account {
-        owner: args.spender,
-        subaccount: args.spender_subaccount,
-    };
+    let spender = with_subaccount(
+        args.spender.resolve(ctx).await?,
+        args.spender_subaccount,
+        "--spender-subaccount",
+    )?;
 
     // Resolve the relative expiry to an absolute timestamp (nanoseconds since epoch)
     let expires_at = args
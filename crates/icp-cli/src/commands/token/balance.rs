@@ -1,11 +1,11 @@
 use std::io::stdout;
 
-use candid::Principal;
 use clap::Args;
 use icp::context::Context;
+use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
 
-use crate::commands::args::TokenCommandArgs;
+use crate::commands::args::{ContactOr, TokenCommandArgs, with_subaccount};
 use crate::commands::parsers::parse_subaccount;
 use crate::operations::token::balance::get_balance;
 
@@ -20,9 +20,10 @@ pub(crate) struct BalanceArgs {
     #[arg(long, value_parser = parse_subaccount)]
     pub(crate) subaccount: Option<[u8; 32]>,
 
-    /// Check the balance of this principal instead of the current identity
+    /// Check the balance of this principal or ICRC-1 account, or `@name` of a contact
+    /// or identity, instead of the current identity
     #[arg(long)]
-    pub(crate) of_principal: Option<Principal>,
+    pub(crate) of_principal: Option<ContactOr<Account>>,
 
     /// Output command results as JSON
     #[arg(long, conflicts_with = "quiet")]
@@ -53,12 +54,14 @@ pub(crate) async fn exec(
             &selections.environment,
         )
         .await?;
-    let owner = args
-        .of_principal
-        .unwrap_or_else(|| agent.get_principal().unwrap());
+    let owner = match &args.of_principal {
+        Some(account) => account.resolve(ctx).await?,
+        None => agent.get_principal().unwrap().into(),
+    };
+    let account = with_subaccount(owner, args.subaccount, "--subaccount")?;
 
     // Get the balance from the ledger
-    let balance = get_balance(&agent, token, owner, args.subaccount).await?;
+    let balance = get_balance(&agent, token, account.owner, account.subaccount).await?;
 
     if args.json {
         serde_json::to_writer(
@@ -6,7 +6,7 @@ use icp::context::Context;
 use icp::parsers::parse_token_amount;
 use serde::Serialize;
 
-use crate::commands::args::{FlexibleAccountId, TokenCommandArgs};
+use crate::commands::args::{ContactOr, FlexibleAccountId, TokenCommandArgs};
 use crate::commands::parsers::parse_subaccount;
 use crate::operations::token::transfer::transfer;
 
@@ -20,8 +20,9 @@ pub(crate) struct TransferArgs {
     pub(crate) amount: BigDecimal,
 
     /// The receiver of the token transfer.
-    /// Can be a principal, an ICRC1 account ID, or an ICP ledger account ID (hex).
-    pub(crate) receiver: FlexibleAccountId,
+    /// Can be a principal, an ICRC1 account ID, an ICP ledger account ID (hex),
+    /// or `@name` of a contact or identity.
+    pub(crate) receiver: ContactOr<FlexibleAccountId>,
 
     /// The subaccount to transfer to (only if the receiver is a principal)
     #[arg(long, value_parser = parse_subaccount)]
@@ -48,7 +49,7 @@ pub(crate) async fn exec(
     token: &str,
     args: &TransferArgs,
 ) -> Result<(), anyhow::Error> {
-    let mut receiver = args.receiver;
+    let mut receiver = args.receiver.resolve(ctx).await?;
     if let Some(subaccount) = args.to_subaccount {
         if let FlexibleAccountId::Icrc1(account) = &mut receiver
             && account.subaccount.is_none()
@@ -15,6 +15,7 @@ use clap::CommandFactory as _;
 use clap_complete::CompleteEnv;
 use clap_complete::engine::CompletionCandidate;
 use icp::context::Context;
+use icp::directories::Access as _;
 use icp::identity::manifest::IdentityList;
 use icp::network::Configuration;
 use icp::prelude::*;
@@ -61,11 +62,24 @@ fn context() -> Option<&'static Context> {
 
     CONTEXT
         .get_or_init(|| {
+            // Recipes can name the registries declared in the user settings
+            let settings = block_on(async {
+                let dirs = icp::directories::Directories::new().ok()?.settings().ok()?;
+                dirs.with_read(async |dirs| icp::settings::Settings::load_from(dirs))
+                    .await
+                    .ok()?
+                    .ok()
+            })
+            .flatten()
+            .unwrap_or_default();
             icp::context::initialize(
                 std::env::var("ICP_PROJECT_ROOT").ok().map(PathBuf::from),
                 false,
+                false,
+                false,
                 Arc::new(|| Err("cannot prompt while completing".to_string())),
                 None,
+                icp::registry::Registries::from_settings(&settings.registries).ok()?,
             )
             .ok()
         })
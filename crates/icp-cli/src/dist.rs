@@ -194,8 +194,9 @@ pub(crate) async fn update_check(ctx: &icp::context::Context) -> Option<String>
         Err(_) => UpdateCheck::Releases,
     };
 
-    let enabled =
-        !matches!(update_check_setting, UpdateCheck::Disabled) && dist_supports_update_check();
+    let enabled = !matches!(update_check_setting, UpdateCheck::Disabled)
+        && !ctx.offline
+        && dist_supports_update_check();
     if !enabled {
         return None;
     }
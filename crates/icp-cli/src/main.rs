@@ -80,6 +80,25 @@ struct Cli {
     #[arg(long, default_value = "false", global = true, help_heading = heading::GLOBAL_PARAMETERS)]
     debug: bool,
 
+    /// Fail instead of updating icp.lock when a floating reference resolves to
+    /// something not pinned there
+    #[arg(long, global = true, help_heading = heading::GLOBAL_PARAMETERS)]
+    locked: bool,
+
+    /// Never download recipes, wasms, plugins or the network launcher: serve them
+    /// from the package cache only. Also skips the update check. Defaults to
+    /// `icp settings offline`; `--offline=false` goes online despite it.
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL",
+        help_heading = heading::GLOBAL_PARAMETERS,
+    )]
+    offline: Option<bool>,
+
     /// Read identity password from a file instead of prompting
     #[arg(long, global = true, value_name = "FILE", help_heading = heading::GLOBAL_PARAMETERS, value_hint = ValueHint::FilePath)]
     identity_password_file: Option<PathBuf>,
@@ -174,21 +193,24 @@ async fn run() -> Result<(), Error> {
                 .map_err(|e| e.to_string())
         }),
     };
-    let pem_session_duration = {
+    let settings = {
         let dirs = icp::directories::Directories::new()?;
         let settings_dirs = dirs.settings()?;
-        let settings = settings_dirs
+        settings_dirs
             .with_read(async |dirs| icp::settings::Settings::load_from(dirs))
-            .await??;
-        settings
-            .session_length
-            .map(|m| std::time::Duration::from_secs((u64::from(m) + 2) * 60))
+            .await??
     };
+    let pem_session_duration = settings
+        .session_length
+        .map(|m| std::time::Duration::from_secs((u64::from(m) + 2) * 60));
     let ctx = icp::context::initialize(
         cli.project_root_override,
         cli.debug,
+        cli.locked,
+        cli.offline.unwrap_or(settings.offline),
         password_func,
         pem_session_duration,
+        icp::registry::Registries::from_settings(&settings.registries)?,
     )?;
 
     let telemetry_session = telemetry::setup(&ctx, &raw_args, &Cli::command()).await;
@@ -225,9 +247,34 @@ async fn run() -> Result<(), Error> {
 /// Dispatch the command to its handler.
 async fn dispatch(ctx: &icp::context::Context, command: Command) -> Result<(), Error> {
     match command {
+        // Account
+        Command::Account(cmd) => match cmd {
+            commands::account::Command::Convert(args) => {
+                commands::account::convert::exec(ctx, &args).await?
+            }
+            commands::account::Command::Inspect(args) => {
+                commands::account::inspect::exec(ctx, &args).await?
+            }
+            commands::account::Command::Subaccount(args) => {
+                commands::account::subaccount::exec(ctx, &args).await?
+            }
+        },
+
         // Build
         Command::Build(args) => commands::build::exec(ctx, &args).await?,
 
+        // Cache
+        Command::Cache(cmd) => match cmd {
+            commands::cache::Command::Clear(args) => {
+                commands::cache::clear::exec(ctx, &args).await?
+            }
+            commands::cache::Command::List(args) => commands::cache::list::exec(ctx, &args).await?,
+            commands::cache::Command::Prune(args) => {
+                commands::cache::prune::exec(ctx, &args).await?
+            }
+            commands::cache::Command::Size(args) => commands::cache::size::exec(ctx, &args).await?,
+        },
+
         // Canister
         Command::Canister(cmd) => match cmd {
             commands::canister::Command::Call(args) => {
@@ -326,6 +373,19 @@ async fn dispatch(ctx: &icp::context::Context, command: Command) -> Result<(), E
         // Completions: handled in `main` before the context exists
         Command::Completions(_) => unreachable!(),
 
+        // Contacts
+        Command::Contacts(cmd) => match cmd {
+            commands::contacts::Command::Add(args) => {
+                commands::contacts::add::exec(ctx, &args).await?
+            }
+            commands::contacts::Command::List(args) => {
+                commands::contacts::list::exec(ctx, &args).await?
+            }
+            commands::contacts::Command::Remove(args) => {
+                commands::contacts::remove::exec(ctx, &args).await?
+            }
+        },
+
         // Cycles
         Command::Cycles(cmd) => match cmd {
             commands::cycles::Command::Balance(args) => {
@@ -349,6 +409,12 @@ async fn dispatch(ctx: &icp::context::Context, command: Command) -> Result<(), E
             commands::environment::Command::List(args) => {
                 commands::environment::list::exec(ctx, &args).await?
             }
+            commands::environment::Command::Promote(args) => {
+                commands::environment::promote::exec(ctx, &args).await?
+            }
+            commands::environment::Command::Show(args) => {
+                commands::environment::show::exec(ctx, &args).await?
+            }
         },
 
         // Identity
@@ -357,6 +423,14 @@ async fn dispatch(ctx: &icp::context::Context, command: Command) -> Result<(), E
                 commands::identity::account_id::exec(ctx, &args).await?
             }
 
+            commands::identity::Command::Backup(args) => {
+                commands::identity::backup::exec(ctx, &args).await?
+            }
+
+            commands::identity::Command::ChangePassword(args) => {
+                commands::identity::change_password::exec(ctx, &args).await?
+            }
+
             commands::identity::Command::Default(args) => {
                 commands::identity::default::exec(ctx, &args).await?
             }
@@ -386,9 +460,15 @@ async fn dispatch(ctx: &icp::context::Context, command: Command) -> Result<(), E
             }
 
             commands::identity::Command::Link(cmd) => match cmd {
+                commands::identity::link::Command::External(args) => {
+                    commands::identity::link::external::exec(ctx, &args).await?
+                }
                 commands::identity::link::Command::Hsm(args) => {
                     commands::identity::link::hsm::exec(ctx, &args).await?
                 }
+                commands::identity::link::Command::SshAgent(args) => {
+                    commands::identity::link::ssh_agent::exec(ctx, &args).await?
+                }
                 commands::identity::link::Command::Web(args) => {
                     commands::identity::link::web::exec(ctx, &args).await?
                 }
@@ -413,6 +493,15 @@ async fn dispatch(ctx: &icp::context::Context, command: Command) -> Result<(), E
             commands::identity::Command::Rename(args) => {
                 commands::identity::rename::exec(ctx, &args).await?
             }
+            commands::identity::Command::Restore(args) => {
+                commands::identity::restore::exec(ctx, &args).await?
+            }
+            commands::identity::Command::Rotate(args) => {
+                commands::identity::rotate::exec(ctx, &args).await?
+            }
+            commands::identity::Command::Storage(args) => {
+                commands::identity::storage::exec(ctx, &args).await?
+            }
         },
 
         // Network
@@ -450,17 +539,60 @@ async fn dispatch(ctx: &icp::context::Context, command: Command) -> Result<(), E
             commands::project::Command::Show(args) => {
                 commands::project::show::exec(ctx, &args).await?
             }
+            commands::project::Command::ImportDfx(args) => {
+                commands::project::import_dfx::exec(ctx, &args).await?
+            }
+            commands::project::Command::Prefetch(args) => {
+                commands::project::prefetch::exec(ctx, &args).await?
+            }
+            commands::project::Command::UpdateLock(args) => {
+                commands::project::update_lock::exec(ctx, &args).await?
+            }
             commands::project::Command::Bundle(args) => {
                 commands::project::bundle::exec(ctx, &args).await?
             }
         },
 
+        // Recipe
+        Command::Recipe(cmd) => match cmd {
+            commands::recipe::Command::Describe(args) => {
+                commands::recipe::describe::exec(ctx, &args).await?
+            }
+            commands::recipe::Command::List(args) => {
+                commands::recipe::list::exec(ctx, &args).await?
+            }
+            commands::recipe::Command::Outdated(args) => {
+                commands::recipe::outdated::exec(ctx, &args).await?
+            }
+            commands::recipe::Command::Render(args) => {
+                commands::recipe::render::exec(ctx, &args).await?
+            }
+            commands::recipe::Command::Search(args) => {
+                commands::recipe::search::exec(ctx, &args).await?
+            }
+        },
+
         // Settings
         Command::Settings(args) => commands::settings::exec(ctx, &args).await?,
 
         // Sync
         Command::Sync(args) => commands::sync::exec(ctx, &args).await?,
 
+        // Telemetry
+        Command::Telemetry(cmd) => match cmd {
+            commands::telemetry::Command::Show(args) => {
+                commands::telemetry::show::exec(ctx, &args).await?
+            }
+
+            commands::telemetry::Command::Export(args) => {
+                commands::telemetry::export::exec(ctx, &args).await?
+            }
+
+            commands::telemetry::Command::Purge(args) => {
+                commands::telemetry::purge::exec(ctx, &args).await?
+            }
+        },
+
         // Token
         Command::Token(cmd) => match cmd.command {
             commands::token::Commands::Balance(args) => {
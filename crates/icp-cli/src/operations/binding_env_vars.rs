@@ -3,8 +3,8 @@ use std::collections::{BTreeMap, HashSet};
 use futures::{StreamExt, stream::FuturesOrdered};
 use ic_agent::{Agent, export::Principal};
 use ic_management_canister_types::{CanisterSettings, EnvironmentVariable, UpdateSettingsArgs};
-use icp::Canister;
-use snafu::Snafu;
+use icp::{Canister, canister::secret::SecretError};
+use snafu::{ResultExt, Snafu};
 use tracing::error;
 
 use crate::progress::{ProgressManager, ProgressManagerSettings};
@@ -20,6 +20,9 @@ pub enum BindingEnvVarsOperationError {
         canister_names: Vec<String>,
     },
 
+    #[snafu(display("failed to read a secret environment variable"))]
+    ResolveSecret { source: SecretError },
+
     #[snafu(transparent)]
     UpdateOrProxy { source: UpdateOrProxyError },
 }
@@ -45,9 +48,9 @@ pub(crate) async fn set_env_vars_for_canister(
     binding_vars: &[(String, String)],
 ) -> Result<(), BindingEnvVarsOperationError> {
     let mut environment_variables = canister_info
-        .settings
-        .environment_variables
-        .to_owned()
+        .environment_variables()
+        .await
+        .context(ResolveSecretSnafu)?
         .unwrap_or_default();
 
     // inject the ids of the other canisters
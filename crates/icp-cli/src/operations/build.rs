@@ -158,6 +158,14 @@ pub(crate) async fn build_many_with_progress_bar(
                 failure.canister_name,
             );
             error!("'{}'", failure.error);
+            {
+                use std::error::Error;
+                let mut cause = failure.error.source();
+                while let Some(err) = cause {
+                    error!("  caused by: {err}");
+                    cause = err.source();
+                }
+            }
             for line in &failure.progress_output {
                 error!("{line}");
             }
@@ -11,14 +11,18 @@ use camino::Utf8Component;
 use flate2::{Compression, write::GzEncoder};
 use icp::{
     Canister, InitArgs,
-    canister::{ControllerRef, ManifestEnvVar, Settings, build::Build, wasm},
-    fs,
+    canister::{
+        ControllerRef, ManifestEnvVar, Settings,
+        build::Build,
+        sync::{ResolvePluginError, resolve_plugin_wasm},
+    },
+    fs, lockfile,
     manifest::{
         ArgsFormat, BuildStep, BuildSteps, CanisterManifest, DependencyManifest,
         EnvironmentManifest, Instructions, Item, LoadManifestFromPathError, ManagedMode,
         ManifestInitArgs, Mode, NetworkManifest, PROJECT_MANIFEST, ProjectManifest, SyncStep,
-        SyncSteps, load_manifest_from_path, plugin, prebuilt,
-        prebuilt::{LocalSource, SourceField},
+        SyncSteps, VariableError, Variables, load_manifest_from_path, plugin, prebuilt,
+        prebuilt::LocalSource,
     },
     package::PackageCache,
     prelude::*,
@@ -49,6 +53,9 @@ pub enum BundleError {
     #[snafu(transparent)]
     Workspace { source: WorkspaceInstancesError },
 
+    #[snafu(display("failed to read the project's variables"))]
+    Variables { source: VariableError },
+
     #[snafu(display(
         "dependency '{alias}' (path '{path}') resolves to '{dir}', which is outside the workspace \
          root '{root}'; bundling requires every dependency to live inside the workspace root, so \
@@ -186,7 +193,7 @@ pub enum BundleError {
     #[snafu(display("failed to resolve plugin wasm for canister '{canister}'"))]
     ResolvePlugin {
         canister: String,
-        source: wasm::WasmError,
+        source: ResolvePluginError,
     },
 
     #[snafu(display("failed to read plugin wasm for canister '{canister}'"))]
@@ -328,6 +335,8 @@ pub(crate) async fn create_bundle(
     builder: Arc<dyn Build>,
     artifacts: Arc<dyn store_artifact::Access>,
     pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
     debug: bool,
     output: &Path,
 ) -> Result<(), BundleError> {
@@ -335,8 +344,9 @@ pub(crate) async fn create_bundle(
     // each dependency instance at its workspace-relative directory, so the
     // dependency declarations — and the store keys and `PUBLIC_CANISTER_ID`
     // wiring deploy derives from them — carry over unchanged.
+    let vars = Variables::load(project_dir).context(VariablesSnafu)?;
     let instances = group_canisters(
-        workspace_instances(project_dir).await?,
+        workspace_instances(project_dir, &vars).await?,
         &canisters,
         project_dir,
     )?;
@@ -384,11 +394,19 @@ pub(crate) async fn create_bundle(
     let mut manifests: Vec<InstanceManifest> = Vec::with_capacity(instances.len());
 
     for instance in &instances {
-        let canister_items =
-            prepare_canisters(instance, &*artifacts, pkg_cache, &mut bundle_artifacts).await?;
-        let networks = inline_networks(&instance.manifest.networks, &instance.dir).await?;
+        let canister_items = prepare_canisters(
+            instance,
+            &*artifacts,
+            pkg_cache,
+            lockfile,
+            offline,
+            &mut bundle_artifacts,
+        )
+        .await?;
+        let networks = inline_networks(&instance.manifest.networks, &instance.dir, &vars).await?;
         let environments = inline_environments(
             &instance.manifest.environments,
+            &vars,
             &instance.prefix,
             &instance.dir,
             &canonical_project_dir,
@@ -404,6 +422,10 @@ pub(crate) async fn create_bundle(
             dependencies: rewrite_dependencies(instance)?,
             networks,
             environments,
+            identity: instance.manifest.identity.clone(),
+            // Bundled canisters carry their rendered recipes, so nothing is
+            // fetched from a registry when the bundle is deployed
+            registries: vec![],
         };
 
         manifests.push(InstanceManifest {
@@ -576,6 +598,8 @@ async fn prepare_canisters(
     instance: &Instance,
     artifacts: &dyn store_artifact::Access,
     pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
     out: &mut BundleArtifacts,
 ) -> Result<Vec<Item<CanisterManifest>>, BundleError> {
     // Store key -> local name, for rewriting controller references back to the
@@ -595,6 +619,8 @@ async fn prepare_canisters(
             &local_names,
             artifacts,
             pkg_cache,
+            lockfile,
+            offline,
             out,
         )
         .await?;
@@ -611,6 +637,8 @@ async fn prepare_canister(
     local_names: &HashMap<&str, &str>,
     artifacts: &dyn store_artifact::Access,
     pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
     out: &mut BundleArtifacts,
 ) -> Result<Item<CanisterManifest>, BundleError> {
     let local = local_name(&canister.name);
@@ -651,6 +679,8 @@ async fn prepare_canister(
                         &path_name,
                         idx,
                         pkg_cache,
+                        lockfile,
+                        offline,
                         out,
                     )
                     .await?,
@@ -668,9 +698,18 @@ async fn prepare_canister(
         bytes: wasm,
     });
 
+    // Secret environment variables are written as their source, never their value
+    let mut settings: Settings<ManifestEnvVar> = canister.settings.clone().into();
+    if !canister.secret_environment_variables.is_empty() {
+        let vars = settings.environment_variables.get_or_insert_default();
+        for (name, secret) in &canister.secret_environment_variables {
+            vars.insert(name.clone(), ManifestEnvVar::Secret(secret.source.clone()));
+        }
+    }
+
     Ok(Item::Manifest(CanisterManifest {
         name: local.to_owned(),
-        settings: localize_controllers(canister.settings.clone().into(), local_names),
+        settings: localize_controllers(settings, local_names),
         init_args: canister.init_args.as_ref().map(convert_init_args),
         instructions: Instructions::BuildSync {
             build: BuildSteps {
@@ -719,21 +758,18 @@ async fn prepare_plugin_step(
     path_name: &str,
     idx: usize,
     pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
     out: &mut BundleArtifacts,
 ) -> Result<SyncStep, BundleError> {
     let plugin_wasm_path = format!("plugins/{path_name}/{idx}.wasm");
 
-    let resolved = wasm::resolve(
-        &adapter.source,
-        canister_path,
-        adapter.sha256.as_deref(),
-        None,
-        pkg_cache,
-    )
-    .await
-    .context(ResolvePluginSnafu {
-        canister: canister.name.clone(),
-    })?;
+    let (resolved, _) =
+        resolve_plugin_wasm(adapter, canister_path, None, pkg_cache, lockfile, offline)
+            .await
+            .context(ResolvePluginSnafu {
+                canister: canister.name.clone(),
+            })?;
 
     let plugin_bytes = fs::read(&resolved).context(ReadPluginSnafu {
         canister: canister.name.clone(),
@@ -782,7 +818,7 @@ async fn prepare_plugin_step(
     });
 
     Ok(SyncStep::Plugin(plugin::Adapter {
-        source: SourceField::Local(LocalSource {
+        source: plugin::PluginSource::Local(LocalSource {
             path: plugin_wasm_path.as_str().into(),
         }),
         sha256: Some(plugin_sha256),
@@ -794,6 +830,7 @@ async fn prepare_plugin_step(
 async fn inline_networks(
     items: &[Item<NetworkManifest>],
     instance_dir: &Path,
+    vars: &Variables,
 ) -> Result<Vec<Item<NetworkManifest>>, BundleError> {
     let mut out = Vec::with_capacity(items.len());
     for item in items {
@@ -801,7 +838,7 @@ async fn inline_networks(
             Item::Manifest(_) => item.clone(),
             Item::Path(path) => {
                 let full = instance_dir.join(path);
-                let m = load_manifest_from_path::<NetworkManifest>(&full)
+                let m = load_manifest_from_path::<NetworkManifest>(&full, vars)
                     .await
                     .context(LoadNetworkSnafu { path: full })?;
                 Item::Manifest(m)
@@ -845,6 +882,7 @@ fn override_base_dir<'a>(
 #[allow(clippy::too_many_arguments)]
 async fn inline_environments(
     items: &[Item<EnvironmentManifest>],
+    vars: &Variables,
     instance_prefix: &str,
     instance_dir: &Path,
     canonical_project_dir: &Path,
@@ -860,7 +898,7 @@ async fn inline_environments(
             Item::Manifest(_) => item.clone(),
             Item::Path(path) => {
                 let full = instance_dir.join(path);
-                let m = load_manifest_from_path::<EnvironmentManifest>(&full)
+                let m = load_manifest_from_path::<EnvironmentManifest>(&full, vars)
                     .await
                     .context(LoadEnvironmentSnafu { path: full })?;
                 Item::Manifest(m)
@@ -1567,5 +1605,9 @@ fn convert_init_args(args: &InitArgs) -> ManifestInitArgs {
             value: hex::encode(bytes),
             format: ArgsFormat::Hex,
         },
+        InitArgs::Secret { secret, format } => ManifestInitArgs::Secret {
+            source: secret.source.clone(),
+            format: format.clone(),
+        },
     }
 }
@@ -0,0 +1,395 @@
+//! Conversion of a dfx project's `dfx.json` into an `icp.yaml`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use candid::Principal;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use url::Url;
+
+/// The recipe versions converted canisters use.
+const RUST_RECIPE: &str = "@dfinity/rust@v3.0.0";
+const MOTOKO_RECIPE: &str = "@dfinity/motoko@v4.0.0";
+const ASSETS_RECIPE: &str = "@dfinity/asset-canister@v2.2.1";
+
+/// Hosts that serve IC mainnet, whose root key need not be fetched.
+const MAINNET_HOSTS: [&str; 4] = ["icp-api.io", "icp0.io", "ic0.app", "icp-api.ic0.app"];
+
+/// The subset of `dfx.json` that has a counterpart in `icp.yaml`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct DfxJson {
+    #[serde(default)]
+    canisters: IndexMap<String, DfxCanister>,
+    #[serde(default)]
+    networks: IndexMap<String, DfxNetwork>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DfxCanister {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    package: Option<String>,
+    candid: Option<String>,
+    main: Option<String>,
+    #[serde(default)]
+    source: Vec<String>,
+    wasm: Option<String>,
+    build: Option<OneOrMany>,
+    init_arg: Option<String>,
+    init_arg_file: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DfxNetwork {
+    #[serde(default)]
+    providers: Vec<String>,
+    bind: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+/// `canister_ids.json`: for each canister, its ID on each persistent network.
+pub(crate) type DfxCanisterIds = BTreeMap<String, BTreeMap<String, Principal>>;
+
+/// The result of converting a `dfx.json`.
+pub(crate) struct ConvertedProject {
+    /// The contents of `icp.yaml`
+    pub(crate) yaml: String,
+
+    /// The environments named after a persistent dfx network, including `ic`, for
+    /// which dfx records canister IDs in `canister_ids.json`. All of them are on
+    /// connected networks.
+    pub(crate) environments: BTreeSet<String>,
+
+    /// The names of the canisters that were converted
+    pub(crate) canisters: Vec<String>,
+
+    /// What could not be converted as is
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Converts `dfx`. `has_package_json` tells whether the project has a
+/// `package.json`, whose build dfx runs for asset canisters.
+pub(crate) fn convert_dfx_json(dfx: DfxJson, has_package_json: bool) -> ConvertedProject {
+    let mut warnings = Vec::new();
+    let mut canisters = Vec::new();
+    let mut canister_items = Vec::new();
+
+    for (name, canister) in dfx.canisters {
+        let kind = canister.kind.as_deref().unwrap_or("custom");
+        let mut item = Mapping::new();
+        item.insert("name".into(), name.clone().into());
+
+        match kind {
+            "rust" => {
+                let mut configuration = Mapping::new();
+                insert_some(&mut configuration, "package", canister.package);
+                insert_some(&mut configuration, "candid", canister.candid);
+                item.insert("recipe".into(), recipe(RUST_RECIPE, configuration));
+            }
+            "motoko" => {
+                let mut configuration = Mapping::new();
+                insert_some(&mut configuration, "main", canister.main);
+                insert_some(&mut configuration, "candid", canister.candid);
+                item.insert("recipe".into(), recipe(MOTOKO_RECIPE, configuration));
+            }
+            "assets" => {
+                let mut sources = canister.source.into_iter();
+                let mut configuration = Mapping::new();
+                insert_some(&mut configuration, "dir", sources.next());
+                if sources.next().is_some() {
+                    warnings.push(format!(
+                        "asset canister `{name}` has several source directories; only the first is used"
+                    ));
+                }
+                let build = match canister.build {
+                    Some(build) => build.into_vec(),
+                    None if has_package_json => {
+                        vec!["npm install".to_string(), "npm run build".to_string()]
+                    }
+                    None => vec![],
+                };
+                if !build.is_empty() {
+                    configuration.insert("build".into(), strings(build));
+                }
+                item.insert("recipe".into(), recipe(ASSETS_RECIPE, configuration));
+            }
+            "custom" => {
+                let Some(wasm) = canister.wasm else {
+                    warnings.push(format!(
+                        "custom canister `{name}` has no `wasm` and was not converted"
+                    ));
+                    continue;
+                };
+                let commands = canister.build.map(OneOrMany::into_vec).unwrap_or_default();
+                let mut step = Mapping::new();
+                if commands.is_empty() {
+                    step.insert("type".into(), "pre-built".into());
+                    let field = if wasm.starts_with("https://") || wasm.starts_with("http://") {
+                        "url"
+                    } else {
+                        "path"
+                    };
+                    step.insert(field.into(), wasm.into());
+                } else {
+                    let mut commands = commands;
+                    commands.push(format!("cp {wasm} \"$ICP_WASM_OUTPUT_PATH\""));
+                    step.insert("type".into(), "script".into());
+                    step.insert("commands".into(), strings(commands));
+                }
+                let mut build = Mapping::new();
+                build.insert("steps".into(), Value::Sequence(vec![step.into()]));
+                item.insert("build".into(), build.into());
+            }
+            other => {
+                warnings.push(format!(
+                    "canister `{name}` of type `{other}` has no icp-cli counterpart and was not converted"
+                ));
+                continue;
+            }
+        }
+
+        if let Some(init_arg) = canister.init_arg {
+            item.insert("init_args".into(), init_arg.into());
+        } else if let Some(path) = canister.init_arg_file {
+            let mut init_args = Mapping::new();
+            init_args.insert("path".into(), path.into());
+            item.insert("init_args".into(), init_args.into());
+        }
+
+        canisters.push(name);
+        canister_items.push(Value::Mapping(item));
+    }
+
+    let mut networks = Vec::new();
+    let mut environments = Vec::new();
+    let mut connected = BTreeSet::from(["ic".to_string()]);
+    for (name, network) in dfx.networks {
+        if name == "ic" {
+            continue;
+        }
+
+        let mut item = Mapping::new();
+        item.insert("name".into(), name.clone().into());
+        let persistent = match network.kind.as_deref() {
+            Some(kind) => kind == "persistent",
+            None => !network.providers.is_empty(),
+        };
+        if persistent {
+            let mut providers = network.providers.into_iter();
+            let Some(url) = providers.next() else {
+                warnings.push(format!(
+                    "network `{name}` has no providers and was not converted"
+                ));
+                continue;
+            };
+            if providers.next().is_some() {
+                warnings.push(format!(
+                    "network `{name}` has several providers; only `{url}` is used"
+                ));
+            }
+            let mainnet = Url::parse(&url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| MAINNET_HOSTS.contains(&host)))
+                .unwrap_or(false);
+            if !mainnet {
+                warnings.push(format!(
+                    "network `{name}` fetches its root key on each use; pin it with `root-key` in icp.yaml"
+                ));
+            }
+            item.insert("mode".into(), "connected".into());
+            item.insert("url".into(), url.into());
+            item.insert(
+                "root-key".into(),
+                if mainnet { "mainnet" } else { "fetch" }.into(),
+            );
+            connected.insert(name.clone());
+        } else {
+            let Some(bind) = network.bind else {
+                // The implicit `local` network, or one dfx would set up the same way
+                if name != "local" {
+                    warnings.push(format!(
+                        "local network `{name}` has no `bind` address and was not converted"
+                    ));
+                }
+                continue;
+            };
+            item.insert("mode".into(), "managed".into());
+            let mut gateway = Mapping::new();
+            match bind.rsplit_once(':') {
+                Some((host, port)) if port.parse::<u16>().is_ok() => {
+                    gateway.insert("bind".into(), host.into());
+                    gateway.insert(
+                        "port".into(),
+                        port.parse::<u16>().expect("checked above").into(),
+                    );
+                }
+                _ => {
+                    gateway.insert("bind".into(), bind.into());
+                }
+            }
+            item.insert("gateway".into(), gateway.into());
+        }
+
+        if name != "local" {
+            let mut environment = Mapping::new();
+            environment.insert("name".into(), name.clone().into());
+            environment.insert("network".into(), name.into());
+            environments.push(Value::Mapping(environment));
+        }
+        networks.push(Value::Mapping(item));
+    }
+
+    let mut doc = Mapping::new();
+    doc.insert("canisters".into(), Value::Sequence(canister_items));
+    if !networks.is_empty() {
+        doc.insert("networks".into(), Value::Sequence(networks));
+    }
+    if !environments.is_empty() {
+        doc.insert("environments".into(), Value::Sequence(environments));
+    }
+    let yaml = format!(
+        "# Converted from dfx.json by `icp project import-dfx`\n{}",
+        serde_yaml::to_string(&doc).expect("infallible yaml serialization")
+    );
+
+    ConvertedProject {
+        yaml,
+        environments: connected,
+        canisters,
+        warnings,
+    }
+}
+
+fn recipe(recipe_type: &str, configuration: Mapping) -> Value {
+    let mut recipe = Mapping::new();
+    recipe.insert("type".into(), recipe_type.into());
+    if !configuration.is_empty() {
+        recipe.insert("configuration".into(), configuration.into());
+    }
+    recipe.into()
+}
+
+fn insert_some(map: &mut Mapping, key: &str, value: Option<String>) {
+    if let Some(value) = value {
+        map.insert(key.into(), value.into());
+    }
+}
+
+fn strings(values: Vec<String>) -> Value {
+    Value::Sequence(values.into_iter().map(Value::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use icp::manifest::ProjectManifest;
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn converts_canisters_and_networks() {
+        let dfx: DfxJson = serde_json::from_str(
+            r#"{
+                "canisters": {
+                    "backend": { "type": "rust", "package": "backend", "candid": "src/backend/backend.did" },
+                    "frontend": { "type": "assets", "source": ["dist"], "dependencies": ["backend"] },
+                    "ledger": { "type": "custom", "wasm": "ledger.wasm.gz", "candid": "ledger.did", "init_arg": "(record {})" },
+                    "tool": { "type": "custom", "wasm": "out/tool.wasm", "build": "make tool" },
+                    "ii": { "type": "pull", "id": "rdmx6-jaaaa-aaaaa-aaadq-cai" }
+                },
+                "networks": {
+                    "local": { "bind": "127.0.0.1:4943", "type": "ephemeral" },
+                    "staging": { "providers": ["https://icp0.io"], "type": "persistent" },
+                    "testnet": { "providers": ["https://testnet.example.com"] }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let converted = convert_dfx_json(dfx, true);
+
+        assert_eq!(
+            converted.yaml,
+            indoc! {r#"
+                # Converted from dfx.json by `icp project import-dfx`
+                canisters:
+                - name: backend
+                  recipe:
+                    type: '@dfinity/rust@v3.0.0'
+                    configuration:
+                      package: backend
+                      candid: src/backend/backend.did
+                - name: frontend
+                  recipe:
+                    type: '@dfinity/asset-canister@v2.2.1'
+                    configuration:
+                      dir: dist
+                      build:
+                      - npm install
+                      - npm run build
+                - name: ledger
+                  build:
+                    steps:
+                    - type: pre-built
+                      path: ledger.wasm.gz
+                  init_args: (record {})
+                - name: tool
+                  build:
+                    steps:
+                    - type: script
+                      commands:
+                      - make tool
+                      - cp out/tool.wasm "$ICP_WASM_OUTPUT_PATH"
+                networks:
+                - name: local
+                  mode: managed
+                  gateway:
+                    bind: 127.0.0.1
+                    port: 4943
+                - name: staging
+                  mode: connected
+                  url: https://icp0.io
+                  root-key: mainnet
+                - name: testnet
+                  mode: connected
+                  url: https://testnet.example.com
+                  root-key: fetch
+                environments:
+                - name: staging
+                  network: staging
+                - name: testnet
+                  network: testnet
+            "#}
+        );
+        assert_eq!(
+            converted.canisters,
+            ["backend", "frontend", "ledger", "tool"]
+        );
+        assert_eq!(
+            converted.environments.iter().collect::<Vec<_>>(),
+            ["ic", "staging", "testnet"]
+        );
+        assert_eq!(converted.warnings.len(), 2);
+
+        // The result is a valid project manifest
+        serde_yaml::from_str::<ProjectManifest>(&converted.yaml).unwrap();
+    }
+}
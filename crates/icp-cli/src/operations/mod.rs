@@ -4,10 +4,14 @@ pub(crate) mod bundle;
 pub(crate) mod candid_compat;
 pub(crate) mod canister_migration;
 pub(crate) mod create;
+pub(crate) mod import_dfx;
 pub(crate) mod install;
+pub(crate) mod promote;
 pub(crate) mod proxy;
 pub(crate) mod proxy_management;
 pub(crate) mod recover_cycles;
+pub(crate) mod remote;
+pub(crate) mod rotate;
 pub(crate) mod settings;
 pub(crate) mod snapshot_transfer;
 pub(crate) mod sync;
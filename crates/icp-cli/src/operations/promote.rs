@@ -0,0 +1,248 @@
+use candid::Principal;
+use ic_agent::{Agent, AgentError};
+use ic_management_canister_types::{
+    CanisterIdRecord, CanisterStatusType, DeleteCanisterSnapshotArgs, TakeCanisterSnapshotArgs,
+};
+use icp::store_artifact::{self, LookupArtifactError, SaveError};
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+use tracing::{info, warn};
+
+use super::{
+    proxy::UpdateOrProxyError,
+    proxy_management,
+    snapshot_transfer::{self, BlobType, SnapshotTransferError},
+};
+
+#[derive(Debug, Snafu)]
+pub(crate) enum PromoteError {
+    #[snafu(display(
+        "failed to read the module hash of canister '{canister}' in environment '{environment}'"
+    ))]
+    ReadModuleHash {
+        source: AgentError,
+        canister: String,
+        environment: String,
+    },
+
+    #[snafu(display(
+        "canister '{canister}' has no module installed in environment '{environment}'"
+    ))]
+    NoModule {
+        canister: String,
+        environment: String,
+    },
+
+    #[snafu(display(
+        "no local build of canister '{canister}' to promote; build it from the source \
+         deployed to environment '{environment}' first"
+    ))]
+    LookupArtifact {
+        source: LookupArtifactError,
+        canister: String,
+        environment: String,
+    },
+
+    #[snafu(display(
+        "the local build of canister '{canister}' (module hash 0x{local}) is not the module \
+         installed in environment '{environment}' (module hash 0x{installed}); build it from \
+         the source deployed there first"
+    ))]
+    ModuleHashMismatch {
+        canister: String,
+        environment: String,
+        local: String,
+        installed: String,
+    },
+
+    #[snafu(display(
+        "failed to snapshot canister '{canister}' in environment '{environment}' to fetch its module"
+    ))]
+    SnapshotSource {
+        #[snafu(source(from(UpdateOrProxyError, Box::new)))]
+        source: Box<UpdateOrProxyError>,
+        canister: String,
+        environment: String,
+    },
+
+    #[snafu(display(
+        "failed to download the module of canister '{canister}' from its snapshot in \
+         environment '{environment}'"
+    ))]
+    DownloadModule {
+        source: SnapshotTransferError,
+        canister: String,
+        environment: String,
+    },
+
+    #[snafu(display(
+        "the module in the snapshot of canister '{canister}' in environment '{environment}' \
+         does not have the installed module hash"
+    ))]
+    SnapshotModuleMismatch {
+        canister: String,
+        environment: String,
+    },
+
+    #[snafu(display(
+        "failed to store the module of canister '{canister}' fetched from '{environment}'"
+    ))]
+    SaveFetchedModule {
+        source: SaveError,
+        canister: String,
+        environment: String,
+    },
+}
+
+/// Checks that the local build of a canister is exactly the module installed in
+/// `environment`, so that deploying the build promotes what that environment
+/// runs rather than a rebuild of it.
+///
+/// With `fetch`, a missing or different local build is replaced by the installed
+/// module, downloaded from a snapshot of the canister.
+pub(crate) async fn verify_artifact(
+    agent: &Agent,
+    environment: &str,
+    canister: &str,
+    cid: Principal,
+    artifacts: &dyn store_artifact::Access,
+    fetch: bool,
+) -> Result<(), PromoteError> {
+    let installed = match agent.read_state_canister_module_hash(cid).await {
+        Ok(hash) => hash,
+        Err(AgentError::LookupPathAbsent(_)) => {
+            return NoModuleSnafu {
+                canister,
+                environment,
+            }
+            .fail();
+        }
+        Err(source) => {
+            return Err(source).context(ReadModuleHashSnafu {
+                canister,
+                environment,
+            });
+        }
+    };
+
+    let local = match artifacts.lookup(canister).await {
+        Ok(wasm) => Sha256::digest(&wasm).to_vec(),
+        Err(_) if fetch => {
+            return fetch_module(agent, environment, canister, cid, &installed, artifacts).await;
+        }
+        Err(source) => {
+            return Err(source).context(LookupArtifactSnafu {
+                canister,
+                environment,
+            });
+        }
+    };
+
+    if local != installed {
+        if fetch {
+            return fetch_module(agent, environment, canister, cid, &installed, artifacts).await;
+        }
+        return ModuleHashMismatchSnafu {
+            canister,
+            environment,
+            local: hex::encode(local),
+            installed: hex::encode(installed),
+        }
+        .fail();
+    }
+    Ok(())
+}
+
+/// Downloads the module installed in a canister through a snapshot and stores
+/// it as the canister's local build.
+///
+/// Snapshots can only be taken of stopped canisters, so a running canister is
+/// stopped for the snapshot and started again right after. The snapshot is
+/// deleted once its module is downloaded.
+async fn fetch_module(
+    agent: &Agent,
+    environment: &str,
+    canister: &str,
+    cid: Principal,
+    installed: &[u8],
+    artifacts: &dyn store_artifact::Access,
+) -> Result<(), PromoteError> {
+    info!("Fetching the module of canister '{canister}' from a snapshot in '{environment}'");
+
+    let record = CanisterIdRecord { canister_id: cid };
+    let snapshot_context = SnapshotSourceSnafu {
+        canister,
+        environment,
+    };
+    let status = proxy_management::canister_status(agent, None, record.clone())
+        .await
+        .context(snapshot_context)?;
+    let restart = status.status != CanisterStatusType::Stopped;
+    if restart {
+        proxy_management::stop_canister(agent, None, record.clone())
+            .await
+            .context(snapshot_context)?;
+    }
+    let snapshot = proxy_management::take_canister_snapshot(
+        agent,
+        None,
+        TakeCanisterSnapshotArgs {
+            canister_id: cid,
+            replace_snapshot: None,
+            uninstall_code: None,
+            sender_canister_version: None,
+        },
+    )
+    .await;
+    if restart {
+        proxy_management::start_canister(agent, None, record)
+            .await
+            .context(snapshot_context)?;
+    }
+    let snapshot = snapshot.context(snapshot_context)?;
+
+    let download_context = DownloadModuleSnafu {
+        canister,
+        environment,
+    };
+    let wasm = async {
+        let metadata =
+            snapshot_transfer::read_snapshot_metadata(agent, None, cid, &snapshot.id).await?;
+        snapshot_transfer::read_blob(
+            agent,
+            None,
+            cid,
+            &snapshot.id,
+            BlobType::WasmModule,
+            metadata.wasm_module_size,
+        )
+        .await
+    }
+    .await;
+    let delete = DeleteCanisterSnapshotArgs {
+        canister_id: cid,
+        snapshot_id: snapshot.id.clone(),
+    };
+    if let Err(err) = proxy_management::delete_canister_snapshot(agent, None, delete).await {
+        warn!(
+            "failed to delete snapshot {} of canister '{canister}': {err}",
+            hex::encode(&snapshot.id)
+        );
+    }
+    let wasm = wasm.context(download_context)?;
+
+    if Sha256::digest(&wasm).as_slice() != installed {
+        return SnapshotModuleMismatchSnafu {
+            canister,
+            environment,
+        }
+        .fail();
+    }
+    artifacts
+        .save(canister, &wasm)
+        .await
+        .context(SaveFetchedModuleSnafu {
+            canister,
+            environment,
+        })
+}
@@ -0,0 +1,207 @@
+//! Resolving the remote artifacts a project references: pre-built wasms, sync
+//! plugins and network launchers. Each is served from the package cache when
+//! possible and downloaded otherwise, unless offline.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Context as _, bail};
+use icp::{
+    Canister, Project,
+    canister::{
+        sync::{ResolvePluginError, resolve_plugin_wasm},
+        wasm::{self, WasmError},
+    },
+    context::Context,
+    manifest::{BuildStep, SyncStep, prebuilt::SourceField},
+    network::{
+        ManagedLauncherConfig,
+        managed::cache::{
+            check_launcher_update_available, download_launcher_version,
+            get_cached_launcher_version, get_cached_launcher_version_if_fresh,
+        },
+    },
+    prelude::*,
+};
+use tracing::{debug, info};
+
+use crate::progress::{ProgressManager, ProgressManagerSettings};
+
+/// Every canister of the project as rendered at the project level and for each
+/// environment: recipes render per environment, so an environment can download
+/// what the project-level rendering does not.
+pub(crate) fn project_canisters(project: &Project) -> impl Iterator<Item = (&Path, &Canister)> {
+    project
+        .canisters
+        .values()
+        .chain(
+            project
+                .environments
+                .values()
+                .flat_map(|env| env.canisters.values()),
+        )
+        .map(|(path, c)| (path.as_path(), c))
+}
+
+/// Resolve the remote pre-built wasms and the sync plugins of `canisters`,
+/// downloading what the package cache lacks and pinning what floats in icp.lock.
+///
+/// Offline, every wasm and plugin missing from the package cache is reported at
+/// once, rather than only the first a build or sync step runs into.
+pub(crate) async fn resolve_canister_downloads<'a>(
+    ctx: &Context,
+    canisters: impl IntoIterator<Item = (&'a Path, &'a Canister)>,
+) -> Result<(), anyhow::Error> {
+    let pkg_cache = ctx.dirs.package_cache()?;
+    let mut not_cached = BTreeSet::new();
+    for (path, c) in canisters {
+        for step in &c.build.steps {
+            if let BuildStep::Prebuilt(adapter) = step
+                && let SourceField::Remote(_) = adapter.source
+            {
+                match wasm::resolve(
+                    &adapter.source,
+                    path,
+                    adapter.sha256.as_deref(),
+                    None,
+                    &pkg_cache,
+                    ctx.lockfile.as_ref(),
+                    ctx.offline,
+                )
+                .await
+                {
+                    Err(WasmError::NotCached { artifact }) => {
+                        not_cached.insert(artifact);
+                    }
+                    result => {
+                        result.with_context(|| {
+                            format!("failed to resolve wasm of canister '{}'", c.name)
+                        })?;
+                    }
+                }
+            }
+        }
+        for step in &c.sync.steps {
+            if let SyncStep::Plugin(adapter) = step {
+                match resolve_plugin_wasm(
+                    adapter,
+                    path,
+                    None,
+                    &pkg_cache,
+                    ctx.lockfile.as_ref(),
+                    ctx.offline,
+                )
+                .await
+                {
+                    Err(ResolvePluginError::Wasm {
+                        source: WasmError::NotCached { artifact },
+                    }) => {
+                        not_cached.insert(artifact);
+                    }
+                    result => {
+                        result.with_context(|| {
+                            format!("failed to resolve sync plugin of canister '{}'", c.name)
+                        })?;
+                    }
+                }
+            }
+        }
+    }
+    if !not_cached.is_empty() {
+        bail!(
+            "wasms and plugins are not in the package cache and cannot be downloaded in \
+             offline mode; run `icp project prefetch` while online:{}",
+            not_cached
+                .iter()
+                .map(|a| format!("\n  {a}"))
+                .collect::<String>()
+        );
+    }
+    Ok(())
+}
+
+/// Resolve the launcher of a managed network to the path of its binary,
+/// downloading it if necessary.
+///
+/// A launcher without a declared version floats to the latest release, pinned
+/// in icp.lock once resolved. Offline, whatever version was last downloaded is
+/// used, however stale.
+pub(crate) async fn resolve_launcher(
+    ctx: &Context,
+    network: &str,
+    cfg: &ManagedLauncherConfig,
+) -> Result<PathBuf, anyhow::Error> {
+    let pinned = match &cfg.version {
+        Some(_) => None,
+        None => ctx.lockfile.load()?.launcher(network).map(str::to_owned),
+    };
+    let version = cfg
+        .version
+        .as_deref()
+        .or(pinned.as_deref())
+        .unwrap_or("latest");
+    let update_hint = if pinned.is_some() {
+        "icp project update-lock"
+    } else {
+        "icp network update"
+    };
+    let debug = ctx.debug;
+    let client = reqwest::Client::new();
+    let (resolved, path) = ctx
+        .dirs
+        .package_cache()?
+        .with_write(async |pkg| {
+            if ctx.offline {
+                return get_cached_launcher_version(pkg.read(), version)?.with_context(|| {
+                    format!(
+                        "icp-cli-network-launcher {version} is not in the package cache and \
+                         cannot be downloaded in offline mode; run `icp project prefetch` while online"
+                    )
+                });
+            }
+            // Resolve the declared version to a real version, if it's fresh
+            // A fresh version is one that is either specified exactly, or was last updated since icp-cli was updated
+            if let Some((resolved, path)) =
+                get_cached_launcher_version_if_fresh(pkg.read(), version)?
+            {
+                // The version has already been downloaded. Use it, but first, check for updates and nag if so
+                if let Some(update) = check_launcher_update_available(pkg, &resolved, &client).await {
+                    info!("A newer network launcher version is available: {update} (current: {resolved}). Run `{update_hint}` to update.");
+                }
+                anyhow::Ok((resolved, path))
+            } else {
+                // The version is not fresh or not cached, download it
+                debug!("Downloading icp-cli-network-launcher version `{version}`");
+                let progress_manager =
+                    ProgressManager::new(ProgressManagerSettings { hidden: debug });
+                let pb = progress_manager.create_independent_progress_bar();
+                pb.set_message(format!("Downloading icp-cli-network-launcher {version}..."));
+                let version_slot: Arc<OnceLock<String>> = Arc::new(OnceLock::new());
+                let version_capture = version_slot.clone();
+                let version_report = version_slot.clone();
+                let path = ProgressManager::execute_with_progress(
+                    &pb,
+                    async {
+                        let (ver, path) =
+                            download_launcher_version(pkg, version, &client).await?;
+                        let _ = version_capture.set(ver);
+                        anyhow::Ok(path)
+                    },
+                    move || {
+                        let ver = version_report.get().map(String::as_str).unwrap();
+                        format!("Downloaded icp-cli-network-launcher {ver}")
+                    },
+                    |err| format!("Failed to download icp-cli-network-launcher: {err}"),
+                )
+                .await?;
+                let resolved = version_slot.get().cloned().unwrap();
+                Ok((resolved, path))
+            }
+        })
+        .await??;
+    if cfg.version.is_none() {
+        ctx.lockfile
+            .update(&mut |lock| lock.pin_launcher(network, &resolved))?;
+    }
+    Ok(path)
+}
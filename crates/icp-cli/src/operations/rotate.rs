@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+
+use candid::Principal;
+use ic_agent::{Agent, AgentError};
+use ic_management_canister_types::{CanisterIdRecord, CanisterSettings, UpdateSettingsArgs};
+use icp::{
+    fs::{create_dir_all, json},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu, ensure};
+
+use super::{proxy::UpdateOrProxyError, proxy_management};
+
+#[derive(Debug, Snafu)]
+pub(crate) enum RotationProgressError {
+    #[snafu(display("failed to read the rotation progress at {path}"))]
+    ReadProgress { source: json::Error, path: PathBuf },
+
+    #[snafu(display("failed to create the directory of the rotation progress at {path}"))]
+    CreateProgressDir {
+        source: icp::fs::IoError,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to record the rotation progress at {path}"))]
+    WriteProgress { source: json::Error, path: PathBuf },
+
+    #[snafu(display(
+        "a rotation of {old} to {new} is in progress in this environment; finish it first, \
+         or delete {path} to abandon it"
+    ))]
+    OtherRotation {
+        path: PathBuf,
+        old: Principal,
+        new: Principal,
+    },
+}
+
+#[derive(Debug, Snafu)]
+pub(crate) enum RotateError {
+    #[snafu(transparent)]
+    Progress { source: RotationProgressError },
+
+    #[snafu(display("failed to read the controllers of canister '{canister}'"))]
+    ReadControllers {
+        source: AgentError,
+        canister: String,
+    },
+
+    #[snafu(display("failed to add the new controller to canister '{canister}'"))]
+    AddController {
+        source: UpdateOrProxyError,
+        canister: String,
+    },
+
+    #[snafu(display(
+        "the new identity could not call canister_status on canister '{canister}', so the old \
+         identity was left a controller"
+    ))]
+    VerifyControl {
+        source: UpdateOrProxyError,
+        canister: String,
+    },
+
+    #[snafu(display("failed to remove the old controller from canister '{canister}'"))]
+    RemoveController {
+        source: UpdateOrProxyError,
+        canister: String,
+    },
+}
+
+/// How far the rotation of one canister got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RotationStage {
+    /// The new controller was added
+    Added,
+    /// The new controller called `canister_status`
+    Verified,
+    /// The old controller was removed
+    Done,
+}
+
+/// The progress of rotating one principal out of the controllers of an
+/// environment's canisters, kept so that an interrupted rotation can continue.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RotationProgress {
+    pub(crate) old: Principal,
+    pub(crate) new: Principal,
+    pub(crate) canisters: BTreeMap<String, RotationStage>,
+}
+
+impl RotationProgress {
+    /// The progress file of a rotation in `environment`.
+    pub(crate) fn path(data_dir: &Path, environment: &str) -> PathBuf {
+        data_dir
+            .join("rotations")
+            .join(format!("{environment}.json"))
+    }
+
+    /// The progress of rotating `old` to `new` recorded at `path`, or a fresh
+    /// start if there is none.
+    pub(crate) fn load_or_start(
+        path: &Path,
+        old: Principal,
+        new: Principal,
+    ) -> Result<Self, RotationProgressError> {
+        if !path.exists() {
+            return Ok(Self {
+                old,
+                new,
+                canisters: BTreeMap::new(),
+            });
+        }
+
+        let progress: Self = json::load(path).context(ReadProgressSnafu { path })?;
+        ensure!(
+            progress.old == old && progress.new == new,
+            OtherRotationSnafu {
+                path,
+                old: progress.old,
+                new: progress.new,
+            }
+        );
+        Ok(progress)
+    }
+
+    fn record(
+        &mut self,
+        path: &Path,
+        canister: &str,
+        stage: RotationStage,
+    ) -> Result<(), RotationProgressError> {
+        self.canisters.insert(canister.to_owned(), stage);
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir).context(CreateProgressDirSnafu { path })?;
+        }
+        json::save(path, self).context(WriteProgressSnafu { path })
+    }
+}
+
+/// Rotates the old principal of `progress` out of the controllers of one
+/// canister: adds the new principal as `old_agent`, checks that `new_agent`
+/// can call `canister_status`, then removes the old principal as `new_agent`.
+/// Each step is recorded at `path`.
+///
+/// Returns whether the canister is rotated, by this call or an earlier one;
+/// `false` if the old principal never controlled it.
+pub(crate) async fn rotate_controller(
+    old_agent: &Agent,
+    new_agent: &Agent,
+    canister: &str,
+    cid: Principal,
+    progress: &mut RotationProgress,
+    path: &Path,
+) -> Result<bool, RotateError> {
+    let (old, new) = (progress.old, progress.new);
+    let controllers = new_agent
+        .read_state_canister_controllers(cid)
+        .await
+        .context(ReadControllersSnafu { canister })?;
+
+    if !controllers.contains(&old) {
+        // Either an earlier run removed it, or it was never a controller
+        return Ok(progress.canisters.contains_key(canister));
+    }
+
+    if !controllers.contains(&new) {
+        let with_new = controllers.iter().copied().chain([new]).collect();
+        set_controllers(old_agent, cid, with_new)
+            .await
+            .context(AddControllerSnafu { canister })?;
+    }
+    progress.record(path, canister, RotationStage::Added)?;
+
+    proxy_management::canister_status(new_agent, None, CanisterIdRecord { canister_id: cid })
+        .await
+        .context(VerifyControlSnafu { canister })?;
+    progress.record(path, canister, RotationStage::Verified)?;
+
+    let without_old = controllers
+        .iter()
+        .copied()
+        .filter(|c| *c != old && *c != new)
+        .chain([new])
+        .collect();
+    set_controllers(new_agent, cid, without_old)
+        .await
+        .context(RemoveControllerSnafu { canister })?;
+    progress.record(path, canister, RotationStage::Done)?;
+
+    Ok(true)
+}
+
+async fn set_controllers(
+    agent: &Agent,
+    cid: Principal,
+    controllers: Vec<Principal>,
+) -> Result<(), UpdateOrProxyError> {
+    proxy_management::update_settings(
+        agent,
+        None,
+        UpdateSettingsArgs {
+            canister_id: cid,
+            settings: CanisterSettings {
+                controllers: Some(controllers),
+                ..Default::default()
+            },
+            sender_canister_version: None,
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use camino_tempfile::Utf8TempDir;
+
+    use super::*;
+
+    #[test]
+    fn progress_continues_the_same_rotation_only() {
+        let tmp = Utf8TempDir::new().unwrap();
+        let path = RotationProgress::path(tmp.path(), "ic");
+        let (old, new) = (
+            Principal::self_authenticating([1]),
+            Principal::self_authenticating([2]),
+        );
+
+        let mut progress = RotationProgress::load_or_start(&path, old, new).unwrap();
+        assert!(progress.canisters.is_empty());
+        progress
+            .record(&path, "backend", RotationStage::Added)
+            .unwrap();
+
+        let progress = RotationProgress::load_or_start(&path, old, new).unwrap();
+        assert_eq!(progress.canisters["backend"], RotationStage::Added);
+
+        assert!(matches!(
+            RotationProgress::load_or_start(&path, old, Principal::anonymous()),
+            Err(RotationProgressError::OtherRotation { .. })
+        ));
+    }
+}
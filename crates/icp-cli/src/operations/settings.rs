@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     sync::Arc,
 };
 
@@ -11,8 +11,8 @@ use ic_management_canister_types::{
 };
 use icp::{
     Canister,
-    canister::{Settings, resolve_controllers},
-    context::{Context, EnvironmentSelection},
+    canister::{Settings, resolve_controllers, secret::SecretError},
+    context::{CanisterSelection, Context, EnvironmentSelection, NetworkSelection},
     store_id::IdMapping,
 };
 use itertools::Itertools;
@@ -38,6 +38,11 @@ pub(crate) enum SyncSettingsOperationError {
         source: UpdateOrProxyError,
         canister: Principal,
     },
+    #[snafu(display("failed to read a secret environment variable of canister {canister}"))]
+    ResolveSecret {
+        source: SecretError,
+        canister: Principal,
+    },
 }
 
 #[derive(Debug, Snafu)]
@@ -76,6 +81,63 @@ fn environment_variables_eq(a: &[EnvironmentVariable], b: &[EnvironmentVariable]
     map_a == map_b
 }
 
+/// The names of the environment variables the project reads from a secret for
+/// canister `cid`, selected as `canister`.
+///
+/// A canister selected by name is looked up in the selected environment. One
+/// selected by principal is looked up by its ID, in the selected environment or,
+/// when a network is selected instead, in every environment of the project.
+/// Empty when it is not a canister of the project.
+pub(crate) async fn secret_names(
+    ctx: &Context,
+    canister: &CanisterSelection,
+    cid: Principal,
+    environment: &EnvironmentSelection,
+    network: &NetworkSelection,
+) -> BTreeSet<String> {
+    let environments = match (environment, network) {
+        (EnvironmentSelection::Default, NetworkSelection::Named(_) | NetworkSelection::Url(..)) => {
+            let Ok(p) = ctx.project.load().await else {
+                return BTreeSet::new();
+            };
+            p.environments
+                .keys()
+                .map(|name| EnvironmentSelection::Named(name.clone()))
+                .collect()
+        }
+        _ => vec![environment.clone()],
+    };
+
+    let mut secrets = BTreeSet::new();
+    for environment in &environments {
+        let Ok(env) = ctx.get_environment(environment).await else {
+            continue;
+        };
+        let name = match canister {
+            CanisterSelection::Named(name) => Some(name.clone()),
+            CanisterSelection::Principal(_) => ctx
+                .ids_by_environment(environment)
+                .await
+                .ok()
+                .and_then(|ids| ids.into_iter().find(|(_, id)| *id == cid))
+                .map(|(name, _)| name),
+        };
+        if let Some((_, canister)) = name.and_then(|name| env.canisters.get(&name)) {
+            secrets.extend(canister.secret_environment_variables.keys().cloned());
+        }
+    }
+    secrets
+}
+
+/// Masks the values of the environment variables named in `secrets`.
+pub(crate) fn redact_secrets(variables: &mut [EnvironmentVariable], secrets: &BTreeSet<String>) {
+    for v in variables {
+        if secrets.contains(&v.name) {
+            v.value = "<redacted>".to_string();
+        }
+    }
+}
+
 /// Syncs the manifest settings to the canister. Returns names of any controller canister
 /// references that could not be resolved because the referenced canister has not been created
 /// yet. Resolved controllers are always applied immediately.
@@ -99,9 +161,13 @@ pub(crate) async fn sync_settings(
         ref wasm_memory_limit,
         ref wasm_memory_threshold,
         ref log_memory_limit,
-        ref environment_variables,
+        environment_variables: _,
         ref controllers,
     } = &canister.settings;
+    let environment_variables = canister
+        .environment_variables()
+        .await
+        .context(ResolveSecretSnafu { canister: *cid })?;
     let current_settings = status.settings;
 
     // Convert our log_visibility to IC type for comparison and update
@@ -618,6 +618,41 @@ pub async fn download_blob_to_file(
     Ok(())
 }
 
+/// Read a whole blob of a snapshot into memory.
+pub async fn read_blob(
+    agent: &Agent,
+    proxy: Option<Principal>,
+    canister_id: Principal,
+    snapshot_id: &[u8],
+    blob_type: BlobType,
+    total_size: u64,
+) -> Result<Vec<u8>, SnapshotTransferError> {
+    let mut blob = Vec::with_capacity(total_size as usize);
+    let mut offset = 0u64;
+    while offset < total_size {
+        let args = ReadCanisterSnapshotDataArgs {
+            canister_id,
+            snapshot_id: snapshot_id.to_vec(),
+            kind: blob_type.make_read_kind(offset, chunk_size_at(offset, total_size)),
+        };
+        let result = with_retry(|| {
+            let args = args.clone();
+            async move { proxy_management::read_canister_snapshot_data(agent, proxy, args).await }
+        })
+        .await
+        .context(ReadDataChunkSnafu { offset })?;
+        if result.chunk.is_empty() {
+            return InvalidSnapshotMetadataSnafu {
+                reason: format!("the blob ends at offset {offset} of {total_size}"),
+            }
+            .fail();
+        }
+        offset += result.chunk.len() as u64;
+        blob.extend(result.chunk);
+    }
+    Ok(blob)
+}
+
 /// Download a single WASM chunk by hash.
 pub async fn download_wasm_chunk(
     agent: &Agent,
@@ -61,6 +61,12 @@ impl EnvironmentOpt {
     pub(crate) fn name(&self) -> &str {
         self.environment.as_deref().unwrap_or(LOCAL)
     }
+
+    pub(crate) fn named(name: String) -> Self {
+        Self {
+            environment: Some(name),
+        }
+    }
 }
 
 impl From<EnvironmentOpt> for EnvironmentSelection {
@@ -3,6 +3,9 @@
 //! Collects anonymous usage data (command name, arguments, duration, outcome)
 //! and periodically ships it in a detached background process. All I/O errors
 //! are silently ignored so telemetry never affects CLI behaviour.
+//!
+//! In local-only mode, records also name the project each command ran in and
+//! are appended to a separate `local.jsonl`, which is never rotated or sent.
 
 use std::{
     io::Write as _,
@@ -20,6 +23,7 @@ use time::OffsetDateTime;
 use crate::version::icp_cli_version_str;
 
 const EVENTS_FILE: &str = "events.jsonl";
+const LOCAL_EVENTS_FILE: &str = "local.jsonl";
 const MACHINE_ID_FILE: &str = "machine-id";
 const NOTICE_SHOWN_FILE: &str = "notice-shown";
 const NEXT_SEND_TIME_FILE: &str = "next-send-time";
@@ -97,6 +101,12 @@ pub(crate) struct TelemetryRecord {
     pub num_canisters: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recipes: Option<Vec<String>>,
+
+    // --- Local-only mode
+    /// Directory of the project the command ran in. Only recorded in
+    /// local-only mode, whose records are never sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
 }
 
 /// Tracks the timing and metadata of one CLI invocation.
@@ -106,6 +116,12 @@ pub(crate) struct TelemetrySession {
     command: String,
     arguments: Vec<Argument>,
     autocontainerize: Option<bool>,
+    /// Offline sessions record their event but leave sending to a later session.
+    offline: bool,
+    /// Local-only sessions record their event in `local.jsonl`, which is never sent.
+    local_only: bool,
+    /// The project the command runs in, recorded in local-only mode only.
+    project: Option<String>,
 }
 
 impl TelemetrySession {
@@ -115,6 +131,9 @@ impl TelemetrySession {
         command: String,
         arguments: Vec<Argument>,
         autocontainerize: Option<bool>,
+        offline: bool,
+        local_only: bool,
+        project: Option<String>,
     ) -> Self {
         Self {
             start: Instant::now(),
@@ -122,6 +141,9 @@ impl TelemetrySession {
             command,
             arguments,
             autocontainerize,
+            offline,
+            local_only,
+            project,
         }
     }
 
@@ -152,10 +174,17 @@ impl TelemetrySession {
             network_type: telemetry_data.network_type(),
             num_canisters: telemetry_data.num_canisters(),
             recipes: telemetry_data.recipes(),
+            project: self.project,
         };
 
-        append_record(&self.telemetry_dir, &record);
-        maybe_send(&self.telemetry_dir);
+        if self.local_only {
+            append_record(&self.telemetry_dir.join(LOCAL_EVENTS_FILE), &record);
+            return;
+        }
+        append_record(&self.telemetry_dir.join(EVENTS_FILE), &record);
+        if !self.offline {
+            maybe_send(&self.telemetry_dir);
+        }
     }
 }
 
@@ -173,25 +202,27 @@ pub(crate) async fn setup(
 
     // Load settings to check the user preference (best-effort; default to enabled).
     // Also capture `autocontainerize` while settings are in hand.
-    let (enabled, autocontainerize) = async {
+    let (enabled, local_only, autocontainerize) = async {
         let dirs = ctx.dirs.settings().ok()?;
         let settings = dirs
             .with_read(async |dirs| Settings::load_from(dirs))
             .await
             .ok()?
             .ok()?;
-        Some((settings.telemetry_enabled, settings.autocontainerize))
+        Some((
+            settings.telemetry_enabled,
+            settings.telemetry_local_only,
+            settings.autocontainerize,
+        ))
     }
     .await
-    .map(|(e, a)| (e, Some(a)))
-    .unwrap_or((true, None));
+    .map(|(e, l, a)| (e, l, Some(a)))
+    .unwrap_or((true, false, None));
 
     if !enabled {
         return None;
     }
 
-    show_notice_if_needed(&telemetry_dir);
-
     // Re-parse raw args into ArgMatches to derive command name and arguments
     // in one pass. This never fails in practice since Cli::parse() already
     // succeeded.
@@ -201,11 +232,27 @@ pub(crate) async fn setup(
         .map(|m| collect_command_and_arguments(&m, clap_command))
         .unwrap_or_default();
 
+    // Inspecting or purging the records must not add to them
+    if cmd_name.split(' ').next() == Some("telemetry") {
+        return None;
+    }
+
+    // Nothing leaves the machine in local-only mode, so there is nothing to notify about
+    let project = if local_only {
+        ctx.project.member_dir().map(|dir| dir.to_string())
+    } else {
+        show_notice_if_needed(&telemetry_dir);
+        None
+    };
+
     Some(TelemetrySession::begin(
         telemetry_dir,
         cmd_name,
         arguments,
         autocontainerize,
+        ctx.offline,
+        local_only,
+        project,
     ))
 }
 
@@ -247,21 +294,54 @@ fn get_or_create_machine_id(telemetry_dir: &Path) -> String {
     id
 }
 
-fn append_record(telemetry_dir: &Path, record: &TelemetryRecord) {
+fn append_record(events_path: &Path, record: &TelemetryRecord) {
     let Ok(line) = serde_json::to_string(record) else {
         return;
     };
-    let events_path = telemetry_dir.join(EVENTS_FILE);
-    let _ = std::fs::create_dir_all(telemetry_dir);
+    if let Some(dir) = events_path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
     if let Ok(mut f) = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(&events_path)
+        .open(events_path)
     {
         let _ = writeln!(f, "{line}");
     }
 }
 
+/// The files holding records that have not been sent yet, oldest first: the
+/// batches rotated out for sending, then `events.jsonl`.
+pub(crate) fn pending_files(telemetry_dir: &Path) -> Vec<PathBuf> {
+    let mut files = list_batches(telemetry_dir);
+    files.sort();
+    let events_path = telemetry_dir.join(EVENTS_FILE);
+    if events_path.exists() {
+        files.push(events_path);
+    }
+    files
+}
+
+/// The file holding the records of local-only mode.
+pub(crate) fn local_file(telemetry_dir: &Path) -> PathBuf {
+    telemetry_dir.join(LOCAL_EVENTS_FILE)
+}
+
+/// Reads the records stored in `files`, as they are stored. Lines that are not
+/// JSON, such as one cut short by a full disk, are skipped.
+pub(crate) fn read_records(files: &[PathBuf]) -> Vec<serde_json::Value> {
+    files
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
 fn unix_now() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -355,14 +435,12 @@ fn maybe_send(telemetry_dir: &Path) {
     spawn_send_batch(&batch_path);
 }
 
-fn cleanup_stale_batches(telemetry_dir: &Path) {
+/// The batch files rotated out of `events.jsonl` that are waiting to be sent.
+fn list_batches(telemetry_dir: &Path) -> Vec<PathBuf> {
     let Ok(entries) = std::fs::read_dir(telemetry_dir) else {
-        return;
+        return Vec::new();
     };
-
-    let cutoff = unix_now().saturating_sub(MAX_BATCH_AGE_SECS);
-
-    let mut batches: Vec<PathBuf> = entries
+    entries
         .flatten()
         .filter_map(|e| {
             let name = e.file_name();
@@ -373,7 +451,13 @@ fn cleanup_stale_batches(telemetry_dir: &Path) {
                 None
             }
         })
-        .collect();
+        .collect()
+}
+
+fn cleanup_stale_batches(telemetry_dir: &Path) {
+    let cutoff = unix_now().saturating_sub(MAX_BATCH_AGE_SECS);
+
+    let mut batches = list_batches(telemetry_dir);
 
     // Delete batches that are too old
     batches.retain(|p| {
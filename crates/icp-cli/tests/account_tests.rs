@@ -0,0 +1,129 @@
+use common::TestContext;
+use predicates::{ord::eq, str::contains};
+
+mod common;
+
+#[test]
+fn account_subaccount_forms() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["account", "subaccount", "index:258"])
+        .assert()
+        .success()
+        .stdout(eq(format!("{:0>64}\n", "0102")));
+    ctx.icp()
+        .args([
+            "account",
+            "subaccount",
+            "principal:ryjl3-tyaaa-aaaaa-aaaba-cai",
+        ])
+        .assert()
+        .success()
+        .stdout(eq(format!("{:0<64}\n", "0a00000000000000020101")));
+    ctx.icp()
+        .args(["account", "subaccount", "index:nope"])
+        .assert()
+        .failure()
+        .stderr(contains("Invalid subaccount index"));
+}
+
+#[test]
+fn account_convert_and_inspect() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["account", "convert", "2vxsx-fae", "--subaccount", "1"])
+        .assert()
+        .success()
+        .stdout(eq("2vxsx-fae-22yutvy.1\n"));
+    ctx.icp()
+        .args([
+            "account",
+            "convert",
+            "2vxsx-fae-22yutvy.1",
+            "--to",
+            "ledger",
+        ])
+        .assert()
+        .success()
+        .stdout(eq(
+            "b8fab0be4ad596a3739ab93e7316a8647ee72e167709441da49ce9171828629d\n",
+        ));
+    ctx.icp()
+        .args([
+            "account",
+            "convert",
+            "2vxsx-fae-22yutvy.1",
+            "--subaccount",
+            "2",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Cannot use --subaccount with an account that has a subaccount",
+        ));
+    ctx.icp()
+        .args([
+            "account",
+            "convert",
+            "b8fab0be4ad596a3739ab93e7316a8647ee72e167709441da49ce9171828629d",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("cannot be converted to an ICRC-1 account"));
+
+    ctx.icp()
+        .args(["account", "inspect", "2vxsx-fae-22yutvy.1"])
+        .assert()
+        .success()
+        .stdout(contains("Owner:       2vxsx-fae"))
+        .stdout(contains(
+            "ICP ledger:  b8fab0be4ad596a3739ab93e7316a8647ee72e167709441da49ce9171828629d",
+        ));
+    ctx.icp()
+        .args([
+            "account",
+            "inspect",
+            "--json",
+            "b8fab0be4ad596a3739ab93e7316a8647ee72e167709441da49ce9171828629d",
+        ])
+        .assert()
+        .success()
+        .stdout(eq(
+            r#"{"format":"ledger","ledger":"b8fab0be4ad596a3739ab93e7316a8647ee72e167709441da49ce9171828629d"}"#,
+        ));
+
+    // Checksums are validated
+    ctx.icp()
+        .args(["account", "inspect", "2vxsx-fae-aaaaaaa.1"])
+        .assert()
+        .failure()
+        .stderr(contains("invalid checksum"));
+    ctx.icp()
+        .args([
+            "account",
+            "inspect",
+            "00fab0be4ad596a3739ab93e7316a8647ee72e167709441da49ce9171828629d",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("checksum does not match"));
+}
+
+#[test]
+fn account_accepts_contacts() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["contacts", "add", "anon", "2vxsx-fae-22yutvy.1"])
+        .assert()
+        .success();
+    ctx.icp()
+        .args(["account", "convert", "@anon", "--to", "ledger"])
+        .assert()
+        .success()
+        .stdout(eq(
+            "b8fab0be4ad596a3739ab93e7316a8647ee72e167709441da49ce9171828629d\n",
+        ));
+}
@@ -269,3 +269,81 @@ fn build_adapter_pre_built_url_valid_checksum() {
         .assert()
         .success();
 }
+
+#[test]
+fn build_adapter_pre_built_url_offline() {
+    let ctx = TestContext::new();
+
+    // Setup project
+    let project_dir = ctx.create_project_dir("icp");
+
+    // Use vendored WASM
+    let wasm = ctx.make_asset("example_icp_mo.wasm");
+    let bs = read(&wasm).expect("failed to load wasm test-file");
+
+    // Spawn HTTP servers, each expecting a single download
+    let server_a = spawn_test_server("GET", "/a.wasm", &bs);
+    let addr_a = server_a.addr();
+    let server_b = spawn_test_server("GET", "/b.wasm", &bs);
+    let addr_b = server_b.addr();
+
+    // Project manifest
+    let pm = formatdoc! {r#"
+        canisters:
+          - name: canister-a
+            build:
+              steps:
+                - type: pre-built
+                  url: http://{addr_a}/a.wasm
+          - name: canister-b
+            build:
+              steps:
+                - type: pre-built
+                  url: http://{addr_b}/b.wasm
+    "#};
+
+    write_string(
+        &project_dir.join("icp.yaml"), // path
+        &pm,                           // contents
+    )
+    .expect("failed to write project manifest");
+
+    ctx.icp()
+        .args(["settings", "offline", "true"])
+        .assert()
+        .success();
+
+    // Every uncached wasm is reported together
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build"])
+        .assert()
+        .failure()
+        .stderr(
+            contains("offline mode")
+                .and(contains(format!("http://{addr_a}/a.wasm")))
+                .and(contains(format!("http://{addr_b}/b.wasm"))),
+        );
+
+    // Prefetching needs the network, which the command line can allow
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env("ICP_CLI_NETWORK_LAUNCHER_PATH", "unused")
+        .args(["project", "prefetch"])
+        .assert()
+        .failure()
+        .stderr(contains("--offline=false"));
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env("ICP_CLI_NETWORK_LAUNCHER_PATH", "unused")
+        .args(["project", "prefetch", "--offline=false"])
+        .assert()
+        .success();
+
+    // Offline now succeeds from the cache
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build"])
+        .assert()
+        .success();
+}
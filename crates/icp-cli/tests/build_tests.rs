@@ -277,6 +277,8 @@ fn build_adapter_display_failing_prebuilt_output() {
     let expected_output = indoc! {r#"
         ERR ----- Failed to build canister 'my-canister' -----
         ERR 'failed to read wasm file at '/nonexistent/path/to/wasm.wasm''
+        ERR   caused by: Filesystem operation failed at /nonexistent/path/to/wasm.wasm
+        ERR   caused by: No such file or directory (os error 2)
         ERR [my-canister] Build output:
         ERR [my-canister] Building: step 2 of 2 (pre-built):
         ERR [my-canister] path: /nonexistent/path/to/wasm.wasm, sha: invalid:
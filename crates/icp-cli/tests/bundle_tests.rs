@@ -428,6 +428,75 @@ fn bundle_inlines_file_backed_env_vars() {
     );
 }
 
+/// Secret environment variables and init args are bundled as their source: the
+/// values are only read when the bundle is deployed.
+#[test]
+fn bundle_keeps_secret_sources() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+    let wasm_src = ctx.make_asset("example_icp_mo.wasm");
+
+    write_string(&project_dir.join("init-args"), "(\"init-s3cret\")\n")
+        .expect("failed to write init args");
+
+    let pm = formatdoc! {r#"
+        canisters:
+          - name: my-canister
+            settings:
+              environment_variables:
+                API_KEY:
+                  env: API_KEY
+            init_args:
+              command: cat init-args
+            build:
+              steps:
+                - type: script
+                  command: cp '{wasm_src}' "$ICP_WASM_OUTPUT_PATH"
+    "#};
+
+    write_string(&project_dir.join("icp.yaml"), &pm).expect("failed to write project manifest");
+
+    let bundle_path = project_dir.join("bundle.tar.gz");
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env("API_KEY", "env-s3cret")
+        .args(["project", "bundle", "--output", bundle_path.as_str()])
+        .assert()
+        .success();
+
+    let bundle_bytes = fs::read(bundle_path.as_std_path()).expect("failed to read bundle");
+    let gz = GzDecoder::new(BufReader::new(bundle_bytes.as_slice()));
+    let mut archive = Archive::new(gz);
+
+    let mut contents = String::new();
+    let mut manifest_yaml = String::new();
+    for entry in archive.entries().expect("failed to read archive entries") {
+        let mut entry = entry.expect("failed to read archive entry");
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .expect("failed to read archive entry");
+        let text = String::from_utf8_lossy(&bytes);
+        contents.push_str(&text);
+        if entry.path().expect("failed to get entry path").as_os_str() == "icp.yaml" {
+            manifest_yaml = text.into_owned();
+        }
+    }
+
+    assert!(
+        manifest_yaml.contains("env: API_KEY"),
+        "the environment variable's source should be bundled; manifest: {manifest_yaml}"
+    );
+    assert!(
+        manifest_yaml.contains("command: cat init-args"),
+        "the init args' source should be bundled; manifest: {manifest_yaml}"
+    );
+    assert!(
+        !contents.contains("env-s3cret") && !contents.contains("init-s3cret"),
+        "no secret value should be bundled"
+    );
+}
+
 /// A file backing an environment variable that lives outside the project must be
 /// rejected, in a canister's own settings as well as in an environment override —
 /// its contents would otherwise be inlined into an archive meant to be handed on.
@@ -814,6 +883,7 @@ fn bundle_rejects_source_outside_project() {
 
 /// The environment reaching build steps as `ICP_CLI_ENVIRONMENT` defaults to `ic`
 /// for a bundle, rather than the `local` the rest of the CLI defaults to.
+/// An environment that is not defined, or lacks canisters, is an error.
 #[test]
 fn bundle_builds_for_ic_by_default() {
     let ctx = TestContext::new();
@@ -830,6 +900,18 @@ fn bundle_builds_for_ic_by_default() {
                   commands:
                     - echo "$ICP_CLI_ENVIRONMENT" > '{recorded}'
                     - cp '{wasm_src}' "$ICP_WASM_OUTPUT_PATH"
+          - name: other-canister
+            build:
+              steps:
+                - type: script
+                  command: cp '{wasm_src}' "$ICP_WASM_OUTPUT_PATH"
+
+        environments:
+          - name: staging
+            network: local
+          - name: partial
+            network: local
+            canisters: [my-canister]
     "#};
 
     write_string(&project_dir.join("icp.yaml"), &pm).expect("failed to write project manifest");
@@ -864,6 +946,41 @@ fn bundle_builds_for_ic_by_default() {
         read_to_string(&recorded).expect("failed to read recorded environment"),
         "staging\n"
     );
+
+    // An environment the project does not define is an error rather than a
+    // bundle rendered for no environment in particular
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "project",
+            "bundle",
+            "--output",
+            bundle_path.as_str(),
+            "--environment",
+            "unknown",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "project does not contain an environment named 'unknown'",
+        ));
+
+    // So is one that leaves out some of the canisters
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "project",
+            "bundle",
+            "--output",
+            bundle_path.as_str(),
+            "--environment",
+            "partial",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "canister 'other-canister' is not in environment 'partial'",
+        ));
 }
 
 /// A plugin's synced directory is often an output of the canister's own build
@@ -0,0 +1,98 @@
+use icp::{fs::create_dir_all, fs::write, prelude::*};
+use predicates::{prelude::*, str::contains};
+
+mod common;
+use common::TestContext;
+
+// Run in portable mode so the package cache is at a known location on every platform
+fn icp(ctx: &TestContext) -> assert_cmd::Command {
+    let mut cmd = ctx.icp();
+    cmd.env("ICP_HOME", ctx.home_path().join("icp"));
+    cmd
+}
+
+fn pkg_cache(ctx: &TestContext) -> PathBuf {
+    ctx.home_path().join("icp").join("pkg")
+}
+
+fn seed_cache(ctx: &TestContext) {
+    let pkg = pkg_cache(ctx);
+    for (dir, file, contents) in [
+        ("wasms/aaaa", "module.wasm", &b"wasm"[..]),
+        ("recipes/bbbb", "recipe.hbs", &b"recipe"[..]),
+        (
+            "network-launcher/v1.0.0",
+            "icp-cli-network-launcher",
+            &b"launcher"[..],
+        ),
+    ] {
+        create_dir_all(&pkg.join(dir)).unwrap();
+        write(&pkg.join(dir).join(file), contents).unwrap();
+        write(&pkg.join(dir).join(".atime"), b"").unwrap();
+    }
+}
+
+#[test]
+fn cache_list_and_size() {
+    let ctx = TestContext::new();
+    seed_cache(&ctx);
+
+    icp(&ctx)
+        .args(["cache", "list"])
+        .assert()
+        .success()
+        .stdout(contains("launcher v1.0.0"))
+        .stdout(contains("recipe   bbbb"))
+        .stdout(contains("wasm     aaaa"));
+
+    icp(&ctx)
+        .args(["cache", "list", "--kind", "wasm"])
+        .assert()
+        .success()
+        .stdout(contains("aaaa").and(contains("bbbb").not()));
+
+    icp(&ctx)
+        .args(["cache", "size"])
+        .assert()
+        .success()
+        .stdout(contains("total        3 entries       18 B"));
+}
+
+#[test]
+fn cache_prune_by_age_and_kind() {
+    let ctx = TestContext::new();
+    seed_cache(&ctx);
+
+    // Nothing has gone unused for 30 days yet
+    icp(&ctx)
+        .args(["cache", "prune"])
+        .assert()
+        .success()
+        .stdout(contains("Removed 0 entries"));
+
+    icp(&ctx)
+        .args(["cache", "prune", "--older-than", "0s", "--kind", "launcher"])
+        .assert()
+        .success()
+        .stdout(contains("launcher v1.0.0"))
+        .stdout(contains("Removed 1 entries, 8 B"));
+
+    let pkg = pkg_cache(&ctx);
+    assert!(!pkg.join("network-launcher/v1.0.0").exists());
+    assert!(pkg.join("wasms/aaaa/module.wasm").exists());
+    assert!(pkg.join("recipes/bbbb/recipe.hbs").exists());
+}
+
+#[test]
+fn cache_clear() {
+    let ctx = TestContext::new();
+    seed_cache(&ctx);
+
+    icp(&ctx).args(["cache", "clear"]).assert().success();
+
+    icp(&ctx)
+        .args(["cache", "size"])
+        .assert()
+        .success()
+        .stdout(contains("total        0 entries"));
+}
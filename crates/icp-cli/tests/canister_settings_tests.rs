@@ -862,6 +862,108 @@ async fn canister_settings_environment_variable_from_file() {
         .stdout(contains("API_KEY: s3cret"));
 }
 
+/// The values of environment variables read from a secret are masked by
+/// `canister settings show` and `canister status`, however the canister is
+/// selected.
+#[tokio::test]
+async fn canister_secret_environment_variables_are_masked() {
+    let ctx = TestContext::new();
+
+    let project_dir = ctx.create_project_dir("icp");
+    let wasm = ctx.make_asset("example_icp_mo.wasm");
+
+    let pm = formatdoc! {r#"
+        canisters:
+          - name: my-canister
+            settings:
+              environment_variables:
+                API_KEY:
+                  env: API_KEY
+            build:
+              steps:
+                - type: script
+                  command: cp '{wasm}' "$ICP_WASM_OUTPUT_PATH"
+
+        {NETWORK_RANDOM_PORT}
+        {ENVIRONMENT_RANDOM_PORT}
+    "#};
+
+    write_string(&project_dir.join("icp.yaml"), &pm).expect("failed to write project manifest");
+
+    let _g = ctx.start_network_in(&project_dir, "random-network").await;
+    ctx.ping_until_healthy(&project_dir, "random-network");
+
+    clients::icp(&ctx, &project_dir, Some("random-environment".to_string()))
+        .mint_cycles(200 * TRILLION);
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env("API_KEY", "s3cret")
+        .args(["deploy", "--environment", "random-environment"])
+        .assert()
+        .success();
+
+    let output = ctx
+        .icp()
+        .current_dir(&project_dir)
+        .args([
+            "canister",
+            "status",
+            "my-canister",
+            "--environment",
+            "random-environment",
+            "--id-only",
+        ])
+        .assert()
+        .success();
+    let cid = String::from_utf8_lossy(&output.get_output().stdout)
+        .trim()
+        .to_string();
+
+    for canister in ["my-canister", cid.as_str()] {
+        ctx.icp()
+            .current_dir(&project_dir)
+            .args([
+                "canister",
+                "settings",
+                "show",
+                canister,
+                "--environment",
+                "random-environment",
+            ])
+            .assert()
+            .success()
+            .stdout(contains("API_KEY: <redacted>").and(contains("s3cret").not()));
+        ctx.icp()
+            .current_dir(&project_dir)
+            .args([
+                "canister",
+                "status",
+                canister,
+                "--environment",
+                "random-environment",
+            ])
+            .assert()
+            .success()
+            .stdout(contains("Value: <redacted>").and(contains("s3cret").not()));
+    }
+
+    // Selected by principal on a network, the canister is found by its ID
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "canister",
+            "settings",
+            "show",
+            cid.as_str(),
+            "--network",
+            "random-network",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("API_KEY: <redacted>").and(contains("s3cret").not()));
+}
+
 /// A file the manifest points at but that does not exist is reported before
 /// anything is deployed, naming the variable and the canister.
 #[tokio::test]
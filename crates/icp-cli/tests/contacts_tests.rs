@@ -0,0 +1,176 @@
+use common::{ENVIRONMENT_RANDOM_PORT, NETWORK_RANDOM_PORT, TestContext, clients};
+use icp::{fs::write_string, prelude::*};
+use indoc::formatdoc;
+use predicates::{ord::eq, prelude::*, str::contains};
+
+mod common;
+
+#[test]
+fn contacts_add_list_remove() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["contacts", "list"])
+        .assert()
+        .success()
+        .stdout(eq(""));
+
+    ctx.icp()
+        .args(["contacts", "add", "mgmt", "aaaaa-aa"])
+        .assert()
+        .success();
+    ctx.icp()
+        .args(["contacts", "add", "mgmt", "2vxsx-fae"])
+        .assert()
+        .failure()
+        .stderr(contains("contact `mgmt` already exists"));
+    ctx.icp()
+        .args(["contacts", "add", "bad@name", "aaaaa-aa"])
+        .assert()
+        .failure()
+        .stderr(contains("contact names may not"));
+    ctx.icp()
+        .args(["contacts", "add", "other", "not-a-principal"])
+        .assert()
+        .failure();
+
+    ctx.icp()
+        .args(["contacts", "list"])
+        .assert()
+        .success()
+        .stdout(eq("mgmt aaaaa-aa\n"));
+    ctx.icp()
+        .args(["contacts", "list", "--json"])
+        .assert()
+        .success()
+        .stdout(eq(r#"{"contacts":{"mgmt":"aaaaa-aa"}}"#));
+
+    ctx.icp()
+        .args(["contacts", "remove", "mgmt"])
+        .assert()
+        .success();
+    ctx.icp()
+        .args(["contacts", "remove", "mgmt"])
+        .assert()
+        .failure()
+        .stderr(contains("no contact found with name `mgmt`"));
+}
+
+#[test]
+fn contacts_resolve_as_principals() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["contacts", "add", "mgmt", "aaaaa-aa"])
+        .assert()
+        .success();
+    ctx.icp()
+        .args(["identity", "account-id", "--of-principal", "@mgmt"])
+        .assert()
+        .success()
+        .stdout(
+            contains("2d0e897f7e862d2b57d9bc9ea5c65f9a24ac6c074575f47898314b8d6cb0929d").trim(),
+        );
+
+    // Identities resolve as contacts too
+    ctx.icp()
+        .args(["identity", "import", "alice", "--from-pem"])
+        .arg(ctx.make_asset("decrypted_sec1_k256.pem"))
+        .assert()
+        .success();
+    ctx.icp()
+        .args(["identity", "account-id", "--of-principal", "@alice"])
+        .assert()
+        .success()
+        .stdout(
+            contains("4f3d4b40cdb852732601fccf8bd24dffe44957a647cb867913e982d98cf85676").trim(),
+        );
+
+    ctx.icp()
+        .args(["identity", "account-id", "--of-principal", "@nobody"])
+        .assert()
+        .failure()
+        .stderr(contains("no contact or identity found with name `nobody`"));
+}
+
+#[tokio::test]
+async fn deploy_with_contact_controller() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+    let wasm = ctx.make_asset("example_icp_mo.wasm");
+
+    let pm = formatdoc! {r#"
+        canisters:
+          - name: my-canister
+            build:
+              steps:
+                - type: script
+                  command: cp '{wasm}' "$ICP_WASM_OUTPUT_PATH"
+
+        {NETWORK_RANDOM_PORT}
+        {ENVIRONMENT_RANDOM_PORT}
+    "#};
+    write_string(&project_dir.join("icp.yaml"), &pm).expect("failed to write project manifest");
+
+    // The anonymous principal is the active identity, so it keeps access
+    ctx.icp()
+        .args(["contacts", "add", "mgmt", "aaaaa-aa"])
+        .assert()
+        .success();
+    ctx.icp()
+        .args(["contacts", "add", "me", "2vxsx-fae"])
+        .assert()
+        .success();
+
+    // An unknown contact fails before anything is deployed
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "deploy",
+            "--environment",
+            "random-environment",
+            "--controller",
+            "@nobody",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("no contact or identity found with name `nobody`"));
+
+    let _g = ctx.start_network_in(&project_dir, "random-network").await;
+    ctx.ping_until_healthy(&project_dir, "random-network");
+
+    clients::icp(&ctx, &project_dir, Some("random-environment".to_string()))
+        .mint_cycles(10 * TRILLION);
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "deploy",
+            "--environment",
+            "random-environment",
+            "--controller",
+            "@mgmt",
+            "--controller",
+            "@me",
+        ])
+        .assert()
+        .success();
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "canister",
+            "settings",
+            "show",
+            "my-canister",
+            "--environment",
+            "random-environment",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Controllers:")
+                .and(contains("aaaaa-aa"))
+                .and(contains("2vxsx-fae")),
+        );
+}
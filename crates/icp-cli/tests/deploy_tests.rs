@@ -11,7 +11,7 @@ use crate::common::{
     TestContext, build_sync_plugin_example, clients,
 };
 use icp::{
-    fs::{create_dir_all, read_to_string, write_string},
+    fs::{create_dir_all, read, read_to_string, write, write_string},
     prelude::*,
     store_id::IdMapping,
 };
@@ -1185,6 +1185,167 @@ fn deploy_with_args_multiple_canisters_fails() {
         ));
 }
 
+#[test]
+fn deploy_from_environment_rejects_unpromotable_sources() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+
+    let pm = indoc! {r#"
+        canisters:
+          - name: my-canister
+            build:
+              steps:
+                - type: script
+                  command: echo hi
+
+        environments:
+          - name: staging
+            network: local
+    "#};
+
+    write_string(&project_dir.join("icp.yaml"), pm).expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["deploy", "--from-environment", "local"])
+        .assert()
+        .failure()
+        .stderr(contains("cannot promote environment 'local' to itself"));
+
+    // Nothing to verify against when the source environment has no such canister
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["deploy", "--from-environment", "staging"])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "failed to lookup canister ID for canister 'my-canister' in environment 'staging'",
+        ));
+}
+
+/// `deploy --from-environment` installs the build another environment runs,
+/// and refuses a local build that differs from it unless the installed module
+/// is fetched from a snapshot.
+#[tokio::test]
+async fn deploy_from_environment_promotes_verified_builds() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+
+    // The build copies this file, so the test can change what gets built
+    let wasm = project_dir.join("app.wasm");
+    let original = read(&ctx.make_asset("example_icp_mo.wasm")).expect("failed to read wasm");
+    write(&wasm, &original).expect("failed to write wasm");
+
+    let pm = formatdoc! {r#"
+        canisters:
+          - name: my-canister
+            build:
+              steps:
+                - type: script
+                  command: cp '{wasm}' "$ICP_WASM_OUTPUT_PATH"
+
+        {NETWORK_RANDOM_PORT}
+        {ENVIRONMENT_RANDOM_PORT}
+          - name: promoted-environment
+            network: random-network
+    "#};
+
+    write_string(&project_dir.join("icp.yaml"), &pm).expect("failed to write project manifest");
+
+    let _g = ctx.start_network_in(&project_dir, "random-network").await;
+    ctx.ping_until_healthy(&project_dir, "random-network");
+
+    clients::icp(&ctx, &project_dir, Some("random-environment".to_string()))
+        .mint_cycles(10 * TRILLION);
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["deploy", "--environment", "random-environment"])
+        .assert()
+        .success();
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "deploy",
+            "--from-environment",
+            "random-environment",
+            "--environment",
+            "promoted-environment",
+        ])
+        .assert()
+        .success();
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "canister",
+            "call",
+            "--environment",
+            "promoted-environment",
+            "my-canister",
+            "greet",
+            "(\"test\")",
+        ])
+        .assert()
+        .success()
+        .stdout(eq("(\"Hello, test!\")").trim());
+
+    // A build that differs by an extra custom section is not what the source runs
+    let mut changed = original;
+    changed.extend_from_slice(&[0x00, 0x04, 0x03, b'i', b'c', b'p']);
+    write(&wasm, &changed).expect("failed to write wasm");
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "--environment", "random-environment"])
+        .assert()
+        .success();
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "deploy",
+            "--from-environment",
+            "random-environment",
+            "--environment",
+            "promoted-environment",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "is not the module installed in environment 'random-environment'",
+        ));
+
+    // Fetching the installed module from a snapshot replaces the differing build
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "environment",
+            "promote",
+            "random-environment",
+            "promoted-environment",
+            "--fetch-from-snapshot",
+        ])
+        .assert()
+        .success();
+
+    // The source canister is running again
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args([
+            "canister",
+            "call",
+            "--environment",
+            "random-environment",
+            "my-canister",
+            "greet",
+            "(\"test\")",
+        ])
+        .assert()
+        .success()
+        .stdout(eq("(\"Hello, test!\")").trim());
+}
+
 #[tokio::test]
 async fn deploy_through_proxy() {
     let ctx = TestContext::new();
@@ -0,0 +1,165 @@
+use httptest::{Expectation, Server, matchers::request, responders::status_code};
+use indoc::formatdoc;
+use k256::sha2::{Digest, Sha256};
+use predicates::{prelude::PredicateBooleanExt, str::contains};
+
+use crate::common::TestContext;
+use icp::fs::{read, read_to_string, write_string};
+
+mod common;
+
+/// Serve `body` at `/canister.wasm` for as many requests as are made.
+fn serve_wasm(server: &Server, body: &[u8]) {
+    server.expect(
+        Expectation::matching(request::method_path("GET", "/canister.wasm"))
+            .times(..)
+            .respond_with(status_code(200).body(body.to_owned())),
+    );
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+#[test]
+fn locked_fails_when_the_lock_file_would_change() {
+    let ctx = TestContext::new();
+
+    // Setup project
+    let project_dir = ctx.create_project_dir("icp");
+
+    // Serve the vendored WASM
+    let wasm = read(&ctx.make_asset("example_icp_mo.wasm")).expect("failed to load wasm test-file");
+    let server = Server::run();
+    serve_wasm(&server, &wasm);
+    let addr = server.addr();
+
+    // Project manifest, with a floating pre-built URL
+    let pm = formatdoc! {r#"
+        canisters:
+          - name: my-canister
+            build:
+              steps:
+                - type: pre-built
+                  url: http://{addr}/canister.wasm
+    "#};
+
+    write_string(
+        &project_dir.join("icp.yaml"), // path
+        &pm,                           // contents
+    )
+    .expect("failed to write project manifest");
+
+    // The first resolution would create icp.lock
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "--locked"])
+        .assert()
+        .failure()
+        .stderr(
+            contains("icp.lock needs to be updated but --locked was passed")
+                .and(contains(format!("wasm http://{addr}/canister.wasm"))),
+        );
+    assert!(!project_dir.join("icp.lock").exists());
+
+    // Without --locked, the wasm is pinned
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build"])
+        .assert()
+        .success();
+    let lock = read_to_string(&project_dir.join("icp.lock")).expect("failed to read icp.lock");
+    assert!(lock.contains(&sha256(&wasm)), "{lock}");
+
+    // Once pinned, --locked resolves from the lock file
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "--locked"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn update_lock_repins_floating_references() {
+    let ctx = TestContext::new();
+
+    // Setup project
+    let project_dir = ctx.create_project_dir("icp");
+
+    let original =
+        read(&ctx.make_asset("example_icp_mo.wasm")).expect("failed to load wasm test-file");
+    let mut server = Server::run();
+    serve_wasm(&server, &original);
+    let addr = server.addr();
+
+    // Project manifest, with a floating pre-built URL. The launcher version is
+    // fixed so that only the wasm floats.
+    let pm = formatdoc! {r#"
+        canisters:
+          - name: my-canister
+            build:
+              steps:
+                - type: pre-built
+                  url: http://{addr}/canister.wasm
+
+        networks:
+          - name: local
+            mode: managed
+            version: v0.3.0
+    "#};
+
+    write_string(
+        &project_dir.join("icp.yaml"), // path
+        &pm,                           // contents
+    )
+    .expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build"])
+        .assert()
+        .success();
+
+    // Nothing floated since
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["project", "update-lock"])
+        .assert()
+        .success()
+        .stdout(contains("icp.lock is up to date"));
+
+    // A new release is published behind the same URL, with an extra custom section
+    let mut updated = original.clone();
+    updated.extend_from_slice(&[0x00, 0x04, 0x03, b'i', b'c', b'p']);
+    server.verify_and_clear();
+    serve_wasm(&server, &updated);
+
+    // Updating the lock file is refused with --locked
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["project", "update-lock", "--locked"])
+        .assert()
+        .failure()
+        .stderr(contains("--locked was passed"));
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["project", "update-lock"])
+        .assert()
+        .success()
+        .stdout(contains(format!(
+            "wasm http://{addr}/canister.wasm: {} -> {}",
+            sha256(&original),
+            sha256(&updated)
+        )));
+    let lock = read_to_string(&project_dir.join("icp.lock")).expect("failed to read icp.lock");
+    assert!(lock.contains(&sha256(&updated)), "{lock}");
+    assert!(!lock.contains(&sha256(&original)), "{lock}");
+
+    // The new pin holds
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "--locked"])
+        .assert()
+        .success();
+}
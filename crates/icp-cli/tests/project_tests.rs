@@ -1,6 +1,6 @@
 use camino_tempfile::NamedUtf8TempFile as NamedTempFile;
 use indoc::{formatdoc, indoc};
-use predicates::str::contains;
+use predicates::{prelude::*, str::contains};
 
 use crate::common::TestContext;
 use icp::fs::{create_dir_all, write_string};
@@ -293,3 +293,217 @@ fn redefine_ic_network_disallowed() {
         .failure()
         .stderr(contains("`ic` is a reserved network name"));
 }
+
+#[test]
+fn environment_show_resolves_extends() {
+    let ctx = TestContext::new();
+
+    let project_dir = ctx.create_project_dir("icp");
+    let pm = indoc! {r#"
+        canisters:
+          - name: backend
+            build:
+              steps:
+                - type: pre-built
+                  path: backend.wasm
+        environments:
+          - name: staging
+            network: ic
+            settings:
+              backend:
+                compute_allocation: 1
+                memory_allocation: 4GiB
+          - name: prod-eu
+            extends: staging
+            settings:
+              backend:
+                compute_allocation: 5
+    "#};
+    write_string(&project_dir.join("icp.yaml"), pm).expect("failed to write project manifest");
+
+    // As declared
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["environment", "show", "prod-eu"])
+        .assert()
+        .success()
+        .stdout(contains("extends: staging"))
+        .stdout(contains("memory_allocation").not());
+
+    // As resolved
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["environment", "show", "prod-eu", "--resolved"])
+        .assert()
+        .success()
+        .stdout(contains("extends").not())
+        .stdout(contains("network: ic"))
+        .stdout(contains("compute_allocation: 5"))
+        .stdout(contains("memory_allocation"));
+
+    // Implicit environments only set their network
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["environment", "show", "local"])
+        .assert()
+        .success()
+        .stdout(contains("network: local"));
+}
+
+#[test]
+fn environment_extends_cycle_is_rejected() {
+    let ctx = TestContext::new();
+
+    let project_dir = ctx.create_project_dir("icp");
+    let pm = indoc! {r#"
+        canisters: []
+        environments:
+          - name: a
+            extends: b
+          - name: b
+            extends: a
+    "#};
+    write_string(&project_dir.join("icp.yaml"), pm).expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["environment", "list"])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "environments extend each other in a cycle: a -> b -> a",
+        ));
+}
+
+#[test]
+fn manifest_variables_are_interpolated_and_secrets_redacted() {
+    let ctx = TestContext::new();
+
+    let project_dir = ctx.create_project_dir("icp");
+    let pm = indoc! {r#"
+        canisters:
+          - name: backend
+            build:
+              steps:
+                - type: pre-built
+                  path: backend.wasm
+            settings:
+              environment_variables:
+                GATEWAY: "https://${DOMAIN}"
+                LOG_LEVEL: "${LOG_LEVEL:-info}"
+                API_KEY: "${secret:API_KEY}"
+    "#};
+    write_string(&project_dir.join("icp.yaml"), pm).expect("failed to write project manifest");
+    write_string(&project_dir.join(".env"), "DOMAIN=example.org\n").expect("failed to write .env");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env("API_KEY", "s3cret-value")
+        .args(["project", "show"])
+        .assert()
+        .success()
+        .stdout(contains("https://example.org"))
+        .stdout(contains("LOG_LEVEL: info"))
+        .stdout(contains("API_KEY: <redacted>"))
+        .stdout(contains("s3cret-value").not());
+
+    // A variable that is not set fails the load
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env_remove("API_KEY")
+        .args(["project", "show"])
+        .assert()
+        .failure()
+        .stderr(contains("variable 'API_KEY' is not set"));
+}
+
+/// Secret providers are only read at deploy time: loading the project does not
+/// need the secret, and only its source is shown.
+#[test]
+fn secret_providers_are_not_read_at_load() {
+    let ctx = TestContext::new();
+
+    let project_dir = ctx.create_project_dir("icp");
+    let pm = indoc! {r#"
+        canisters:
+          - name: backend
+            build:
+              steps:
+                - type: pre-built
+                  path: backend.wasm
+            settings:
+              environment_variables:
+                API_KEY:
+                  env: BACKEND_API_KEY
+            init_args:
+              command: exit 1
+    "#};
+    write_string(&project_dir.join("icp.yaml"), pm).expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env("BACKEND_API_KEY", "s3cret-value")
+        .args(["project", "show"])
+        .assert()
+        .success()
+        .stdout(contains("env: BACKEND_API_KEY"))
+        .stdout(contains("command: exit 1"))
+        .stdout(contains("s3cret-value").not());
+}
+
+#[test]
+fn import_dfx_project() {
+    let ctx = TestContext::new();
+
+    let project_dir = ctx.home_path().join("dfx-project");
+    create_dir_all(&project_dir).expect("failed to create project directory");
+    let dfx_json = indoc! {r#"
+        {
+            "canisters": {
+                "backend": { "type": "motoko", "main": "src/backend/main.mo" },
+                "ii": { "type": "pull", "id": "rdmx6-jaaaa-aaaaa-aaadq-cai" }
+            },
+            "networks": {
+                "staging": { "providers": ["https://icp0.io"], "type": "persistent" }
+            }
+        }
+    "#};
+    write_string(&project_dir.join("dfx.json"), dfx_json).expect("failed to write dfx.json");
+    let canister_ids = indoc! {r#"
+        {
+            "backend": { "ic": "ryjl3-tyaaa-aaaaa-aaaba-cai", "staging": "r7inp-6aaaa-aaaaa-aaabq-cai" },
+            "ii": { "ic": "rdmx6-jaaaa-aaaaa-aaadq-cai" }
+        }
+    "#};
+    write_string(&project_dir.join("canister_ids.json"), canister_ids)
+        .expect("failed to write canister_ids.json");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["project", "import-dfx"])
+        .assert()
+        .success()
+        .stderr(contains("canister `ii` of type `pull`"))
+        .stderr(contains("Imported 2 canister IDs"));
+
+    let icp_yaml =
+        std::fs::read_to_string(project_dir.join("icp.yaml")).expect("failed to read icp.yaml");
+    assert!(icp_yaml.contains("@dfinity/motoko"));
+    assert!(icp_yaml.contains("name: staging"));
+
+    let ic_ids = std::fs::read_to_string(project_dir.join(".icp/data/mappings/ic.ids.json"))
+        .expect("failed to read ic mappings");
+    assert!(ic_ids.contains("ryjl3-tyaaa-aaaaa-aaaba-cai"));
+    let staging_ids =
+        std::fs::read_to_string(project_dir.join(".icp/data/mappings/staging.ids.json"))
+            .expect("failed to read staging mappings");
+    assert!(staging_ids.contains("r7inp-6aaaa-aaaaa-aaabq-cai"));
+
+    // An existing icp.yaml is not overwritten
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["project", "import-dfx"])
+        .assert()
+        .failure()
+        .stderr(contains("already exists"));
+}
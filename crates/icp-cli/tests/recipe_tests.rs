@@ -332,3 +332,455 @@ fn recipe_local_file_valid_checksum() {
         .assert()
         .success();
 }
+
+#[test]
+fn recipe_remote_url_offline() {
+    let ctx = TestContext::new();
+
+    // Setup project
+    let project_dir = ctx.create_project_dir("icp");
+
+    // Spawn HTTP server with the recipe template
+    let server = spawn_test_server("GET", "/recipe.hbs", RECIPE_TEMPLATE.as_bytes());
+    let addr = server.addr();
+
+    // Project manifest
+    let pm = formatdoc! {"
+        canisters:
+          - name: my-canister
+            recipe:
+              type: http://{addr}/recipe.hbs
+    "};
+
+    write_string(
+        &project_dir.join("icp.yaml"), // path
+        &pm,                           // contents
+    )
+    .expect("failed to write project manifest");
+
+    // Offline, the uncached recipe is reported rather than downloaded
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "my-canister", "--offline"])
+        .assert()
+        .failure()
+        .stderr(
+            contains("offline mode")
+                .and(contains(format!("http://{addr}/recipe.hbs")))
+                .and(contains("icp project prefetch")),
+        );
+
+    // Populate the cache while online
+    ctx.icp()
+        .current_dir(&project_dir)
+        .env("ICP_CLI_NETWORK_LAUNCHER_PATH", "unused")
+        .args(["project", "prefetch"])
+        .assert()
+        .success();
+
+    // Offline now succeeds from the cache
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "my-canister", "--offline"])
+        .assert()
+        .success();
+}
+
+const SCHEMA_RECIPE_TEMPLATE: &str = indoc! {r#"
+    {{!-- schema
+    description: Writes a test wasm
+    type: object
+    required: [package]
+    additionalProperties: false
+    properties:
+      package:
+        type: string
+        description: The package to build
+      shrink:
+        type: boolean
+        default: false
+    --}}
+    build:
+      steps:
+        - type: script
+          command: echo "{{ package }} {{ shrink }}" > "$ICP_WASM_OUTPUT_PATH"
+"#};
+
+#[test]
+fn recipe_configuration_is_validated_against_schema() {
+    let ctx = TestContext::new();
+
+    // Setup project
+    let project_dir = ctx.create_project_dir("icp");
+
+    write_string(
+        &project_dir.join("recipe.hbs"), // path
+        SCHEMA_RECIPE_TEMPLATE,          // contents
+    )
+    .expect("failed to write recipe template");
+
+    // Project manifest with a misspelled key and a mistyped value
+    let pm = formatdoc! {"
+        canisters:
+          - name: my-canister
+            recipe:
+              type: file://./recipe.hbs
+              configuration:
+                pakage: my-canister
+                shrink: yes-please
+    "};
+
+    write_string(
+        &project_dir.join("icp.yaml"), // path
+        &pm,                           // contents
+    )
+    .expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "my-canister"])
+        .assert()
+        .failure()
+        .stderr(
+            contains("invalid configuration for recipe")
+                .and(contains(
+                    r#"configuration: "package" is a required property"#,
+                ))
+                .and(contains("configuration.shrink: "))
+                .and(contains("pakage")),
+        );
+}
+
+#[test]
+fn recipe_describe() {
+    let ctx = TestContext::new();
+
+    // Spawn HTTP server with the recipe template
+    let server = spawn_test_server("GET", "/recipe.hbs", SCHEMA_RECIPE_TEMPLATE.as_bytes());
+    let addr = server.addr();
+
+    // Outside of any project
+    ctx.icp()
+        .args(["recipe", "describe", &format!("http://{addr}/recipe.hbs")])
+        .assert()
+        .success()
+        .stdout(
+            contains("Writes a test wasm")
+                .and(contains("package  string, required"))
+                .and(contains("The package to build"))
+                .and(contains("shrink   boolean, default: false")),
+        );
+
+    // A local recipe, with the schema as JSON
+    let recipe_path = ctx.home_path().join("recipe.hbs");
+    write_string(&recipe_path, SCHEMA_RECIPE_TEMPLATE).expect("failed to write recipe template");
+    ctx.icp()
+        .args([
+            "recipe",
+            "describe",
+            "--json",
+            &format!("file://{recipe_path}"),
+        ])
+        .assert()
+        .success()
+        .stdout(contains(r#""required": ["#));
+}
+
+#[test]
+fn recipe_renders_for_the_environment() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+
+    write_string(
+        &project_dir.join("recipe.hbs"),
+        indoc! {r#"
+            build:
+              steps:
+                - type: script
+                  commands:
+                    - echo "{{_.environment.name}} {{_.network.name}} {{_.network.mode}}" > "{{_.project.root}}/built-for.txt"
+                    - echo "test" > "$ICP_WASM_OUTPUT_PATH"
+        "#},
+    )
+    .expect("failed to write recipe template");
+
+    write_string(
+        &project_dir.join("icp.yaml"),
+        indoc! {"
+            canisters:
+              - name: my-canister
+                recipe:
+                  type: file://./recipe.hbs
+        "},
+    )
+    .expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "my-canister", "--environment", "ic"])
+        .assert()
+        .success();
+    assert_eq!(
+        icp::fs::read_to_string(&project_dir.join("built-for.txt"))
+            .unwrap()
+            .trim(),
+        "ic ic connected"
+    );
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "my-canister"])
+        .assert()
+        .success();
+    assert_eq!(
+        icp::fs::read_to_string(&project_dir.join("built-for.txt"))
+            .unwrap()
+            .trim(),
+        "local local managed"
+    );
+}
+
+/// Writes a registry in `dir` publishing `RECIPE_TEMPLATE` as `test@v1.0.0`.
+fn write_dir_registry(dir: &icp::prelude::Path) {
+    let version_dir = dir.join("test").join("v1.0.0");
+    icp::fs::create_dir_all(&version_dir).expect("failed to create registry directory");
+    write_string(&version_dir.join("recipe.hbs"), RECIPE_TEMPLATE)
+        .expect("failed to write recipe template");
+    write_string(
+        &dir.join("index.json"),
+        indoc! {r#"
+            {
+              "recipes": [
+                { "name": "test", "description": "Writes a test wasm", "versions": ["v0.9.0", "v1.0.0"] },
+                { "name": "other", "versions": ["v1.0.0"] }
+              ]
+            }
+        "#},
+    )
+    .expect("failed to write registry index");
+}
+
+#[test]
+fn recipe_from_project_registry() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+    write_dir_registry(&project_dir.join("registry"));
+
+    write_string(
+        &project_dir.join("icp.yaml"),
+        indoc! {"
+            registries:
+              - name: local
+                path: ./registry
+
+            canisters:
+              - name: my-canister
+                recipe:
+                  type: \"@local/test@v1.0.0\"
+        "},
+    )
+    .expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build", "my-canister"])
+        .assert()
+        .success();
+
+    // The registry is only known inside the project
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["recipe", "list", "--registry", "local"])
+        .assert()
+        .success()
+        .stdout(
+            contains("@local/test@v1.0.0   Writes a test wasm")
+                .and(contains("@local/other@v1.0.0")),
+        );
+    ctx.icp()
+        .args(["recipe", "list", "--registry", "local"])
+        .assert()
+        .failure()
+        .stderr(contains("unknown registry '@local'"));
+}
+
+#[test]
+fn recipe_search_user_registry() {
+    let ctx = TestContext::new();
+    let registry_dir = ctx.home_path().join("registry");
+    write_dir_registry(&registry_dir);
+
+    ctx.icp()
+        .args([
+            "settings",
+            "registry",
+            "add",
+            "mine",
+            "--path",
+            registry_dir.as_str(),
+        ])
+        .assert()
+        .success();
+
+    ctx.icp()
+        .args(["recipe", "search", "WASM", "--registry", "mine"])
+        .assert()
+        .success()
+        .stdout(contains("@mine/test@v1.0.0").and(contains("other").not()));
+
+    ctx.icp()
+        .args(["recipe", "search", "other", "--registry", "mine", "--json"])
+        .assert()
+        .success()
+        .stdout(contains(r#""registry": "mine""#).and(contains(r#""name": "other""#)));
+}
+
+#[test]
+fn recipe_render() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+
+    write_string(
+        &project_dir.join("recipe.hbs"),
+        indoc! {r#"
+            build:
+              steps:
+                - type: script
+                  command: echo "{{_.canister.name}} {{_.environment.name}} {{package}}"
+            settings:
+              compute_allocation: 3
+        "#},
+    )
+    .expect("failed to write recipe template");
+
+    write_string(
+        &project_dir.join("icp.yaml"),
+        indoc! {"
+            canisters:
+              - name: my-canister
+                recipe:
+                  type: file://./recipe.hbs
+                  configuration:
+                    package: pkg
+              - name: plain
+                build:
+                  steps:
+                    - type: script
+                      command: echo plain
+        "},
+    )
+    .expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["recipe", "render", "my-canister", "--environment", "ic"])
+        .assert()
+        .success()
+        .stdout(
+            contains("my-canister:")
+                .and(contains("echo \"my-canister ic pkg\""))
+                .and(contains("compute_allocation: 3")),
+        );
+
+    // `--all` skips canisters without a recipe
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["recipe", "render", "--all", "--json"])
+        .assert()
+        .success()
+        .stdout(contains("my-canister local pkg").and(contains("plain").not()));
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["recipe", "render", "plain"])
+        .assert()
+        .failure()
+        .stderr(contains("canister 'plain' does not use a recipe"));
+
+    // A template outside of any project
+    let recipe_path = ctx.home_path().join("recipe.hbs");
+    write_string(&recipe_path, SCHEMA_RECIPE_TEMPLATE).expect("failed to write recipe template");
+    ctx.icp()
+        .current_dir(ctx.home_path())
+        .args([
+            "recipe",
+            "render",
+            "--template",
+            "recipe.hbs",
+            "--config",
+            "package=backend",
+            "--config",
+            "shrink=true",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("echo \"backend true\""));
+    ctx.icp()
+        .current_dir(ctx.home_path())
+        .args(["recipe", "render", "--template", "recipe.hbs"])
+        .assert()
+        .failure()
+        .stderr(contains("package"));
+}
+
+#[test]
+fn recipe_version_requirement_and_outdated() {
+    let ctx = TestContext::new();
+    let project_dir = ctx.create_project_dir("icp");
+    let registry_dir = project_dir.join("registry");
+    for version in ["v1.0.0", "v1.1.0", "v2.0.0"] {
+        let version_dir = registry_dir.join("test").join(version);
+        icp::fs::create_dir_all(&version_dir).expect("failed to create registry directory");
+        write_string(&version_dir.join("recipe.hbs"), RECIPE_TEMPLATE)
+            .expect("failed to write recipe template");
+    }
+    write_string(
+        &registry_dir.join("index.json"),
+        r#"{"recipes": [{"name": "test", "versions": ["v1.0.0", "v1.1.0", "v2.0.0"]}]}"#,
+    )
+    .expect("failed to write registry index");
+
+    write_string(
+        &project_dir.join("icp.yaml"),
+        indoc! {"
+            registries:
+              - name: local
+                path: ./registry
+
+            canisters:
+              - name: ranged
+                recipe:
+                  type: \"@local/test@^1\"
+              - name: exact
+                recipe:
+                  type: \"@local/test@v1.0.0\"
+        "},
+    )
+    .expect("failed to write project manifest");
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["build"])
+        .assert()
+        .success();
+    let lock = icp::fs::read_to_string(&project_dir.join("icp.lock")).unwrap();
+    assert!(
+        lock.contains(r#""@local/test@^1": "v1.1.0""#),
+        "requirement not pinned: {lock}"
+    );
+
+    ctx.icp()
+        .current_dir(&project_dir)
+        .args(["recipe", "outdated"])
+        .assert()
+        .success()
+        .stdout(
+            contains("CANISTER  RECIPE       CURRENT  COMPATIBLE  LATEST")
+                .and(contains(
+                    "ranged    @local/test  v1.1.0   v1.1.0      v2.0.0",
+                ))
+                .and(contains(
+                    "exact     @local/test  v1.0.0   v1.1.0      v2.0.0",
+                )),
+        );
+}
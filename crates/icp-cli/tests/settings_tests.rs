@@ -164,3 +164,103 @@ fn settings_telemetry_persists() {
         .success()
         .stdout(eq("false").trim());
 }
+
+// ---------------------------------------------------------------------------
+// offline
+// ---------------------------------------------------------------------------
+
+#[test]
+fn settings_offline_default() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["settings", "offline"])
+        .assert()
+        .success()
+        .stdout(eq("false").trim());
+}
+
+#[test]
+fn settings_offline_set_true() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["settings", "offline", "true"])
+        .assert()
+        .success()
+        .stderr(contains("Set offline to true"));
+
+    ctx.icp()
+        .args(["settings", "offline"])
+        .assert()
+        .success()
+        .stdout(eq("true").trim());
+}
+
+// ---------------------------------------------------------------------------
+// registry
+// ---------------------------------------------------------------------------
+
+#[test]
+fn settings_registry_add_list_remove() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args(["settings", "registry", "list"])
+        .assert()
+        .success()
+        .stdout(eq(""));
+
+    ctx.icp()
+        .args([
+            "settings",
+            "registry",
+            "add",
+            "acme",
+            "--url",
+            "https://recipes.acme.dev",
+            "--auth-header-env",
+            "ACME_TOKEN",
+        ])
+        .assert()
+        .success()
+        .stderr(contains("Added registry 'acme'"));
+
+    ctx.icp()
+        .args(["settings", "registry", "list"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "acme  https://recipes.acme.dev  (auth: $ACME_TOKEN)",
+        ));
+
+    ctx.icp()
+        .args(["settings", "registry", "remove", "acme"])
+        .assert()
+        .success()
+        .stderr(contains("Removed registry 'acme'"));
+
+    ctx.icp()
+        .args(["settings", "registry", "remove", "acme"])
+        .assert()
+        .failure()
+        .stderr(contains("no registry named 'acme'"));
+}
+
+#[test]
+fn settings_registry_rejects_builtin_name() {
+    let ctx = TestContext::new();
+
+    ctx.icp()
+        .args([
+            "settings",
+            "registry",
+            "add",
+            "dfinity",
+            "--url",
+            "https://example.com",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("built in"));
+}
@@ -11,6 +11,7 @@
 //! 6. **Batch send** — `__telemetry-send-batch`: payload shape, silent failure, file cleanup
 //! 7. **Stale batch cleanup** — old/excess batch files pruned when a send is triggered
 //! 8. **Machine-id persistence** — same UUID is reused across invocations
+//! 9. **Inspection** — `icp telemetry show|export|purge` and local-only mode
 //!
 //! Full-pipeline tests run `icp settings telemetry` (a fast, network-free
 //! command) with `ICP_HOME` set to a known temp path and all opt-out env vars
@@ -540,3 +541,113 @@ fn telemetry_machine_id_persists_across_invocations() {
         ids[0], ids[1]
     );
 }
+
+/// `show`, `export` and `purge` act on all pending records, batches included,
+/// and never add records of their own.
+#[test]
+fn telemetry_show_export_purge_pending_records() {
+    let ctx = TestContext::new();
+    let icp_home = ctx.home_path().join("icp-home");
+    let telemetry_dir = icp_home.join("telemetry");
+    init_telemetry_dir(&telemetry_dir, Some(FAR_FUTURE_SECS));
+    std::fs::write(
+        telemetry_dir.join("batch-1000.jsonl"),
+        format!("{FAKE_RECORD}\n"),
+    )
+    .unwrap();
+    std::fs::write(
+        telemetry_dir.join("events.jsonl"),
+        format!("{FAKE_RECORD}\nnot json\n"),
+    )
+    .unwrap();
+
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    cmd.args(["telemetry", "show"])
+        .assert()
+        .success()
+        .stdout(predstr::contains("version"))
+        .stdout(predstr::contains("0.04s"))
+        .stdout(predstr::contains("2 pending telemetry records"));
+
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    let output = cmd.args(["telemetry", "show", "--json"]).output().unwrap();
+    let records: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(records.as_array().map(Vec::len), Some(2));
+    assert_eq!(records[0]["machine_id"], "test-machine");
+
+    let out = ctx.home_path().join("export.jsonl");
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    cmd.args(["telemetry", "export", "--out", out.as_str()])
+        .assert()
+        .success()
+        .stderr(predstr::contains("Exported 2 telemetry records"));
+    let exported = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(exported.lines().count(), 2);
+
+    // Inspecting did not append a record of its own
+    let events = std::fs::read_to_string(telemetry_dir.join("events.jsonl")).unwrap();
+    assert_eq!(events.lines().count(), 2);
+
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    cmd.args(["telemetry", "purge"])
+        .assert()
+        .success()
+        .stderr(predstr::contains("Deleted 2 telemetry records"));
+    assert!(!telemetry_dir.join("events.jsonl").exists());
+    assert_eq!(count_batch_files(&telemetry_dir), 0);
+
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    cmd.args(["telemetry", "show"])
+        .assert()
+        .success()
+        .stdout(predstr::contains("0 pending telemetry records"));
+}
+
+/// In local-only mode records name the project, land in `local.jsonl` and are
+/// never queued for sending, even when a send is due.
+#[test]
+fn telemetry_local_only_mode() {
+    let ctx = TestContext::new();
+    let icp_home = ctx.home_path().join("icp-home");
+    let telemetry_dir = icp_home.join("telemetry");
+    init_telemetry_dir(&telemetry_dir, Some(FAR_FUTURE_SECS));
+    let project_dir = ctx.create_project_dir("icp");
+
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    cmd.args(["settings", "telemetry-local-only", "true"])
+        .assert()
+        .success();
+    std::fs::remove_file(telemetry_dir.join("events.jsonl")).unwrap();
+
+    // A send is due, but nothing may be queued
+    std::fs::write(telemetry_dir.join("next-send-time"), "0").unwrap();
+    let (_, mut cmd) = icp_with_telemetry!(ctx, enable_send);
+    cmd.current_dir(&project_dir)
+        .args(["settings", "telemetry"])
+        .assert()
+        .success();
+
+    assert!(!telemetry_dir.join("events.jsonl").exists());
+    assert_eq!(count_batch_files(&telemetry_dir), 0);
+    let contents = std::fs::read_to_string(telemetry_dir.join("local.jsonl")).unwrap();
+    let record: Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+    assert_eq!(record["command"], "settings telemetry");
+    assert_eq!(
+        record["project"].as_str().map(Path::new),
+        Some(project_dir.as_path())
+    );
+
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    cmd.args(["telemetry", "show", "--local"])
+        .assert()
+        .success()
+        .stdout(predstr::contains(project_dir.as_str()))
+        .stdout(predstr::contains("1 local telemetry record"));
+
+    let (_, mut cmd) = icp_with_telemetry!(ctx);
+    cmd.args(["telemetry", "purge", "--local"])
+        .assert()
+        .success()
+        .stderr(predstr::contains("Deleted 1 telemetry record"));
+    assert!(!telemetry_dir.join("local.jsonl").exists());
+}
@@ -0,0 +1,43 @@
+//! Ahead-of-time compilation cache for plugin components.
+//!
+//! Compiling a component with Cranelift dominates the cost of a plugin run —
+//! several seconds for a large plugin such as an asset uploader — while the
+//! compiled artifact depends only on the component bytes and the engine that
+//! compiled it. The runtime therefore asks a [`ComponentCache`] for a
+//! previously compiled artifact before compiling, and hands it the result
+//! afterwards. Where the artifacts live is the caller's business: the CLI keeps
+//! them in its package cache, keyed by the plugin's sha256 plus the
+//! [engine key](ComponentCache) supplied here.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use wasmtime::Engine;
+
+/// Storage for precompiled plugin components.
+///
+/// Implementations are scoped to a single plugin (e.g. by its sha256). The
+/// `engine_key` passed to both methods identifies the wasmtime version and
+/// engine configuration that produced the artifact: an artifact is only
+/// loadable by a compatible engine, so it must be stored under this key as well.
+///
+/// Both methods are best-effort. A failed lookup is a cache miss, and a failed
+/// store only means the next run compiles again, so neither reports an error.
+pub trait ComponentCache {
+    /// Return the artifact previously stored under `engine_key`, if any.
+    fn load(&self, engine_key: &str) -> Option<Vec<u8>>;
+
+    /// Persist a freshly compiled artifact under `engine_key`.
+    fn store(&self, engine_key: &str, compiled: &[u8]);
+}
+
+/// The key under which `engine`'s compiled artifacts are stored.
+///
+/// Derived from wasmtime's precompile compatibility hash, which covers the
+/// wasmtime version, the compilation settings and the target. The hasher is not
+/// guaranteed to be stable across Rust releases; a changed key only costs a
+/// recompile, and wasmtime validates the artifact header on load regardless.
+pub(crate) fn engine_key(engine: &Engine) -> String {
+    let mut hasher = DefaultHasher::new();
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
@@ -1,6 +1,8 @@
+mod cache;
 mod path;
 mod runtime;
 
+pub use cache::ComponentCache;
 pub use runtime::{
     DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS, PLUGIN_COMPUTE_LIMIT_ENV, RunPluginError, run_plugin,
 };
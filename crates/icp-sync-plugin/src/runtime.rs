@@ -27,10 +27,14 @@ use ic_agent::Agent;
 use snafu::prelude::*;
 use tokio::io::{self, AsyncWrite};
 use tokio::sync::mpsc::Sender;
+use wasmtime::Engine;
+use wasmtime::component::Component;
 use wasmtime_wasi::cli::{IsTerminal, StdoutStream};
 use wasmtime_wasi::p2::{OutputStream, Pollable, StreamError};
 use wasmtime_wasi::{DirPerms, FilePerms};
 
+use crate::cache::{ComponentCache, engine_key};
+
 wasmtime::component::bindgen!({
     world: "sync-plugin",
     path: "sync-plugin.wit",
@@ -214,9 +218,10 @@ pub fn run_plugin(
     environment: String,
     compute_limit_secs: u64,
     stdio: Option<Sender<String>>,
+    component_cache: Option<&dyn ComponentCache>,
 ) -> Result<Vec<String>, RunPluginError> {
-    use wasmtime::component::{Component, Linker};
-    use wasmtime::{Config, Engine, Store};
+    use wasmtime::component::Linker;
+    use wasmtime::{Config, Store};
 
     let mut config = Config::new();
     config.wasm_component_model(true);
@@ -252,10 +257,7 @@ pub fn run_plugin(
         TickerGuard(ticker_stop)
     };
 
-    let component =
-        Component::from_file(&engine, wasm_path.as_std_path()).context(LoadComponentSnafu {
-            path: wasm_path.clone(),
-        })?;
+    let component = load_component(&engine, &wasm_path, component_cache)?;
 
     // Preopen each declared directory read-only. The guest sees it at the
     // same relative path it used in the manifest.
@@ -369,6 +371,53 @@ pub fn run_plugin(
     Ok(lines)
 }
 
+/// Obtain the compiled component for `wasm_path`, reusing a precompiled
+/// artifact from `cache` when one exists for this engine and storing a fresh
+/// one otherwise.
+///
+/// An artifact that fails to deserialize (e.g. truncated by an interrupted
+/// write) is treated as a miss: the component is recompiled and the artifact
+/// overwritten, so a bad cache entry never fails a sync.
+fn load_component(
+    engine: &Engine,
+    wasm_path: &Utf8PathBuf,
+    cache: Option<&dyn ComponentCache>,
+) -> Result<Component, RunPluginError> {
+    let key = cache.map(|_| engine_key(engine));
+
+    if let (Some(cache), Some(key)) = (cache, &key)
+        && let Some(compiled) = cache.load(key)
+    {
+        // SAFETY: `Component::deserialize` trusts its input to be an artifact
+        // produced by `Component::serialize`, as loading one runs native code.
+        // The cache only ever holds artifacts this runtime serialized, in a
+        // directory owned by the user running the CLI — the same trust
+        // boundary as the CLI binary itself. wasmtime still checks the
+        // artifact header against the engine, so an incompatible or corrupt
+        // artifact is rejected here rather than executed.
+        match unsafe { Component::deserialize(engine, &compiled) } {
+            Ok(component) => return Ok(component),
+            Err(err) => {
+                tracing::debug!("discarding unusable precompiled plugin at {wasm_path}: {err:#}")
+            }
+        }
+    }
+
+    let component =
+        Component::from_file(engine, wasm_path.as_std_path()).context(LoadComponentSnafu {
+            path: wasm_path.clone(),
+        })?;
+
+    if let (Some(cache), Some(key)) = (cache, &key) {
+        match component.serialize() {
+            Ok(compiled) => cache.store(key, &compiled),
+            Err(err) => tracing::debug!("failed to serialize plugin at {wasm_path}: {err:#}"),
+        }
+    }
+
+    Ok(component)
+}
+
 // -------------------------------------------------------------------------
 // Plugin stdout/stderr capture
 // -------------------------------------------------------------------------
@@ -557,6 +606,7 @@ mod tests {
             "test".to_string(),
             DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
             None,
+            None,
         );
         assert!(matches!(result, Err(RunPluginError::LoadComponent { .. })));
     }
@@ -595,6 +645,7 @@ mod tests {
             "test".to_string(),
             DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
             None,
+            None,
         );
         assert!(matches!(result, Err(RunPluginError::PreopenDir { .. })));
     }
@@ -623,6 +674,7 @@ mod tests {
             "test".to_string(),
             DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
             None,
+            None,
         );
         assert!(matches!(result, Err(RunPluginError::SymlinkDir { .. })));
     }
@@ -644,6 +696,7 @@ mod tests {
             "test".to_string(),
             DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
             None,
+            None,
         );
         assert!(matches!(result, Err(RunPluginError::ReadFile { .. })));
     }
@@ -672,6 +725,7 @@ mod tests {
             "test".to_string(),
             DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
             None,
+            None,
         );
         assert!(matches!(result, Err(RunPluginError::SymlinkFile { .. })));
     }
@@ -693,6 +747,7 @@ mod tests {
             "ok".to_string(),
             DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
             None,
+            None,
         );
         assert!(result.is_ok());
     }
@@ -714,6 +769,7 @@ mod tests {
             "error".to_string(),
             DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
             None,
+            None,
         );
         assert!(matches!(
             result,
@@ -740,6 +796,7 @@ mod tests {
             "spin".to_string(),
             1,
             None,
+            None,
         );
         let err = result.expect_err("spinning plugin should hit the compute limit");
         // The trap surfaces through the CallExec source chain, so walk it and
@@ -756,6 +813,93 @@ mod tests {
         );
     }
 
+    /// An in-memory [`ComponentCache`] that counts how it was used.
+    #[derive(Default)]
+    struct MemoryCache {
+        artifacts: StdMutex<std::collections::HashMap<String, Vec<u8>>>,
+        hits: AtomicU64,
+        stores: AtomicU64,
+    }
+
+    impl ComponentCache for MemoryCache {
+        fn load(&self, engine_key: &str) -> Option<Vec<u8>> {
+            let found = self.artifacts.lock().unwrap().get(engine_key).cloned();
+            if found.is_some() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            found
+        }
+
+        fn store(&self, engine_key: &str, compiled: &[u8]) {
+            self.stores.fetch_add(1, Ordering::Relaxed);
+            self.artifacts
+                .lock()
+                .unwrap()
+                .insert(engine_key.to_owned(), compiled.to_vec());
+        }
+    }
+
+    fn run_ok_with_cache(wasm_path: &str, cache: &MemoryCache) {
+        let result = run_plugin(
+            wasm_path.into(),
+            ".".into(),
+            vec![],
+            vec![],
+            anon(),
+            dummy_agent(),
+            None,
+            anon(),
+            "ok".to_string(),
+            DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
+            None,
+            Some(cache),
+        );
+        assert!(result.is_ok(), "plugin should succeed: {result:?}");
+    }
+
+    #[test]
+    fn compiled_component_is_stored_then_reused() {
+        let Some(wasm_path) = option_env!("TEST_PLUGIN_WASM") else {
+            return;
+        };
+        let cache = MemoryCache::default();
+
+        run_ok_with_cache(wasm_path, &cache);
+        assert_eq!(cache.hits.load(Ordering::Relaxed), 0);
+        assert_eq!(cache.stores.load(Ordering::Relaxed), 1);
+
+        // The second run deserializes the stored artifact instead of compiling.
+        run_ok_with_cache(wasm_path, &cache);
+        assert_eq!(cache.hits.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.stores.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn unusable_compiled_component_is_recompiled() {
+        let Some(wasm_path) = option_env!("TEST_PLUGIN_WASM") else {
+            return;
+        };
+        let cache = MemoryCache::default();
+
+        run_ok_with_cache(wasm_path, &cache);
+        for artifact in cache.artifacts.lock().unwrap().values_mut() {
+            *artifact = b"not a compiled component".to_vec();
+        }
+
+        // A corrupt artifact is a miss, not a failure, and gets replaced.
+        run_ok_with_cache(wasm_path, &cache);
+        assert_eq!(cache.stores.load(Ordering::Relaxed), 2);
+        assert!(
+            cache
+                .artifacts
+                .lock()
+                .unwrap()
+                .values()
+                .all(|a| a != b"not a compiled component"),
+            "the corrupt artifact should have been overwritten"
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn plugin_stdout_forwarded_through_stdio_channel() {
         let Some(wasm_path) = option_env!("TEST_PLUGIN_WASM") else {
@@ -775,6 +919,7 @@ mod tests {
                 "print".to_string(),
                 DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
                 Some(tx),
+                None,
             )
         });
         assert!(result.is_ok());
@@ -801,6 +946,7 @@ mod tests {
                 "hello".to_string(),
                 DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS,
                 Some(tx),
+                None,
             )
         });
         let lines = result.expect("plugin should succeed");
@@ -1,8 +1,11 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 
 use snafu::prelude::*;
 use tokio::sync::mpsc::Sender;
 
+use crate::lockfile;
 use crate::manifest::canister::BuildStep;
 use crate::package::PackageCache;
 use crate::prelude::*;
@@ -35,7 +38,19 @@ pub trait Build: Sync + Send {
     ) -> Result<(), BuildError>;
 }
 
-pub struct Builder;
+/// Runs build steps. Prebuilt wasm URLs without a `sha256` are pinned in the
+/// project's lock file, and in offline mode are served from the package cache
+/// only.
+pub struct Builder {
+    lockfile: Arc<dyn lockfile::Access>,
+    offline: bool,
+}
+
+impl Builder {
+    pub fn new(lockfile: Arc<dyn lockfile::Access>, offline: bool) -> Self {
+        Self { lockfile, offline }
+    }
+}
 
 #[async_trait]
 impl Build for Builder {
@@ -47,9 +62,15 @@ impl Build for Builder {
         pkg_cache: &PackageCache,
     ) -> Result<(), BuildError> {
         match step {
-            BuildStep::Prebuilt(adapter) => {
-                Ok(prebuilt::build(adapter, params, stdio, pkg_cache).await?)
-            }
+            BuildStep::Prebuilt(adapter) => Ok(prebuilt::build(
+                adapter,
+                params,
+                stdio,
+                pkg_cache,
+                self.lockfile.as_ref(),
+                self.offline,
+            )
+            .await?),
             BuildStep::Script(adapter) => Ok(script::build(adapter, params, stdio).await?),
         }
     }
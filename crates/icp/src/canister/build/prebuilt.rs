@@ -1,7 +1,9 @@
 use snafu::prelude::*;
 use tokio::sync::mpsc::Sender;
 
-use crate::{canister::wasm, fs, manifest::adapter::prebuilt::Adapter, package::PackageCache};
+use crate::{
+    canister::wasm, fs, lockfile, manifest::adapter::prebuilt::Adapter, package::PackageCache,
+};
 
 use super::Params;
 
@@ -19,6 +21,8 @@ pub(super) async fn build(
     params: &Params,
     stdio: Option<Sender<String>>,
     pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
 ) -> Result<(), PrebuiltError> {
     let src = wasm::resolve(
         &adapter.source,
@@ -26,6 +30,8 @@ pub(super) async fn build(
         adapter.sha256.as_deref(),
         stdio.as_ref(),
         pkg_cache,
+        lockfile,
+        offline,
     )
     .await?;
 
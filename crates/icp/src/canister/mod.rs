@@ -6,6 +6,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    canister::secret::SecretSource,
     parsers::{CyclesAmount, DurationAmount, MemoryAmount},
     prelude::*,
 };
@@ -15,6 +16,7 @@ pub mod recipe;
 pub mod sync;
 
 mod script;
+pub mod secret;
 pub mod wasm;
 
 /// Controls who can read canister logs.
@@ -226,8 +228,19 @@ impl schemars::JsonSchema for ControllerRef {
 ///   API_KEY:
 ///     path: ./secrets/api-key
 /// ```
+///
+/// A secret is read from a [`SecretSource`] only when the canister's settings
+/// are deployed:
+/// ```yaml
+/// environment_variables:
+///   API_KEY:
+///     env: API_KEY
+/// ```
 #[derive(Clone, Debug, PartialEq, Deserialize, JsonSchema, Serialize)]
-#[serde(untagged, expecting = "a string, or `{ path: <file> }`")]
+#[serde(
+    untagged,
+    expecting = "a string, `{ path: <file> }`, `{ env: <NAME> }` or `{ command: <command> }`"
+)]
 pub enum ManifestEnvVar {
     /// The value, written inline.
     Value(String),
@@ -237,6 +250,8 @@ pub enum ManifestEnvVar {
         #[schemars(with = "String")]
         path: PathBuf,
     },
+    /// A secret, read when the settings are deployed.
+    Secret(SecretSource),
 }
 
 impl Default for ManifestEnvVar {
@@ -294,7 +309,8 @@ pub struct Settings<EnvVar = String> {
     /// Environment variables for the canister as key-value pairs.
     /// These variables are accessible within the canister and can be used to configure
     /// behavior without hardcoding values in the WASM module.
-    /// A value may also be read from a file with `{ path: <file> }`.
+    /// A value may also be read from a file with `{ path: <file> }`, or be a
+    /// secret read at deploy time with `{ env: <NAME> }` or `{ command: <command> }`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub environment_variables: Option<HashMap<String, EnvVar>>,
 
@@ -305,6 +321,49 @@ pub struct Settings<EnvVar = String> {
     pub controllers: Option<Vec<ControllerRef>>,
 }
 
+impl<EnvVar: Clone> Settings<EnvVar> {
+    /// These settings, with each field they leave unset taken from `base`.
+    /// Environment variables merge by name.
+    pub fn with_base(self, base: &Self) -> Self {
+        let Settings {
+            log_visibility,
+            compute_allocation,
+            memory_allocation,
+            freezing_threshold,
+            reserved_cycles_limit,
+            wasm_memory_limit,
+            wasm_memory_threshold,
+            log_memory_limit,
+            environment_variables,
+            controllers,
+        } = self;
+
+        let environment_variables = match (environment_variables, &base.environment_variables) {
+            (Some(own), Some(base)) => {
+                let mut merged = base.clone();
+                merged.extend(own);
+                Some(merged)
+            }
+            (own, base) => own.or_else(|| base.clone()),
+        };
+
+        Self {
+            log_visibility: log_visibility.or_else(|| base.log_visibility.clone()),
+            compute_allocation: compute_allocation.or(base.compute_allocation),
+            memory_allocation: memory_allocation.or_else(|| base.memory_allocation.clone()),
+            freezing_threshold: freezing_threshold.or_else(|| base.freezing_threshold.clone()),
+            reserved_cycles_limit: reserved_cycles_limit
+                .or_else(|| base.reserved_cycles_limit.clone()),
+            wasm_memory_limit: wasm_memory_limit.or_else(|| base.wasm_memory_limit.clone()),
+            wasm_memory_threshold: wasm_memory_threshold
+                .or_else(|| base.wasm_memory_threshold.clone()),
+            log_memory_limit: log_memory_limit.or_else(|| base.log_memory_limit.clone()),
+            environment_variables,
+            controllers: controllers.or_else(|| base.controllers.clone()),
+        }
+    }
+}
+
 impl From<Settings> for ManifestSettings {
     fn from(settings: Settings) -> Self {
         let Settings {
@@ -557,6 +616,35 @@ allowed_viewers:
         );
     }
 
+    #[test]
+    fn settings_environment_variables_take_secrets() {
+        let yaml = indoc! {r#"
+            environment_variables:
+              API_KEY:
+                env: API_KEY
+              DB_PASSWORD:
+                command: pass show db-password
+        "#};
+        let settings: ManifestSettings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            settings.environment_variables,
+            Some(HashMap::from([
+                (
+                    "API_KEY".to_owned(),
+                    ManifestEnvVar::Secret(SecretSource::Env {
+                        env: "API_KEY".to_owned(),
+                    }),
+                ),
+                (
+                    "DB_PASSWORD".to_owned(),
+                    ManifestEnvVar::Secret(SecretSource::Command {
+                        command: "pass show db-password".to_owned(),
+                    }),
+                ),
+            ])),
+        );
+    }
+
     #[test]
     fn settings_environment_variable_rejects_unknown_object_form() {
         let yaml = indoc! {r#"
@@ -565,9 +653,9 @@ allowed_viewers:
                 file: ./secrets/api-key
         "#};
         let err = serde_yaml::from_str::<ManifestSettings>(yaml)
-            .expect_err("only the `path` object form is accepted");
+            .expect_err("an unknown object form is rejected");
         assert!(
-            err.to_string().contains("a string, or `{ path: <file> }`"),
+            err.to_string().contains("a string, `{ path: <file> }`"),
             "unhelpful error: {err}"
         );
     }
@@ -580,7 +668,7 @@ allowed_viewers:
             serde_yaml::from_str::<ManifestSettings>("environment_variables:\n  PORT: 8080\n")
                 .expect_err("a bare integer is not a value");
         assert!(
-            err.to_string().contains("a string, or `{ path: <file> }`"),
+            err.to_string().contains("a string, `{ path: <file> }`"),
             "unhelpful error: {err}"
         );
     }
@@ -1,9 +1,12 @@
 //! Stage one of recipe resolution: get the template text.
 
-use std::{str::FromStr, string::FromUtf8Error};
+use std::{str::FromStr, string::FromUtf8Error, sync::Arc};
 
 use async_trait::async_trait;
-use reqwest::{Method, Request, Url};
+use reqwest::{
+    Method, Request, Url,
+    header::{AUTHORIZATION, HeaderValue},
+};
 use sha2::{Digest, Sha256};
 use snafu::prelude::*;
 use tracing::debug;
@@ -11,12 +14,14 @@ use url::ParseError;
 
 use crate::{
     fs::read,
+    lockfile,
     manifest::recipe::{Recipe, RecipeType},
     package::{
-        PackageCache, cache_registry_recipe, cache_uri_recipe, read_cached_registry_recipe,
-        read_cached_uri_recipe,
+        PackageCache, cache_registry_recipe, cache_uri_recipe, read_cached_recipe,
+        read_cached_registry_recipe, read_cached_uri_recipe,
     },
     prelude::*,
+    registry::{Asset, RECIPE_ASSET, Registries, version_requirement},
 };
 
 use super::{CommitSnafu, FetchSnafu, Resolve, ResolveError};
@@ -25,11 +30,18 @@ use super::{CommitSnafu, FetchSnafu, Resolve, ResolveError};
 /// Template *rendering* is a separate stage
 /// ([`render_recipe`](super::render_recipe)); this only produces the raw template
 /// text.
+///
+/// A remote recipe without a `sha256` in the manifest is pinned in `icp.lock`
+/// the first time it resolves, and verified against that pin afterwards.
 pub struct RecipeFetcher {
     /// Http client for fetching remote recipe templates
     pub http_client: reqwest::Client,
     /// Package cache for caching downloaded recipe templates
     pub pkg_cache: PackageCache,
+    /// Lock file pinning remote recipes that have no checksum in the manifest
+    pub lockfile: Arc<dyn lockfile::Access>,
+    /// Serve remote recipes from the package cache only, never downloading them
+    pub offline: bool,
 }
 
 /// The result of the fetch stage.
@@ -37,24 +49,41 @@ pub struct Fetched {
     /// Raw Handlebars template source.
     pub template: String,
 
-    /// A cache write deliberately held back until the template is known to
-    /// render; `None` when there is nothing to cache (a local file or a cache
-    /// hit) or when the download was already cached because it was checksummed.
+    /// Writes deliberately held back until the template is known to render;
+    /// `None` when there is nothing to write: nothing to cache (a local file, a
+    /// cache hit, or a checksummed download, which is cached at once) and no
+    /// newly resolved version to pin.
     ///
     /// Pass to [`Resolve::commit`] after [`render_recipe`](super::render_recipe)
     /// succeeds.
     pub pending_cache: Option<PendingCache>,
 }
 
-/// A cache write for an unpinned download, held until the template renders.
+/// The writes for a fetched template, held until the template renders: the
+/// cache write of an unpinned download, and the lock file pin of a version
+/// requirement resolved for it.
 ///
 /// A checksummed download is cached the moment its checksum verifies: the
 /// checksum is what establishes the bytes are the ones that were asked for, and
 /// refetching would only produce the same bytes again. An unpinned download has
 /// no such guarantee — caching it before it is known good would let a single bad
 /// response become sticky, and every later resolution would read those bytes
-/// back instead of refetching.
+/// back instead of refetching. Likewise, pinning a version whose template does
+/// not render would hold the project to it until the lock file is updated.
 pub struct PendingCache {
+    download: Option<PendingDownload>,
+    /// The `icp.lock` key of a version requirement and the version it resolved to
+    version: Option<(String, String)>,
+}
+
+impl PendingCache {
+    fn new(download: Option<PendingDownload>, version: Option<(String, String)>) -> Option<Self> {
+        (download.is_some() || version.is_some()).then_some(Self { download, version })
+    }
+}
+
+/// An unpinned download, to be cached and pinned once it renders.
+struct PendingDownload {
     target: CacheTarget,
     hash: [u8; 32],
     template: String,
@@ -66,6 +95,16 @@ enum CacheTarget {
     Registry { package: String, version: String },
 }
 
+impl CacheTarget {
+    /// The `icp.lock` key of the recipe, i.e. its manifest `type`.
+    fn lock_key(&self) -> String {
+        match self {
+            CacheTarget::Uri(u) => u.to_owned(),
+            CacheTarget::Registry { package, version } => format!("{package}@{version}"),
+        }
+    }
+}
+
 enum TemplateSource {
     LocalPath(PathBuf),
     RemoteUrl(String),
@@ -85,9 +124,19 @@ pub enum RecipeFetchError {
     #[snafu(display("failed to parse user-provided url"))]
     UrlParse { source: ParseError },
 
+    #[snafu(display("failed to locate recipe in registry"))]
+    Registry {
+        source: crate::registry::RegistryError,
+    },
+
     #[snafu(display("failed to execute http request"))]
     HttpRequest { source: reqwest::Error },
 
+    #[snafu(display("registry auth header is not a valid header value"))]
+    Authorization {
+        source: reqwest::header::InvalidHeaderValue,
+    },
+
     #[snafu(display("request to '{url}' returned '{status}' status-code"))]
     HttpStatus { url: String, status: u16 },
 
@@ -108,18 +157,54 @@ pub enum RecipeFetchError {
 
     #[snafu(display("failed to acquire lock on package cache"))]
     LockCache { source: crate::fs::lock::LockError },
+
+    #[snafu(display("failed to pin recipe template in icp.lock"))]
+    Lockfile {
+        source: crate::lockfile::LockfileError,
+    },
+
+    #[snafu(display(
+        "recipe '{recipe}' is not in the package cache and cannot be downloaded in offline mode"
+    ))]
+    NotCached { recipe: String },
+
+    #[snafu(display(
+        "the version of recipe '{recipe}' is not pinned in icp.lock and cannot be resolved in offline mode"
+    ))]
+    UnresolvedOffline { recipe: String },
+
+    #[snafu(display("registry '{registry}' does not publish a recipe named '{name}'"))]
+    UnknownRecipe { registry: String, name: String },
+
+    #[snafu(display("no published version of recipe '{recipe}' satisfies its requirement"))]
+    NoMatchingVersion { recipe: String },
 }
 
 impl RecipeFetcher {
     /// Fetch a recipe's Handlebars template text: read a local file, or fetch a
-    /// remote URL or registry recipe. Verifies `sha256` when set.
+    /// remote URL or a recipe from one of `registries`. Verifies `sha256` when set, or else the
+    /// pin in `icp.lock`.
     ///
     /// A checksummed download is cached here. An unpinned one is returned as a
     /// [`PendingCache`] for the caller to commit once it renders — see
     /// [`PendingCache`] for why.
-    async fn fetch_recipe(&self, recipe: &Recipe) -> Result<Fetched, RecipeFetchError> {
+    async fn fetch_recipe(
+        &self,
+        recipe: &Recipe,
+        registries: &Registries,
+    ) -> Result<Fetched, RecipeFetchError> {
+        // A version requirement is fetched as the version it resolves to
+        let (resolved, version_pin) = match self
+            .resolve_version(&recipe.recipe_type, registries)
+            .await?
+        {
+            Some((resolved, pin)) => (Some(resolved), pin),
+            None => (None, None),
+        };
+        let recipe_type = resolved.as_ref().unwrap_or(&recipe.recipe_type);
+
         // Determine the template source
-        let tmpl_source = match &recipe.recipe_type {
+        let tmpl_source = match recipe_type {
             RecipeType::File(path) => TemplateSource::LocalPath(Path::new(&path).into()),
             RecipeType::Url(url) => TemplateSource::RemoteUrl(url.to_owned()),
             RecipeType::Registry {
@@ -129,6 +214,27 @@ impl RecipeFetcher {
             } => TemplateSource::Registry(name.to_owned(), recipe.to_owned(), version.to_owned()),
         };
 
+        // A remote recipe without a manifest checksum is held to its lock file pin
+        let lock_key = match recipe_type {
+            RecipeType::File(_) => None,
+            _ if recipe.sha256.is_some() => None,
+            t => Some(t.to_string()),
+        };
+        let pinned = match &lock_key {
+            Some(key) => self
+                .lockfile
+                .load()
+                .context(LockfileSnafu)?
+                .recipe(key)
+                .map(str::to_owned),
+            None => None,
+        };
+        let expected = recipe.sha256.clone().or(pinned);
+
+        // While refreshing the lock file, unpinned recipes are refetched rather
+        // than read back from the cache
+        let use_cache = recipe.sha256.is_some() || !self.lockfile.refreshing();
+
         // Retrieve the template, using cache for remote/registry sources
         let (tmpl, should_cache) = match &tmpl_source {
             TemplateSource::LocalPath(path) => {
@@ -141,8 +247,10 @@ impl RecipeFetcher {
                 let maybe_cached = self
                     .pkg_cache
                     .with_read(async |r| {
-                        read_cached_uri_recipe(r, u, recipe.sha256.as_deref())
-                            .context(ReadCacheSnafu)
+                        if !use_cache {
+                            return Ok(None);
+                        }
+                        read_cached_uri_recipe(r, u, expected.as_deref()).context(ReadCacheSnafu)
                     })
                     .await
                     .context(LockCacheSnafu)?;
@@ -151,44 +259,62 @@ impl RecipeFetcher {
                     (parse_bytes_to_string(cached)?, false)
                 } else {
                     // Download the template
-                    let tmpl = self.fetch_remote_bytes(u).await?;
+                    ensure!(!self.offline, NotCachedSnafu { recipe: u });
+                    let tmpl = self.fetch_remote_bytes(u, None).await?;
                     (parse_bytes_to_string(tmpl)?, true)
                 }
             }
 
-            // TMP(or.ricon): Temporarily hardcode a dfinity registry
             TemplateSource::Registry(registry, recipe_name, version) => {
-                if registry != "dfinity" {
-                    panic!("only the dfinity registry is currently supported");
-                }
-
                 let package = format!("@{registry}/{recipe_name}");
-                let release_tag = format!("{recipe_name}-{version}");
-
-                // Check cache
-                let maybe_cached = self
-                    .pkg_cache
-                    .with_read(async |r| {
-                        read_cached_registry_recipe(r, &package, version).context(ReadCacheSnafu)
-                    })
-                    .await
-                    .context(LockCacheSnafu)?;
-                if let Some(cached) = maybe_cached? {
-                    debug!("Using cached recipe template for {package}@{version}");
-                    (parse_bytes_to_string(cached)?, false)
-                } else {
-                    // Download the template
-                    let url = format!(
-                        "https://github.com/dfinity/icp-cli-recipes/releases/download/{release_tag}/recipe.hbs"
-                    );
-                    let bytes = self.fetch_remote_bytes(&url).await?;
-
-                    (parse_bytes_to_string(bytes)?, true)
+                let asset = registries
+                    .get(registry)
+                    .and_then(|r| r.asset(recipe_name, version, RECIPE_ASSET))
+                    .context(RegistrySnafu)?;
+
+                match asset {
+                    // A registry in a local directory is read like a local file
+                    Asset::File(path) => {
+                        let bytes = read(&path).context(ReadFileSnafu)?;
+                        (parse_bytes_to_string(bytes)?, false)
+                    }
+
+                    Asset::Url { url, authorization } => {
+                        // Check cache
+                        let maybe_cached = self
+                            .pkg_cache
+                            .with_read(async |r| match &expected {
+                                // A pinned template is looked up by content
+                                Some(sha) => read_cached_recipe(r, sha).context(ReadCacheSnafu),
+                                None if !use_cache => Ok(None),
+                                None => read_cached_registry_recipe(r, &package, version)
+                                    .context(ReadCacheSnafu),
+                            })
+                            .await
+                            .context(LockCacheSnafu)?;
+                        if let Some(cached) = maybe_cached? {
+                            debug!("Using cached recipe template for {package}@{version}");
+                            (parse_bytes_to_string(cached)?, false)
+                        } else {
+                            // Download the template
+                            ensure!(
+                                !self.offline,
+                                NotCachedSnafu {
+                                    recipe: format!("{package}@{version}")
+                                }
+                            );
+                            let bytes = self
+                                .fetch_remote_bytes(&url, authorization.as_deref())
+                                .await?;
+
+                            (parse_bytes_to_string(bytes)?, true)
+                        }
+                    }
                 }
             }
         };
 
-        let hash = if let Some(sha256) = &recipe.sha256 {
+        let hash = if let Some(sha256) = &expected {
             verify_checksum(tmpl.as_bytes(), sha256)?
         } else {
             Sha256::digest(tmpl.as_bytes()).into()
@@ -196,9 +322,12 @@ impl RecipeFetcher {
 
         // Nothing was downloaded (local file, or a cache hit): nothing to cache.
         if !should_cache {
+            if let Some(key) = &lock_key {
+                self.pin(key, &hash)?;
+            }
             return Ok(Fetched {
                 template: tmpl,
-                pending_cache: None,
+                pending_cache: PendingCache::new(None, version_pin),
             });
         }
 
@@ -211,30 +340,91 @@ impl RecipeFetcher {
             },
         };
 
-        let pending = PendingCache {
+        let pending = PendingDownload {
             target,
             hash,
             template: tmpl,
         };
 
         // A checksummed download is trustworthy the moment the checksum matches,
-        // so cache it now. An unpinned one waits for a successful render.
-        if recipe.sha256.is_some() {
+        // so cache it now. An unpinned one waits for a successful render, and is
+        // only pinned once committed.
+        if expected.is_some() {
             self.write_cache(&pending).await?;
+            if let Some(key) = &lock_key {
+                self.pin(key, &hash)?;
+            }
             return Ok(Fetched {
                 template: pending.template,
-                pending_cache: None,
+                pending_cache: PendingCache::new(None, version_pin),
             });
         }
 
         Ok(Fetched {
             template: pending.template.clone(),
-            pending_cache: Some(pending),
+            pending_cache: PendingCache::new(Some(pending), version_pin),
         })
     }
 
+    /// The exact version a registry recipe's version requirement resolves to,
+    /// or `None` when the recipe does not give one. The resolution is pinned in
+    /// `icp.lock`; an unpinned requirement picks the highest version the
+    /// registry's index lists that satisfies it, and is returned with the pin
+    /// to record once its template renders.
+    async fn resolve_version(
+        &self,
+        recipe_type: &RecipeType,
+        registries: &Registries,
+    ) -> Result<Option<(RecipeType, Option<(String, String)>)>, RecipeFetchError> {
+        let RecipeType::Registry {
+            name: registry,
+            recipe: name,
+            version,
+        } = recipe_type
+        else {
+            return Ok(None);
+        };
+        let Some(requirement) = version_requirement(version) else {
+            return Ok(None);
+        };
+        let with_version = |version: &str| RecipeType::Registry {
+            name: registry.clone(),
+            recipe: name.clone(),
+            version: version.to_owned(),
+        };
+
+        let key = recipe_type.to_string();
+        let lock = self.lockfile.load().context(LockfileSnafu)?;
+        if let Some(pinned) = lock.recipe_version(&key) {
+            return Ok(Some((with_version(pinned), None)));
+        }
+
+        let r = registries.get(registry).context(RegistrySnafu)?;
+        // A registry in a local directory is read even when offline
+        let local = matches!(r.index(), Ok(Asset::File(_)));
+        ensure!(
+            local || !self.offline,
+            UnresolvedOfflineSnafu { recipe: &key }
+        );
+        let index = r
+            .fetch_index(&self.http_client)
+            .await
+            .context(RegistrySnafu)?;
+        let resolved = index
+            .recipe(name)
+            .context(UnknownRecipeSnafu { registry, name })?
+            .latest_matching(&requirement)
+            .context(NoMatchingVersionSnafu { recipe: &key })?;
+        debug!("Resolved recipe {key} to version {resolved}");
+
+        Ok(Some((
+            with_version(resolved),
+            Some((key, resolved.to_owned())),
+        )))
+    }
+
     /// Write a fetched template into the package cache.
-    async fn write_cache(&self, pending: &PendingCache) -> Result<(), RecipeFetchError> {
+    async fn write_cache(&self, pending: &PendingDownload) -> Result<(), RecipeFetchError> {
         let hash = hex::encode(pending.hash);
         let bytes = pending.template.as_bytes();
         match &pending.target {
@@ -260,14 +450,32 @@ impl RecipeFetcher {
         Ok(())
     }
 
-    /// Fetch raw bytes from a remote URL.
-    async fn fetch_remote_bytes(&self, url: &str) -> Result<Vec<u8>, RecipeFetchError> {
+    /// Record the sha256 of a remote recipe in the lock file.
+    fn pin(&self, key: &str, hash: &[u8; 32]) -> Result<(), RecipeFetchError> {
+        let sha256 = hex::encode(hash);
+        self.lockfile
+            .update(&mut |lock| lock.pin_recipe(key, &sha256))
+            .context(LockfileSnafu)
+    }
+
+    /// Fetch raw bytes from a remote URL, sending `authorization` as the
+    /// `Authorization` header.
+    async fn fetch_remote_bytes(
+        &self,
+        url: &str,
+        authorization: Option<&str>,
+    ) -> Result<Vec<u8>, RecipeFetchError> {
         let u = Url::from_str(url).context(UrlParseSnafu)?;
         debug!("Requesting template from: {u}");
 
+        let mut request = Request::new(Method::GET, u.clone());
+        if let Some(value) = authorization {
+            let value = HeaderValue::from_str(value).context(AuthorizationSnafu)?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
         let resp = self
             .http_client
-            .execute(Request::new(Method::GET, u.clone()))
+            .execute(request)
             .await
             .context(HttpRequestSnafu)?;
 
@@ -285,12 +493,29 @@ impl RecipeFetcher {
 
 #[async_trait]
 impl Resolve for RecipeFetcher {
-    async fn resolve(&self, recipe: &Recipe) -> Result<Fetched, ResolveError> {
-        self.fetch_recipe(recipe).await.context(FetchSnafu)
+    async fn resolve(
+        &self,
+        recipe: &Recipe,
+        registries: &Registries,
+    ) -> Result<Fetched, ResolveError> {
+        self.fetch_recipe(recipe, registries)
+            .await
+            .context(FetchSnafu)
     }
 
     async fn commit(&self, pending: PendingCache) -> Result<(), ResolveError> {
-        self.write_cache(&pending).await.context(CommitSnafu)
+        if let Some(download) = &pending.download {
+            self.write_cache(download).await.context(CommitSnafu)?;
+            self.pin(&download.target.lock_key(), &download.hash)
+                .context(CommitSnafu)?;
+        }
+        if let Some((key, version)) = &pending.version {
+            self.lockfile
+                .update(&mut |lock| lock.pin_recipe_version(key, version))
+                .context(LockfileSnafu)
+                .context(CommitSnafu)?;
+        }
+        Ok(())
     }
 }
 
@@ -326,6 +551,8 @@ mod tests {
         RecipeFetcher {
             http_client: reqwest::Client::new(),
             pkg_cache: PackageCache::new(cache_dir.to_owned()).unwrap(),
+            lockfile: Arc::new(crate::lockfile::mock::MockInMemoryLockfile::default()),
+            offline: false,
         }
     }
 
@@ -350,7 +577,7 @@ mod tests {
         };
 
         let fetched = fetcher(&tmp.path().join("pkg"))
-            .fetch_recipe(&recipe)
+            .fetch_recipe(&recipe, &Registries::default())
             .await
             .unwrap();
         assert_eq!(fetched.template, body);
@@ -374,7 +601,9 @@ mod tests {
         };
 
         assert!(matches!(
-            fetcher(&tmp.path().join("pkg")).fetch_recipe(&recipe).await,
+            fetcher(&tmp.path().join("pkg"))
+                .fetch_recipe(&recipe, &Registries::default())
+                .await,
             Err(RecipeFetchError::ChecksumMismatch { .. })
         ));
     }
@@ -439,7 +668,10 @@ mod tests {
         };
 
         // Fetch succeeds and hands back a held-back cache write.
-        let fetched = f.fetch_recipe(&recipe).await.expect("first fetch");
+        let fetched = f
+            .fetch_recipe(&recipe, &Registries::default())
+            .await
+            .expect("first fetch");
         assert!(
             fetched.pending_cache.is_some(),
             "an unpinned download must defer its cache write"
@@ -448,6 +680,8 @@ mod tests {
         // Rendering fails, so the caller never commits.
         let ctx = super::super::RecipeContext {
             canister_name: "c".to_owned(),
+            project_root: PathBuf::from("/project"),
+            environment: None,
         };
         assert!(
             super::super::render_recipe(&fetched.template, &recipe, &ctx).is_err(),
@@ -463,7 +697,7 @@ mod tests {
         // bad bytes from cache.
         assert!(
             matches!(
-                f.fetch_recipe(&recipe).await,
+                f.fetch_recipe(&recipe, &Registries::default()).await,
                 Err(RecipeFetchError::HttpStatus { status: 500, .. })
             ),
             "second resolution must refetch, not read the uncommitted template back"
@@ -491,14 +725,22 @@ mod tests {
             sha256: None,
         };
 
-        let fetched = f.fetch_recipe(&recipe).await.expect("first fetch");
+        let fetched = f
+            .fetch_recipe(&recipe, &Registries::default())
+            .await
+            .expect("first fetch");
         let pending = fetched.pending_cache.expect("unpinned defers its write");
-        f.write_cache(&pending).await.expect("commit");
+        f.write_cache(pending.download.as_ref().unwrap())
+            .await
+            .expect("commit");
 
         assert!(cache_has_template(&cache_dir));
 
         // Served from cache now, even though the server would answer 500.
-        let again = f.fetch_recipe(&recipe).await.expect("second fetch");
+        let again = f
+            .fetch_recipe(&recipe, &Registries::default())
+            .await
+            .expect("second fetch");
         assert_eq!(again.template, fetched.template);
         assert!(
             again.pending_cache.is_none(),
@@ -523,11 +765,227 @@ mod tests {
             sha256: Some(hex::encode(Sha256::digest(UNRENDERABLE.as_bytes()))),
         };
 
-        let fetched = f.fetch_recipe(&recipe).await.expect("first fetch");
+        let fetched = f
+            .fetch_recipe(&recipe, &Registries::default())
+            .await
+            .expect("first fetch");
         assert!(
             fetched.pending_cache.is_none(),
             "a checksummed download is cached during the fetch"
         );
         assert!(cache_has_template(&cache_dir));
     }
+
+    /// An unpinned template is pinned in the lock file once committed, and a
+    /// later fetch is held to that pin.
+    #[tokio::test]
+    async fn committed_download_is_pinned() {
+        let good = indoc::indoc! {r#"
+            build:
+              steps:
+                - type: script
+                  command: "build {{_.canister.name}}"
+        "#};
+        let (_server, url) = serve_once_then_fail(good);
+
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let f = fetcher(&tmp.path().join("pkg"));
+        let recipe = Recipe {
+            recipe_type: RecipeType::Url(url.clone()),
+            configuration: Default::default(),
+            sha256: None,
+        };
+
+        let fetched = f
+            .fetch_recipe(&recipe, &Registries::default())
+            .await
+            .expect("fetch");
+        assert_eq!(f.lockfile.load().unwrap().recipe(&url), None);
+
+        f.commit(fetched.pending_cache.unwrap())
+            .await
+            .expect("commit");
+        let sha256 = hex::encode(Sha256::digest(good.as_bytes()));
+        assert_eq!(f.lockfile.load().unwrap().recipe(&url), Some(&*sha256));
+    }
+
+    /// A template that no longer matches its pin is rejected, just like a
+    /// mismatched `sha256` in the manifest.
+    #[tokio::test]
+    async fn pin_mismatch_is_rejected() {
+        let (_server, url) = serve_once_then_fail(UNRENDERABLE);
+
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let f = fetcher(&tmp.path().join("pkg"));
+        f.lockfile
+            .update(&mut |lock| lock.pin_recipe(&url, &"0".repeat(64)))
+            .unwrap();
+        let recipe = Recipe {
+            recipe_type: RecipeType::Url(url),
+            configuration: Default::default(),
+            sha256: None,
+        };
+
+        assert!(matches!(
+            f.fetch_recipe(&recipe, &Registries::default()).await,
+            Err(RecipeFetchError::ChecksumMismatch { .. })
+        ));
+    }
+
+    /// Offline, a cached template is still served, while one that would need
+    /// downloading is reported instead of fetched.
+    #[tokio::test]
+    async fn offline_serves_cached_recipes_only() {
+        let good = indoc::indoc! {r#"
+            build:
+              steps:
+                - type: script
+                  command: "build {{_.canister.name}}"
+        "#};
+        let (_server, url) = serve_once_then_fail(good);
+
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let cache_dir = tmp.path().join("pkg");
+        let recipe = |url: String| Recipe {
+            recipe_type: RecipeType::Url(url),
+            configuration: Default::default(),
+            sha256: None,
+        };
+
+        let online = fetcher(&cache_dir);
+        let fetched = online
+            .fetch_recipe(&recipe(url.clone()), &Registries::default())
+            .await
+            .unwrap();
+        online.commit(fetched.pending_cache.unwrap()).await.unwrap();
+
+        let offline = RecipeFetcher {
+            offline: true,
+            ..fetcher(&cache_dir)
+        };
+        let again = offline
+            .fetch_recipe(&recipe(url.clone()), &Registries::default())
+            .await
+            .unwrap();
+        assert_eq!(again.template, fetched.template);
+
+        let missing = format!("{url}.missing");
+        assert!(matches!(
+            offline.fetch_recipe(&recipe(missing.clone()), &Registries::default()).await,
+            Err(RecipeFetchError::NotCached { recipe }) if recipe == missing
+        ));
+    }
+
+    /// A recipe of a registry declared with `auth-header-env` is downloaded from
+    /// `<base>/<name>/<version>/recipe.hbs` with that variable as its
+    /// `Authorization` header.
+    #[tokio::test]
+    async fn private_registry_sends_the_auth_header() {
+        use httptest::{Expectation, Server, matchers::*, responders::*};
+
+        const AUTH_VAR: &str = "ICP_CLI_TEST_REGISTRY_AUTH";
+        // SAFETY: the name is used by this test alone.
+        unsafe { std::env::set_var(AUTH_VAR, "Bearer s3cret") };
+
+        let body = "build:\n  steps: []\n";
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/icp/rust/v1.0.0/recipe.hbs"),
+                request::headers(contains(("authorization", "Bearer s3cret"))),
+            ])
+            .respond_with(status_code(200).body(body)),
+        );
+
+        let registries = Registries::from_settings(&[crate::manifest::RegistryManifest {
+            name: "acme".to_owned(),
+            url: Some(server.url("/icp").to_string()),
+            path: None,
+            auth_header_env: Some(AUTH_VAR.to_owned()),
+        }])
+        .unwrap();
+        let recipe = Recipe {
+            recipe_type: RecipeType::Registry {
+                name: "acme".to_owned(),
+                recipe: "rust".to_owned(),
+                version: "v1.0.0".to_owned(),
+            },
+            configuration: Default::default(),
+            sha256: None,
+        };
+
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let fetched = fetcher(&tmp.path().join("pkg"))
+            .fetch_recipe(&recipe, &registries)
+            .await
+            .unwrap();
+        assert_eq!(fetched.template, body);
+    }
+
+    /// A version requirement resolves to the highest matching version in the
+    /// registry's index, and is pinned to it: a later release does not change
+    /// what the project gets until the lock file is updated.
+    #[tokio::test]
+    async fn version_requirement_resolves_and_is_pinned() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let registry_dir = tmp.path().join("registry");
+        let write_version = |version: &str| {
+            let dir = registry_dir.join("rust").join(version);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("recipe.hbs"), format!("# {version}\n")).unwrap();
+        };
+        let write_index = |versions: &str| {
+            std::fs::write(
+                registry_dir.join("index.json"),
+                format!(r#"{{"recipes": [{{"name": "rust", "versions": [{versions}]}}]}}"#),
+            )
+            .unwrap();
+        };
+        for v in ["v1.0.0", "v1.4.0", "v1.5.0", "v2.0.0"] {
+            write_version(v);
+        }
+        write_index(r#""v1.0.0", "v1.4.0", "v2.0.0""#);
+
+        let registries = Registries::from_settings(&[crate::manifest::RegistryManifest {
+            name: "local".to_owned(),
+            url: None,
+            path: Some(registry_dir.to_string()),
+            auth_header_env: None,
+        }])
+        .unwrap();
+        let recipe = |version: &str| Recipe {
+            recipe_type: RecipeType::Registry {
+                name: "local".to_owned(),
+                recipe: "rust".to_owned(),
+                version: version.to_owned(),
+            },
+            configuration: Default::default(),
+            sha256: None,
+        };
+
+        let f = fetcher(&tmp.path().join("pkg"));
+        let fetched = f.fetch_recipe(&recipe("^1"), &registries).await.unwrap();
+        assert_eq!(fetched.template, "# v1.4.0\n");
+
+        // The resolution is only pinned once the template has rendered
+        assert_eq!(
+            f.lockfile.load().unwrap().recipe_version("@local/rust@^1"),
+            None
+        );
+        f.commit(fetched.pending_cache.expect("the resolution is held back"))
+            .await
+            .unwrap();
+        let lock = f.lockfile.load().unwrap();
+        assert_eq!(lock.recipe_version("@local/rust@^1"), Some("v1.4.0"));
+
+        // A newer release does not move the pin
+        write_index(r#""v1.0.0", "v1.4.0", "v1.5.0", "v2.0.0""#);
+        let fetched = f.fetch_recipe(&recipe("^1"), &registries).await.unwrap();
+        assert_eq!(fetched.template, "# v1.4.0\n");
+
+        assert!(matches!(
+            f.fetch_recipe(&recipe("^3"), &registries).await,
+            Err(RecipeFetchError::NoMatchingVersion { recipe }) if recipe == "@local/rust@^3"
+        ));
+    }
 }
@@ -2,7 +2,8 @@
 //!
 //! [`fetch`] retrieves a recipe's Handlebars template — reading a local file, or
 //! downloading a remote URL or registry recipe — and returns the raw template
-//! text. [`render`] turns that text into concrete build/sync steps. The first
+//! text. [`render`] turns that text into concrete build/sync steps, plus
+//! defaults for the canister's settings and init args. The first
 //! stage does I/O and nothing else; the second is a pure function.
 //!
 //! The [`Resolve`] seam therefore covers only the fetching half, so a caller that
@@ -20,13 +21,17 @@
 use async_trait::async_trait;
 use snafu::prelude::*;
 
-use crate::manifest::recipe::Recipe;
+use crate::{manifest::recipe::Recipe, registry::Registries};
 
 pub mod fetch;
 pub mod render;
+pub mod schema;
 
 pub use fetch::{Fetched, PendingCache};
-pub use render::{RecipeContext, RenderRecipeError, render_recipe};
+pub use render::{
+    RecipeContext, RecipeEnvironment, RenderRecipeError, RenderedRecipe, render_recipe,
+};
+pub use schema::RecipeSchema;
 
 /// Retrieves the recipe templates a project references.
 ///
@@ -35,8 +40,13 @@ pub use render::{RecipeContext, RenderRecipeError, render_recipe};
 #[async_trait]
 pub trait Resolve: Sync + Send {
     /// Fetch the Handlebars template for `recipe`, returning its raw source and
-    /// any cache write held back until the template is known to render.
-    async fn resolve(&self, recipe: &Recipe) -> Result<Fetched, ResolveError>;
+    /// any cache write held back until the template is known to render. A
+    /// registry recipe is looked up in `registries`.
+    async fn resolve(
+        &self,
+        recipe: &Recipe,
+        registries: &Registries,
+    ) -> Result<Fetched, ResolveError>;
 
     /// Write a held-back download to the cache, now that it has rendered.
     ///
@@ -7,9 +7,17 @@ use serde::Deserialize;
 use snafu::prelude::*;
 use tracing::debug;
 
-use crate::manifest::{
-    canister::{BuildSteps, SyncSteps},
-    recipe::{Recipe, RecipeType},
+use super::schema::{RecipeSchema, RecipeSchemaError};
+use crate::{
+    Network,
+    canister::{ManifestEnvVar, ManifestSettings},
+    manifest::{
+        ManifestInitArgs,
+        canister::{BuildSteps, SyncSteps},
+        recipe::{Recipe, RecipeType},
+    },
+    network::Configuration,
+    prelude::*,
 };
 
 /// Describes the canister being built, for the render stage.
@@ -23,9 +31,44 @@ use crate::manifest::{
 /// ```yaml
 /// canister:
 ///   name: <canister_name>
+/// environment:
+///   name: <environment name>
+/// network:
+///   name: <network name>
+///   mode: managed | connected
+/// project:
+///   root: <directory of the project declaring the canister>
 /// ```
+///
+/// Outside an environment, the `environment` and `network` values are null, so
+/// a template comparing them renders its environment-independent branch.
 pub struct RecipeContext {
     pub canister_name: String,
+    pub project_root: PathBuf,
+    pub environment: Option<RecipeEnvironment>,
+}
+
+/// The environment a recipe is rendered for.
+pub struct RecipeEnvironment {
+    pub name: String,
+    pub network_name: String,
+    /// `managed` or `connected`.
+    pub network_mode: String,
+}
+
+impl RecipeEnvironment {
+    /// The environment `name`, deployed to `network`.
+    pub fn new(name: &str, network: &Network) -> Self {
+        Self {
+            name: name.to_owned(),
+            network_name: network.name.clone(),
+            network_mode: match network.configuration {
+                Configuration::Managed { .. } => "managed",
+                Configuration::Connected { .. } => "connected",
+            }
+            .to_owned(),
+        }
+    }
 }
 
 impl RecipeContext {
@@ -34,11 +77,30 @@ impl RecipeContext {
     pub fn to_yaml(&self) -> serde_yaml::Value {
         use serde_yaml::{Mapping, Value};
 
+        let string_or_null = |v: Option<&String>| match v {
+            Some(v) => Value::String(v.clone()),
+            None => Value::Null,
+        };
+        let env = self.environment.as_ref();
+
         let mut canister = Mapping::new();
         canister.insert("name".into(), Value::String(self.canister_name.clone()));
 
+        let mut environment = Mapping::new();
+        environment.insert("name".into(), string_or_null(env.map(|e| &e.name)));
+
+        let mut network = Mapping::new();
+        network.insert("name".into(), string_or_null(env.map(|e| &e.network_name)));
+        network.insert("mode".into(), string_or_null(env.map(|e| &e.network_mode)));
+
+        let mut project = Mapping::new();
+        project.insert("root".into(), Value::String(self.project_root.to_string()));
+
         let mut root = Mapping::new();
         root.insert("canister".into(), Value::Mapping(canister));
+        root.insert("environment".into(), Value::Mapping(environment));
+        root.insert("network".into(), Value::Mapping(network));
+        root.insert("project".into(), Value::Mapping(project));
 
         Value::Mapping(root)
     }
@@ -46,6 +108,21 @@ impl RecipeContext {
 
 #[derive(Debug, Snafu)]
 pub enum RenderRecipeError {
+    #[snafu(display("recipe '{recipe}' declares an invalid configuration schema"))]
+    Schema {
+        source: RecipeSchemaError,
+        recipe: RecipeType,
+    },
+
+    #[snafu(display(
+        "invalid configuration for recipe '{recipe}':{}",
+        problems.iter().map(|p| format!("\n  - {p}")).collect::<String>()
+    ))]
+    Configuration {
+        recipe: RecipeType,
+        problems: Vec<String>,
+    },
+
     #[snafu(display("recipe template for '{recipe}' failed to render"))]
     Render {
         // Boxed to keep `Result<_, RenderRecipeError>` small; `RenderError`
@@ -60,18 +137,39 @@ pub enum RenderRecipeError {
         source: serde_yaml::Error,
         recipe: RecipeType,
     },
+
+    #[snafu(display("recipe '{recipe}' may not set `{key}`; only the canister manifest can"))]
+    Forbidden { recipe: RecipeType, key: String },
+}
+
+/// What a recipe renders into: the canister's build and sync steps, plus
+/// defaults for its settings and init args. The defaults apply beneath the
+/// canister's own manifest and environment values, key by key.
+#[derive(Debug)]
+pub struct RenderedRecipe {
+    pub build: BuildSteps,
+    pub sync: SyncSteps,
+    pub settings: ManifestSettings,
+    pub init_args: Option<ManifestInitArgs>,
 }
 
 /// Render a recipe's Handlebars `template` into concrete build/sync steps.
 ///
-/// The template is rendered with the recipe's `configuration` plus the reserved
-/// `_` namespace (the `_` key always overrides any user-supplied value), then the
-/// resulting YAML is parsed. A recipe may only produce `build` and `sync`.
+/// If the template declares a [configuration schema](super::schema), the
+/// recipe's `configuration` is completed with its defaults and validated first.
+/// The template is rendered with that configuration plus the reserved `_`
+/// namespace (the `_` key always overrides any user-supplied value), then the
+/// resulting YAML is parsed. A recipe may only produce `build`, `sync`,
+/// `settings` and `init_args`.
+///
+/// A recipe cannot set `controllers`, nor read values from files: a remote
+/// recipe must not be able to hand a canister to someone else or copy local
+/// files into it.
 pub fn render_recipe(
     template: &str,
     recipe: &Recipe,
     recipe_context: &RecipeContext,
-) -> Result<(BuildSteps, SyncSteps), RenderRecipeError> {
+) -> Result<RenderedRecipe, RenderRecipeError> {
     let mut reg = Handlebars::new();
     // The output is YAML, not HTML, so disable HTML escaping.
     reg.register_escape_fn(handlebars::no_escape);
@@ -81,7 +179,18 @@ pub fn render_recipe(
 
     // User-provided configuration plus the injected `_.*` variables. The `_` key
     // is reserved and always overrides any user-supplied value.
-    let mut render_context: HashMap<String, serde_yaml::Value> = recipe.configuration.clone();
+    let schema = RecipeSchema::from_template(template).context(SchemaSnafu {
+        recipe: recipe.recipe_type.clone(),
+    })?;
+    let mut render_context: HashMap<String, serde_yaml::Value> = match schema {
+        Some(schema) => schema.apply(&recipe.configuration).map_err(|problems| {
+            RenderRecipeError::Configuration {
+                recipe: recipe.recipe_type.clone(),
+                problems,
+            }
+        })?,
+        None => recipe.configuration.clone(),
+    };
     render_context.insert("_".to_string(), recipe_context.to_yaml());
 
     debug!("Rendering recipe template:\n------\n{template}\n------");
@@ -97,18 +206,47 @@ pub fn render_recipe(
     // message.
     debug!("Rendered recipe template:\n------\n{out}\n------");
 
-    // Recipes can only render `build`/`sync`.
+    // Recipes can only render `build`/`sync` and defaults for `settings`/`init_args`.
     #[derive(Deserialize)]
-    struct BuildSyncHelper {
+    struct RenderedHelper {
         build: BuildSteps,
         #[serde(default)]
         sync: SyncSteps,
+        #[serde(default)]
+        settings: ManifestSettings,
+        #[serde(default)]
+        init_args: Option<ManifestInitArgs>,
     }
 
-    let helper: BuildSyncHelper = serde_yaml::from_str(&out).context(ParseSnafu {
+    let helper: RenderedHelper = serde_yaml::from_str(&out).context(ParseSnafu {
         recipe: recipe.recipe_type.clone(),
     })?;
-    Ok((helper.build, helper.sync))
+
+    let forbidden = |key: String| ForbiddenSnafu {
+        recipe: recipe.recipe_type.clone(),
+        key,
+    };
+    ensure!(
+        helper.settings.controllers.is_none(),
+        forbidden("settings.controllers".to_string())
+    );
+    for (name, var) in helper.settings.environment_variables.iter().flatten() {
+        ensure!(
+            matches!(var, ManifestEnvVar::Value(_)),
+            forbidden(format!("settings.environment_variables.{name}.path"))
+        );
+    }
+    ensure!(
+        !matches!(helper.init_args, Some(ManifestInitArgs::Path { .. })),
+        forbidden("init_args.path".to_string())
+    );
+
+    Ok(RenderedRecipe {
+        build: helper.build,
+        sync: helper.sync,
+        settings: helper.settings,
+        init_args: helper.init_args,
+    })
 }
 
 /// Handlebars helper for string replacement operations.
@@ -156,13 +294,26 @@ mod tests {
     fn ctx(name: &str) -> RecipeContext {
         RecipeContext {
             canister_name: name.to_owned(),
+            project_root: PathBuf::from("/project"),
+            environment: None,
+        }
+    }
+
+    fn env_ctx(name: &str, environment: &str, network: &str, mode: &str) -> RecipeContext {
+        RecipeContext {
+            environment: Some(RecipeEnvironment {
+                name: environment.to_owned(),
+                network_name: network.to_owned(),
+                network_mode: mode.to_owned(),
+            }),
+            ..ctx(name)
         }
     }
 
     /// The only build step's command, for a recipe that renders a single script step.
     fn rendered_command(template: &str, recipe: &Recipe, context: &RecipeContext) -> String {
-        let (build, _sync) = render_recipe(template, recipe, context).unwrap();
-        match &build.steps[0] {
+        let rendered = render_recipe(template, recipe, context).unwrap();
+        match &rendered.build.steps[0] {
             BuildStep::Script(adapter) => adapter.command.as_vec()[0].clone(),
             other => panic!("expected a script build step, got {other:?}"),
         }
@@ -215,6 +366,51 @@ mod tests {
         );
     }
 
+    /// The environment, its network and the project root are injected alongside
+    /// the canister name.
+    #[test]
+    fn environment_network_and_project_are_injected() {
+        let template = indoc::indoc! {r#"
+            build:
+              steps:
+                - type: script
+                  command: "build {{_.environment.name}} {{_.network.name}} {{_.network.mode}} {{_.project.root}}"
+        "#};
+        assert_eq!(
+            rendered_command(
+                template,
+                &recipe(&[]),
+                &env_ctx("backend", "staging", "testnet", "connected")
+            ),
+            "build staging testnet connected /project"
+        );
+    }
+
+    /// Outside an environment the environment-dependent values are null rather
+    /// than missing, so comparing them selects the template's fallback branch
+    /// instead of failing strict rendering.
+    #[test]
+    fn environment_is_null_outside_an_environment() {
+        let template = indoc::indoc! {r#"
+            build:
+              steps:
+                - type: script
+                  command: "cargo build{{#if (eq _.environment.name "ic")}} --release{{/if}}"
+        "#};
+        assert_eq!(
+            rendered_command(template, &recipe(&[]), &ctx("backend")),
+            "cargo build"
+        );
+        assert_eq!(
+            rendered_command(
+                template,
+                &recipe(&[]),
+                &env_ctx("backend", "ic", "ic", "connected")
+            ),
+            "cargo build --release"
+        );
+    }
+
     /// User configuration cannot override the reserved `_` namespace.
     #[test]
     fn reserved_namespace_cannot_be_overridden_by_user_config() {
@@ -251,6 +447,98 @@ mod tests {
         ));
     }
 
+    /// Defaults declared by the template's schema are visible to the template.
+    #[test]
+    fn schema_defaults_are_rendered() {
+        let template = indoc::indoc! {r#"
+            {{!-- schema
+            type: object
+            properties:
+              profile:
+                type: string
+                default: release
+            --}}
+            build:
+              steps:
+                - type: script
+                  command: "cargo build --profile {{ profile }}"
+        "#};
+        assert_eq!(
+            rendered_command(template, &recipe(&[]), &ctx("c")),
+            "cargo build --profile release"
+        );
+    }
+
+    /// Configuration that violates the template's schema is rejected before
+    /// rendering, naming the offending key.
+    #[test]
+    fn schema_violation_is_a_configuration_error() {
+        let template = indoc::indoc! {r#"
+            {{!-- schema
+            type: object
+            required: [package]
+            --}}
+            build:
+              steps:
+                - type: script
+                  command: "cargo build --package {{ package }}"
+        "#};
+        let err = render_recipe(template, &recipe(&[]), &ctx("c")).unwrap_err();
+        assert!(matches!(err, RenderRecipeError::Configuration { .. }));
+        assert!(
+            err.to_string()
+                .contains("\"package\" is a required property")
+        );
+    }
+
+    /// Settings and init args rendered by a recipe are returned as defaults.
+    #[test]
+    fn settings_and_init_args_are_rendered() {
+        let template = indoc::indoc! {r#"
+            build:
+              steps:
+                - type: script
+                  command: "build"
+            settings:
+              log_visibility: public
+              environment_variables:
+                CANISTER: "{{ _.canister.name }}"
+            init_args: "(record { name = \"{{ _.canister.name }}\" })"
+        "#};
+        let rendered = render_recipe(template, &recipe(&[]), &ctx("assets")).unwrap();
+        assert!(rendered.settings.log_visibility.is_some());
+        assert_eq!(
+            rendered.settings.environment_variables.unwrap()["CANISTER"],
+            ManifestEnvVar::Value("assets".to_string())
+        );
+        assert_eq!(
+            rendered.init_args,
+            Some(ManifestInitArgs::String(
+                r#"(record { name = "assets" })"#.to_string()
+            ))
+        );
+    }
+
+    /// A recipe cannot add controllers or read local files.
+    #[test]
+    fn recipe_cannot_set_controllers_or_read_files() {
+        for extra in [
+            "settings:\n  controllers: [aaaaa-aa]\n",
+            "settings:\n  environment_variables:\n    KEY:\n      path: ~/.ssh/id_ed25519\n",
+            "init_args:\n  path: ./secret.bin\n  format: bin\n",
+        ] {
+            let template =
+                format!("build:\n  steps:\n    - type: script\n      command: build\n{extra}");
+            assert!(
+                matches!(
+                    render_recipe(&template, &recipe(&[]), &ctx("c")),
+                    Err(RenderRecipeError::Forbidden { .. })
+                ),
+                "{extra}"
+            );
+        }
+    }
+
     /// A template that renders to invalid build/sync YAML is a `Parse` error,
     /// not a panic.
     #[test]
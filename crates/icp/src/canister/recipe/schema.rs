@@ -0,0 +1,360 @@
+//! The configuration schema a recipe template may declare.
+//!
+//! A recipe declares the `configuration` it accepts as a JSON Schema, written as
+//! YAML in a Handlebars comment whose first word is `schema`:
+//!
+//! ```text
+//! {{!-- schema
+//! type: object
+//! required: [package]
+//! properties:
+//!   package:
+//!     type: string
+//!     description: The Cargo package to build
+//!   shrink:
+//!     type: boolean
+//!     default: false
+//! --}}
+//! ```
+//!
+//! Handlebars drops comments when rendering, so a template with a schema still
+//! renders unchanged under CLI versions that predate schemas. Before rendering,
+//! the configuration is filled in with the schema's `default`s and validated
+//! against it, so a typo is reported against the key it concerns rather than
+//! as a failed render.
+//!
+//! References (`$ref`) are only resolved within the schema itself; a recipe
+//! cannot make the CLI read files or fetch URLs by declaring a schema.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use snafu::prelude::*;
+
+const COMMENT_START: &str = "{{!--";
+const COMMENT_END: &str = "--}}";
+const SCHEMA_MARKER: &str = "schema";
+
+/// A recipe's configuration schema.
+#[derive(Clone, Debug)]
+pub struct RecipeSchema {
+    schema: Value,
+}
+
+/// One top-level key of a recipe's configuration, as declared by its schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaProperty {
+    pub name: String,
+    /// The JSON Schema `type`, or the allowed values of an `enum`.
+    pub ty: Option<String>,
+    pub required: bool,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum RecipeSchemaError {
+    #[snafu(display("configuration schema is not valid YAML"))]
+    Parse { source: serde_yaml::Error },
+
+    #[snafu(display("configuration schema is not a valid JSON Schema: {message}"))]
+    Invalid { message: String },
+}
+
+impl RecipeSchema {
+    /// The schema declared by `template`, if it declares one.
+    pub fn from_template(template: &str) -> Result<Option<Self>, RecipeSchemaError> {
+        let mut rest = template;
+        while let Some(start) = rest.find(COMMENT_START) {
+            let body = &rest[start + COMMENT_START.len()..];
+            let Some(end) = body.find(COMMENT_END) else {
+                // Unterminated; Handlebars reports it when rendering
+                return Ok(None);
+            };
+            let comment = body[..end].trim_start();
+            if let Some(schema) = comment.strip_prefix(SCHEMA_MARKER)
+                && schema.starts_with(char::is_whitespace)
+            {
+                let schema: Value = serde_yaml::from_str(schema).context(ParseSnafu)?;
+                jsonschema::validator_for(&schema).map_err(|err| RecipeSchemaError::Invalid {
+                    message: err.to_string(),
+                })?;
+                return Ok(Some(Self { schema }));
+            }
+            rest = &body[end + COMMENT_END.len()..];
+        }
+        Ok(None)
+    }
+
+    /// The schema as JSON.
+    pub fn as_json(&self) -> &Value {
+        &self.schema
+    }
+
+    /// The schema's `description`, or failing that its `title`.
+    pub fn description(&self) -> Option<&str> {
+        self.schema
+            .get("description")
+            .or_else(|| self.schema.get("title"))
+            .and_then(Value::as_str)
+    }
+
+    /// The top-level configuration keys the schema declares, in declaration order.
+    pub fn properties(&self) -> Vec<SchemaProperty> {
+        let required: Vec<&str> = self
+            .schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let Some(properties) = self.schema.get("properties").and_then(Value::as_object) else {
+            return vec![];
+        };
+        properties
+            .iter()
+            .map(|(name, prop)| SchemaProperty {
+                name: name.clone(),
+                ty: type_of(prop),
+                required: required.contains(&name.as_str()),
+                default: prop.get("default").cloned(),
+                description: prop
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_owned),
+            })
+            .collect()
+    }
+
+    /// Fill in the schema's defaults for keys missing from `configuration`, then
+    /// validate the result. On failure, returns one message per problem, each
+    /// prefixed with the path of the offending value.
+    pub fn apply(
+        &self,
+        configuration: &HashMap<String, serde_yaml::Value>,
+    ) -> Result<HashMap<String, serde_yaml::Value>, Vec<String>> {
+        let mut instance =
+            serde_json::to_value(configuration).map_err(|err| vec![err.to_string()])?;
+        fill_defaults(&self.schema, &mut instance);
+
+        // The schema was checked when it was parsed
+        let validator = jsonschema::validator_for(&self.schema).expect("schema is valid");
+        let problems: Vec<String> = validator
+            .iter_errors(&instance)
+            .map(|err| format!("{}: {err}", display_path(err.instance_path().as_str())))
+            .collect();
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        serde_json::from_value(instance).map_err(|err| vec![err.to_string()])
+    }
+}
+
+fn type_of(prop: &Value) -> Option<String> {
+    if let Some(values) = prop.get("enum").and_then(Value::as_array) {
+        return Some(
+            values
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(" | "),
+        );
+    }
+    match prop.get("type")? {
+        Value::String(ty) => Some(ty.clone()),
+        Value::Array(types) => Some(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" | "),
+        ),
+        _ => None,
+    }
+}
+
+/// Insert the `default` of every property missing from `instance`, recursing
+/// into nested objects and array items.
+fn fill_defaults(schema: &Value, instance: &mut Value) {
+    if let (Some(properties), Some(object)) = (
+        schema.get("properties").and_then(Value::as_object),
+        instance.as_object_mut(),
+    ) {
+        for (name, prop) in properties {
+            match object.get_mut(name) {
+                Some(value) => fill_defaults(prop, value),
+                None => {
+                    if let Some(default) = prop.get("default") {
+                        let mut value = default.clone();
+                        fill_defaults(prop, &mut value);
+                        object.insert(name.clone(), value);
+                    }
+                }
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), instance.as_array_mut())
+        && items.is_object()
+    {
+        for value in array {
+            fill_defaults(items, value);
+        }
+    }
+}
+
+/// Turn a JSON pointer into the instance, e.g. `/metadata/0/name`, into the
+/// path a manifest author would write: `configuration.metadata[0].name`.
+fn display_path(pointer: &str) -> String {
+    let mut path = String::from("configuration");
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if segment.parse::<usize>().is_ok() {
+            path.push_str(&format!("[{segment}]"));
+        } else {
+            path.push('.');
+            path.push_str(&segment);
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    const TEMPLATE: &str = indoc! {r#"
+        {{! A recipe for building a rust canister }}
+        {{!-- schema
+        description: Builds a Rust canister
+        type: object
+        required: [package]
+        additionalProperties: false
+        properties:
+          package:
+            type: string
+            description: The Cargo package to build
+          shrink:
+            type: boolean
+            default: false
+          metadata:
+            type: array
+            items:
+              type: object
+              required: [name, value]
+              properties:
+                name: { type: string }
+                value: { type: string }
+        --}}
+        build:
+          steps:
+            - type: script
+              command: cargo build --package {{ package }}
+    "#};
+
+    fn schema() -> RecipeSchema {
+        RecipeSchema::from_template(TEMPLATE).unwrap().unwrap()
+    }
+
+    fn config(yaml: &str) -> HashMap<String, serde_yaml::Value> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn template_without_schema_comment() {
+        let template = "{{!-- just a comment --}}\nbuild:\n  steps: []\n";
+        assert!(RecipeSchema::from_template(template).unwrap().is_none());
+    }
+
+    #[test]
+    fn schema_comment_must_start_with_the_marker_word() {
+        let template = "{{!-- schemas are described below --}}\nbuild:\n  steps: []\n";
+        assert!(RecipeSchema::from_template(template).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_schema_is_an_error() {
+        let template = "{{!-- schema\ntype: 42\n--}}\n";
+        assert!(matches!(
+            RecipeSchema::from_template(template),
+            Err(RecipeSchemaError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn describes_properties() {
+        let schema = schema();
+        assert_eq!(schema.description(), Some("Builds a Rust canister"));
+        let props = schema.properties();
+        assert_eq!(props.len(), 3);
+        assert_eq!(
+            props.iter().find(|p| p.name == "package").unwrap(),
+            &SchemaProperty {
+                name: "package".to_string(),
+                ty: Some("string".to_string()),
+                required: true,
+                default: None,
+                description: Some("The Cargo package to build".to_string()),
+            }
+        );
+        let shrink = props.iter().find(|p| p.name == "shrink").unwrap();
+        assert!(!shrink.required);
+        assert_eq!(shrink.default, Some(Value::Bool(false)));
+    }
+
+    #[test]
+    fn defaults_are_filled_in() {
+        let applied = schema().apply(&config("package: backend")).unwrap();
+        assert_eq!(applied["shrink"], serde_yaml::Value::Bool(false));
+        assert_eq!(applied["package"], serde_yaml::Value::from("backend"));
+    }
+
+    #[test]
+    fn given_values_win_over_defaults() {
+        let applied = schema()
+            .apply(&config("package: backend\nshrink: true"))
+            .unwrap();
+        assert_eq!(applied["shrink"], serde_yaml::Value::Bool(true));
+    }
+
+    #[test]
+    fn problems_are_reported_with_paths() {
+        let problems = schema()
+            .apply(&config(indoc! {r#"
+                shrink: "yes"
+                metadata:
+                  - name: version
+                pakage: backend
+            "#}))
+            .unwrap_err();
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with("configuration: ") && p.contains("\"package\"")),
+            "{problems:?}"
+        );
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with("configuration.shrink: ")),
+            "{problems:?}"
+        );
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with("configuration.metadata[0]: ") && p.contains("\"value\"")),
+            "{problems:?}"
+        );
+        assert!(
+            problems.iter().any(|p| p.contains("pakage")),
+            "{problems:?}"
+        );
+    }
+
+    #[test]
+    fn references_outside_the_schema_are_not_resolved() {
+        let template = "{{!-- schema\n$ref: file:///etc/passwd\n--}}\n";
+        assert!(RecipeSchema::from_template(template).is_err());
+    }
+}
@@ -167,7 +167,7 @@ pub(super) async fn execute_commands(
     Ok(())
 }
 
-fn shell_command(s: &str, cwd: &Path) -> Result<Command, ScriptError> {
+pub(super) fn shell_command(s: &str, cwd: &Path) -> Result<Command, ScriptError> {
     let words = shellwords::split(s).map_err(|e| ScriptError::Parse {
         command: s.to_owned(),
         reason: e.to_string(),
@@ -0,0 +1,177 @@
+use std::{fmt, process::Stdio};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+
+use super::script::{ScriptError, shell_command};
+use crate::prelude::*;
+
+/// Where a secret value is read from. Secrets are only read when a deployment
+/// needs them, never when the project is loaded.
+///
+/// ```yaml
+/// environment_variables:
+///   API_KEY:
+///     env: API_KEY
+///   DB_PASSWORD:
+///     command: pass show db-password
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum SecretSource {
+    /// The value of an environment variable of the `icp` process.
+    Env { env: String },
+
+    /// The standard output of a shell command, run in the directory of the
+    /// canister. Surrounding whitespace is trimmed off.
+    Command { command: String },
+}
+
+impl fmt::Display for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretSource::Env { env: name } => write!(f, "environment variable '{name}'"),
+            SecretSource::Command { command } => write!(f, "command '{command}'"),
+        }
+    }
+}
+
+/// A secret of a canister: its source, and the directory a command runs in.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Secret {
+    #[serde(flatten)]
+    pub source: SecretSource,
+
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Snafu)]
+pub enum SecretError {
+    #[snafu(display("environment variable '{name}' is not set"))]
+    EnvUnset { name: String },
+
+    #[snafu(display("failed to run command '{command}'"))]
+    Spawn {
+        source: ScriptError,
+        command: String,
+    },
+
+    #[snafu(display("failed to run command '{command}'"))]
+    Output {
+        source: std::io::Error,
+        command: String,
+    },
+
+    #[snafu(display("command '{command}' failed with status code {code}: {stderr}"))]
+    Status {
+        command: String,
+        code: String,
+        stderr: String,
+    },
+
+    #[snafu(display("command '{command}' printed output that is not valid UTF-8"))]
+    NotUtf8 { command: String },
+}
+
+impl Secret {
+    /// Reads the secret's value.
+    pub async fn resolve(&self) -> Result<String, SecretError> {
+        match &self.source {
+            SecretSource::Env { env: name } => {
+                std::env::var(name).ok().context(EnvUnsetSnafu { name })
+            }
+            SecretSource::Command { command } => {
+                let mut cmd = shell_command(command, &self.dir).context(SpawnSnafu { command })?;
+                cmd.stdin(Stdio::null());
+                let output = cmd.output().await.context(OutputSnafu { command })?;
+                ensure!(
+                    output.status.success(),
+                    StatusSnafu {
+                        command,
+                        code: output
+                            .status
+                            .code()
+                            .map_or("N/A".to_string(), |c| c.to_string()),
+                        stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+                    }
+                );
+                let stdout = String::from_utf8(output.stdout)
+                    .ok()
+                    .context(NotUtf8Snafu { command })?;
+                Ok(stdout.trim().to_owned())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use camino_tempfile::Utf8TempDir;
+
+    use super::*;
+
+    #[test]
+    fn sources_deserialize_from_their_key() {
+        assert_eq!(
+            serde_yaml::from_str::<SecretSource>("env: API_KEY").unwrap(),
+            SecretSource::Env {
+                env: "API_KEY".to_string(),
+            }
+        );
+        assert_eq!(
+            serde_yaml::from_str::<SecretSource>("command: pass show api-key").unwrap(),
+            SecretSource::Command {
+                command: "pass show api-key".to_string(),
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn command_output_is_the_value() {
+        let tmp = Utf8TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("key"), "s3cret\n").unwrap();
+        let secret = Secret {
+            source: SecretSource::Command {
+                command: "cat key".to_string(),
+            },
+            dir: tmp.path().to_owned(),
+        };
+
+        assert_eq!(secret.resolve().await.unwrap(), "s3cret");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_command_is_reported() {
+        let secret = Secret {
+            source: SecretSource::Command {
+                command: "echo denied >&2; exit 3".to_string(),
+            },
+            dir: ".".into(),
+        };
+
+        let err = secret.resolve().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "command 'echo denied >&2; exit 3' failed with status code 3: denied"
+        );
+    }
+
+    #[tokio::test]
+    async fn unset_variable_is_reported() {
+        let secret = Secret {
+            source: SecretSource::Env {
+                env: "ICP_TEST_SURELY_UNSET_SECRET".to_string(),
+            },
+            dir: ".".into(),
+        };
+
+        assert!(matches!(
+            secret.resolve().await,
+            Err(SecretError::EnvUnset { .. })
+        ));
+    }
+}
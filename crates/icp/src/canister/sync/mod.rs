@@ -7,6 +7,7 @@ use ic_agent::Agent;
 use snafu::prelude::*;
 use tokio::sync::mpsc::Sender;
 
+use crate::lockfile;
 use crate::manifest::canister::SyncStep;
 use crate::package::PackageCache;
 use crate::prelude::*;
@@ -14,6 +15,8 @@ use crate::prelude::*;
 mod plugin;
 pub mod script;
 
+pub use plugin::{ResolvePluginError, resolve_plugin_wasm};
+
 use script::{HostScripts, ScriptInvocation, ScriptRunError, ScriptRunner};
 
 pub struct Params {
@@ -54,19 +57,30 @@ pub trait Synchronize: Sync + Send {
 /// Dispatches each sync step to the machinery that runs it. Plugin steps run in
 /// the wasmtime WASI sandbox, which this drives directly; script steps go through
 /// an injected [`ScriptRunner`], since spawning a subprocess is not available
-/// everywhere.
+/// everywhere. Registry plugins are pinned in the project's lock file, and in
+/// offline mode plugins are served from the package cache only.
 pub struct Syncer {
     scripts: Arc<dyn ScriptRunner>,
+    lockfile: Arc<dyn lockfile::Access>,
+    offline: bool,
 }
 
 impl Syncer {
     /// A syncer that runs script steps as host subprocesses.
-    pub fn host() -> Self {
-        Self::new(Arc::new(HostScripts))
+    pub fn host(lockfile: Arc<dyn lockfile::Access>, offline: bool) -> Self {
+        Self::new(Arc::new(HostScripts), lockfile, offline)
     }
 
-    pub fn new(scripts: Arc<dyn ScriptRunner>) -> Self {
-        Self { scripts }
+    pub fn new(
+        scripts: Arc<dyn ScriptRunner>,
+        lockfile: Arc<dyn lockfile::Access>,
+        offline: bool,
+    ) -> Self {
+        Self {
+            scripts,
+            lockfile,
+            offline,
+        }
     }
 }
 
@@ -93,6 +107,8 @@ impl Synchronize for Syncer {
                 params.proxy,
                 stdio,
                 pkg_cache,
+                self.lockfile.as_ref(),
+                self.offline,
             )
             .await?),
         }
@@ -159,7 +175,11 @@ mod tests {
     #[tokio::test]
     async fn script_steps_are_dispatched_to_the_injected_runner() {
         let scripts = Arc::new(RecordingScripts::default());
-        let syncer = Syncer::new(scripts.clone());
+        let syncer = Syncer::new(
+            scripts.clone(),
+            Arc::new(lockfile::mock::MockInMemoryLockfile::default()),
+            false,
+        );
 
         let cid = Principal::from_slice(&[7; 4]);
         let params = Params {
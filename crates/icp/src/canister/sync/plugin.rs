@@ -2,20 +2,41 @@ use camino::Utf8PathBuf;
 use candid::Principal;
 use ic_agent::Agent;
 use icp_sync_plugin::{
-    DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS, PLUGIN_COMPUTE_LIMIT_ENV, RunPluginError, run_plugin,
+    ComponentCache, DEFAULT_PLUGIN_COMPUTE_LIMIT_SECS, PLUGIN_COMPUTE_LIMIT_ENV, RunPluginError,
+    run_plugin,
 };
+use sha2::{Digest, Sha256};
 use snafu::prelude::*;
 use tokio::sync::mpsc::Sender;
+use tracing::debug;
 
-use crate::{canister::wasm, manifest::adapter::plugin::Adapter, package::PackageCache};
+use crate::{
+    canister::wasm,
+    lockfile::{self, LockfileError},
+    manifest::{
+        adapter::plugin::{Adapter, PluginSource},
+        prebuilt::SourceField,
+    },
+    package::{PackageCache, cache_component, read_cached_component},
+    prelude::*,
+};
 
 use super::Params;
 
 #[derive(Debug, Snafu)]
-pub enum PluginError {
+pub enum ResolvePluginError {
     #[snafu(transparent)]
     Wasm { source: wasm::WasmError },
 
+    #[snafu(transparent)]
+    Lockfile { source: LockfileError },
+}
+
+#[derive(Debug, Snafu)]
+pub enum PluginError {
+    #[snafu(transparent)]
+    Resolve { source: ResolvePluginError },
+
     #[snafu(display("failed to get identity principal: {err}"))]
     GetIdentityPrincipal { err: String },
 
@@ -56,6 +77,92 @@ fn parse_compute_limit(value: &str) -> Result<u64, PluginError> {
     }
 }
 
+/// Keeps a plugin's precompiled components in the package cache, under
+/// `plugins/<sha256>/<engine-key>.cwasm`.
+///
+/// `run_plugin` is synchronous and runs inside `block_in_place`, so the cache
+/// lock is taken by blocking on the current runtime, as the plugin's own
+/// canister calls do. Failures are logged and otherwise ignored: the worst case
+/// is compiling the plugin again.
+struct PackageComponentCache<'a> {
+    pkg_cache: &'a PackageCache,
+    sha256: String,
+}
+
+impl ComponentCache for PackageComponentCache<'_> {
+    fn load(&self, engine_key: &str) -> Option<Vec<u8>> {
+        let read = tokio::runtime::Handle::current().block_on(
+            self.pkg_cache
+                .with_read(async |r| read_cached_component(r, &self.sha256, engine_key)),
+        );
+        match read {
+            Ok(Ok(compiled)) => compiled,
+            Ok(Err(err)) => {
+                debug!("failed to read precompiled plugin {}: {err}", self.sha256);
+                None
+            }
+            Err(err) => {
+                debug!("failed to lock package cache: {err}");
+                None
+            }
+        }
+    }
+
+    fn store(&self, engine_key: &str, compiled: &[u8]) {
+        let written = tokio::runtime::Handle::current().block_on(
+            self.pkg_cache
+                .with_write(async |w| cache_component(w, &self.sha256, engine_key, compiled)),
+        );
+        match written {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => debug!("failed to cache precompiled plugin {}: {err}", self.sha256),
+            Err(err) => debug!("failed to lock package cache: {err}"),
+        }
+    }
+}
+
+/// Resolve a plugin step's wasm to a local path. Returns the path together with
+/// the plugin's sha256 when it is known without reading the file.
+///
+/// - Local: sha256 is verified if present, then the original path is returned.
+/// - Remote: downloaded to cache (sha256 required, enforced at parse time) and the
+///   stable cache path is returned.
+/// - Registry: verified against the manifest's sha256, else the lock file's pin;
+///   an unpinned plugin is downloaded and its sha256 pinned in the lock file.
+pub async fn resolve_plugin_wasm(
+    adapter: &Adapter,
+    base_dir: &Path,
+    stdio: Option<&Sender<String>>,
+    pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
+) -> Result<(PathBuf, Option<String>), ResolvePluginError> {
+    let wasm_source = match &adapter.source {
+        PluginSource::Local(s) => SourceField::Local(s.clone()),
+        PluginSource::Remote(s) => SourceField::Remote(s.clone()),
+        PluginSource::Registry(s) => {
+            let pinned = lockfile.load()?.plugin(&s.registry).map(str::to_owned);
+            let expected = adapter.sha256.as_deref().or(pinned.as_deref());
+            let (path, sha256) =
+                wasm::resolve_registry_plugin(&s.registry, expected, stdio, pkg_cache, offline)
+                    .await?;
+            lockfile.update(&mut |l| l.pin_plugin(&s.registry, &sha256))?;
+            return Ok((path, Some(sha256)));
+        }
+    };
+    let path = wasm::resolve(
+        &wasm_source,
+        base_dir,
+        adapter.sha256.as_deref(),
+        stdio,
+        pkg_cache,
+        lockfile,
+        offline,
+    )
+    .await?;
+    Ok((path, adapter.sha256.clone()))
+}
+
 pub(super) async fn sync(
     adapter: &Adapter,
     params: &Params,
@@ -64,6 +171,8 @@ pub(super) async fn sync(
     proxy: Option<Principal>,
     stdio: Option<Sender<String>>,
     pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
 ) -> Result<Vec<String>, PluginError> {
     // 0. Resolve the compute-time limit up front so a malformed
     //    ICP_CLI_PLUGIN_COMPUTE_LIMIT_SECS fails fast — before downloading the
@@ -71,19 +180,28 @@ pub(super) async fn sync(
     let compute_limit_secs = resolve_compute_limit_secs()?;
 
     // 1. Determine the on-disk path for the wasm. run_plugin needs a path, not raw bytes.
-    //    - Local: sha256 is verified if present, then the original path is returned.
-    //    - Remote: downloaded to cache (sha256 required, enforced at parse time) and the
-    //      stable cache path is returned — no temp file needed.
-    let wasm_path = wasm::resolve(
-        &adapter.source,
+    let (wasm_path, plugin_sha256) = resolve_plugin_wasm(
+        adapter,
         &params.path,
-        adapter.sha256.as_deref(),
         stdio.as_ref(),
         pkg_cache,
+        lockfile,
+        offline,
     )
     .await?;
 
-    // 2. Collect inputs as manifest strings. `run_plugin` preopens the `dirs`
+    // 2. Identify the plugin for the compilation cache. A known checksum has
+    //    just been verified against the bytes, so it is reused; otherwise the
+    //    file is hashed. A file that cannot be read is left for `run_plugin` to
+    //    report, and simply runs uncached.
+    let plugin_sha256 = plugin_sha256.or_else(|| {
+        crate::fs::read(&wasm_path)
+            .ok()
+            .map(|bytes| hex::encode(Sha256::digest(bytes)))
+    });
+    let component_cache = plugin_sha256.map(|sha256| PackageComponentCache { pkg_cache, sha256 });
+
+    // 3. Collect inputs as manifest strings. `run_plugin` preopens the `dirs`
     //    and reads the `files` itself — both anchored at `base_dir`, and both
     //    subject to the runtime's path-safety checks (no escaping or symlinked
     //    paths).
@@ -91,7 +209,7 @@ pub(super) async fn sync(
     let dirs: Vec<String> = adapter.dirs.clone().unwrap_or_default();
     let files: Vec<String> = adapter.files.clone().unwrap_or_default();
 
-    // 3. Run the plugin (blocking call — signal Tokio that this thread will block).
+    // 4. Run the plugin (blocking call — signal Tokio that this thread will block).
     let identity_principal = agent
         .get_principal()
         .map_err(|err| PluginError::GetIdentityPrincipal { err })?;
@@ -113,6 +231,9 @@ pub(super) async fn sync(
             environment_owned,
             compute_limit_secs,
             stdio_clone,
+            component_cache
+                .as_ref()
+                .map(|cache| cache as &dyn ComponentCache),
         )
     })
     .context(RunSnafu)
@@ -140,4 +261,132 @@ mod tests {
             );
         }
     }
+
+    fn registry_adapter(sha256: Option<&str>) -> Adapter {
+        Adapter {
+            source: PluginSource::Registry(crate::manifest::plugin::RegistrySource {
+                registry: "@dfinity/populate-data@v1.0.0".parse().unwrap(),
+            }),
+            sha256: sha256.map(str::to_owned),
+            dirs: None,
+            files: None,
+        }
+    }
+
+    /// A registry plugin already in the package cache resolves without a
+    /// download, and its sha256 is pinned in the lock file on first use.
+    #[tokio::test]
+    async fn cached_registry_plugin_is_pinned() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let pkg_cache = PackageCache::new(tmp.path().to_owned()).unwrap();
+        let sha256 = hex::encode(Sha256::digest(b"plugin"));
+        pkg_cache
+            .with_write(async |w| {
+                crate::package::cache_registry_plugin(
+                    w,
+                    "@dfinity/populate-data",
+                    "v1.0.0",
+                    &sha256,
+                    b"plugin",
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        let lockfile = crate::lockfile::mock::MockInMemoryLockfile::default();
+
+        let (path, resolved) = resolve_plugin_wasm(
+            &registry_adapter(None),
+            tmp.path(),
+            None,
+            &pkg_cache,
+            &lockfile,
+            false,
+        )
+        .await
+        .expect("cached registry plugin should resolve");
+
+        assert_eq!(resolved.as_deref(), Some(sha256.as_str()));
+        assert_eq!(crate::fs::read(&path).unwrap(), b"plugin");
+        let reference = "@dfinity/populate-data@v1.0.0".parse().unwrap();
+        assert_eq!(
+            lockfile::Access::load(&lockfile)
+                .unwrap()
+                .plugin(&reference),
+            Some(sha256.as_str())
+        );
+    }
+
+    /// A pin takes precedence over the version cached for the reference: a
+    /// different cached plugin is not silently used in its place.
+    #[tokio::test]
+    async fn pinned_registry_plugin_ignores_other_cached_version() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let pkg_cache = PackageCache::new(tmp.path().to_owned()).unwrap();
+        let pinned = hex::encode(Sha256::digest(b"pinned"));
+        pkg_cache
+            .with_write(async |w| {
+                crate::package::cache_registry_plugin(
+                    w,
+                    "@dfinity/populate-data",
+                    "v1.0.0",
+                    &pinned,
+                    b"pinned",
+                )?;
+                crate::package::cache_registry_plugin(
+                    w,
+                    "@dfinity/populate-data",
+                    "v1.0.0",
+                    &hex::encode(Sha256::digest(b"other")),
+                    b"other",
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        let lockfile = crate::lockfile::mock::MockInMemoryLockfile::default();
+        let reference = "@dfinity/populate-data@v1.0.0".parse().unwrap();
+        lockfile::Access::update(&lockfile, &mut |l| l.pin_plugin(&reference, &pinned)).unwrap();
+
+        let (path, resolved) = resolve_plugin_wasm(
+            &registry_adapter(None),
+            tmp.path(),
+            None,
+            &pkg_cache,
+            &lockfile,
+            true,
+        )
+        .await
+        .expect("pinned registry plugin should resolve offline");
+
+        assert_eq!(resolved.as_deref(), Some(pinned.as_str()));
+        assert_eq!(crate::fs::read(&path).unwrap(), b"pinned");
+    }
+
+    /// Offline, a plugin missing from the package cache is reported by name
+    /// rather than downloaded.
+    #[tokio::test]
+    async fn uncached_registry_plugin_is_an_error_offline() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let pkg_cache = PackageCache::new(tmp.path().to_owned()).unwrap();
+        let lockfile = crate::lockfile::mock::MockInMemoryLockfile::default();
+
+        let err = resolve_plugin_wasm(
+            &registry_adapter(None),
+            tmp.path(),
+            None,
+            &pkg_cache,
+            &lockfile,
+            true,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            ResolvePluginError::Wasm {
+                source: wasm::WasmError::NotCached { artifact }
+            } if artifact == "@dfinity/populate-data@v1.0.0"
+        ));
+    }
 }
@@ -7,8 +7,13 @@ use url::Url;
 
 use crate::{
     fs::read,
-    manifest::adapter::prebuilt::SourceField,
-    package::{PackageCache, cache_wasm},
+    lockfile,
+    manifest::{RegistryRef, adapter::prebuilt::SourceField},
+    package::{
+        PackageCache, cache_registry_plugin, cache_wasm, read_cached_plugin,
+        read_cached_registry_plugin,
+    },
+    registry::{PLUGIN_ASSET, asset_url},
 };
 
 #[derive(Debug, Snafu)]
@@ -37,21 +42,49 @@ pub enum WasmError {
     #[snafu(display("failed to cache wasm file"))]
     CacheFile { source: crate::fs::IoError },
 
+    #[snafu(display("failed to locate plugin in registry"))]
+    Registry {
+        source: crate::registry::RegistryError,
+    },
+
+    #[snafu(display("failed to read or write cached plugin"))]
+    PluginCache {
+        source: crate::package::PluginCacheError,
+    },
+
     #[snafu(display("failed to acquire lock on package cache"))]
     LockCache { source: crate::fs::lock::LockError },
+
+    #[snafu(display("failed to pin wasm in the lock file"))]
+    Lockfile {
+        source: crate::lockfile::LockfileError,
+    },
+
+    #[snafu(display(
+        "'{artifact}' is not in the package cache and cannot be downloaded in offline mode; \
+         run `icp project prefetch` while online"
+    ))]
+    NotCached { artifact: String },
 }
 
 /// Resolve a wasm source to a local filesystem path, optionally verifying the sha256 checksum.
 ///
 /// - Local: verifies sha256 if provided, returns the local path.
 /// - Remote with sha256: checks the cache first; downloads, verifies, and caches on miss.
-/// - Remote without sha256: always downloads, computes sha256, caches by the computed sha256.
+/// - Remote without sha256: uses the sha256 pinned in the lock file as if it were
+///   given. Unpinned, always downloads, computes sha256, caches by the computed
+///   sha256 and pins it.
+///
+/// In `offline` mode a remote wasm that is not cached is a [`WasmError::NotCached`]
+/// rather than a download.
 pub async fn resolve(
     source: &SourceField,
     base_dir: &Utf8Path,
     sha256: Option<&str>,
     stdio: Option<&Sender<String>>,
     pkg_cache: &PackageCache,
+    lockfile: &dyn lockfile::Access,
+    offline: bool,
 ) -> Result<crate::prelude::PathBuf, WasmError> {
     match source {
         SourceField::Local(s) => {
@@ -78,6 +111,18 @@ pub async fn resolve(
             Ok(path)
         }
         SourceField::Remote(s) => {
+            // Without a checksum in the manifest, the URL is pinned in the lock file.
+            let pinned = match sha256 {
+                Some(_) => None,
+                None => lockfile
+                    .load()
+                    .context(LockfileSnafu)?
+                    .wasm(&s.url)
+                    .map(str::to_owned),
+            };
+            let manifest_sha256 = sha256;
+            let sha256 = sha256.or(pinned.as_deref());
+
             // Pre-download cache check is only possible when sha256 is known.
             if let Some(expected) = sha256 {
                 let cached = pkg_cache
@@ -101,19 +146,13 @@ pub async fn resolve(
                 }
             }
 
-            let url = Url::parse(&s.url).context(ParseUrlSnafu)?;
-            if let Some(tx) = stdio {
-                let _ = tx.send(format!("Fetching wasm: {url}")).await;
-            }
-            let resp = Client::new()
-                .execute(Request::new(Method::GET, url))
-                .await
-                .context(HttpRequestSnafu)?;
-            let status = resp.status();
-            if !status.is_success() {
-                return HttpStatusSnafu { status }.fail();
-            }
-            let bytes = resp.bytes().await.context(HttpResponseSnafu)?.to_vec();
+            ensure!(
+                !offline,
+                NotCachedSnafu {
+                    artifact: s.url.clone()
+                }
+            );
+            let bytes = fetch(&s.url, stdio).await?;
 
             // Use provided sha256 as cache key (after verifying), or compute from bytes.
             let cache_sha = match sha256 {
@@ -139,6 +178,12 @@ pub async fn resolve(
                 .await
                 .context(LockCacheSnafu)??;
 
+            if manifest_sha256.is_none() {
+                lockfile
+                    .update(&mut |l| l.pin_wasm(&s.url, &cache_sha))
+                    .context(LockfileSnafu)?;
+            }
+
             pkg_cache
                 .with_read(async |r| r.wasm_sha(&cache_sha).wasm())
                 .await
@@ -146,3 +191,91 @@ pub async fn resolve(
         }
     }
 }
+
+/// Resolve a registry plugin to the path of its wasm in the package cache.
+/// Returns the path together with the plugin's sha256.
+///
+/// - With `sha256` (from the manifest or the lock file): the cached wasm with
+///   that hash is used; on a miss it is downloaded and must match.
+/// - Without: the version cached for this reference is used; on a miss it is
+///   downloaded and its hash computed. The caller is expected to pin it.
+///
+/// In `offline` mode a miss is a [`WasmError::NotCached`].
+pub async fn resolve_registry_plugin(
+    reference: &RegistryRef,
+    sha256: Option<&str>,
+    stdio: Option<&Sender<String>>,
+    pkg_cache: &PackageCache,
+    offline: bool,
+) -> Result<(crate::prelude::PathBuf, String), WasmError> {
+    let package = reference.package();
+    let cached = pkg_cache
+        .with_read(async |r| match sha256 {
+            Some(expected) => {
+                Ok(read_cached_plugin(r, expected).map(|path| (path, expected.to_owned())))
+            }
+            None => read_cached_registry_plugin(r, &package, &reference.version)
+                .context(PluginCacheSnafu),
+        })
+        .await
+        .context(LockCacheSnafu)??;
+    if let Some(cached) = cached {
+        if let Some(tx) = stdio {
+            let _ = tx.send("Using cached file".to_string()).await;
+        }
+        return Ok(cached);
+    }
+
+    ensure!(
+        !offline,
+        NotCachedSnafu {
+            artifact: reference.to_string()
+        }
+    );
+    let url = asset_url(reference, PLUGIN_ASSET).context(RegistrySnafu)?;
+    let bytes = fetch(&url, stdio).await?;
+    let actual = hex::encode(Sha256::digest(&bytes));
+    if let Some(expected) = sha256 {
+        if let Some(tx) = stdio {
+            let _ = tx.send("Verifying checksum".to_string()).await;
+        }
+        ensure!(
+            actual == expected,
+            ChecksumMismatchSnafu {
+                expected: expected.to_owned(),
+                actual,
+            }
+        );
+    }
+
+    pkg_cache
+        .with_write(async |w| {
+            cache_registry_plugin(w, &package, &reference.version, &actual, &bytes)
+                .context(PluginCacheSnafu)
+        })
+        .await
+        .context(LockCacheSnafu)??;
+
+    let path = pkg_cache
+        .with_read(async |r| r.plugin_sha(&actual).wasm())
+        .await
+        .context(LockCacheSnafu)?;
+    Ok((path, actual))
+}
+
+/// Download a wasm file.
+async fn fetch(url: &str, stdio: Option<&Sender<String>>) -> Result<Vec<u8>, WasmError> {
+    let url = Url::parse(url).context(ParseUrlSnafu)?;
+    if let Some(tx) = stdio {
+        let _ = tx.send(format!("Fetching wasm: {url}")).await;
+    }
+    let resp = Client::new()
+        .execute(Request::new(Method::GET, url))
+        .await
+        .context(HttpRequestSnafu)?;
+    let status = resp.status();
+    if !status.is_success() {
+        return HttpStatusSnafu { status }.fail();
+    }
+    Ok(resp.bytes().await.context(HttpResponseSnafu)?.to_vec())
+}
@@ -8,11 +8,13 @@ use crate::canister::sync::Syncer;
 use crate::context::Context;
 use crate::directories::{Access as _, Directories};
 use crate::prelude::*;
+use crate::registry::Registries;
 use crate::store_artifact::ArtifactStore;
 use std::time::Duration;
 
 use crate::{
-    Lazy, ProjectLoadImpl, agent, identity, identity::PasswordFunc, manifest, network, store_id,
+    Lazy, ProjectLoadImpl, agent, identity, identity::PasswordFunc, lockfile, manifest, network,
+    store_id,
 };
 
 #[derive(Debug, Snafu)]
@@ -38,8 +40,11 @@ pub enum ContextInitError {
 pub fn initialize(
     project_root_override: Option<PathBuf>,
     debug: bool,
+    locked: bool,
+    offline: bool,
     password_func: PasswordFunc,
     pem_session_duration: Option<Duration>,
+    registries: Registries,
 ) -> Result<Context, ContextInitError> {
     // Setup global directory structure
     let dirs = Arc::new(Directories::new().context(DirectoriesSnafu)?);
@@ -83,6 +88,12 @@ pub fn initialize(
     // Canister Artifact Store (wasm)
     let artifacts = Arc::new(ArtifactStore::new(project_root_locate.clone()));
 
+    // Project lock file
+    let lockfile = Arc::new(lockfile::AccessImpl::new(
+        project_root_locate.clone(),
+        locked,
+    ));
+
     // Prepare http client
     let http_client = reqwest::Client::new();
 
@@ -93,18 +104,21 @@ pub fn initialize(
     let recipe = Arc::new(RecipeFetcher {
         http_client,
         pkg_cache,
+        lockfile: lockfile.clone(),
+        offline,
     });
 
     // Canister builder
-    let builder = Arc::new(Builder);
+    let builder = Arc::new(Builder::new(lockfile.clone(), offline));
 
     // Canister syncer
-    let syncer = Arc::new(Syncer::host());
+    let syncer = Arc::new(Syncer::host(lockfile.clone(), offline));
 
     // Project loader
     let pload = ProjectLoadImpl {
         project_root_locate: project_root_locate.clone(),
         recipe,
+        registries: registries.clone(),
     };
 
     let pload = Lazy::new(pload);
@@ -143,6 +157,7 @@ pub fn initialize(
         dirs,
         ids,
         artifacts,
+        lockfile,
         project: pload,
         identity: idload,
         network: netaccess,
@@ -150,6 +165,8 @@ pub fn initialize(
         builder,
         syncer,
         debug,
+        offline,
+        registries,
         telemetry_data,
         password_func,
     })
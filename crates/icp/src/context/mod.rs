@@ -10,6 +10,7 @@ use crate::{
     manifest::network::RootKeySpec,
     network::{Configuration as NetworkConfiguration, access::NetworkAccess},
     prelude::*,
+    registry::Registries,
     store_id::{IdMapping, LookupIdError},
     telemetry_data::NetworkType,
 };
@@ -81,6 +82,9 @@ pub struct Context {
     /// An artifact store for canister build artifacts
     pub artifacts: Arc<dyn crate::store_artifact::Access>,
 
+    /// The project lock file
+    pub lockfile: Arc<dyn crate::lockfile::Access>,
+
     /// Project loader
     pub project: Arc<dyn crate::ProjectLoad>,
 
@@ -102,6 +106,14 @@ pub struct Context {
     /// Whether debug is enabled
     pub debug: bool,
 
+    /// Whether downloads are forbidden, so that remote artifacts are served
+    /// from the package cache only
+    pub offline: bool,
+
+    /// Recipe registries declared in the user settings. A loaded project's
+    /// [`Project::registries`](crate::Project::registries) adds its own.
+    pub registries: Registries,
+
     /// Telemetry data collected during command execution
     pub telemetry_data: Arc<crate::telemetry_data::TelemetryData>,
 
@@ -254,21 +266,22 @@ impl Context {
         environment: &EnvironmentSelection,
     ) -> Result<(PathBuf, Canister), GetEnvCanisterError> {
         let p = self.project.load().await?;
-        let Some((path, canister)) = p.get_canister(canister_name) else {
+        if p.get_canister(canister_name).is_none() {
             return CanisterNotFoundInProjectSnafu {
                 canister_name: canister_name.to_owned(),
             }
             .fail();
-        };
+        }
 
+        // The environment's copy: rendered for it, with its overrides applied
         let env = self.get_environment(environment).await?;
-        if !env.contains_canister(canister_name) {
+        let Some((path, canister)) = env.canisters.get(canister_name) else {
             return CanisterNotInEnvSnafu {
                 canister_name: canister_name.to_owned(),
                 environment_name: environment.name().to_owned(),
             }
             .fail();
-        }
+        };
         Ok((path.clone(), canister.clone()))
     }
 
@@ -384,11 +397,45 @@ impl Context {
         let env = self.get_environment(environment).await?;
         let access = self.network.access(&env.network).await?;
         let id = self
-            .get_identity(identity, Some(access.root_key.clone()))
+            .get_identity_in_env(identity, &env, Some(access.root_key.clone()))
             .await?;
         Ok(self.create_agent(id, access).await?)
     }
 
+    /// Gets the identity to use in an environment: the one selected on the
+    /// command line, or else the one the project manifest chooses for the
+    /// environment, or else the default identity.
+    async fn get_identity_in_env(
+        &self,
+        identity: &IdentitySelection,
+        env: &crate::Environment,
+        network_root_key: Option<Vec<u8>>,
+    ) -> Result<Arc<dyn Identity>, GetIdentityError> {
+        let Some(name) = env
+            .identity
+            .as_ref()
+            .filter(|_| *identity == IdentitySelection::Default)
+        else {
+            return self.get_identity(identity, network_root_key).await;
+        };
+
+        let selection = match name.as_str() {
+            "anonymous" => IdentitySelection::Anonymous,
+            _ => IdentitySelection::Named(name.clone()),
+        };
+        self.get_identity(&selection, network_root_key)
+            .await
+            .map_err(|e| match e {
+                GetIdentityError::IdentityLoad { source, .. } if is_no_such_identity(&source) => {
+                    GetIdentityError::ManifestIdentityNotFound {
+                        name: name.clone(),
+                        environment: env.name.clone(),
+                    }
+                }
+                e => e,
+            })
+    }
+
     /// Creates an agent for a given identity and network.
     pub async fn get_agent_for_network(
         &self,
@@ -605,6 +652,7 @@ impl Context {
             dirs: Arc::new(crate::directories::UnimplementedMockDirs),
             ids: Arc::new(crate::store_id::mock::MockInMemoryIdStore::new()),
             artifacts: Arc::new(crate::store_artifact::MockInMemoryArtifactStore::new()),
+            lockfile: Arc::new(crate::lockfile::mock::MockInMemoryLockfile::default()),
             project: Arc::new(crate::MockProjectLoader::minimal()),
             identity: Arc::new(crate::identity::MockIdentityLoader::anonymous()),
             network: Arc::new(crate::network::MockNetworkAccessor::new()),
@@ -612,6 +660,8 @@ impl Context {
             builder: Arc::new(crate::canister::build::UnimplementedMockBuilder),
             syncer: Arc::new(crate::canister::sync::UnimplementedMockSyncer),
             debug: false,
+            offline: false,
+            registries: Registries::default(),
             telemetry_data: Arc::new(crate::telemetry_data::TelemetryData::default()),
             password_func: Arc::new(|| Err("no password available in mock context".to_string())),
         }
@@ -625,6 +675,31 @@ pub enum GetIdentityError {
         source: crate::identity::LoadError,
         identity: IdentitySelection,
     },
+
+    #[snafu(display(
+        "identity '{name}', chosen for environment '{environment}' in the project manifest, \
+         does not exist; create or import it, or select another with --identity"
+    ))]
+    ManifestIdentityNotFound { name: String, environment: String },
+}
+
+/// Whether loading an identity failed because there is no identity of that name.
+fn is_no_such_identity(e: &crate::identity::LoadError) -> bool {
+    use crate::identity::{
+        LoadError,
+        key::{LoadIdentityError, LoadIdentityInContextError},
+    };
+
+    matches!(
+        e,
+        LoadError::LoadIdentity {
+            source: LoadIdentityError::NoSuchIdentity { .. }
+        } | LoadError::LoadIdentityInContext {
+            source: LoadIdentityInContextError::LoadIdentity {
+                source: LoadIdentityError::NoSuchIdentity { .. }
+            }
+        }
+    )
 }
 
 #[derive(Debug, Snafu)]
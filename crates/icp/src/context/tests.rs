@@ -381,6 +381,72 @@ async fn test_get_agent_for_env_uses_environment_network() {
     assert_eq!(agent.read_root_key(), staging_root_key);
 }
 
+/// The complex mock project, with the "dev" environment choosing `identity`,
+/// and an identity loader holding a "deployer" identity.
+fn context_with_dev_identity(identity: &str) -> (Context, Principal) {
+    let mut project = MockProjectLoader::complex().project;
+    project.environments.get_mut("dev").unwrap().identity = Some(identity.to_string());
+
+    let deployer: Arc<dyn Identity> =
+        Arc::new(ic_agent::identity::BasicIdentity::from_raw_key(&[7; 32]));
+    let principal = deployer.sender().unwrap();
+
+    let ctx = Context {
+        project: Arc::new(MockProjectLoader::new(project)),
+        identity: Arc::new(MockIdentityLoader::anonymous().with_identity("deployer", deployer)),
+        network: Arc::new(MockNetworkAccessor::new().with_network(
+            LOCAL,
+            NetworkAccess {
+                root_key: vec![1, 2, 3],
+                root_key_source: crate::network::RootKeySource::Configured,
+                api_url: Url::parse(DEFAULT_LOCAL_NETWORK_URL).unwrap(),
+                http_gateway_url: None,
+                use_friendly_domains: false,
+            },
+        )),
+        ..Context::mocked()
+    };
+    (ctx, principal)
+}
+
+#[tokio::test]
+async fn test_get_agent_for_env_uses_environment_identity() {
+    let (ctx, deployer) = context_with_dev_identity("deployer");
+    let dev = EnvironmentSelection::Named("dev".to_string());
+
+    let agent = ctx
+        .get_agent_for_env(&IdentitySelection::Default, &dev)
+        .await
+        .unwrap();
+    assert_eq!(agent.get_principal().unwrap(), deployer);
+
+    // An identity selected on the command line takes precedence
+    let agent = ctx
+        .get_agent_for_env(&IdentitySelection::Anonymous, &dev)
+        .await
+        .unwrap();
+    assert_eq!(agent.get_principal().unwrap(), Principal::anonymous());
+}
+
+#[tokio::test]
+async fn test_get_agent_for_env_environment_identity_not_found() {
+    let (ctx, _) = context_with_dev_identity("ghost");
+
+    let result = ctx
+        .get_agent_for_env(
+            &IdentitySelection::Default,
+            &EnvironmentSelection::Named("dev".to_string()),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(GetAgentForEnvError::GetIdentity {
+            source: GetIdentityError::ManifestIdentityNotFound { name, environment }
+        }) if name == "ghost" && environment == "dev"
+    ));
+}
+
 #[tokio::test]
 async fn test_get_agent_for_env_environment_not_found() {
     let ctx = Context::mocked();
@@ -631,6 +697,7 @@ async fn test_get_agent_defaults_inside_project_with_default_local() {
     let local_env = Environment {
         name: LOCAL.to_string(),
         network: local_network,
+        identity: None,
         canisters: IndexMap::new(), // No canisters needed for get_agent test
     };
 
@@ -643,6 +710,9 @@ async fn test_get_agent_defaults_inside_project_with_default_local() {
         networks,
         environments,
         member_missing_envs: std::collections::HashMap::new(),
+        registries: Registries::default(),
+        declared_environments: HashMap::new(),
+        secret_values: Default::default(),
     };
 
     let ctx = Context {
@@ -704,6 +774,7 @@ async fn test_get_agent_defaults_with_overridden_local_network() {
     let local_env = Environment {
         name: LOCAL.to_string(),
         network: custom_local_network,
+        identity: None,
         canisters: IndexMap::new(), // No canisters needed for get_agent test
     };
 
@@ -716,6 +787,9 @@ async fn test_get_agent_defaults_with_overridden_local_network() {
         networks,
         environments,
         member_missing_envs: std::collections::HashMap::new(),
+        registries: Registries::default(),
+        declared_environments: HashMap::new(),
+        secret_values: Default::default(),
     };
 
     let custom_root_key = vec![1, 2, 3, 4];
@@ -803,6 +877,7 @@ async fn test_get_agent_defaults_with_overridden_local_environment() {
     let local_env = Environment {
         name: LOCAL.to_string(),
         network: custom_network,
+        identity: None,
         canisters: IndexMap::new(), // No canisters needed for get_agent test
     };
 
@@ -815,6 +890,9 @@ async fn test_get_agent_defaults_with_overridden_local_environment() {
         networks,
         environments,
         member_missing_envs: std::collections::HashMap::new(),
+        registries: Registries::default(),
+        declared_environments: HashMap::new(),
+        secret_values: Default::default(),
     };
 
     let local_root_key = vec![1, 2, 3, 4];
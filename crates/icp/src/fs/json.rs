@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
 
 use crate::{
-    fs::{read, write_string},
+    fs::{read, write_atomic},
     prelude::*,
 };
 
@@ -36,10 +36,13 @@ pub fn load_or_default<T: for<'a> Deserialize<'a> + Default>(path: &Path) -> Res
     }
 }
 
+/// Writes `value` to `path`, replacing the file in one step.
 pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
-    write_string(
+    write_atomic(
         path,
-        &serde_json::to_string_pretty(&value).context(SerializeSnafu { path })?,
+        serde_json::to_string_pretty(&value)
+            .context(SerializeSnafu { path })?
+            .as_bytes(),
     )?;
 
     Ok(())
@@ -1,5 +1,5 @@
 use snafu::prelude::*;
-use std::io::{self, ErrorKind};
+use std::io::{self, ErrorKind, Write as _};
 
 use crate::prelude::*;
 
@@ -48,6 +48,16 @@ pub fn read_to_string(path: &Path) -> Result<String, IoError> {
     std::fs::read_to_string(path).context(IoSnafu { path })
 }
 
+pub fn read_dir(path: &Path) -> Result<Vec<camino::Utf8DirEntry>, IoError> {
+    path.read_dir_utf8()
+        .and_then(|entries| entries.collect())
+        .context(IoSnafu { path })
+}
+
+pub fn metadata(path: &Path) -> Result<std::fs::Metadata, IoError> {
+    std::fs::metadata(path).context(IoSnafu { path })
+}
+
 pub fn remove_dir_all(path: &Path) -> Result<(), IoError> {
     std::fs::remove_dir_all(path).context(IoSnafu { path })
 }
@@ -73,3 +83,45 @@ pub fn write(path: &Path, contents: &[u8]) -> Result<(), IoError> {
 pub fn write_string(path: &Path, contents: &str) -> Result<(), IoError> {
     std::fs::write(path, contents.as_bytes()).context(IoSnafu { path })
 }
+
+/// Writes `contents` to `path`, readable and writable only by the owner on
+/// Unix. An existing file is restricted before it is overwritten.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<(), IoError> {
+    let write = || -> io::Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt as _, PermissionsExt as _};
+            options.mode(0o600);
+            if std::fs::symlink_metadata(path).is_ok() {
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        let mut file = options.open(path)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    };
+    write().context(IoSnafu { path })
+}
+
+/// Replaces the contents of `path` with `contents` by writing them to a file
+/// next to it and renaming that over `path`, so that an interruption leaves
+/// either the old or the new contents. An existing file keeps its permissions.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), IoError> {
+    let tmp = path.with_file_name(format!(".{}.tmp", path.file_name().unwrap_or_default()));
+    let write = || -> io::Result<()> {
+        let mut file = std::fs::File::create(&tmp)?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    };
+    write()
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+        .context(IoSnafu { path })
+}
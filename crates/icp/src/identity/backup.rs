@@ -0,0 +1,556 @@
+//! Password-encrypted backups of the whole identity store.
+//!
+//! A backup is a JSON file holding the scrypt parameters the key is derived
+//! from the password with, and the contents encrypted with AES-256-GCM, so a
+//! modified backup fails to decrypt: every identity's spec, its key as stored
+//! locally (keys held in the keyring are read out of it), its delegation chain,
+//! and the default identity. Password-protected PEM keys stay encrypted with
+//! their own password inside the backup.
+
+use std::collections::BTreeMap;
+
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce, aead::Aead};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use keyring::Entry;
+use rand::Rng;
+use scrypt::Params;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+use zeroize::Zeroizing;
+
+use crate::{
+    fs::{
+        self,
+        lock::{LRead, LWrite},
+    },
+    identity::{
+        IdentityPaths,
+        delegation::{self, DelegationChain},
+        key::{
+            SERVICE_NAME, WriteIdentityError, dlg_keyring_key, validate_identity_name,
+            validate_password, write_identity, write_keyring_entry,
+        },
+        manifest::{
+            DelegationKeyStorage, IdentityDefaults, IdentityList, IdentitySpec,
+            LoadIdentityManifestError, WriteIdentityManifestError,
+        },
+    },
+};
+
+/// The version of the backup format written by this version of icp-cli.
+const BACKUP_VERSION: u32 = 2;
+
+/// The scrypt cost of new backups, as in password-protected PEM keys.
+const SCRYPT_LOG_N: u8 = 17;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// The highest scrypt cost a backup is restored with. The parameters come
+/// from the file, and scrypt allocates 128 * r * 2^log_n bytes up front.
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_R: u32 = 8;
+const MAX_SCRYPT_P: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BackupArchive {
+    v: u32,
+    /// How the encryption key is derived from the password
+    kdf: ScryptParams,
+    /// The AES-256-GCM nonce, base64
+    nonce: String,
+    /// The encrypted [`BackupContents`] with its authentication tag, base64
+    contents: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ScryptParams {
+    /// base64
+    salt: String,
+    log_n: u8,
+    r: u32,
+    p: u32,
+}
+
+impl ScryptParams {
+    /// The AES-256 key for `password`, or `None` if the parameters are invalid
+    /// or costlier than any backup icp-cli writes.
+    fn derive_key(&self, password: &str) -> Option<Zeroizing<[u8; 32]>> {
+        if self.log_n > MAX_SCRYPT_LOG_N || self.r > MAX_SCRYPT_R || self.p > MAX_SCRYPT_P {
+            return None;
+        }
+        let salt = BASE64.decode(&self.salt).ok()?;
+        let params = Params::new(self.log_n, self.r, self.p, 32).ok()?;
+        let mut key = Zeroizing::new([0; 32]);
+        scrypt::scrypt(password.as_bytes(), &salt, &params, key.as_mut()).ok()?;
+        Some(key)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BackupContents {
+    default: String,
+    identities: BTreeMap<String, BackedUpIdentity>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BackedUpIdentity {
+    spec: IdentitySpec,
+    /// The PEM of the key as stored, if the identity has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delegation: Option<DelegationChain>,
+}
+
+/// Where the key of an identity is kept locally.
+enum KeyLocation {
+    File,
+    Keyring(String),
+}
+
+fn key_location(name: &str, spec: &IdentitySpec) -> Option<KeyLocation> {
+    match spec {
+        IdentitySpec::Pem { .. } => Some(KeyLocation::File),
+        IdentitySpec::Keyring { .. } => Some(KeyLocation::Keyring(name.to_string())),
+        IdentitySpec::WebAuth { storage, .. }
+        | IdentitySpec::PendingDelegation { storage, .. }
+        | IdentitySpec::Delegation { storage, .. } => Some(match storage {
+            DelegationKeyStorage::Keyring => KeyLocation::Keyring(dlg_keyring_key(name)),
+            DelegationKeyStorage::Pem { .. } => KeyLocation::File,
+        }),
+        IdentitySpec::Anonymous
+        | IdentitySpec::Hsm { .. }
+        | IdentitySpec::External { .. }
+        | IdentitySpec::SshAgent { .. } => None,
+    }
+}
+
+fn has_delegation_chain(spec: &IdentitySpec) -> bool {
+    matches!(
+        spec,
+        IdentitySpec::WebAuth { .. } | IdentitySpec::Delegation { .. }
+    )
+}
+
+#[derive(Debug, Snafu)]
+pub enum BackupIdentitiesError {
+    #[snafu(transparent)]
+    LoadIdentityManifest { source: LoadIdentityManifestError },
+
+    #[snafu(display("{message}"))]
+    BadBackupPassword { message: String },
+
+    #[snafu(display("failed to read the key of identity `{name}`"))]
+    ReadKeyFileForBackup { name: String, source: fs::IoError },
+
+    #[snafu(display("failed to read keyring entry for identity `{name}`"))]
+    ReadKeyringEntryForBackup {
+        name: String,
+        source: keyring::Error,
+    },
+
+    #[snafu(display("failed to load the delegation chain of identity `{name}`"))]
+    LoadDelegationForBackup {
+        name: String,
+        source: delegation::LoadError,
+    },
+}
+
+/// A backup of the identity store.
+pub struct IdentityBackup {
+    /// The backup file's contents
+    pub archive: String,
+    /// The names of the identities it holds
+    pub identities: Vec<String>,
+}
+
+/// Backs up every identity in the store, encrypted with `password`.
+pub fn backup_identities(
+    dirs: LRead<&IdentityPaths>,
+    password: &str,
+) -> Result<IdentityBackup, BackupIdentitiesError> {
+    validate_password(password)
+        .map_err(|message| BackupIdentitiesError::BadBackupPassword { message })?;
+
+    let list = IdentityList::load_from(dirs)?;
+    let defaults = IdentityDefaults::load_from(dirs)?;
+
+    let mut identities = BTreeMap::new();
+    for (name, spec) in list.identities {
+        if matches!(spec, IdentitySpec::Anonymous) {
+            continue;
+        }
+
+        let key = match key_location(&name, &spec) {
+            Some(KeyLocation::File) => Some(
+                fs::read_to_string(&dirs.key_pem_path(&name))
+                    .context(ReadKeyFileForBackupSnafu { name: &name })?,
+            ),
+            Some(KeyLocation::Keyring(username)) => Some(
+                Entry::new(SERVICE_NAME, &username)
+                    .and_then(|entry| entry.get_password())
+                    .context(ReadKeyringEntryForBackupSnafu { name: &name })?,
+            ),
+            None => None,
+        };
+        let delegation = if has_delegation_chain(&spec) {
+            Some(
+                delegation::load(&dirs.delegation_chain_path(&name))
+                    .context(LoadDelegationForBackupSnafu { name: &name })?,
+            )
+        } else {
+            None
+        };
+
+        identities.insert(
+            name,
+            BackedUpIdentity {
+                spec,
+                key,
+                delegation,
+            },
+        );
+    }
+
+    let names = identities.keys().cloned().collect();
+    let contents = BackupContents {
+        default: defaults.default,
+        identities,
+    };
+    Ok(IdentityBackup {
+        archive: encrypt_backup(&contents, password),
+        identities: names,
+    })
+}
+
+fn encrypt_backup(contents: &BackupContents, password: &str) -> String {
+    let plaintext = Zeroizing::new(serde_json::to_vec(contents).expect("infallible JSON"));
+
+    let mut salt = [0; 16];
+    let mut nonce = [0; 12];
+    let mut rng = rand::rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+    let kdf = ScryptParams {
+        salt: BASE64.encode(salt),
+        log_n: SCRYPT_LOG_N,
+        r: SCRYPT_R,
+        p: SCRYPT_P,
+    };
+    let key = kdf.derive_key(password).expect("valid scrypt params");
+    let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()))
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .expect("infallible encryption");
+
+    let archive = BackupArchive {
+        v: BACKUP_VERSION,
+        kdf,
+        nonce: BASE64.encode(nonce),
+        contents: BASE64.encode(ciphertext),
+    };
+    serde_json::to_string_pretty(&archive).expect("infallible JSON")
+}
+
+/// What to do with a backed-up identity whose name is already taken.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum OnConflict {
+    /// Restore nothing
+    #[default]
+    Error,
+    /// Keep the existing identity and skip the backed-up one
+    Skip,
+    /// Restore the backed-up identity under a new name
+    Rename,
+}
+
+/// What happened to one backed-up identity.
+pub enum RestoreOutcome {
+    Restored { name: String },
+    Renamed { name: String, to: String },
+    Skipped { name: String },
+}
+
+#[derive(Debug, Snafu)]
+pub enum RestoreIdentitiesError {
+    #[snafu(transparent)]
+    LoadIdentityManifest { source: LoadIdentityManifestError },
+
+    #[snafu(transparent)]
+    WriteIdentityManifest { source: WriteIdentityManifestError },
+
+    #[snafu(transparent)]
+    WriteIdentity { source: WriteIdentityError },
+
+    #[snafu(display("the file is not an identity backup"))]
+    ParseBackup { source: serde_json::Error },
+
+    #[snafu(display("the backup was made by an incompatible version of icp-cli"))]
+    BadBackupVersion,
+
+    #[snafu(display("the backup is corrupt"))]
+    DecodeBackup { source: base64::DecodeError },
+
+    #[snafu(display("the backup's encryption parameters are corrupt"))]
+    DecodeBackupEncryption,
+
+    #[snafu(display(
+        "failed to decrypt the backup; is the password correct and the file unmodified?"
+    ))]
+    DecryptBackup,
+
+    #[snafu(display("the backup holds an identity with an invalid name: {message}"))]
+    InvalidBackedUpName { message: String },
+
+    #[snafu(display("the decrypted backup is corrupt"))]
+    ParseBackupContents { source: serde_json::Error },
+
+    #[snafu(display(
+        "identity `{name}` already exists; pass --on-conflict skip or rename to restore the others"
+    ))]
+    IdentityExistsForRestore { name: String },
+
+    #[snafu(display("the backup holds no key for identity `{name}`"))]
+    MissingBackedUpKey { name: String },
+
+    #[snafu(display("the backup holds no delegation chain for identity `{name}`"))]
+    MissingBackedUpDelegation { name: String },
+
+    #[snafu(display("failed to create delegation directory"))]
+    CreateDelegationDirForRestore { source: fs::IoError },
+
+    #[snafu(display("failed to save the delegation chain of identity `{name}`"))]
+    SaveDelegationForRestore {
+        name: String,
+        source: delegation::SaveError,
+    },
+}
+
+/// Restores the identities in `archive` into the store, handling names that
+/// are already taken according to `on_conflict`.
+///
+/// If the store's default is the anonymous identity, the backup's default
+/// becomes the default, under the name it was restored as.
+pub fn restore_identities(
+    dirs: LWrite<&IdentityPaths>,
+    archive: &str,
+    password: &str,
+    on_conflict: OnConflict,
+) -> Result<Vec<RestoreOutcome>, RestoreIdentitiesError> {
+    let contents = decrypt_backup(archive, password)?;
+    let mut list = IdentityList::load_from(dirs.read())?;
+
+    // Names become file names, so a crafted backup must not reach outside the
+    // identity directory
+    for name in contents.identities.keys() {
+        validate_identity_name(name)
+            .map_err(|message| RestoreIdentitiesError::InvalidBackedUpName { message })?;
+    }
+
+    // Decide every name before writing anything, so a conflict that is an
+    // error leaves the store untouched.
+    let mut targets = BTreeMap::new();
+    for name in contents.identities.keys() {
+        if !list.identities.contains_key(name) {
+            targets.insert(name.clone(), Some(name.clone()));
+            continue;
+        }
+        match on_conflict {
+            OnConflict::Error => return IdentityExistsForRestoreSnafu { name }.fail(),
+            OnConflict::Skip => {
+                targets.insert(name.clone(), None);
+            }
+            OnConflict::Rename => {
+                let taken = |n: &String| {
+                    list.identities.contains_key(n)
+                        || contents.identities.contains_key(n)
+                        || targets.values().any(|t| t.as_ref() == Some(n))
+                };
+                let to = std::iter::once(format!("{name}-restored"))
+                    .chain((2..).map(|i| format!("{name}-restored-{i}")))
+                    .find(|n| !taken(n))
+                    .expect("unbounded candidates");
+                targets.insert(name.clone(), Some(to));
+            }
+        }
+    }
+
+    let mut outcomes = vec![];
+    for (name, backed_up) in contents.identities {
+        let Some(target) = targets.remove(&name).flatten() else {
+            outcomes.push(RestoreOutcome::Skipped { name });
+            continue;
+        };
+
+        if let Some(location) = key_location(&target, &backed_up.spec) {
+            let key = backed_up
+                .key
+                .as_deref()
+                .context(MissingBackedUpKeySnafu { name: &name })?;
+            match location {
+                KeyLocation::File => write_identity(dirs, &target, key)?,
+                KeyLocation::Keyring(username) => write_keyring_entry(&username, key)?,
+            }
+        }
+        if has_delegation_chain(&backed_up.spec) {
+            let chain = backed_up
+                .delegation
+                .as_ref()
+                .context(MissingBackedUpDelegationSnafu { name: &name })?;
+            let path = dirs
+                .ensure_delegation_chain_path(&target)
+                .context(CreateDelegationDirForRestoreSnafu)?;
+            delegation::save(&path, chain)
+                .context(SaveDelegationForRestoreSnafu { name: &name })?;
+        }
+
+        // Written per identity, so that a failure part way leaves every
+        // restored key listed.
+        list.identities.insert(target.clone(), backed_up.spec);
+        list.write_to(dirs)?;
+
+        outcomes.push(if target == name {
+            RestoreOutcome::Restored { name }
+        } else {
+            RestoreOutcome::Renamed { name, to: target }
+        });
+    }
+
+    let mut defaults = IdentityDefaults::load_from(dirs.read())?;
+    if defaults.default == "anonymous" {
+        let restored_default = outcomes.iter().find_map(|outcome| match outcome {
+            RestoreOutcome::Restored { name } if *name == contents.default => Some(name),
+            RestoreOutcome::Renamed { name, to } if *name == contents.default => Some(to),
+            _ => None,
+        });
+        if let Some(name) = restored_default {
+            defaults.default = name.clone();
+            defaults.write_to(dirs)?;
+        }
+    }
+
+    Ok(outcomes)
+}
+
+fn decrypt_backup(archive: &str, password: &str) -> Result<BackupContents, RestoreIdentitiesError> {
+    let archive: BackupArchive = serde_json::from_str(archive).context(ParseBackupSnafu)?;
+    ensure!(archive.v == BACKUP_VERSION, BadBackupVersionSnafu);
+
+    let key = archive
+        .kdf
+        .derive_key(password)
+        .context(DecodeBackupEncryptionSnafu)?;
+    let nonce = BASE64.decode(&archive.nonce).context(DecodeBackupSnafu)?;
+    ensure!(nonce.len() == 12, DecodeBackupEncryptionSnafu);
+    let ciphertext = BASE64
+        .decode(&archive.contents)
+        .context(DecodeBackupSnafu)?;
+    let plaintext = Zeroizing::new(
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()))
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| RestoreIdentitiesError::DecryptBackup)?,
+    );
+
+    serde_json::from_slice(&plaintext).context(ParseBackupContentsSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn backup_decrypts_with_its_password_only() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let dirs = IdentityPaths::new(tmp.path().to_path_buf()).unwrap();
+
+        let backup = dirs
+            .with_read(async |dirs| backup_identities(dirs, "backup-password"))
+            .await
+            .unwrap()
+            .unwrap();
+        // Only the anonymous identity exists, which is never backed up
+        assert!(backup.identities.is_empty());
+
+        let contents = decrypt_backup(&backup.archive, "backup-password").unwrap();
+        assert_eq!(contents.default, "anonymous");
+        assert!(matches!(
+            decrypt_backup(&backup.archive, "wrong-password"),
+            Err(RestoreIdentitiesError::DecryptBackup)
+        ));
+
+        // A modified backup does not decrypt
+        let mut archive: BackupArchive = serde_json::from_str(&backup.archive).unwrap();
+        let mut ciphertext = BASE64.decode(&archive.contents).unwrap();
+        ciphertext[0] ^= 1;
+        archive.contents = BASE64.encode(ciphertext);
+        assert!(matches!(
+            decrypt_backup(&serde_json::to_string(&archive).unwrap(), "backup-password"),
+            Err(RestoreIdentitiesError::DecryptBackup)
+        ));
+    }
+
+    #[tokio::test]
+    async fn oversized_scrypt_parameters_are_rejected() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let dirs = IdentityPaths::new(tmp.path().to_path_buf()).unwrap();
+
+        let backup = dirs
+            .with_read(async |dirs| backup_identities(dirs, "backup-password"))
+            .await
+            .unwrap()
+            .unwrap();
+        let mut archive: BackupArchive = serde_json::from_str(&backup.archive).unwrap();
+        archive.kdf.log_n = 40;
+        let archive = serde_json::to_string(&archive).unwrap();
+
+        let restored = dirs
+            .with_write(async |dirs| {
+                restore_identities(dirs, &archive, "backup-password", OnConflict::Error)
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            restored,
+            Err(RestoreIdentitiesError::DecodeBackupEncryption)
+        ));
+    }
+
+    #[tokio::test]
+    async fn names_that_leave_the_identity_directory_are_rejected() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let dirs = IdentityPaths::new(tmp.path().join("identity")).unwrap();
+
+        let archive = encrypt_backup(
+            &BackupContents {
+                default: "anonymous".to_string(),
+                identities: BTreeMap::from([(
+                    "../escaped".to_string(),
+                    BackedUpIdentity {
+                        spec: IdentitySpec::Anonymous,
+                        key: Some("not a key".to_string()),
+                        delegation: None,
+                    },
+                )]),
+            },
+            "backup-password",
+        );
+
+        let restored = dirs
+            .with_write(async |dirs| {
+                restore_identities(dirs, &archive, "backup-password", OnConflict::Error)
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            restored,
+            Err(RestoreIdentitiesError::InvalidBackedUpName { .. })
+        ));
+        assert!(!tmp.path().join("identity/escaped.pem").exists());
+    }
+}
@@ -0,0 +1,166 @@
+//! An address book of named principals and accounts, kept next to the
+//! identity list.
+//!
+//! Commands that take a principal or account accept `@name` for a contact.
+//! Identities are contacts too: `@name` resolves to the principal of
+//! identity `name` if no contact has that name.
+
+use std::{collections::BTreeMap, io::ErrorKind};
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, Snafu, ensure};
+
+use crate::{
+    fs::{
+        json,
+        lock::{LRead, LWrite},
+    },
+    identity::{
+        IdentityPaths,
+        manifest::{IdentityList, LoadIdentityManifestError},
+    },
+    prelude::*,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ContactList {
+    pub v: u32,
+    /// Each contact's principal, ICRC-1 account or ICP ledger account
+    /// identifier, in its textual form
+    pub contacts: BTreeMap<String, String>,
+}
+
+impl Default for ContactList {
+    fn default() -> Self {
+        Self {
+            v: 1,
+            contacts: BTreeMap::new(),
+        }
+    }
+}
+
+impl ContactList {
+    pub fn write_to(&self, dirs: LWrite<&IdentityPaths>) -> Result<(), WriteContactsError> {
+        json::save(&dirs.contacts_path(), self)?;
+        Ok(())
+    }
+
+    pub fn load_from(dirs: LRead<&IdentityPaths>) -> Result<Self, LoadContactsError> {
+        let path = dirs.contacts_path();
+
+        let list = json::load(&path).or_else(|err| match err {
+            json::Error::Io { source } if source.kind() == ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            _ => Err(err),
+        })?;
+
+        ensure!(list.v == 1, BadContactsVersionSnafu { path: &path });
+
+        Ok(list)
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum LoadContactsError {
+    #[snafu(transparent)]
+    LoadContactsJson { source: json::Error },
+
+    #[snafu(display("file `{path}` was modified by an incompatible new version of icp-cli"))]
+    BadContactsVersion { path: PathBuf },
+}
+
+#[derive(Debug, Snafu)]
+pub enum WriteContactsError {
+    #[snafu(transparent)]
+    WriteContactsJson { source: json::Error },
+}
+
+#[derive(Debug, Snafu)]
+pub enum ChangeContactsError {
+    #[snafu(transparent)]
+    LoadContacts { source: LoadContactsError },
+
+    #[snafu(transparent)]
+    WriteContacts { source: WriteContactsError },
+
+    #[snafu(display("contact names may not be empty or contain `@`, `,` or whitespace"))]
+    InvalidContactName,
+
+    #[snafu(display("contact `{name}` already exists"))]
+    ContactAlreadyExists { name: String },
+
+    #[snafu(display("no contact found with name `{name}`"))]
+    NoSuchContactToRemove { name: String },
+}
+
+/// Adds contact `name` for `address`, a principal or account in its textual
+/// form, which the caller has validated.
+pub fn add_contact(
+    dirs: LWrite<&IdentityPaths>,
+    name: &str,
+    address: &str,
+) -> Result<(), ChangeContactsError> {
+    ensure!(
+        !name.is_empty() && !name.contains(|c: char| c == '@' || c == ',' || c.is_whitespace()),
+        InvalidContactNameSnafu
+    );
+
+    let mut list = ContactList::load_from(dirs.read())?;
+    ensure!(
+        !list.contacts.contains_key(name),
+        ContactAlreadyExistsSnafu { name }
+    );
+    list.contacts.insert(name.to_string(), address.to_string());
+    list.write_to(dirs)?;
+
+    Ok(())
+}
+
+pub fn remove_contact(dirs: LWrite<&IdentityPaths>, name: &str) -> Result<(), ChangeContactsError> {
+    let mut list = ContactList::load_from(dirs.read())?;
+    list.contacts
+        .remove(name)
+        .context(NoSuchContactToRemoveSnafu { name })?;
+    list.write_to(dirs)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Snafu)]
+pub enum ResolveContactError {
+    #[snafu(transparent)]
+    LoadContacts { source: LoadContactsError },
+
+    #[snafu(transparent)]
+    LoadIdentityManifest { source: LoadIdentityManifestError },
+
+    #[snafu(display("no contact or identity found with name `{name}`"))]
+    NoSuchContact { name: String },
+
+    #[snafu(display("identity `{name}` has no principal yet"))]
+    ContactIdentityWithoutPrincipal { name: String },
+}
+
+/// The textual address of contact `name`, or if there is no such contact,
+/// the principal of identity `name`.
+pub fn resolve_contact(
+    dirs: LRead<&IdentityPaths>,
+    name: &str,
+) -> Result<String, ResolveContactError> {
+    if let Some(address) = ContactList::load_from(dirs)?.contacts.remove(name) {
+        return Ok(address);
+    }
+
+    let identities = IdentityList::load_from(dirs)?;
+    let spec = identities
+        .identities
+        .get(name)
+        .context(NoSuchContactSnafu { name })?;
+    let principal = spec
+        .principal()
+        .context(ContactIdentityWithoutPrincipalSnafu { name })?;
+
+    Ok(principal.to_text())
+}
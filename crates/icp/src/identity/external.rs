@@ -0,0 +1,342 @@
+//! Identities whose key is held by an external signer.
+//!
+//! The signer is an executable `icp` runs once per operation. It reads a single
+//! JSON request from stdin and writes a single JSON response to stdout; its
+//! stderr is left attached to the terminal, so it may prompt the user there.
+//!
+//! ```text
+//! > {"version":1,"method":"get-public-key","algorithm":"secp256k1"}
+//! < {"public_key":"3056301006072a8648ce3d020106052b8104000a034200..."}
+//!
+//! > {"version":1,"method":"sign-bytes","algorithm":"secp256k1","bytes":"0a69632d72657175657374..."}
+//! < {"signature":"7f3c..."}
+//! ```
+//!
+//! All binary values are hex-encoded. The public key is the DER-encoded
+//! `SubjectPublicKeyInfo`. The signature is over `bytes` as the Internet
+//! Computer expects for the algorithm: for Ed25519, over the bytes themselves;
+//! for ECDSA, over their SHA-256 digest, as the 64-byte `r || s`.
+//!
+//! A signer that cannot serve a request responds with `{"error":"<message>"}`
+//! or exits with a non-zero status.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use candid::Principal;
+use ic_agent::{
+    Identity,
+    agent::EnvelopeContent,
+    identity::{Delegation, Signature},
+};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+use crate::identity::manifest::IdentityKeyAlgorithm;
+
+/// The version of the protocol, sent with every request.
+const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Snafu)]
+pub enum ExternalSignerError {
+    #[snafu(display("failed to parse signer command '{command}'"))]
+    ParseCommand {
+        source: shellwords::MismatchedQuotes,
+        command: String,
+    },
+
+    #[snafu(display("signer command must not be empty"))]
+    EmptyCommand,
+
+    #[snafu(display("failed to run signer command '{command}'"))]
+    Spawn {
+        source: std::io::Error,
+        command: String,
+    },
+
+    #[snafu(display("signer command '{command}' failed with status code {code}"))]
+    Status { command: String, code: String },
+
+    #[snafu(display("signer command '{command}' returned an invalid response"))]
+    InvalidResponse {
+        source: serde_json::Error,
+        command: String,
+    },
+
+    #[snafu(display("signer command '{command}' failed: {message}"))]
+    Signer { command: String, message: String },
+
+    #[snafu(display("signer command '{command}' returned no `{field}`"))]
+    MissingField {
+        command: String,
+        field: &'static str,
+    },
+
+    #[snafu(display("signer command '{command}' returned a `{field}` that is not valid hex"))]
+    InvalidHex {
+        source: hex::FromHexError,
+        command: String,
+        field: &'static str,
+    },
+
+    #[snafu(display(
+        "signer command '{command}' holds the key of principal {actual}, not of {expected}"
+    ))]
+    PrincipalMismatch {
+        command: String,
+        expected: Principal,
+        actual: Principal,
+    },
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    version: u32,
+    method: &'a str,
+    algorithm: &'a IdentityKeyAlgorithm,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    public_key: Option<String>,
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+/// The signer of an external identity.
+#[derive(Clone, Debug)]
+pub struct ExternalSigner {
+    command: String,
+    algorithm: IdentityKeyAlgorithm,
+}
+
+impl ExternalSigner {
+    pub fn new(command: impl Into<String>, algorithm: IdentityKeyAlgorithm) -> Self {
+        Self {
+            command: command.into(),
+            algorithm,
+        }
+    }
+
+    /// The DER-encoded public key of the signer's key.
+    pub fn public_key(&self) -> Result<Vec<u8>, ExternalSignerError> {
+        let response = self.request("get-public-key", None)?;
+        self.decode(response.public_key, "public_key")
+    }
+
+    /// The signature of `bytes` with the signer's key.
+    pub fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ExternalSignerError> {
+        let response = self.request("sign-bytes", Some(hex::encode(bytes)))?;
+        self.decode(response.signature, "signature")
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        bytes: Option<String>,
+    ) -> Result<Response, ExternalSignerError> {
+        let command = &self.command;
+        let words = shellwords::split(command).context(ParseCommandSnafu { command })?;
+        let (program, args) = words.split_first().context(EmptyCommandSnafu)?;
+
+        let request = serde_json::to_string(&Request {
+            version: PROTOCOL_VERSION,
+            method,
+            algorithm: &self.algorithm,
+            bytes,
+        })
+        .expect("requests serialize to JSON");
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .context(SpawnSnafu { command })?;
+        // A signer that exits without reading its request fails below instead
+        let _ = child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(format!("{request}\n").as_bytes());
+        let output = child.wait_with_output().context(SpawnSnafu { command })?;
+        ensure!(
+            output.status.success(),
+            StatusSnafu {
+                command,
+                code: output
+                    .status
+                    .code()
+                    .map_or("N/A".to_string(), |c| c.to_string()),
+            }
+        );
+
+        let response: Response =
+            serde_json::from_slice(&output.stdout).context(InvalidResponseSnafu { command })?;
+        if let Some(message) = response.error {
+            return SignerSnafu { command, message }.fail();
+        }
+        Ok(response)
+    }
+
+    fn decode(
+        &self,
+        value: Option<String>,
+        field: &'static str,
+    ) -> Result<Vec<u8>, ExternalSignerError> {
+        let command = &self.command;
+        let value = value.context(MissingFieldSnafu { command, field })?;
+        hex::decode(value.trim()).context(InvalidHexSnafu { command, field })
+    }
+}
+
+/// An identity whose key is held by an [`ExternalSigner`].
+pub struct ExternalIdentity {
+    signer: ExternalSigner,
+    public_key: Vec<u8>,
+}
+
+impl ExternalIdentity {
+    /// Connects to the signer, checking that it holds the key of `principal`.
+    pub fn new(signer: ExternalSigner, principal: Principal) -> Result<Self, ExternalSignerError> {
+        let public_key = signer.public_key()?;
+        let actual = Principal::self_authenticating(&public_key);
+        ensure!(
+            actual == principal,
+            PrincipalMismatchSnafu {
+                command: &signer.command,
+                expected: principal,
+                actual,
+            }
+        );
+        Ok(Self { signer, public_key })
+    }
+}
+
+impl Identity for ExternalIdentity {
+    fn sender(&self) -> Result<Principal, String> {
+        Ok(Principal::self_authenticating(&self.public_key))
+    }
+
+    fn public_key(&self) -> Option<Vec<u8>> {
+        Some(self.public_key.clone())
+    }
+
+    fn sign(&self, content: &EnvelopeContent) -> Result<Signature, String> {
+        self.sign_arbitrary(&content.to_request_id().signable())
+    }
+
+    fn sign_delegation(&self, content: &Delegation) -> Result<Signature, String> {
+        self.sign_arbitrary(&content.signable())
+    }
+
+    fn sign_arbitrary(&self, content: &[u8]) -> Result<Signature, String> {
+        let signature = self.signer.sign(content).map_err(|e| e.to_string())?;
+        Ok(Signature {
+            signature: Some(signature),
+            public_key: self.public_key(),
+            delegations: None,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use camino_tempfile::Utf8TempDir;
+
+    use super::*;
+    use crate::prelude::*;
+
+    /// Writes a signer script that logs its requests next to itself and answers
+    /// with `public_key` and a fixed signature.
+    fn write_signer(dir: &Path, public_key: &str) -> String {
+        let script = dir.join("signer.sh");
+        std::fs::write(
+            &script,
+            format!(
+                r#"req=$(cat)
+echo "$req" >> "{dir}/requests.log"
+case "$req" in
+  *get-public-key*) echo '{{"public_key":"{public_key}"}}' ;;
+  *sign-bytes*) echo '{{"signature":"c0ffee"}}' ;;
+esac
+"#
+            ),
+        )
+        .unwrap();
+        format!("sh '{script}'")
+    }
+
+    fn public_key() -> Vec<u8> {
+        let key = k256::SecretKey::from_slice(&[7; 32]).unwrap();
+        ic_agent::identity::Secp256k1Identity::from_private_key(key)
+            .public_key()
+            .unwrap()
+    }
+
+    #[test]
+    fn signs_through_the_signer() {
+        let tmp = Utf8TempDir::new().unwrap();
+        let public_key = public_key();
+        let command = write_signer(tmp.path(), &hex::encode(&public_key));
+        let principal = Principal::self_authenticating(&public_key);
+
+        let identity = ExternalIdentity::new(
+            ExternalSigner::new(command, IdentityKeyAlgorithm::Secp256k1),
+            principal,
+        )
+        .unwrap();
+        let signature = identity.sign_arbitrary(b"hello").unwrap();
+
+        assert_eq!(identity.sender().unwrap(), principal);
+        assert_eq!(signature.signature, Some(vec![0xc0, 0xff, 0xee]));
+        assert_eq!(signature.public_key, Some(public_key));
+        let log = std::fs::read_to_string(tmp.path().join("requests.log")).unwrap();
+        assert_eq!(
+            log.lines().collect::<Vec<_>>(),
+            [
+                r#"{"version":1,"method":"get-public-key","algorithm":"secp256k1"}"#,
+                r#"{"version":1,"method":"sign-bytes","algorithm":"secp256k1","bytes":"68656c6c6f"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn a_different_key_is_rejected() {
+        let tmp = Utf8TempDir::new().unwrap();
+        let command = write_signer(tmp.path(), &hex::encode(public_key()));
+
+        let result = ExternalIdentity::new(
+            ExternalSigner::new(command, IdentityKeyAlgorithm::Secp256k1),
+            Principal::anonymous(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(ExternalSignerError::PrincipalMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn signer_errors_are_reported() {
+        let signer = ExternalSigner::new(
+            r#"sh -c 'echo "{\"error\":\"key is locked\"}"'"#,
+            IdentityKeyAlgorithm::Ed25519,
+        );
+        assert_eq!(
+            signer.public_key().unwrap_err().to_string(),
+            r#"signer command 'sh -c 'echo "{\"error\":\"key is locked\"}"'' failed: key is locked"#
+        );
+
+        let signer = ExternalSigner::new("sh -c 'exit 4'", IdentityKeyAlgorithm::Ed25519);
+        assert_eq!(
+            signer.public_key().unwrap_err().to_string(),
+            "signer command 'sh -c 'exit 4'' failed with status code 4"
+        );
+    }
+}
@@ -36,10 +36,12 @@ use crate::{
     identity::{
         IdentityPaths, PasswordFunc,
         delegation::{self, SignedDelegation},
+        external::{ExternalIdentity, ExternalSigner, ExternalSignerError},
         manifest::{
             DelegationKeyStorage, IdentityDefaults, IdentityKeyAlgorithm, IdentityList,
             IdentitySpec, LoadIdentityManifestError, PemFormat, WriteIdentityManifestError,
         },
+        ssh_agent::{SshAgent, SshAgentError, SshAgentIdentity},
     },
     prelude::*,
 };
@@ -113,6 +115,12 @@ pub enum LoadIdentityError {
         source: ic_identity_hsm::HardwareIdentityError,
     },
 
+    #[snafu(display("failed to load external identity"))]
+    LoadExternalError { source: ExternalSignerError },
+
+    #[snafu(display("failed to load ssh-agent identity"))]
+    LoadSshAgentError { source: SshAgentError },
+
     #[snafu(display("failed to load delegation chain from `{path}`"))]
     LoadDelegationChain {
         path: PathBuf,
@@ -181,6 +189,15 @@ pub fn load_identity(
             key_id,
             ..
         } => load_hsm_identity(module, *slot, key_id, password_func),
+        IdentitySpec::External {
+            principal,
+            algorithm,
+            command,
+        } => Ok(Arc::new(
+            ExternalIdentity::new(ExternalSigner::new(command, algorithm.clone()), *principal)
+                .context(LoadExternalSnafu)?,
+        )),
+        IdentitySpec::SshAgent { key, .. } => load_ssh_agent_identity(key),
         IdentitySpec::Anonymous => Ok(Arc::new(AnonymousIdentity)),
         IdentitySpec::WebAuth {
             algorithm, storage, ..
@@ -317,14 +334,14 @@ fn load_plaintext_identity(
     }
 }
 
-const SERVICE_NAME: &str = "icp-cli";
+pub(crate) const SERVICE_NAME: &str = "icp-cli";
 
 /// Returns the keyring username for a delegation session key.
 ///
 /// The `delegation:` prefix discriminates session keys from regular identities —
 /// no code path that operates on regular identity names can accidentally
 /// export these keys.
-fn dlg_keyring_key(name: &str) -> String {
+pub(crate) fn dlg_keyring_key(name: &str) -> String {
     format!("delegation:{name}")
 }
 
@@ -643,6 +660,13 @@ fn load_hsm_identity(
     Ok(Arc::new(identity))
 }
 
+fn load_ssh_agent_identity(fingerprint: &str) -> Result<Arc<dyn Identity>, LoadIdentityError> {
+    let agent = SshAgent::from_env().context(LoadSshAgentSnafu)?;
+    let key = agent.find_key(fingerprint).context(LoadSshAgentSnafu)?;
+
+    Ok(Arc::new(SshAgentIdentity::new(agent, key)))
+}
+
 const TWO_MINUTES_NANOS: u64 = 2 * 60 * 1_000_000_000;
 
 fn load_webauth_identity(
@@ -865,6 +889,18 @@ pub async fn load_identity_in_context(
     Ok(identity)
 }
 
+/// Checks that `name` can name an identity: its key and delegation chain are
+/// stored in files named after it, which must stay in the identity directory.
+pub fn validate_identity_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']) {
+        return Err(format!(
+            "`{name}` is not a valid identity name: it must be non-empty, must not be `.` \
+             or `..` and must not contain `/` or `\\`"
+        ));
+    }
+    Ok(())
+}
+
 pub const MIN_IDENTITY_PASSWORD_LEN: usize = 8;
 
 pub fn validate_password(password: &str) -> Result<(), String> {
@@ -1033,15 +1069,7 @@ pub fn create_identity(
             } else {
                 name.to_string()
             };
-            let entry = Entry::new(SERVICE_NAME, &username).context(CreateEntrySnafu)?;
-            let res = entry.set_password(&pem);
-            #[cfg(target_os = "linux")]
-            if let Err(keyring::Error::NoStorageAccess(err)) = &res
-                && err.to_string().contains("no result found")
-            {
-                return NoKeyringSnafu.fail()?;
-            }
-            res.context(SetEntryPasswordSnafu)?;
+            write_keyring_entry(&username, &pem)?;
         }
     }
 
@@ -1131,7 +1159,7 @@ pub enum WriteIdentityError {
     NoKeyring,
 }
 
-fn write_identity(
+pub(crate) fn write_identity(
     dirs: LWrite<&IdentityPaths>,
     name: &str,
     pem: &str,
@@ -1142,6 +1170,18 @@ fn write_identity(
     Ok(())
 }
 
+pub(crate) fn write_keyring_entry(username: &str, pem: &str) -> Result<(), WriteIdentityError> {
+    let entry = Entry::new(SERVICE_NAME, username).context(CreateEntrySnafu)?;
+    let res = entry.set_password(pem);
+    #[cfg(target_os = "linux")]
+    if let Err(keyring::Error::NoStorageAccess(err)) = &res
+        && err.to_string().contains("no result found")
+    {
+        return NoKeyringSnafu.fail();
+    }
+    res.context(SetEntryPasswordSnafu)
+}
+
 #[derive(Debug, Snafu)]
 pub enum RenameIdentityError {
     #[snafu(transparent)]
@@ -1317,6 +1357,14 @@ pub fn rename_identity(
             // No migration required - HSM key stays on device
             OldKeyMaterial::None
         }
+        IdentitySpec::External { .. } => {
+            // No migration required - the key stays with the signer
+            OldKeyMaterial::None
+        }
+        IdentitySpec::SshAgent { .. } => {
+            // No migration required - the key stays in the agent
+            OldKeyMaterial::None
+        }
         IdentitySpec::Anonymous => {
             unreachable!("anonymous identity should have been rejected above")
         }
@@ -1422,6 +1470,347 @@ pub fn rename_identity(
     Ok(())
 }
 
+#[derive(Debug, Snafu)]
+pub enum ChangeIdentityStorageError {
+    #[snafu(transparent)]
+    LoadIdentityManifest { source: LoadIdentityManifestError },
+
+    #[snafu(transparent)]
+    WriteIdentityManifest { source: WriteIdentityManifestError },
+
+    #[snafu(transparent)]
+    WriteIdentity { source: WriteIdentityError },
+
+    #[snafu(display("no identity found with name `{name}`"))]
+    NoSuchIdentityToChange { name: String },
+
+    #[snafu(display("identity `{name}` has no locally stored key ({kind}) to re-wrap"))]
+    NoStoredKey { name: String, kind: String },
+
+    #[snafu(display("identity `{name}` is not stored with a password"))]
+    NotPasswordProtected { name: String },
+
+    #[snafu(display("{message}"))]
+    BadNewPassword { message: String },
+
+    #[snafu(display("failed to get password: {message}"))]
+    GetPasswordForChange { message: String },
+
+    #[snafu(display("failed to read the key file"))]
+    ReadPemFileForChange { source: fs::IoError },
+
+    #[snafu(display("failed to parse the stored key"))]
+    ParsePemForChange { source: pem::PemError },
+
+    #[snafu(display("failed to decode the stored key"))]
+    ParseDerForChange { source: pkcs8::der::Error },
+
+    #[snafu(display("failed to decrypt the key; is the password correct?"))]
+    DecryptPemForChange { source: pkcs8::Error },
+
+    #[snafu(display("failed to load keyring entry for identity `{name}`"))]
+    LoadKeyringEntryForChange {
+        name: String,
+        source: keyring::Error,
+    },
+
+    #[snafu(display("failed to read keyring entry for identity `{name}`"))]
+    ReadKeyringEntryForChange {
+        name: String,
+        source: keyring::Error,
+    },
+
+    #[snafu(display("failed to write the new key file"))]
+    WriteStagedKeyFileForChange { source: fs::IoError },
+
+    #[snafu(display("failed to replace the key file"))]
+    ReplaceKeyFileForChange { source: fs::RenameError },
+
+    #[snafu(display("failed to delete the old key file"))]
+    DeleteOldKeyFileForChange { source: fs::IoError },
+
+    #[snafu(display("failed to delete the old keyring entry for identity `{name}`"))]
+    DeleteKeyringEntryForChange {
+        name: String,
+        source: keyring::Error,
+    },
+}
+
+/// Where the key of an identity is stored, and how.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyStorage {
+    Pem(PemFormat),
+    Keyring,
+}
+
+impl From<&CreateFormat> for KeyStorage {
+    fn from(format: &CreateFormat) -> Self {
+        match format {
+            CreateFormat::Plaintext => KeyStorage::Pem(PemFormat::Plaintext),
+            CreateFormat::Pbes2 { .. } => KeyStorage::Pem(PemFormat::Pbes2),
+            CreateFormat::Keyring => KeyStorage::Keyring,
+        }
+    }
+}
+
+/// Re-stores the key of identity `name` according to `to`, keeping its name,
+/// principal and any delegation chain.
+///
+/// Covers PEM and keyring identities as well as the session keys of
+/// delegation-based identities. `password_func` is only called if the key is
+/// currently password-protected. Any cached session of a password-protected
+/// PEM identity is discarded, so the next use asks for the (new) password.
+pub fn change_identity_storage(
+    dirs: LWrite<&IdentityPaths>,
+    name: &str,
+    to: CreateFormat,
+    password_func: impl FnOnce() -> Result<String, String>,
+) -> Result<(), ChangeIdentityStorageError> {
+    if let CreateFormat::Pbes2 { password } = &to {
+        validate_password(password)
+            .map_err(|message| ChangeIdentityStorageError::BadNewPassword { message })?;
+    }
+
+    let mut identity_list = IdentityList::load_from(dirs.read())?;
+    let spec = identity_list
+        .identities
+        .get(name)
+        .context(NoSuchIdentityToChangeSnafu { name })?
+        .clone();
+
+    // Delegation session keys live in the keyring under a prefixed name, so they
+    // can never be mistaken for a regular identity's key.
+    let (from, username) = match &spec {
+        IdentitySpec::Pem { format, .. } => (KeyStorage::Pem(*format), name.to_string()),
+        IdentitySpec::Keyring { .. } => (KeyStorage::Keyring, name.to_string()),
+        IdentitySpec::WebAuth { storage, .. }
+        | IdentitySpec::PendingDelegation { storage, .. }
+        | IdentitySpec::Delegation { storage, .. } => {
+            let from = match storage {
+                DelegationKeyStorage::Keyring => KeyStorage::Keyring,
+                DelegationKeyStorage::Pem { format } => KeyStorage::Pem(*format),
+            };
+            (from, dlg_keyring_key(name))
+        }
+        IdentitySpec::Anonymous => {
+            return NoStoredKeySnafu {
+                name,
+                kind: "anonymous",
+            }
+            .fail();
+        }
+        IdentitySpec::Hsm { .. } => return NoStoredKeySnafu { name, kind: "HSM" }.fail(),
+        IdentitySpec::External { .. } => {
+            return NoStoredKeySnafu {
+                name,
+                kind: "external signer",
+            }
+            .fail();
+        }
+        IdentitySpec::SshAgent { .. } => {
+            return NoStoredKeySnafu {
+                name,
+                kind: "ssh-agent",
+            }
+            .fail();
+        }
+    };
+
+    // Unwrap the key to its plaintext PKCS#8 form
+    let doc: SecretDocument = match from {
+        KeyStorage::Pem(format) => {
+            let pem = fs::read_to_string(&dirs.key_pem_path(name))
+                .context(ReadPemFileForChangeSnafu)?
+                .parse::<Pem>()
+                .context(ParsePemForChangeSnafu)?;
+            match format {
+                PemFormat::Plaintext => {
+                    SecretDocument::try_from(pem.contents()).context(ParseDerForChangeSnafu)?
+                }
+                PemFormat::Pbes2 => {
+                    let password = Zeroizing::new(password_func().map_err(|message| {
+                        ChangeIdentityStorageError::GetPasswordForChange { message }
+                    })?);
+                    EncryptedPrivateKeyInfo::from_der(pem.contents())
+                        .context(ParseDerForChangeSnafu)?
+                        .decrypt(&*password)
+                        .context(DecryptPemForChangeSnafu)?
+                }
+            }
+        }
+        KeyStorage::Keyring => {
+            let pem = Entry::new(SERVICE_NAME, &username)
+                .context(LoadKeyringEntryForChangeSnafu { name })?
+                .get_password()
+                .context(ReadKeyringEntryForChangeSnafu { name })?
+                .parse::<Pem>()
+                .context(ParsePemForChangeSnafu)?;
+            SecretDocument::try_from(pem.contents()).context(ParseDerForChangeSnafu)?
+        }
+    };
+
+    // Wrap it again. A new PEM file is staged next to the key file, which is
+    // only replaced once the identity list points at the new format.
+    let pem_path = dirs
+        .ensure_key_pem_path(name)
+        .context(WriteStagedKeyFileForChangeSnafu)?;
+    let staged_path = pem_path.with_extension("pem.new");
+    let staged = match &to {
+        CreateFormat::Plaintext => {
+            let pem = doc
+                .to_pem(PrivateKeyInfo::PEM_LABEL, Default::default())
+                .expect("infallible PKI encoding");
+            fs::write_string(&staged_path, &pem).context(WriteStagedKeyFileForChangeSnafu)?;
+            true
+        }
+        CreateFormat::Pbes2 { password } => {
+            fs::write_string(&staged_path, &make_pkcs5_encrypted_pem(&doc, password))
+                .context(WriteStagedKeyFileForChangeSnafu)?;
+            true
+        }
+        CreateFormat::Keyring => {
+            let pem = doc
+                .to_pem(PrivateKeyInfo::PEM_LABEL, Default::default())
+                .expect("infallible PKI encoding");
+            write_keyring_entry(&username, &pem)?;
+            false
+        }
+    };
+
+    let to = KeyStorage::from(&to);
+    let old_spec = spec.clone();
+    let spec = match spec {
+        IdentitySpec::Pem {
+            algorithm,
+            principal,
+            ..
+        }
+        | IdentitySpec::Keyring {
+            algorithm,
+            principal,
+        } => match to {
+            KeyStorage::Pem(format) => IdentitySpec::Pem {
+                format,
+                algorithm,
+                principal,
+            },
+            KeyStorage::Keyring => IdentitySpec::Keyring {
+                principal,
+                algorithm,
+            },
+        },
+        mut spec @ (IdentitySpec::WebAuth { .. }
+        | IdentitySpec::PendingDelegation { .. }
+        | IdentitySpec::Delegation { .. }) => {
+            if let IdentitySpec::WebAuth { storage, .. }
+            | IdentitySpec::PendingDelegation { storage, .. }
+            | IdentitySpec::Delegation { storage, .. } = &mut spec
+            {
+                *storage = match to {
+                    KeyStorage::Keyring => DelegationKeyStorage::Keyring,
+                    KeyStorage::Pem(format) => DelegationKeyStorage::Pem { format },
+                };
+            }
+            spec
+        }
+        _ => unreachable!("identities without a stored key were rejected above"),
+    };
+    let is_pem_identity = matches!(spec, IdentitySpec::Pem { .. });
+    identity_list.identities.insert(name.to_string(), spec);
+    if let Err(err) = identity_list.write_to(dirs) {
+        // The old key is untouched; drop the new copy
+        if staged {
+            let _ = fs::remove_file(&staged_path);
+        } else if from != KeyStorage::Keyring
+            && let Ok(entry) = Entry::new(SERVICE_NAME, &username)
+        {
+            let _ = entry.delete_credential();
+        }
+        return Err(err.into());
+    }
+
+    if staged && let Err(err) = fs::rename(&staged_path, &pem_path) {
+        // Point the list back at the old key, which is still in place
+        identity_list.identities.insert(name.to_string(), old_spec);
+        let _ = identity_list.write_to(dirs);
+        let _ = fs::remove_file(&staged_path);
+        return Err(err).context(ReplaceKeyFileForChangeSnafu);
+    }
+
+    // Delete the old key material once the list points at the new one
+    match (from, to) {
+        (KeyStorage::Pem(_), KeyStorage::Keyring) => {
+            fs::remove_file(&dirs.key_pem_path(name)).context(DeleteOldKeyFileForChangeSnafu)?;
+        }
+        (KeyStorage::Keyring, KeyStorage::Pem(_)) => {
+            Entry::new(SERVICE_NAME, &username)
+                .context(LoadKeyringEntryForChangeSnafu { name })?
+                .delete_credential()
+                .context(DeleteKeyringEntryForChangeSnafu { name })?;
+        }
+        _ => {}
+    }
+
+    // Best-effort: drop any cached session that the old password unlocked.
+    if is_pem_identity {
+        if let Ok(entry) = Entry::new(SERVICE_NAME, &dlg_keyring_key(name)) {
+            let _ = entry.delete_credential();
+        }
+        let _ = fs::remove_file(&dirs.delegation_chain_path(name));
+    }
+
+    Ok(())
+}
+
+/// Re-encrypts the password-protected key of identity `name` with
+/// `new_password`. `password_func` supplies the current password.
+pub fn change_identity_password(
+    dirs: LWrite<&IdentityPaths>,
+    name: &str,
+    new_password: Zeroizing<String>,
+    password_func: impl FnOnce() -> Result<String, String>,
+) -> Result<(), ChangeIdentityStorageError> {
+    let identity_list = IdentityList::load_from(dirs.read())?;
+    let spec = identity_list
+        .identities
+        .get(name)
+        .context(NoSuchIdentityToChangeSnafu { name })?;
+    ensure!(
+        matches!(
+            spec,
+            IdentitySpec::Pem {
+                format: PemFormat::Pbes2,
+                ..
+            } | IdentitySpec::WebAuth {
+                storage: DelegationKeyStorage::Pem {
+                    format: PemFormat::Pbes2
+                },
+                ..
+            } | IdentitySpec::PendingDelegation {
+                storage: DelegationKeyStorage::Pem {
+                    format: PemFormat::Pbes2
+                },
+                ..
+            } | IdentitySpec::Delegation {
+                storage: DelegationKeyStorage::Pem {
+                    format: PemFormat::Pbes2
+                },
+                ..
+            }
+        ),
+        NotPasswordProtectedSnafu { name }
+    );
+
+    change_identity_storage(
+        dirs,
+        name,
+        CreateFormat::Pbes2 {
+            password: new_password,
+        },
+        password_func,
+    )
+}
+
 #[derive(Debug, Snafu)]
 pub enum DeleteIdentityError {
     #[snafu(transparent)]
@@ -1520,7 +1909,9 @@ pub fn delete_identity(
             let delegation_path = dirs.delegation_chain_path(name);
             fs::remove_file(&delegation_path)?;
         }
-        IdentitySpec::Hsm { .. } => {
+        IdentitySpec::Hsm { .. }
+        | IdentitySpec::External { .. }
+        | IdentitySpec::SshAgent { .. } => {
             // no deletion required
         }
         IdentitySpec::Anonymous => {
@@ -1613,6 +2004,97 @@ pub fn link_hsm_identity(
     Ok(())
 }
 
+#[derive(Debug, Snafu)]
+pub enum LinkExternalIdentityError {
+    #[snafu(transparent)]
+    LoadIdentityManifest { source: LoadIdentityManifestError },
+
+    #[snafu(transparent)]
+    WriteIdentityManifest { source: WriteIdentityManifestError },
+
+    #[snafu(display("identity `{name}` already exists"))]
+    ExternalNameTaken { name: String },
+
+    #[snafu(display("failed to get the public key from the signer"))]
+    SignerPublicKey { source: ExternalSignerError },
+}
+
+/// Links a key held by an external signer to a named identity.
+///
+/// The signer is asked for its public key once, to record the principal; the
+/// private key never reaches `icp`.
+pub fn link_external_identity(
+    dirs: LWrite<&IdentityPaths>,
+    name: &str,
+    command: String,
+    algorithm: IdentityKeyAlgorithm,
+) -> Result<(), LinkExternalIdentityError> {
+    let mut identity_list = IdentityList::load_from(dirs.read())?;
+    ensure!(
+        !identity_list.identities.contains_key(name),
+        ExternalNameTakenSnafu { name }
+    );
+
+    let public_key = ExternalSigner::new(command.clone(), algorithm.clone())
+        .public_key()
+        .context(SignerPublicKeySnafu)?;
+
+    let spec = IdentitySpec::External {
+        principal: ic_agent::export::Principal::self_authenticating(&public_key),
+        algorithm,
+        command,
+    };
+    identity_list.identities.insert(name.to_string(), spec);
+    identity_list.write_to(dirs)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Snafu)]
+pub enum LinkSshAgentIdentityError {
+    #[snafu(transparent)]
+    LoadIdentityManifest { source: LoadIdentityManifestError },
+
+    #[snafu(transparent)]
+    WriteIdentityManifest { source: WriteIdentityManifestError },
+
+    #[snafu(display("identity `{name}` already exists"))]
+    SshAgentNameTaken { name: String },
+
+    #[snafu(display("failed to find the key in the ssh-agent"))]
+    FindAgentKey { source: SshAgentError },
+}
+
+/// Links an Ed25519 key held by the SSH agent at `$SSH_AUTH_SOCK` to a named
+/// identity.
+///
+/// `key` selects the key by fingerprint or comment; the identity records its
+/// fingerprint, so only the public key is ever stored.
+pub fn link_ssh_agent_identity(
+    dirs: LWrite<&IdentityPaths>,
+    name: &str,
+    key: &str,
+) -> Result<(), LinkSshAgentIdentityError> {
+    let mut identity_list = IdentityList::load_from(dirs.read())?;
+    ensure!(
+        !identity_list.identities.contains_key(name),
+        SshAgentNameTakenSnafu { name }
+    );
+
+    let key = SshAgent::from_env()
+        .and_then(|agent| agent.find_key(key))
+        .context(FindAgentKeySnafu)?;
+
+    let spec = IdentitySpec::SshAgent {
+        principal: ic_agent::export::Principal::self_authenticating(key.der_public_key()),
+        key: key.fingerprint(),
+    };
+    identity_list.identities.insert(name.to_string(), spec);
+    identity_list.write_to(dirs)?;
+
+    Ok(())
+}
+
 #[derive(Debug, Snafu)]
 pub enum CreatePendingDelegationError {
     #[snafu(transparent)]
@@ -2039,6 +2521,12 @@ pub enum ExportIdentityError {
     #[snafu(display("cannot export an HSM-backed identity"))]
     CannotExportHsm,
 
+    #[snafu(display("cannot export an identity held by an external signer"))]
+    CannotExportExternal,
+
+    #[snafu(display("cannot export an identity held by an ssh-agent"))]
+    CannotExportSshAgent,
+
     #[snafu(display("cannot export a delegation-based identity"))]
     CannotExportDelegationBased,
 
@@ -2149,6 +2637,8 @@ pub fn export_identity(
         }
         IdentitySpec::Anonymous => return CannotExportAnonymousSnafu.fail(),
         IdentitySpec::Hsm { .. } => return CannotExportHsmSnafu.fail(),
+        IdentitySpec::External { .. } => return CannotExportExternalSnafu.fail(),
+        IdentitySpec::SshAgent { .. } => return CannotExportSshAgentSnafu.fail(),
         IdentitySpec::WebAuth { .. } => return CannotExportDelegationBasedSnafu.fail(),
         IdentitySpec::PendingDelegation { .. } | IdentitySpec::Delegation { .. } => {
             return CannotExportDelegationSnafu.fail();
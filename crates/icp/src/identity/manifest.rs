@@ -129,6 +129,20 @@ pub enum IdentitySpec {
         slot: usize,
         key_id: String,
     },
+    /// A key held by an external signer, which `icp` runs to sign. See
+    /// [`external`](crate::identity::external) for the protocol.
+    External {
+        principal: Principal,
+        algorithm: IdentityKeyAlgorithm,
+        command: String,
+    },
+    /// An Ed25519 key held by an SSH agent, which `icp` reaches through
+    /// `$SSH_AUTH_SOCK` to sign.
+    SshAgent {
+        principal: Principal,
+        /// The `SHA256:` fingerprint of the key
+        key: String,
+    },
     #[serde(alias = "internet-identity")]
     WebAuth {
         algorithm: IdentityKeyAlgorithm,
@@ -170,6 +184,8 @@ impl IdentitySpec {
             IdentitySpec::Anonymous => Some(Principal::anonymous()),
             IdentitySpec::Keyring { principal, .. } => Some(*principal),
             IdentitySpec::Hsm { principal, .. } => Some(*principal),
+            IdentitySpec::External { principal, .. } => Some(*principal),
+            IdentitySpec::SshAgent { principal, .. } => Some(*principal),
             IdentitySpec::WebAuth { principal, .. } => Some(*principal),
             IdentitySpec::PendingDelegation { .. } => None,
             IdentitySpec::Delegation { principal, .. } => Some(*principal),
@@ -19,11 +19,16 @@ use crate::{
     telemetry_data::{IdentityStorageType, TelemetryData},
 };
 
+pub mod backup;
+pub mod contacts;
 pub mod delegation;
+pub mod dfx;
+pub mod external;
 pub mod key;
 pub mod keyring_mock;
 pub mod manifest;
 pub mod seed;
+pub mod ssh_agent;
 
 /// Name of the default identities file
 const IDENTITY_DEFAULTS: &str = "identity_defaults.json";
@@ -31,6 +36,9 @@ const IDENTITY_DEFAULTS: &str = "identity_defaults.json";
 /// Name of the identities list file
 const IDENTITIES_LIST: &str = "identity_list.json";
 
+/// Name of the contacts file
+const CONTACTS: &str = "contacts.json";
+
 pub struct IdentityPaths {
     dir: PathBuf,
 }
@@ -58,6 +66,10 @@ impl IdentityPaths {
         Ok(self.dir.join(IDENTITIES_LIST))
     }
 
+    pub fn contacts_path(&self) -> PathBuf {
+        self.dir.join(CONTACTS)
+    }
+
     pub fn key_pem_path(&self, name: &str) -> PathBuf {
         self.dir.join(format!("keys/{name}.pem"))
     }
@@ -0,0 +1,416 @@
+//! Ed25519 identities whose key is held by an SSH agent.
+//!
+//! `icp` talks to the agent at `$SSH_AUTH_SOCK` over the SSH agent protocol:
+//! it lists the agent's keys to find the linked one, and asks the agent to sign
+//! each request. An Ed25519 signature by the agent over the bytes to sign is
+//! exactly what the Internet Computer expects, so no key material ever leaves
+//! the agent.
+
+#[cfg(unix)]
+use std::io::{Read, Write};
+
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+use candid::Principal;
+use ic_agent::{
+    Identity,
+    agent::EnvelopeContent,
+    identity::{Delegation, Signature},
+};
+use sha2::{Digest, Sha256};
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+use crate::prelude::*;
+
+/// The environment variable holding the path of the agent's socket.
+pub const SSH_AUTH_SOCK: &str = "SSH_AUTH_SOCK";
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+
+const ED25519_KEY_TYPE: &str = "ssh-ed25519";
+
+/// Messages larger than this are not valid agent messages.
+const MAX_MESSAGE_LEN: u32 = 256 * 1024;
+
+#[derive(Debug, Snafu)]
+pub enum SshAgentError {
+    #[snafu(display("{SSH_AUTH_SOCK} is not set; is an ssh-agent running?"))]
+    NoAgent,
+
+    #[snafu(display("ssh-agent identities are only supported on Unix"))]
+    Unsupported,
+
+    #[snafu(display("failed to talk to the ssh-agent at `{path}`"))]
+    Io {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("the ssh-agent refused the request"))]
+    Refused,
+
+    #[snafu(display("the ssh-agent sent a malformed response"))]
+    Malformed,
+
+    #[snafu(display("no Ed25519 key matching `{key}` in the ssh-agent; add it with `ssh-add`"))]
+    NoSuchKey { key: String },
+
+    #[snafu(display(
+        "`{key}` matches several keys in the ssh-agent ({}); select one by fingerprint",
+        fingerprints.join(", ")
+    ))]
+    AmbiguousKey {
+        key: String,
+        fingerprints: Vec<String>,
+    },
+}
+
+/// An Ed25519 key held by an SSH agent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SshAgentKey {
+    /// The raw public key
+    pub public_key: [u8; 32],
+    pub comment: String,
+}
+
+impl SshAgentKey {
+    /// The key in the SSH wire format.
+    fn blob(&self) -> Vec<u8> {
+        let mut blob = Vec::new();
+        put_string(&mut blob, ED25519_KEY_TYPE.as_bytes());
+        put_string(&mut blob, &self.public_key);
+        blob
+    }
+
+    /// The fingerprint `ssh-add -l` shows, e.g. `SHA256:p5hY...`.
+    pub fn fingerprint(&self) -> String {
+        format!(
+            "SHA256:{}",
+            STANDARD_NO_PAD.encode(Sha256::digest(self.blob()))
+        )
+    }
+
+    /// The DER-encoded public key.
+    pub fn der_public_key(&self) -> Vec<u8> {
+        ic_ed25519::PublicKey::convert_raw32_to_der(self.public_key)
+    }
+
+    /// Whether `key` selects this key: its fingerprint, with or without the
+    /// `SHA256:` prefix, or its comment.
+    fn matches(&self, key: &str) -> bool {
+        let fingerprint = self.fingerprint();
+        key == fingerprint
+            || Some(key) == fingerprint.strip_prefix("SHA256:")
+            || key == self.comment
+    }
+}
+
+/// A connection point to an SSH agent.
+#[derive(Clone, Debug)]
+pub struct SshAgent {
+    path: PathBuf,
+}
+
+impl SshAgent {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The agent at `$SSH_AUTH_SOCK`.
+    pub fn from_env() -> Result<Self, SshAgentError> {
+        let path = std::env::var(SSH_AUTH_SOCK)
+            .ok()
+            .filter(|p| !p.is_empty())
+            .context(NoAgentSnafu)?;
+        Ok(Self::new(path.into()))
+    }
+
+    /// The Ed25519 keys the agent holds; keys of other types are left out.
+    pub fn keys(&self) -> Result<Vec<SshAgentKey>, SshAgentError> {
+        let response = self.request(SSH_AGENTC_REQUEST_IDENTITIES, &[])?;
+        let mut r = Reader(&response);
+        ensure!(r.u8()? == SSH_AGENT_IDENTITIES_ANSWER, MalformedSnafu);
+
+        let count = r.u32()?;
+        let mut keys = Vec::new();
+        for _ in 0..count {
+            let blob = r.string()?;
+            let comment = String::from_utf8_lossy(r.string()?).into_owned();
+            let mut b = Reader(blob);
+            if b.string()? != ED25519_KEY_TYPE.as_bytes() {
+                continue;
+            }
+            let public_key = b.string()?.try_into().ok().context(MalformedSnafu)?;
+            keys.push(SshAgentKey {
+                public_key,
+                comment,
+            });
+        }
+        Ok(keys)
+    }
+
+    /// The one Ed25519 key selected by `key`, a fingerprint or a comment.
+    pub fn find_key(&self, key: &str) -> Result<SshAgentKey, SshAgentError> {
+        let mut matches: Vec<_> = self
+            .keys()?
+            .into_iter()
+            .filter(|k| k.matches(key))
+            .collect();
+        match matches.len() {
+            0 => NoSuchKeySnafu { key }.fail(),
+            1 => Ok(matches.remove(0)),
+            _ => AmbiguousKeySnafu {
+                key,
+                fingerprints: matches
+                    .iter()
+                    .map(SshAgentKey::fingerprint)
+                    .collect::<Vec<_>>(),
+            }
+            .fail(),
+        }
+    }
+
+    /// The agent's Ed25519 signature of `data` with `key`.
+    pub fn sign(&self, key: &SshAgentKey, data: &[u8]) -> Result<Vec<u8>, SshAgentError> {
+        let mut payload = Vec::new();
+        put_string(&mut payload, &key.blob());
+        put_string(&mut payload, data);
+        payload.extend_from_slice(&0u32.to_be_bytes());
+
+        let response = self.request(SSH_AGENTC_SIGN_REQUEST, &payload)?;
+        let mut r = Reader(&response);
+        ensure!(r.u8()? == SSH_AGENT_SIGN_RESPONSE, MalformedSnafu);
+        let mut signature = Reader(r.string()?);
+        ensure!(
+            signature.string()? == ED25519_KEY_TYPE.as_bytes(),
+            MalformedSnafu
+        );
+        Ok(signature.string()?.to_vec())
+    }
+
+    /// Sends one message and returns the agent's response, without its length.
+    #[cfg(unix)]
+    fn request(&self, kind: u8, payload: &[u8]) -> Result<Vec<u8>, SshAgentError> {
+        let path = &self.path;
+        let mut stream = std::os::unix::net::UnixStream::connect(path).context(IoSnafu { path })?;
+
+        let mut message = Vec::with_capacity(payload.len() + 5);
+        message.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        message.push(kind);
+        message.extend_from_slice(payload);
+        stream.write_all(&message).context(IoSnafu { path })?;
+
+        let mut len = [0; 4];
+        stream.read_exact(&mut len).context(IoSnafu { path })?;
+        let len = u32::from_be_bytes(len);
+        ensure!(len > 0 && len <= MAX_MESSAGE_LEN, MalformedSnafu);
+        let mut response = vec![0; len as usize];
+        stream.read_exact(&mut response).context(IoSnafu { path })?;
+        ensure!(response[0] != SSH_AGENT_FAILURE, RefusedSnafu);
+        Ok(response)
+    }
+
+    #[cfg(not(unix))]
+    fn request(&self, _kind: u8, _payload: &[u8]) -> Result<Vec<u8>, SshAgentError> {
+        UnsupportedSnafu.fail()
+    }
+}
+
+fn put_string(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    buf.extend_from_slice(s);
+}
+
+/// Reads the fields of an agent message.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SshAgentError> {
+        ensure!(self.0.len() >= n, MalformedSnafu);
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SshAgentError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SshAgentError> {
+        Ok(u32::from_be_bytes(
+            self.take(4)?.try_into().expect("4 bytes"),
+        ))
+    }
+
+    fn string(&mut self) -> Result<&'a [u8], SshAgentError> {
+        let len = self.u32()?;
+        self.take(len as usize)
+    }
+}
+
+/// An identity whose Ed25519 key is held by an SSH agent.
+pub struct SshAgentIdentity {
+    agent: SshAgent,
+    key: SshAgentKey,
+}
+
+impl SshAgentIdentity {
+    pub fn new(agent: SshAgent, key: SshAgentKey) -> Self {
+        Self { agent, key }
+    }
+}
+
+impl Identity for SshAgentIdentity {
+    fn sender(&self) -> Result<Principal, String> {
+        Ok(Principal::self_authenticating(self.key.der_public_key()))
+    }
+
+    fn public_key(&self) -> Option<Vec<u8>> {
+        Some(self.key.der_public_key())
+    }
+
+    fn sign(&self, content: &EnvelopeContent) -> Result<Signature, String> {
+        self.sign_arbitrary(&content.to_request_id().signable())
+    }
+
+    fn sign_delegation(&self, content: &Delegation) -> Result<Signature, String> {
+        self.sign_arbitrary(&content.signable())
+    }
+
+    fn sign_arbitrary(&self, content: &[u8]) -> Result<Signature, String> {
+        let signature = self
+            .agent
+            .sign(&self.key, content)
+            .map_err(|e| e.to_string())?;
+        Ok(Signature {
+            signature: Some(signature),
+            public_key: self.public_key(),
+            delegations: None,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::net::UnixListener;
+
+    use camino_tempfile::Utf8TempDir;
+
+    use super::*;
+
+    /// Serves the agent protocol for `keys` on a socket in `dir`, signing with
+    /// them, until the test process exits.
+    fn fake_agent(dir: &Path, keys: Vec<(ic_ed25519::PrivateKey, &'static str)>) -> SshAgent {
+        let path = dir.join("agent.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut len = [0; 4];
+                stream.read_exact(&mut len).unwrap();
+                let mut request = vec![0; u32::from_be_bytes(len) as usize];
+                stream.read_exact(&mut request).unwrap();
+
+                let agent_keys: Vec<_> = keys
+                    .iter()
+                    .map(|(k, comment)| SshAgentKey {
+                        public_key: k.public_key().serialize_raw(),
+                        comment: comment.to_string(),
+                    })
+                    .collect();
+                let mut response = Vec::new();
+                match request[0] {
+                    SSH_AGENTC_REQUEST_IDENTITIES => {
+                        response.push(SSH_AGENT_IDENTITIES_ANSWER);
+                        response.extend_from_slice(&(agent_keys.len() as u32).to_be_bytes());
+                        for k in &agent_keys {
+                            put_string(&mut response, &k.blob());
+                            put_string(&mut response, k.comment.as_bytes());
+                        }
+                    }
+                    SSH_AGENTC_SIGN_REQUEST => {
+                        let mut r = Reader(&request[1..]);
+                        let blob = r.string().unwrap();
+                        let data = r.string().unwrap();
+                        match agent_keys.iter().position(|k| k.blob() == blob) {
+                            Some(i) => {
+                                let mut signature = Vec::new();
+                                put_string(&mut signature, ED25519_KEY_TYPE.as_bytes());
+                                put_string(&mut signature, &keys[i].0.sign_message(data));
+                                response.push(SSH_AGENT_SIGN_RESPONSE);
+                                put_string(&mut response, &signature);
+                            }
+                            None => response.push(SSH_AGENT_FAILURE),
+                        }
+                    }
+                    _ => response.push(SSH_AGENT_FAILURE),
+                }
+                let mut message = (response.len() as u32).to_be_bytes().to_vec();
+                message.extend_from_slice(&response);
+                stream.write_all(&message).unwrap();
+            }
+        });
+        SshAgent::new(path)
+    }
+
+    fn key(seed: u8) -> ic_ed25519::PrivateKey {
+        ic_ed25519::PrivateKey::deserialize_raw_32(&[seed; 32])
+    }
+
+    #[test]
+    fn keys_are_selected_by_fingerprint_or_comment() {
+        let tmp = Utf8TempDir::new().unwrap();
+        let agent = fake_agent(tmp.path(), vec![(key(1), "alice@laptop"), (key(2), "ci")]);
+
+        let keys = agent.keys().unwrap();
+        assert_eq!(keys.len(), 2);
+        let fingerprint = keys[1].fingerprint();
+        assert!(fingerprint.starts_with("SHA256:"));
+
+        assert_eq!(agent.find_key("alice@laptop").unwrap(), keys[0]);
+        assert_eq!(agent.find_key(&fingerprint).unwrap(), keys[1]);
+        assert_eq!(
+            agent
+                .find_key(fingerprint.strip_prefix("SHA256:").unwrap())
+                .unwrap(),
+            keys[1]
+        );
+        assert!(matches!(
+            agent.find_key("bob"),
+            Err(SshAgentError::NoSuchKey { .. })
+        ));
+    }
+
+    #[test]
+    fn the_same_comment_on_two_keys_is_ambiguous() {
+        let tmp = Utf8TempDir::new().unwrap();
+        let agent = fake_agent(tmp.path(), vec![(key(1), "laptop"), (key(2), "laptop")]);
+
+        assert!(matches!(
+            agent.find_key("laptop"),
+            Err(SshAgentError::AmbiguousKey { fingerprints, .. }) if fingerprints.len() == 2
+        ));
+    }
+
+    #[test]
+    fn signatures_verify_against_the_public_key() {
+        let tmp = Utf8TempDir::new().unwrap();
+        let agent = fake_agent(tmp.path(), vec![(key(1), "alice@laptop")]);
+        let key = agent.find_key("alice@laptop").unwrap();
+        let identity = SshAgentIdentity::new(agent, key.clone());
+
+        let signature = identity.sign_arbitrary(b"hello").unwrap();
+
+        ic_ed25519::PublicKey::deserialize_raw(&key.public_key)
+            .unwrap()
+            .verify_signature(b"hello", &signature.signature.unwrap())
+            .unwrap();
+        assert_eq!(
+            identity.sender().unwrap(),
+            Principal::self_authenticating(key.der_public_key())
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     sync::Arc,
 };
 
@@ -13,15 +13,20 @@ use tracing::debug;
 use candid_parser::parse_idl_args;
 
 use crate::{
-    canister::{Settings, recipe::Resolve},
+    canister::{
+        Settings,
+        recipe::Resolve,
+        secret::{Secret, SecretError},
+    },
     manifest::{
         ArgsFormat, LoadManifestFromPathError, PROJECT_MANIFEST, ProjectRootLocate,
-        ProjectRootLocateError,
+        ProjectRootLocateError, Variables,
         canister::{BuildSteps, SyncSteps},
         load_manifest_from_path,
     },
     network::Configuration,
     prelude::*,
+    registry::Registries,
 };
 
 pub mod agent;
@@ -30,12 +35,14 @@ pub mod context;
 pub mod directories;
 pub mod fs;
 pub mod identity;
+pub mod lockfile;
 pub mod manifest;
 pub mod network;
 pub mod package;
 pub mod parsers;
 pub mod prelude;
 pub mod project;
+pub mod registry;
 pub mod settings;
 pub mod signal;
 pub mod store_artifact;
@@ -53,6 +60,9 @@ pub enum InitArgs {
     Text { content: String, format: ArgsFormat },
     /// Raw binary bytes (from a file with `format: bin`). Used directly.
     Binary(Vec<u8>),
+    /// Text content read from a secret, which is only read at install time by
+    /// [`InitArgs::resolve_secret`].
+    Secret { secret: Secret, format: ArgsFormat },
 }
 
 #[derive(Debug, Snafu)]
@@ -65,9 +75,23 @@ pub enum InitArgsToBytesError {
 
     #[snafu(display("failed to encode Candid init args to bytes"))]
     CandidEncode { source: candid::Error },
+
+    #[snafu(display("init args from a secret must be read before they are encoded"))]
+    UnresolvedSecret,
 }
 
 impl InitArgs {
+    /// These init args with their secret, if any, read.
+    pub async fn resolve_secret(&self) -> Result<InitArgs, SecretError> {
+        match self {
+            InitArgs::Secret { secret, format } => Ok(InitArgs::Text {
+                content: secret.resolve().await?,
+                format: format.clone(),
+            }),
+            args => Ok(args.clone()),
+        }
+    }
+
     /// Resolve to raw bytes according to the format.
     pub fn to_bytes(&self) -> Result<Vec<u8>, InitArgsToBytesError> {
         match self {
@@ -82,6 +106,7 @@ impl InitArgs {
                     unreachable!("binary format cannot appear in InitArgs::Text")
                 }
             },
+            InitArgs::Secret { .. } => UnresolvedSecretSnafu.fail(),
         }
     }
 }
@@ -138,6 +163,44 @@ pub struct Canister {
     /// the resolved configuration, so it is never serialized.
     #[serde(skip)]
     pub environment_variable_files: BTreeMap<String, PathBuf>,
+
+    /// The environment variables whose value is a secret, read only when the
+    /// settings are deployed. They are kept out of `settings.environment_variables`,
+    /// so the values are never part of the loaded project.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secret_environment_variables: BTreeMap<String, Secret>,
+
+    /// The settings and init args the canister's recipe rendered. They apply
+    /// beneath the manifest's and the environment's, key by key, so they are
+    /// kept to be applied again beneath an environment override.
+    #[serde(skip)]
+    pub recipe_defaults: RecipeDefaults,
+
+    /// The recipe the canister was declared with, kept to render it again for
+    /// each environment. `None` for explicit build/sync instructions.
+    #[serde(skip)]
+    pub recipe_source: Option<RecipeSource>,
+
+    /// Which settings and init args came from the canister's recipe rather than
+    /// a manifest, e.g. `settings.log_visibility` or `init_args`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub from_recipe: Vec<String>,
+}
+
+/// Settings and init args contributed by a canister's recipe.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecipeDefaults {
+    pub settings: Settings,
+    pub init_args: Option<InitArgs>,
+}
+
+/// A canister's recipe and its fetched template.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecipeSource {
+    pub recipe: manifest::recipe::Recipe,
+    pub template: Arc<str>,
+    /// The directory of the project that declares the canister.
+    pub project_root: PathBuf,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -150,6 +213,11 @@ pub struct Network {
 pub struct Environment {
     pub name: String,
     pub network: Network,
+
+    /// The identity the manifest chooses for this environment, used when none
+    /// is selected on the command line.
+    pub identity: Option<String>,
+
     pub canisters: IndexMap<String, (PathBuf, Canister)>,
 }
 
@@ -189,12 +257,75 @@ pub struct Project {
     /// Empty for standalone projects and workspaces whose members are complete.
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub member_missing_envs: HashMap<String, Vec<String>>,
+
+    /// The registries the project's recipes are looked up in: the user's, the
+    /// project's own and the built-in one.
+    #[serde(skip)]
+    pub registries: Registries,
+
+    /// The environments as the project declares them, keyed by name, next to
+    /// what they resolve to once their `extends` chain is merged in.
+    #[serde(skip)]
+    pub declared_environments: HashMap<String, EnvironmentDeclaration>,
+
+    /// The values of the `${secret:VAR}` references in the project's manifests.
+    #[serde(skip)]
+    pub secret_values: BTreeSet<String>,
+}
+
+/// An environment as declared in the project, and as resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvironmentDeclaration {
+    pub declared: manifest::EnvironmentManifest,
+    pub resolved: manifest::EnvironmentManifest,
+}
+
+impl Canister {
+    /// The canister's environment variables, with its secrets read, or `None`
+    /// when it configures none.
+    pub async fn environment_variables(
+        &self,
+    ) -> Result<Option<HashMap<String, String>>, SecretError> {
+        if self.secret_environment_variables.is_empty() {
+            return Ok(self.settings.environment_variables.clone());
+        }
+        let mut vars = self
+            .settings
+            .environment_variables
+            .clone()
+            .unwrap_or_default();
+        for (name, secret) in &self.secret_environment_variables {
+            vars.insert(name.clone(), secret.resolve().await?);
+        }
+        Ok(Some(vars))
+    }
 }
 
 impl Project {
     pub fn get_canister(&self, canister_name: &str) -> Option<&(PathBuf, Canister)> {
         self.canisters.get(canister_name)
     }
+
+    /// The directory of the project's state that is not a cache, such as the
+    /// canister IDs of connected networks.
+    pub fn data_dir(&self) -> PathBuf {
+        self.dir.join(ICP_BASE).join(DATA_DIR)
+    }
+
+    /// Replaces the secret values in every string of `doc` with `<redacted>`.
+    pub fn redact(&self, doc: &mut serde_yaml::Value) {
+        match doc {
+            serde_yaml::Value::String(s) => {
+                for secret in &self.secret_values {
+                    *s = s.replace(secret.as_str(), "<redacted>");
+                }
+            }
+            serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(|v| self.redact(v)),
+            serde_yaml::Value::Mapping(m) => m.values_mut().for_each(|v| self.redact(v)),
+            serde_yaml::Value::Tagged(t) => self.redact(&mut t.value),
+            _ => {}
+        }
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -209,6 +340,12 @@ pub enum ProjectLoadError {
     Project {
         source: project::ConsolidateManifestError,
     },
+
+    #[snafu(display("invalid recipe registries"))]
+    Registry { source: registry::RegistryError },
+
+    #[snafu(display("failed to read the project's variables"))]
+    Variables { source: manifest::VariableError },
 }
 
 #[async_trait]
@@ -223,11 +360,21 @@ pub trait ProjectLoad: Sync + Send {
     fn member_dir(&self) -> Option<PathBuf> {
         None
     }
+
+    /// The registries recipes are looked up in: the user's, plus the project's
+    /// own when run inside one. Reads only the project manifest, so it works even
+    /// when the project's recipes do not.
+    async fn registries(&self) -> Result<Registries, ProjectLoadError> {
+        Ok(Registries::default())
+    }
 }
 
 pub struct ProjectLoadImpl {
     pub project_root_locate: Arc<dyn ProjectRootLocate>,
     pub recipe: Arc<dyn Resolve>,
+    /// Registries declared in the user settings, beneath which the project's
+    /// own are added
+    pub registries: Registries,
 }
 
 /// Ensures the "operating on a workspace root above your sub-project" notice is
@@ -271,16 +418,18 @@ impl ProjectLoad for ProjectLoadImpl {
         }
 
         // Load project manifest
-        let m = load_manifest_from_path(&pdir.join(PROJECT_MANIFEST))
+        let vars = Variables::load(&pdir).context(VariablesSnafu)?;
+        let m = load_manifest_from_path(&pdir.join(PROJECT_MANIFEST), &vars)
             .await
             .context(ProjectManifestSnafu)?;
 
         debug!("Loaded project manifest: {m:#?}");
 
         // Consolidate manifest into project
-        let p = project::consolidate_manifest(&pdir, self.recipe.as_ref(), &m)
-            .await
-            .context(ProjectSnafu)?;
+        let p =
+            project::consolidate_manifest(&pdir, self.recipe.as_ref(), &m, &self.registries, &vars)
+                .await
+                .context(ProjectSnafu)?;
 
         debug!("Rendered project definition: {p:#?}");
 
@@ -297,6 +446,21 @@ impl ProjectLoad for ProjectLoadImpl {
     fn member_dir(&self) -> Option<PathBuf> {
         self.project_root_locate.locate_member().ok()
     }
+
+    async fn registries(&self) -> Result<Registries, ProjectLoadError> {
+        let pdir = match self.project_root_locate.locate() {
+            Ok(pdir) => pdir,
+            Err(ProjectRootLocateError::NotFound { .. }) => return Ok(self.registries.clone()),
+        };
+        let vars = Variables::load(&pdir).context(VariablesSnafu)?;
+        let m: manifest::ProjectManifest =
+            load_manifest_from_path(&pdir.join(PROJECT_MANIFEST), &vars)
+                .await
+                .context(ProjectManifestSnafu)?;
+        self.registries
+            .with_project(&m.registries, &pdir)
+            .context(RegistrySnafu)
+    }
 }
 
 pub struct Lazy<T, V>(T, Arc<Mutex<Option<V>>>);
@@ -336,6 +500,13 @@ impl<T: ProjectLoad> ProjectLoad for Lazy<T, Project> {
     fn member_dir(&self) -> Option<PathBuf> {
         self.0.member_dir()
     }
+
+    async fn registries(&self) -> Result<Registries, ProjectLoadError> {
+        if let Some(v) = self.1.lock().await.as_ref() {
+            return Ok(v.registries.clone());
+        }
+        self.0.registries().await
+    }
 }
 
 #[cfg(test)]
@@ -382,6 +553,10 @@ impl MockProjectLoader {
             bindings: BTreeMap::new(),
             friendly_names: vec!["backend".to_string()],
             environment_variable_files: BTreeMap::new(),
+            secret_environment_variables: BTreeMap::new(),
+            recipe_defaults: RecipeDefaults::default(),
+            from_recipe: vec![],
+            recipe_source: None,
         };
 
         let local_network = Network {
@@ -406,6 +581,7 @@ impl MockProjectLoader {
         let default_env = Environment {
             name: "default".to_string(),
             network: local_network,
+            identity: None,
             canisters: env_canisters,
         };
 
@@ -418,6 +594,9 @@ impl MockProjectLoader {
             networks,
             environments,
             member_missing_envs: HashMap::new(),
+            registries: Registries::default(),
+            declared_environments: HashMap::new(),
+            secret_values: BTreeSet::new(),
         };
 
         Self::new(project)
@@ -469,6 +648,10 @@ impl MockProjectLoader {
             bindings: BTreeMap::new(),
             friendly_names: vec!["backend".to_string()],
             environment_variable_files: BTreeMap::new(),
+            secret_environment_variables: BTreeMap::new(),
+            recipe_defaults: RecipeDefaults::default(),
+            from_recipe: vec![],
+            recipe_source: None,
         };
 
         let frontend_canister = Canister {
@@ -488,6 +671,10 @@ impl MockProjectLoader {
             bindings: BTreeMap::new(),
             friendly_names: vec!["frontend".to_string()],
             environment_variable_files: BTreeMap::new(),
+            secret_environment_variables: BTreeMap::new(),
+            recipe_defaults: RecipeDefaults::default(),
+            from_recipe: vec![],
+            recipe_source: None,
         };
 
         let database_canister = Canister {
@@ -507,6 +694,10 @@ impl MockProjectLoader {
             bindings: BTreeMap::new(),
             friendly_names: vec!["database".to_string()],
             environment_variable_files: BTreeMap::new(),
+            secret_environment_variables: BTreeMap::new(),
+            recipe_defaults: RecipeDefaults::default(),
+            from_recipe: vec![],
+            recipe_source: None,
         };
 
         // Create networks
@@ -604,6 +795,7 @@ impl MockProjectLoader {
         let dev_env = Environment {
             name: "dev".to_string(),
             network: local_network,
+            identity: None,
             canisters: dev_canisters,
         };
 
@@ -621,6 +813,7 @@ impl MockProjectLoader {
         let test_env = Environment {
             name: "test".to_string(),
             network: staging_network,
+            identity: None,
             canisters: test_canisters,
         };
 
@@ -638,6 +831,7 @@ impl MockProjectLoader {
         let prod_env = Environment {
             name: "prod".to_string(),
             network: ic_network,
+            identity: None,
             canisters: prod_canisters,
         };
 
@@ -653,6 +847,9 @@ impl MockProjectLoader {
             networks,
             environments,
             member_missing_envs: HashMap::new(),
+            registries: Registries::default(),
+            declared_environments: HashMap::new(),
+            secret_values: BTreeSet::new(),
         };
 
         Self::new(project)
@@ -726,7 +923,11 @@ mod tests {
     impl Resolve for MockRecipeResolver {
         /// A minimal template rendering to a single dummy pre-built step. Nothing
         /// is fetched, so there is no cache write to hold back.
-        async fn resolve(&self, _recipe: &Recipe) -> Result<Fetched, ResolveError> {
+        async fn resolve(
+            &self,
+            _recipe: &Recipe,
+            _registries: &Registries,
+        ) -> Result<Fetched, ResolveError> {
             Ok(Fetched {
                 template: indoc! {r#"
                     build:
@@ -760,6 +961,7 @@ mod tests {
         // Create ProjectLoadImpl with mocks
         let loader = ProjectLoadImpl {
             project_root_locate: Arc::new(MockProjectRootLocate::new(project_dir.to_path_buf())),
+            registries: Registries::default(),
             recipe: Arc::new(MockRecipeResolver),
         };
 
@@ -820,6 +1022,7 @@ mod tests {
         // Create ProjectLoadImpl with mocks
         let loader = ProjectLoadImpl {
             project_root_locate: Arc::new(MockProjectRootLocate::new(project_dir.to_path_buf())),
+            registries: Registries::default(),
             recipe: Arc::new(MockRecipeResolver),
         };
 
@@ -853,4 +1056,239 @@ mod tests {
             "The default `ic` network was not injected"
         );
     }
+
+    /// Resolves no recipe, as an offline fetcher with an empty package cache.
+    struct NotCachedRecipeResolver;
+
+    #[async_trait]
+    impl Resolve for NotCachedRecipeResolver {
+        async fn resolve(
+            &self,
+            recipe: &Recipe,
+            _registries: &Registries,
+        ) -> Result<Fetched, ResolveError> {
+            Err(ResolveError::Fetch {
+                source: crate::canister::recipe::fetch::RecipeFetchError::NotCached {
+                    recipe: recipe.recipe_type.to_string(),
+                },
+            })
+        }
+    }
+
+    /// Offline, every recipe missing from the cache is reported at once rather
+    /// than only the first.
+    #[tokio::test]
+    async fn offline_lists_every_uncached_recipe() {
+        let temp_dir = Utf8TempDir::new().unwrap();
+        let project_dir = temp_dir.path();
+        let manifest_content = indoc! {r#"
+            canisters:
+              - name: backend
+                recipe:
+                  type: "@dfinity/rust@v3.0.0"
+              - name: frontend
+                recipe:
+                  type: "@dfinity/asset-canister@v2.1.0"
+        "#};
+        std::fs::write(project_dir.join("icp.yaml"), manifest_content).unwrap();
+
+        let loader = ProjectLoadImpl {
+            project_root_locate: Arc::new(MockProjectRootLocate::new(project_dir.to_path_buf())),
+            registries: Registries::default(),
+            recipe: Arc::new(NotCachedRecipeResolver),
+        };
+
+        let err = loader.load().await.unwrap_err();
+        let report = snafu::Report::from_error(err).to_string();
+        assert!(report.contains("@dfinity/rust@v3.0.0"), "{report}");
+        assert!(
+            report.contains("@dfinity/asset-canister@v2.1.0"),
+            "{report}"
+        );
+    }
+
+    /// A template contributing settings, an environment variable and init args
+    /// alongside its build.
+    struct DefaultsRecipeResolver;
+
+    #[async_trait]
+    impl Resolve for DefaultsRecipeResolver {
+        async fn resolve(
+            &self,
+            _recipe: &Recipe,
+            _registries: &Registries,
+        ) -> Result<Fetched, ResolveError> {
+            Ok(Fetched {
+                template: indoc! {r#"
+                    build:
+                      steps:
+                        - type: pre-built
+                          path: dummy.wasm
+                    settings:
+                      compute_allocation: 5
+                      freezing_threshold: 30d
+                      environment_variables:
+                        MODE: recipe
+                        LEVEL: info
+                    init_args: "(record {})"
+                "#}
+                .to_owned(),
+                pending_cache: None,
+            })
+        }
+    }
+
+    /// The manifest wins over a recipe's defaults, and the defaults still sit
+    /// beneath an environment's settings override.
+    #[tokio::test]
+    async fn recipe_defaults_merge_beneath_manifest_values() {
+        let temp_dir = Utf8TempDir::new().unwrap();
+        let project_dir = temp_dir.path();
+        let manifest_content = indoc! {r#"
+            canisters:
+              - name: backend
+                recipe:
+                  type: "@dfinity/rust@v3.0.0"
+                settings:
+                  compute_allocation: 10
+                  environment_variables:
+                    MODE: manifest
+            environments:
+              - name: staging
+                network: local
+                settings:
+                  backend:
+                    memory_allocation: 1mib
+        "#};
+        std::fs::write(project_dir.join("icp.yaml"), manifest_content).unwrap();
+
+        let loader = ProjectLoadImpl {
+            project_root_locate: Arc::new(MockProjectRootLocate::new(project_dir.to_path_buf())),
+            registries: Registries::default(),
+            recipe: Arc::new(DefaultsRecipeResolver),
+        };
+        let project = loader.load().await.unwrap();
+
+        let (_, canister) = project.get_canister("backend").unwrap();
+        assert_eq!(canister.settings.compute_allocation, Some(10));
+        assert_eq!(
+            canister
+                .settings
+                .freezing_threshold
+                .as_ref()
+                .map(|t| t.get()),
+            Some(2_592_000)
+        );
+        let vars = canister.settings.environment_variables.as_ref().unwrap();
+        assert_eq!(vars["MODE"], "manifest");
+        assert_eq!(vars["LEVEL"], "info");
+        assert!(canister.init_args.is_some());
+        assert_eq!(
+            canister.from_recipe,
+            vec![
+                "init_args",
+                "settings.environment_variables.LEVEL",
+                "settings.freezing_threshold",
+            ]
+        );
+
+        let env = project.environments.get("staging").unwrap();
+        let (_, canister) = env.get_canister_info("backend").unwrap();
+        assert_eq!(
+            canister
+                .settings
+                .memory_allocation
+                .as_ref()
+                .map(|m| m.get()),
+            Some(1 << 20)
+        );
+        assert_eq!(canister.settings.compute_allocation, Some(5));
+        assert_eq!(
+            canister
+                .settings
+                .freezing_threshold
+                .as_ref()
+                .map(|t| t.get()),
+            Some(2_592_000)
+        );
+        assert!(
+            canister
+                .from_recipe
+                .contains(&"settings.compute_allocation".to_string())
+        );
+    }
+
+    /// A template whose steps and defaults depend on the environment.
+    struct EnvironmentRecipeResolver;
+
+    #[async_trait]
+    impl Resolve for EnvironmentRecipeResolver {
+        async fn resolve(
+            &self,
+            _recipe: &Recipe,
+            _registries: &Registries,
+        ) -> Result<Fetched, ResolveError> {
+            Ok(Fetched {
+                template: indoc! {r#"
+                    build:
+                      steps:
+                        - type: script
+                          command: "build {{_.environment.name}} {{_.network.mode}}"
+                    {{#if (eq _.environment.name "ic")}}
+                    settings:
+                      compute_allocation: 20
+                    {{/if}}
+                "#}
+                .to_owned(),
+                pending_cache: None,
+            })
+        }
+    }
+
+    fn script_command(canister: &Canister) -> String {
+        match &canister.build.steps[0] {
+            crate::manifest::canister::BuildStep::Script(adapter) => {
+                adapter.command.as_vec()[0].clone()
+            }
+            other => panic!("expected a script build step, got {other:?}"),
+        }
+    }
+
+    /// Recipes are rendered again for every environment, with its name and
+    /// network, replacing the defaults rendered outside any environment.
+    #[tokio::test]
+    async fn recipes_render_per_environment() {
+        let temp_dir = Utf8TempDir::new().unwrap();
+        let project_dir = temp_dir.path();
+        let manifest_content = indoc! {r#"
+            canisters:
+              - name: backend
+                recipe:
+                  type: "@dfinity/rust@v3.0.0"
+        "#};
+        std::fs::write(project_dir.join("icp.yaml"), manifest_content).unwrap();
+
+        let loader = ProjectLoadImpl {
+            project_root_locate: Arc::new(MockProjectRootLocate::new(project_dir.to_path_buf())),
+            registries: Registries::default(),
+            recipe: Arc::new(EnvironmentRecipeResolver),
+        };
+        let project = loader.load().await.unwrap();
+
+        let (_, canister) = project.get_canister("backend").unwrap();
+        assert_eq!(script_command(canister), "build  ");
+        assert_eq!(canister.settings.compute_allocation, None);
+
+        let local = project.environments.get("local").unwrap();
+        let (_, canister) = local.get_canister_info("backend").unwrap();
+        assert_eq!(script_command(&canister), "build local managed");
+        assert_eq!(canister.settings.compute_allocation, None);
+        assert!(canister.from_recipe.is_empty());
+
+        let ic = project.environments.get("ic").unwrap();
+        let (_, canister) = ic.get_canister_info("backend").unwrap();
+        assert_eq!(script_command(&canister), "build ic connected");
+        assert_eq!(canister.settings.compute_allocation, Some(20));
+        assert_eq!(canister.from_recipe, vec!["settings.compute_allocation"]);
+    }
 }
@@ -0,0 +1,466 @@
+//! The project lock file, `icp.lock`.
+//!
+//! Several things a manifest references float: a recipe or prebuilt wasm URL
+//! without a `sha256` serves whatever is currently behind it, a registry
+//! version is only a label for what the registry serves under it, and a managed
+//! network without a launcher `version` runs the latest launcher. The first time
+//! a project resolves one of them, what it got is pinned in `icp.lock` next to
+//! `icp.yaml` — a sha256, or for a launcher the concrete version — and every
+//! later resolution uses and verifies that pin, so two checkouts of the same
+//! commit deploy the same thing. The file is meant to be committed alongside the
+//! manifest.
+//!
+//! A registry recipe given a version requirement such as `@dfinity/rust@^3` is
+//! pinned to the version it resolved to as well, and that version's template to
+//! its sha256.
+//!
+//! With `--locked`, any change the lock file would need is an error instead, and
+//! `icp project update-lock` re-resolves every floating reference from scratch.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+
+use crate::{
+    fs::json,
+    manifest::{ProjectRootLocate, ProjectRootLocateError, RegistryRef},
+    prelude::*,
+};
+
+pub const LOCKFILE: &str = "icp.lock";
+
+/// Contents of `icp.lock`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Lockfile {
+    /// Schema version for forwards compatibility.
+    pub v: u32,
+
+    /// Pinned sha256 of each remote recipe template without a `sha256` in the
+    /// manifest, keyed by its recipe `type` (a URL or registry reference).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub recipes: BTreeMap<String, String>,
+
+    /// Resolved version of each registry recipe given a version requirement,
+    /// keyed by its `@<registry>/<name>@<requirement>` reference.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub recipe_versions: BTreeMap<String, String>,
+
+    /// Pinned sha256 of each prebuilt wasm URL without a `sha256` in the manifest.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub wasms: BTreeMap<String, String>,
+
+    /// Pinned sha256 of each registry plugin, keyed by its
+    /// `@<registry>/<name>@<version>` reference.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, String>,
+
+    /// Launcher version of each managed network without a `version` in the
+    /// manifest, keyed by network name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub launchers: BTreeMap<String, String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            v: 1,
+            recipes: BTreeMap::new(),
+            recipe_versions: BTreeMap::new(),
+            wasms: BTreeMap::new(),
+            plugins: BTreeMap::new(),
+            launchers: BTreeMap::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// The pinned sha256 of a remote recipe template.
+    pub fn recipe(&self, recipe_type: &str) -> Option<&str> {
+        self.recipes.get(recipe_type).map(String::as_str)
+    }
+
+    /// Pin a remote recipe template to `sha256`.
+    pub fn pin_recipe(&mut self, recipe_type: &str, sha256: &str) {
+        self.recipes
+            .insert(recipe_type.to_owned(), sha256.to_owned());
+    }
+
+    /// The version a registry recipe's version requirement resolved to.
+    pub fn recipe_version(&self, reference: &str) -> Option<&str> {
+        self.recipe_versions.get(reference).map(String::as_str)
+    }
+
+    /// Pin a registry recipe's version requirement to `version`.
+    pub fn pin_recipe_version(&mut self, reference: &str, version: &str) {
+        self.recipe_versions
+            .insert(reference.to_owned(), version.to_owned());
+    }
+
+    /// The pinned sha256 of a prebuilt wasm URL.
+    pub fn wasm(&self, url: &str) -> Option<&str> {
+        self.wasms.get(url).map(String::as_str)
+    }
+
+    /// Pin a prebuilt wasm URL to `sha256`.
+    pub fn pin_wasm(&mut self, url: &str, sha256: &str) {
+        self.wasms.insert(url.to_owned(), sha256.to_owned());
+    }
+
+    /// The pinned sha256 of a registry plugin, if it has been resolved before.
+    pub fn plugin(&self, reference: &RegistryRef) -> Option<&str> {
+        self.plugins.get(&reference.to_string()).map(String::as_str)
+    }
+
+    /// Pin a registry plugin to `sha256`.
+    pub fn pin_plugin(&mut self, reference: &RegistryRef, sha256: &str) {
+        self.plugins
+            .insert(reference.to_string(), sha256.to_owned());
+    }
+
+    /// The pinned launcher version of a managed network.
+    pub fn launcher(&self, network: &str) -> Option<&str> {
+        self.launchers.get(network).map(String::as_str)
+    }
+
+    /// Pin the launcher of a managed network to `version`.
+    pub fn pin_launcher(&mut self, network: &str, version: &str) {
+        self.launchers
+            .insert(network.to_owned(), version.to_owned());
+    }
+
+    /// Every pin that differs between `self` and `new`.
+    pub fn changes(&self, new: &Lockfile) -> Vec<LockChange> {
+        let sections = [
+            ("recipe", &self.recipes, &new.recipes),
+            (
+                "recipe version",
+                &self.recipe_versions,
+                &new.recipe_versions,
+            ),
+            ("wasm", &self.wasms, &new.wasms),
+            ("plugin", &self.plugins, &new.plugins),
+            ("launcher", &self.launchers, &new.launchers),
+        ];
+        let mut changes = Vec::new();
+        for (kind, old, new) in sections {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let (old, new) = (old.get(key), new.get(key));
+                if old != new {
+                    changes.push(LockChange {
+                        kind,
+                        key: key.clone(),
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    });
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// One pin added, changed or removed between two lock files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockChange {
+    /// `recipe`, `recipe version`, `wasm`, `plugin` or `launcher`.
+    pub kind: &'static str,
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl fmt::Display for LockChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let old = self.old.as_deref().unwrap_or("unpinned");
+        let new = self.new.as_deref().unwrap_or("removed");
+        write!(f, "{} {}: {old} -> {new}", self.kind, self.key)
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum LockfileError {
+    #[snafu(transparent)]
+    ProjectRootLocate { source: ProjectRootLocateError },
+
+    #[snafu(display("failed to load lock file"))]
+    Load { source: json::Error },
+
+    #[snafu(display("file `{path}` was modified by an incompatible new version of icp-cli"))]
+    BadVersion { path: PathBuf },
+
+    #[snafu(display("failed to save lock file"))]
+    Save { source: json::Error },
+
+    #[snafu(display(
+        "{LOCKFILE} needs to be updated but --locked was passed:\n{}",
+        changes.iter().map(|c| format!("  {c}")).collect::<Vec<_>>().join("\n")
+    ))]
+    Locked { changes: Vec<LockChange> },
+}
+
+/// Access to the project's lock file.
+pub trait Access: Sync + Send {
+    /// Read the lock file. A project without one reads as an empty lock file.
+    fn load(&self) -> Result<Lockfile, LockfileError>;
+
+    /// Apply `f` to the lock file and write it back. The file is left untouched
+    /// when `f` makes no change, so resolving already-pinned references never
+    /// creates or rewrites it. In locked mode a change is an error.
+    fn update(&self, f: &mut dyn FnMut(&mut Lockfile)) -> Result<(), LockfileError>;
+
+    /// Start re-resolving the lock file from scratch. Until
+    /// [`commit_refresh`](Self::commit_refresh), [`load`](Self::load) sees only
+    /// the pins recorded since, and [`update`](Self::update) records pins in
+    /// memory without writing them.
+    fn begin_refresh(&self);
+
+    /// Whether a refresh is in progress: resolvers should fetch floating
+    /// references anew rather than reuse what they cached for them.
+    fn refreshing(&self) -> bool;
+
+    /// Replace the lock file with the pins recorded since
+    /// [`begin_refresh`](Self::begin_refresh), returning the changes made.
+    fn commit_refresh(&self) -> Result<Vec<LockChange>, LockfileError>;
+}
+
+/// The lock file at `{project_root}/icp.lock`.
+pub(crate) struct AccessImpl {
+    project_root_locate: Arc<dyn ProjectRootLocate>,
+
+    /// Whether changes to the lock file are refused (`--locked`).
+    locked: bool,
+
+    /// Pins recorded since [`Access::begin_refresh`], while a refresh is in
+    /// progress. The mutex also serializes access to the file.
+    refreshed: Mutex<Option<Lockfile>>,
+}
+
+impl AccessImpl {
+    pub(crate) fn new(project_root_locate: Arc<dyn ProjectRootLocate>, locked: bool) -> Self {
+        Self {
+            project_root_locate,
+            locked,
+            refreshed: Mutex::new(None),
+        }
+    }
+
+    fn path(&self) -> Result<PathBuf, ProjectRootLocateError> {
+        Ok(self.project_root_locate.locate()?.join(LOCKFILE))
+    }
+
+    /// Write `new` over `old`, unless nothing changed.
+    fn save(&self, path: &Path, old: &Lockfile, new: &Lockfile) -> Result<(), LockfileError> {
+        let changes = old.changes(new);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        ensure!(!self.locked, LockedSnafu { changes });
+        json::save(path, new).context(SaveSnafu)
+    }
+}
+
+fn load_from(path: &Path) -> Result<Lockfile, LockfileError> {
+    let lockfile: Lockfile = json::load_or_default(path).context(LoadSnafu)?;
+    ensure!(lockfile.v == 1, BadVersionSnafu { path });
+    Ok(lockfile)
+}
+
+impl Access for AccessImpl {
+    fn load(&self) -> Result<Lockfile, LockfileError> {
+        let refreshed = self
+            .refreshed
+            .lock()
+            .expect("failed to acquire lock file lock");
+        match &*refreshed {
+            Some(lockfile) => Ok(lockfile.clone()),
+            None => load_from(&self.path()?),
+        }
+    }
+
+    fn update(&self, f: &mut dyn FnMut(&mut Lockfile)) -> Result<(), LockfileError> {
+        let mut refreshed = self
+            .refreshed
+            .lock()
+            .expect("failed to acquire lock file lock");
+        if let Some(lockfile) = &mut *refreshed {
+            f(lockfile);
+            return Ok(());
+        }
+        let path = self.path()?;
+        let before = load_from(&path)?;
+        let mut after = before.clone();
+        f(&mut after);
+        self.save(&path, &before, &after)
+    }
+
+    fn begin_refresh(&self) {
+        *self
+            .refreshed
+            .lock()
+            .expect("failed to acquire lock file lock") = Some(Lockfile::default());
+    }
+
+    fn refreshing(&self) -> bool {
+        self.refreshed
+            .lock()
+            .expect("failed to acquire lock file lock")
+            .is_some()
+    }
+
+    fn commit_refresh(&self) -> Result<Vec<LockChange>, LockfileError> {
+        let mut refreshed = self
+            .refreshed
+            .lock()
+            .expect("failed to acquire lock file lock");
+        let after = refreshed.take().unwrap_or_default();
+        let path = self.path()?;
+        let before = load_from(&path)?;
+        self.save(&path, &before, &after)?;
+        Ok(before.changes(&after))
+    }
+}
+
+/// A lock file that lives in memory only, for resolving references outside of
+/// a project, such as a recipe named on the command line. Pins are recorded for
+/// the lifetime of the value and never written.
+#[derive(Default)]
+pub struct Detached {
+    lockfile: Mutex<Lockfile>,
+}
+
+impl Access for Detached {
+    fn load(&self) -> Result<Lockfile, LockfileError> {
+        Ok(self.lockfile.lock().unwrap().clone())
+    }
+
+    fn update(&self, f: &mut dyn FnMut(&mut Lockfile)) -> Result<(), LockfileError> {
+        f(&mut self.lockfile.lock().unwrap());
+        Ok(())
+    }
+
+    fn begin_refresh(&self) {}
+
+    fn refreshing(&self) -> bool {
+        false
+    }
+
+    fn commit_refresh(&self) -> Result<Vec<LockChange>, LockfileError> {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    /// In-memory mock implementation of `Access`.
+    pub(crate) type MockInMemoryLockfile = super::Detached;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedRoot(PathBuf);
+
+    impl ProjectRootLocate for FixedRoot {
+        fn locate(&self) -> Result<PathBuf, ProjectRootLocateError> {
+            Ok(self.0.clone())
+        }
+
+        fn locate_member(&self) -> Result<PathBuf, ProjectRootLocateError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn access(root: &Path, locked: bool) -> AccessImpl {
+        AccessImpl::new(Arc::new(FixedRoot(root.to_owned())), locked)
+    }
+
+    #[test]
+    fn pins_are_persisted_and_unchanged_updates_do_not_write() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let access = access(tmp.path(), false);
+        let reference: RegistryRef = "@dfinity/asset-sync@v1.0.0".parse().unwrap();
+
+        // Nothing pinned and no change: no file is created
+        access.update(&mut |_| {}).unwrap();
+        assert!(!tmp.path().join(LOCKFILE).exists());
+        assert_eq!(access.load().unwrap().plugin(&reference), None);
+
+        access
+            .update(&mut |l| l.pin_plugin(&reference, "abc123"))
+            .unwrap();
+        assert!(tmp.path().join(LOCKFILE).exists());
+        assert_eq!(access.load().unwrap().plugin(&reference), Some("abc123"));
+    }
+
+    #[test]
+    fn locked_refuses_changes_but_not_resolving_existing_pins() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        access(tmp.path(), false)
+            .update(&mut |l| l.pin_wasm("https://example.com/a.wasm", "aaa"))
+            .unwrap();
+
+        let locked = access(tmp.path(), true);
+        locked
+            .update(&mut |l| l.pin_wasm("https://example.com/a.wasm", "aaa"))
+            .expect("re-pinning to the same hash is not a change");
+
+        let err = locked
+            .update(&mut |l| l.pin_launcher("local", "v0.3.0"))
+            .unwrap_err();
+        assert!(
+            matches!(&err, LockfileError::Locked { changes } if changes.len() == 1),
+            "unexpected error: {err}"
+        );
+        assert!(
+            err.to_string()
+                .contains("launcher local: unpinned -> v0.3.0"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn refresh_replaces_the_lock_file_with_what_was_resolved() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let access = access(tmp.path(), false);
+        access
+            .update(&mut |l| {
+                l.pin_recipe("https://example.com/r.hbs", "old");
+                l.pin_wasm("https://example.com/gone.wasm", "gone");
+            })
+            .unwrap();
+
+        access.begin_refresh();
+        assert!(access.refreshing());
+        assert_eq!(access.load().unwrap(), Lockfile::default());
+        access
+            .update(&mut |l| l.pin_recipe("https://example.com/r.hbs", "new"))
+            .unwrap();
+        // Nothing is written until the refresh is committed
+        assert_eq!(
+            load_from(&tmp.path().join(LOCKFILE))
+                .unwrap()
+                .recipe("https://example.com/r.hbs"),
+            Some("old")
+        );
+
+        let changes = access.commit_refresh().unwrap();
+        assert!(!access.refreshing());
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "recipe https://example.com/r.hbs: old -> new",
+                "wasm https://example.com/gone.wasm: gone -> removed",
+            ]
+        );
+        let lockfile = access.load().unwrap();
+        assert_eq!(lockfile.recipe("https://example.com/r.hbs"), Some("new"));
+        assert_eq!(lockfile.wasm("https://example.com/gone.wasm"), None);
+    }
+}
@@ -1,7 +1,27 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize};
 
-use super::prebuilt::SourceField;
+use super::prebuilt::{LocalSource, RemoteSource};
+use crate::manifest::RegistryRef;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, JsonSchema, Serialize)]
+pub struct RegistrySource {
+    /// Plugin hosted in a known registry, in the form `@<registry>/<name>@<version>`
+    pub registry: RegistryRef,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, JsonSchema, Serialize)]
+#[serde(untagged, rename_all = "lowercase")]
+pub enum PluginSource {
+    /// Local path on-disk to read the plugin wasm from
+    Local(LocalSource),
+
+    /// Remote url to fetch the plugin wasm from
+    Remote(RemoteSource),
+
+    /// Plugin published to a registry
+    Registry(RegistrySource),
+}
 
 /// Configuration for a sync plugin step.
 ///
@@ -28,13 +48,19 @@ use super::prebuilt::SourceField;
 ///   url: https://example.com/plugins/populate-data.wasm
 ///   sha256: e3b0c44298fc1c149afb...   # required for url
 /// ```
+///
+/// Example (registry — the sha256 is pinned in `icp.lock` on first use):
+/// ```yaml
+/// - type: plugin
+///   registry: "@dfinity/populate-data@v1.0.0"
+/// ```
 #[derive(Clone, Debug, PartialEq, JsonSchema, Serialize)]
 pub struct Adapter {
     #[serde(flatten)]
-    pub source: SourceField,
+    pub source: PluginSource,
 
     /// Optional sha256 checksum of the wasm file.
-    /// Optional for `path`; required for `url`.
+    /// Optional for `path` and `registry`; required for `url`.
     pub sha256: Option<String>,
 
     /// Directories (relative to canister directory) the plugin may read from.
@@ -52,14 +78,14 @@ impl<'de> Deserialize<'de> for Adapter {
         #[derive(Deserialize)]
         struct AdapterHelper {
             #[serde(flatten)]
-            source: SourceField,
+            source: PluginSource,
             sha256: Option<String>,
             dirs: Option<Vec<String>>,
             files: Option<Vec<String>>,
         }
 
         let h = AdapterHelper::deserialize(d)?;
-        if matches!(h.source, SourceField::Remote(_)) && h.sha256.is_none() {
+        if matches!(h.source, PluginSource::Remote(_)) && h.sha256.is_none() {
             return Err(serde::de::Error::custom(
                 "plugin with `url` requires `sha256` for integrity verification",
             ));
@@ -76,7 +102,6 @@ impl<'de> Deserialize<'de> for Adapter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::manifest::adapter::prebuilt::{LocalSource, RemoteSource};
 
     #[test]
     fn local_path() {
@@ -88,7 +113,7 @@ mod tests {
             )
             .expect("failed to deserialize Adapter from yaml"),
             Adapter {
-                source: SourceField::Local(LocalSource {
+                source: PluginSource::Local(LocalSource {
                     path: "plugins/my-sync.wasm".into(),
                 }),
                 sha256: None,
@@ -114,7 +139,7 @@ mod tests {
             )
             .expect("failed to deserialize Adapter from yaml"),
             Adapter {
-                source: SourceField::Local(LocalSource {
+                source: PluginSource::Local(LocalSource {
                     path: "plugins/my-sync.wasm".into(),
                 }),
                 sha256: Some("abc123".to_string()),
@@ -150,7 +175,7 @@ mod tests {
             )
             .expect("failed to deserialize Adapter from yaml"),
             Adapter {
-                source: SourceField::Remote(RemoteSource {
+                source: PluginSource::Remote(RemoteSource {
                     url: "https://example.com/plugins/migrate-v2.wasm".to_string(),
                 }),
                 sha256: Some("a665a45920422f9d417e".to_string()),
@@ -159,4 +184,30 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn registry_without_sha256() {
+        assert_eq!(
+            serde_yaml::from_str::<Adapter>(
+                r#"
+                registry: "@dfinity/populate-data@v1.0.0"
+                dirs:
+                  - assets/seed-data
+                "#
+            )
+            .expect("failed to deserialize Adapter from yaml"),
+            Adapter {
+                source: PluginSource::Registry(RegistrySource {
+                    registry: RegistryRef {
+                        registry: "dfinity".to_string(),
+                        name: "populate-data".to_string(),
+                        version: "v1.0.0".to_string(),
+                    },
+                }),
+                sha256: None,
+                dirs: Some(vec!["assets/seed-data".to_string()]),
+                files: None,
+            },
+        );
+    }
 }
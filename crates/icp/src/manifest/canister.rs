@@ -3,7 +3,7 @@ use std::fmt;
 use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::canister::ManifestSettings;
+use crate::canister::{ManifestSettings, secret::SecretSource};
 
 use super::{adapter, recipe::Recipe, serde_helpers::non_empty_vec};
 
@@ -39,6 +39,11 @@ pub enum ArgsFormat {
 ///   value: "(42)"
 ///   format: candid
 /// ```
+/// ```yaml
+/// init_args:
+///   command: pass show backend-init-args
+///   format: candid
+/// ```
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ManifestInitArgs {
@@ -56,6 +61,13 @@ pub enum ManifestInitArgs {
         #[serde(default)]
         format: ArgsFormat,
     },
+    /// A secret, read when the canister is installed, with explicit format.
+    Secret {
+        #[serde(flatten)]
+        source: SecretSource,
+        #[serde(default)]
+        format: ArgsFormat,
+    },
 }
 
 /// Represents the manifest describing a single canister.
@@ -358,8 +370,11 @@ impl fmt::Display for SyncStep {
                 SyncStep::Script(v) => format!("script {v}"),
                 SyncStep::Plugin(v) => {
                     let src = match &v.source {
-                        adapter::prebuilt::SourceField::Local(l) => format!("path: {}", l.path),
-                        adapter::prebuilt::SourceField::Remote(r) => format!("url: {}", r.url),
+                        adapter::plugin::PluginSource::Local(l) => format!("path: {}", l.path),
+                        adapter::plugin::PluginSource::Remote(r) => format!("url: {}", r.url),
+                        adapter::plugin::PluginSource::Registry(r) => {
+                            format!("registry: {}", r.registry)
+                        }
                     };
                     format!("plugin {src}")
                 }
@@ -383,6 +398,7 @@ mod tests {
     use crate::{
         manifest::{
             adapter::{
+                plugin,
                 prebuilt::{self, RemoteSource, SourceField},
                 script,
             },
@@ -787,7 +803,7 @@ mod tests {
                     sync: Some(SyncSteps {
                         steps: vec![SyncStep::Plugin(
                             crate::manifest::adapter::plugin::Adapter {
-                                source: prebuilt::SourceField::Local(prebuilt::LocalSource {
+                                source: plugin::PluginSource::Local(prebuilt::LocalSource {
                                     path: "./plugins/my-sync.wasm".into(),
                                 }),
                                 sha256: None,
@@ -828,7 +844,7 @@ mod tests {
                     },
                     sync: Some(SyncSteps {
                         steps: vec![SyncStep::Plugin(crate::manifest::adapter::plugin::Adapter {
-                            source: prebuilt::SourceField::Remote(prebuilt::RemoteSource {
+                            source: plugin::PluginSource::Remote(prebuilt::RemoteSource {
                                 url: "https://example.com/plugins/migrate-v2.wasm".to_string(),
                             }),
                             sha256: Some(
@@ -962,6 +978,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn manifest_init_args_secret() {
+        let ia: ManifestInitArgs = serde_yaml::from_str(indoc! {r#"
+            command: pass show backend-init-args
+            format: hex
+        "#})
+        .unwrap();
+        assert_eq!(
+            ia,
+            ManifestInitArgs::Secret {
+                source: SecretSource::Command {
+                    command: "pass show backend-init-args".to_string(),
+                },
+                format: ArgsFormat::Hex,
+            }
+        );
+    }
+
     #[test]
     fn manifest_init_args_inline_string() {
         let ia: ManifestInitArgs = serde_yaml::from_str(r#""(42)""#).unwrap();
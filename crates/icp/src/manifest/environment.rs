@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::{canister::ManifestSettings, prelude::LOCAL};
+use crate::canister::ManifestSettings;
 
 use super::canister::ManifestInitArgs;
 
@@ -11,8 +11,12 @@ use super::canister::ManifestInitArgs;
 pub struct EnvironmentInner {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub network: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub canisters: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub settings: Option<HashMap<String, ManifestSettings>>,
@@ -40,13 +44,25 @@ pub struct EnvironmentManifest {
     // The environment name
     pub name: String,
 
+    /// An environment this one is based on. Its network, canister selection,
+    /// settings and init args apply wherever this environment does not set its own;
+    /// settings are merged field by field.
+    pub extends: Option<String>,
+
     /// The target network for canister deployment.
-    /// Defaults to the `local` network if not specified
-    #[schemars(with = "Option<String>")]
-    pub network: String,
+    /// Defaults to the network of the environment this one `extends`, or else to
+    /// the `local` network
+    pub network: Option<String>,
+
+    /// The identity to deploy and call canisters with in this environment,
+    /// unless `--identity` is given.
+    /// Defaults to the identity of the environment this one `extends`, or else
+    /// to the project's `identity`
+    pub identity: Option<String>,
 
     /// An optional list of the canisters to be included in this environments.
-    /// Defaults to all the canisters if not specified.
+    /// Defaults to the canisters of the environment this one `extends`, or else
+    /// to all the canisters.
     #[schemars(with = "Option<Vec<String>>")]
     pub canisters: CanisterSelection,
 
@@ -61,15 +77,14 @@ impl From<EnvironmentInner> for EnvironmentManifest {
     fn from(v: EnvironmentInner) -> Self {
         let EnvironmentInner {
             name,
+            extends,
             network,
+            identity,
             canisters,
             settings,
             init_args,
         } = v;
 
-        // Network
-        let network = network.unwrap_or(LOCAL.to_string());
-
         // Canisters
         let canisters = match canisters {
             // If the caller provided a list of canisters
@@ -87,7 +102,9 @@ impl From<EnvironmentInner> for EnvironmentManifest {
 
         Self {
             name,
+            extends,
             network,
+            identity,
             canisters,
 
             // Keep as-is, setting overrides is optional
@@ -97,6 +114,50 @@ impl From<EnvironmentInner> for EnvironmentManifest {
     }
 }
 
+impl EnvironmentManifest {
+    /// This environment with what it leaves unset taken from `base`, the
+    /// environment it extends: the network, the identity, the canister
+    /// selection, each canister's settings field by field, and each canister's
+    /// init args.
+    pub fn extend(&self, base: &EnvironmentManifest) -> EnvironmentManifest {
+        let settings = match (&self.settings, &base.settings) {
+            (Some(own), Some(base)) => {
+                let mut merged = base.clone();
+                for (canister, s) in own {
+                    let s = match base.get(canister) {
+                        Some(b) => s.clone().with_base(b),
+                        None => s.clone(),
+                    };
+                    merged.insert(canister.clone(), s);
+                }
+                Some(merged)
+            }
+            (own, base) => own.clone().or_else(|| base.clone()),
+        };
+        let init_args = match (&self.init_args, &base.init_args) {
+            (Some(own), Some(base)) => {
+                let mut merged = base.clone();
+                merged.extend(own.clone());
+                Some(merged)
+            }
+            (own, base) => own.clone().or_else(|| base.clone()),
+        };
+
+        EnvironmentManifest {
+            name: self.name.clone(),
+            extends: None,
+            network: self.network.clone().or_else(|| base.network.clone()),
+            identity: self.identity.clone().or_else(|| base.identity.clone()),
+            canisters: match &self.canisters {
+                CanisterSelection::Everything => base.canisters.clone(),
+                selection => selection.clone(),
+            },
+            settings,
+            init_args,
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for EnvironmentManifest {
     fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
         let inner: EnvironmentInner = Deserialize::deserialize(d)?;
@@ -106,12 +167,6 @@ impl<'de> Deserialize<'de> for EnvironmentManifest {
 
 impl From<&EnvironmentManifest> for EnvironmentInner {
     fn from(env: &EnvironmentManifest) -> Self {
-        let network = if env.network == LOCAL {
-            None
-        } else {
-            Some(env.network.clone())
-        };
-
         let canisters = match &env.canisters {
             CanisterSelection::Everything => None,
             CanisterSelection::Named(names) => Some(names.clone()),
@@ -120,7 +175,9 @@ impl From<&EnvironmentManifest> for EnvironmentInner {
 
         EnvironmentInner {
             name: env.name.clone(),
-            network,
+            extends: env.extends.clone(),
+            network: env.network.clone(),
+            identity: env.identity.clone(),
             canisters,
             settings: env.settings.clone(),
             init_args: env.init_args.clone(),
@@ -149,11 +206,93 @@ mod tests {
             .expect("failed to deserialize EnvironmentManifest from yaml"),
             EnvironmentManifest {
                 name: "my-environment".to_string(),
-                network: "local".to_string(),
+                extends: None,
+                network: None,
+                identity: None,
                 canisters: CanisterSelection::Everything,
                 settings: None,
                 init_args: None,
             },
         );
     }
+
+    #[test]
+    fn extend_merges_beneath_the_base() {
+        let parse = |yaml: &str| serde_yaml::from_str::<EnvironmentManifest>(yaml).unwrap();
+        let base = parse(indoc::indoc! {r#"
+            name: prod
+            network: ic
+            identity: deployer
+            canisters: [backend, frontend]
+            settings:
+              backend:
+                compute_allocation: 5
+                environment_variables:
+                  LOG: info
+                  REGION: us
+              frontend:
+                memory_allocation: 1gib
+            init_args:
+              backend: "(record { mode = \"prod\" })"
+        "#});
+        let child = parse(indoc::indoc! {r#"
+            name: prod-eu
+            extends: prod
+            settings:
+              backend:
+                freezing_threshold: 30d
+                environment_variables:
+                  REGION: eu
+        "#});
+
+        let resolved = child.extend(&base);
+        assert_eq!(resolved.name, "prod-eu");
+        assert_eq!(resolved.extends, None);
+        assert_eq!(resolved.network.as_deref(), Some("ic"));
+        assert_eq!(resolved.identity.as_deref(), Some("deployer"));
+        assert_eq!(
+            resolved.canisters,
+            CanisterSelection::Named(vec!["backend".to_string(), "frontend".to_string()])
+        );
+        assert_eq!(resolved.init_args, base.init_args);
+
+        let settings = resolved.settings.unwrap();
+        let backend = &settings["backend"];
+        assert_eq!(backend.compute_allocation, Some(5));
+        assert!(backend.freezing_threshold.is_some());
+        let vars = backend.environment_variables.as_ref().unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars["REGION"], value("eu"));
+        assert_eq!(vars["LOG"], value("info"));
+        assert!(settings["frontend"].memory_allocation.is_some());
+    }
+
+    fn value(v: &str) -> crate::canister::ManifestEnvVar {
+        crate::canister::ManifestEnvVar::Value(v.to_string())
+    }
+
+    #[test]
+    fn own_canister_selection_wins() {
+        let base = EnvironmentManifest {
+            name: "base".to_string(),
+            extends: None,
+            network: Some("ic".to_string()),
+            identity: None,
+            canisters: CanisterSelection::Named(vec!["a".to_string()]),
+            settings: None,
+            init_args: None,
+        };
+        let child = EnvironmentManifest {
+            name: "child".to_string(),
+            extends: Some("base".to_string()),
+            network: Some("staging".to_string()),
+            identity: None,
+            canisters: CanisterSelection::None,
+            settings: None,
+            init_args: None,
+        };
+        let resolved = child.extend(&base);
+        assert_eq!(resolved.network.as_deref(), Some("staging"));
+        assert_eq!(resolved.canisters, CanisterSelection::None);
+    }
 }
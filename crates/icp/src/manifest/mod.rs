@@ -15,7 +15,9 @@ pub(crate) mod environment;
 pub(crate) mod network;
 pub(crate) mod project;
 pub(crate) mod recipe;
+pub(crate) mod registry;
 pub(crate) mod serde_helpers;
+pub mod variables;
 
 pub use {
     adapter::plugin,
@@ -25,9 +27,12 @@ pub use {
         SyncStep, SyncSteps,
     },
     dependency::DependencyManifest,
-    environment::EnvironmentManifest,
+    environment::{CanisterSelection, EnvironmentManifest},
     network::{ManagedMode, Mode, NetworkManifest},
     project::ProjectManifest,
+    recipe::{Recipe, RecipeType},
+    registry::{RegistryManifest, RegistryRef},
+    variables::{VariableError, Variables},
 };
 
 pub const PROJECT_MANIFEST: &str = "icp.yaml";
@@ -288,15 +293,78 @@ pub enum LoadManifestFromPathError {
         source: serde_yaml::Error,
         path: PathBuf,
     },
+
+    #[snafu(display("failed to resolve variables in manifest at '{path}'"))]
+    Interpolate {
+        source: VariableError,
+        path: PathBuf,
+    },
 }
 
-/// Loads a manifest of type `T` from the specified file path.
-pub async fn load_manifest_from_path<T>(path: &Path) -> Result<T, LoadManifestFromPathError>
-where
-    T: for<'de> Deserialize<'de>,
-{
+/// A manifest document, whose `${VAR}` references are replaced before it is
+/// deserialized.
+pub trait Manifest: for<'de> Deserialize<'de> {
+    /// Replaces the references in `doc`.
+    fn interpolate(doc: &mut serde_yaml::Value, vars: &Variables) -> Result<(), VariableError> {
+        vars.interpolate(doc)
+    }
+}
+
+impl Manifest for CanisterManifest {}
+
+impl Manifest for NetworkManifest {}
+
+/// An environment's definition also sees the variables of its `.env.<name>` file.
+impl Manifest for EnvironmentManifest {
+    fn interpolate(doc: &mut serde_yaml::Value, vars: &Variables) -> Result<(), VariableError> {
+        match doc.get("name").and_then(serde_yaml::Value::as_str) {
+            Some(name) => vars.for_environment(name)?.interpolate(doc),
+            None => vars.interpolate(doc),
+        }
+    }
+}
+
+impl Manifest for ProjectManifest {
+    fn interpolate(doc: &mut serde_yaml::Value, vars: &Variables) -> Result<(), VariableError> {
+        let serde_yaml::Value::Mapping(m) = doc else {
+            return vars.interpolate(doc);
+        };
+        for (k, v) in m.iter_mut() {
+            match (k.as_str(), v) {
+                (Some("environments"), serde_yaml::Value::Sequence(items)) => {
+                    for item in items {
+                        EnvironmentManifest::interpolate(item, vars)?;
+                    }
+                }
+                (_, v) => vars.interpolate(v)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads a manifest of type `T` from the specified file path, resolving its
+/// `${VAR}` references with `vars`.
+pub async fn load_manifest_from_path<T: Manifest>(
+    path: &Path,
+    vars: &Variables,
+) -> Result<T, LoadManifestFromPathError> {
     let content = fs::read(path).context(ReadSnafu)?;
-    let m = serde_yaml::from_slice::<T>(&content).context(ParseSnafu {
+    let mut doc = serde_yaml::from_slice::<serde_yaml::Value>(&content).context(ParseSnafu {
+        path: path.to_path_buf(),
+    })?;
+    let declared = doc.clone();
+    T::interpolate(&mut doc, vars).context(InterpolateSnafu {
+        path: path.to_path_buf(),
+    })?;
+
+    // Parse the file itself when it references no variables, so errors point
+    // at a line and column
+    let m = match doc == declared {
+        true => serde_yaml::from_slice::<T>(&content),
+        false => serde_yaml::from_value::<T>(doc),
+    }
+    .context(ParseSnafu {
         path: path.to_path_buf(),
     })?;
     Ok(m)
@@ -511,4 +579,42 @@ mod tests {
         let locator = ProjectRootLocateImpl::new(link, None);
         assert_eq!(locator.locate().unwrap(), project.path());
     }
+
+    // Each environment sees its own `.env.<name>` file; everything else only `.env`
+    #[tokio::test]
+    async fn environment_definitions_see_their_own_variables() {
+        let tmp = Utf8TempDir::new().unwrap();
+        std::fs::write(
+            tmp.path().join(PROJECT_MANIFEST),
+            "networks:\n  - ${NETWORK}\nenvironments:\n  - name: staging\n    network: ${NETWORK}\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.path().join(".env"), "NETWORK=from-dotenv\n").unwrap();
+        std::fs::write(tmp.path().join(".env.staging"), "NETWORK=from-staging\n").unwrap();
+        let vars = Variables::load(tmp.path()).unwrap();
+
+        let m: ProjectManifest = load_manifest_from_path(&tmp.path().join(PROJECT_MANIFEST), &vars)
+            .await
+            .unwrap();
+
+        assert_eq!(m.networks, vec![Item::Path("from-dotenv".to_string())]);
+        let Item::Manifest(staging) = &m.environments[0] else {
+            panic!("expected an inline environment");
+        };
+        assert_eq!(staging.network.as_deref(), Some("from-staging"));
+    }
+
+    // Script commands keep their shell variables, so the examples using them load
+    #[tokio::test]
+    async fn examples_with_shell_variables_load() {
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+        for example in ["icp-wasm-optimization", "icp-wasm-metadata"] {
+            let path = examples.join(example).join(PROJECT_MANIFEST);
+            let vars = Variables::from_map(Default::default());
+
+            let m = load_manifest_from_path::<ProjectManifest>(&path, &vars).await;
+
+            assert!(m.is_ok(), "{example}: {:?}", m.err());
+        }
+    }
 }
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::manifest::{
     Item, canister::CanisterManifest, dependency::DependencyManifest,
-    environment::EnvironmentManifest, network::NetworkManifest,
+    environment::EnvironmentManifest, network::NetworkManifest, registry::RegistryManifest,
 };
 
 #[derive(Debug, PartialEq, JsonSchema, Deserialize, Serialize)]
@@ -24,6 +24,16 @@ pub struct ProjectManifest {
 
     #[serde(default)]
     pub environments: Vec<Item<EnvironmentManifest>>,
+
+    /// The identity to deploy and call canisters with in every environment
+    /// that does not choose its own, unless `--identity` is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+
+    /// Registries recipes can be referenced from, besides `dfinity`. A
+    /// registry of the same name in the user settings takes precedence.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<RegistryManifest>,
 }
 
 #[cfg(test)]
@@ -123,6 +133,8 @@ mod tests {
                 canisters: vec![],
                 networks: vec![],
                 environments: vec![],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -157,6 +169,8 @@ mod tests {
                 })],
                 networks: vec![],
                 environments: vec![],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -239,6 +253,8 @@ mod tests {
                 })],
                 networks: vec![],
                 environments: vec![],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -277,6 +293,8 @@ mod tests {
                 ],
                 networks: vec![],
                 environments: vec![],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -308,6 +326,8 @@ mod tests {
                     }),
                 })],
                 environments: vec![],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -327,11 +347,15 @@ mod tests {
                 networks: vec![],
                 environments: vec![Item::Manifest(EnvironmentManifest {
                     name: "my-environment".to_string(),
-                    network: "my-network".to_string(),
+                    extends: None,
+                    network: Some("my-network".to_string()),
+                    identity: None,
                     canisters: CanisterSelection::Named(vec!["my-canister".to_string()]),
                     settings: None,
                     init_args: None,
                 })],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -351,11 +375,15 @@ mod tests {
                 networks: vec![],
                 environments: vec![Item::Manifest(EnvironmentManifest {
                     name: "my-environment".to_string(),
-                    network: "my-network".to_string(),
+                    extends: None,
+                    network: Some("my-network".to_string()),
+                    identity: None,
                     canisters: CanisterSelection::Named(vec!["my-canister".to_string()]),
                     settings: None,
                     init_args: None,
                 })],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -378,26 +406,34 @@ mod tests {
                 environments: vec![
                     Item::Manifest(EnvironmentManifest {
                         name: "environment-1".to_string(),
-                        network: "local".to_string(),
+                        extends: None,
+                        network: None,
+                        identity: None,
                         canisters: CanisterSelection::None,
                         settings: None,
                         init_args: None,
                     }),
                     Item::Manifest(EnvironmentManifest {
                         name: "environment-2".to_string(),
-                        network: "local".to_string(),
+                        extends: None,
+                        network: None,
+                        identity: None,
                         canisters: CanisterSelection::Named(vec!["my-canister".to_string()]),
                         settings: None,
                         init_args: None,
                     }),
                     Item::Manifest(EnvironmentManifest {
                         name: "environment-3".to_string(),
-                        network: "local".to_string(),
+                        extends: None,
+                        network: None,
+                        identity: None,
                         canisters: CanisterSelection::Everything,
                         settings: None,
                         init_args: None,
                     }),
                 ],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -420,7 +456,9 @@ mod tests {
                 networks: vec![],
                 environments: vec![Item::Manifest(EnvironmentManifest {
                     name: "my-environment".to_string(),
-                    network: "local".to_string(),
+                    extends: None,
+                    network: None,
+                    identity: None,
                     canisters: CanisterSelection::Everything,
                     settings: Some(HashMap::from([
                         (
@@ -440,6 +478,8 @@ mod tests {
                     ])),
                     init_args: None,
                 })],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -462,7 +502,9 @@ mod tests {
                 networks: vec![],
                 environments: vec![Item::Manifest(EnvironmentManifest {
                     name: "my-environment".to_string(),
-                    network: "local".to_string(),
+                    extends: None,
+                    network: None,
+                    identity: None,
                     canisters: CanisterSelection::Everything,
                     settings: None,
                     init_args: Some(HashMap::from([
@@ -479,6 +521,8 @@ mod tests {
                         ),
                     ])),
                 })],
+                registries: vec![],
+                identity: None,
             },
         );
     }
@@ -1,8 +1,10 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, de::Error as _};
 
+use super::registry::RegistryRef;
+
 /// Represents the accepted values for a recipe type in
 /// the canister manifest
 #[derive(Clone, Debug, PartialEq, JsonSchema)]
@@ -35,13 +37,10 @@ impl Display for RecipeType {
     }
 }
 
-impl<'de> Deserialize<'de> for RecipeType {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let v = String::deserialize(deserializer)?;
+impl FromStr for RecipeType {
+    type Err = String;
 
+    fn from_str(v: &str) -> Result<Self, Self::Err> {
         if let Some(path) = v.strip_prefix("file://") {
             let path = path.into();
 
@@ -52,28 +51,34 @@ impl<'de> Deserialize<'de> for RecipeType {
             return Ok(Self::Url(v.to_owned()));
         }
 
-        if let Some(fq_recipe) = v.strip_prefix("@")
-            && v.contains("/")
-        {
-            // Check for version delimiter
-            let Some((fq_name, version)) = fq_recipe.rsplit_once("@") else {
-                return Err(D::Error::custom(format!(
-                    "Missing version from recipe `{v}@<version>`"
-                )));
-            };
-
-            let (registry, recipe) = fq_name.split_once("/").expect("delimiter missing");
+        if v.starts_with('@') && v.contains('/') {
+            let RegistryRef {
+                registry,
+                name,
+                version,
+            } = v.parse()?;
 
             return Ok(Self::Registry {
-                name: registry.to_owned(),
-                recipe: recipe.to_owned(),
-                version: version.to_owned(),
+                name: registry,
+                recipe: name,
+                version,
             });
         }
 
-        Err(D::Error::custom(format!(
+        Err(format!(
             "Invalid recipe type: `{v}`. Valid types are urls eg: http:// or file://, or @<registry>/<name>@<version>."
-        )))
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for RecipeType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
     }
 }
 
@@ -105,9 +110,10 @@ pub struct Recipe {
     ///
     /// `http://<url_to_recipe>` - point to a remote recipe template
     ///
-    /// `@<registry>/<recipe_name>@<version>` - Point to a recipe in a known registry.
-    ///
-    /// For now the only registry is the `dfinity` registry at https://github.com/dfinity/icp-cli-recipes
+    /// `@<registry>/<recipe_name>@<version>` - Point to a recipe in a known registry:
+    /// the built-in `dfinity` registry at https://github.com/dfinity/icp-cli-recipes,
+    /// or one declared under `registries`. The version may be a semver requirement
+    /// such as `^3`, resolved to the highest matching version and pinned in icp.lock
     ///
     /// It is recommended to also provide a hash in the `sha256` field
     #[serde(rename = "type")]
@@ -0,0 +1,168 @@
+use std::{fmt, str::FromStr};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, de::Error as _};
+
+/// A package hosted in a known registry, written `@<registry>/<name>@<version>`
+/// in manifests, e.g. `@dfinity/rust@v3.0.0`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, JsonSchema)]
+#[schemars(with = "String")]
+pub struct RegistryRef {
+    /// The name of the registry
+    pub registry: String,
+
+    /// The name of the package within the registry
+    pub name: String,
+
+    /// The version of the package
+    pub version: String,
+}
+
+impl RegistryRef {
+    /// The registry-qualified package name without the version, e.g. `@dfinity/rust`.
+    pub fn package(&self) -> String {
+        format!("@{}/{}", self.registry, self.name)
+    }
+}
+
+impl fmt::Display for RegistryRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}/{}@{}", self.registry, self.name, self.version)
+    }
+}
+
+impl FromStr for RegistryRef {
+    type Err = String;
+
+    fn from_str(v: &str) -> Result<Self, Self::Err> {
+        let Some(fq_name) = v.strip_prefix('@').filter(|s| s.contains('/')) else {
+            return Err(format!(
+                "Invalid registry reference: `{v}`. Expected @<registry>/<name>@<version>."
+            ));
+        };
+
+        // Check for version delimiter
+        let Some((fq_name, version)) = fq_name.rsplit_once('@') else {
+            return Err(format!("Missing version from `{v}@<version>`"));
+        };
+
+        let Some((registry, name)) = fq_name.split_once('/') else {
+            return Err(format!(
+                "Invalid registry reference: `{v}`. Expected @<registry>/<name>@<version>."
+            ));
+        };
+
+        // Each component becomes a path segment or URL segment of its own
+        for (kind, component) in [("registry", registry), ("name", name), ("version", version)] {
+            if component.is_empty()
+                || component == "."
+                || component == ".."
+                || component.contains(['/', '\\'])
+            {
+                return Err(format!(
+                    "Invalid {kind} `{component}` in registry reference `{v}`: it must be non-empty, \
+                     must not be `.` or `..` and must not contain `/` or `\\`."
+                ));
+            }
+        }
+
+        Ok(Self {
+            registry: registry.to_owned(),
+            name: name.to_owned(),
+            version: version.to_owned(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for RegistryRef {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for RegistryRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+/// A registry packages can be referenced from as `@<name>/<package>@<version>`,
+/// declared in `icp.yaml` or in the user settings.
+///
+/// A registry is hosted at a base URL or in a local directory, either holding
+/// `index.json` and each package version's assets under `<package>/<version>/`.
+#[derive(Clone, Debug, PartialEq, JsonSchema, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RegistryManifest {
+    /// The name packages reference the registry by
+    pub name: String,
+
+    /// Base URL the registry is served from. Exactly one of `url` and `path`
+    /// must be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Directory holding the registry, relative to the declaring project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Environment variable holding the value of the `Authorization` header sent
+    /// to a `url` registry over https, e.g. `Bearer <token>`. In `icp.yaml` its
+    /// name must start with `ICP_REGISTRY_`, and a registry in the user settings
+    /// must send the same variable to the same host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header_env: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let r: RegistryRef = "@dfinity/asset-sync@v1.2.0".parse().unwrap();
+        assert_eq!(
+            r,
+            RegistryRef {
+                registry: "dfinity".to_owned(),
+                name: "asset-sync".to_owned(),
+                version: "v1.2.0".to_owned(),
+            }
+        );
+        assert_eq!(r.package(), "@dfinity/asset-sync");
+        assert_eq!(r.to_string(), "@dfinity/asset-sync@v1.2.0");
+    }
+
+    #[test]
+    fn missing_version() {
+        let err = "@dfinity/asset-sync".parse::<RegistryRef>().unwrap_err();
+        assert!(err.contains("Missing version"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn not_a_registry_reference() {
+        assert!("dfinity/asset-sync@v1".parse::<RegistryRef>().is_err());
+        assert!("@asset-sync@v1".parse::<RegistryRef>().is_err());
+    }
+
+    #[test]
+    fn malformed_components_are_rejected() {
+        for v in [
+            // The `/` only appears in the version
+            "@reg@v/x",
+            "@/asset-sync@v1",
+            "@dfinity/@v1",
+            "@dfinity/asset-sync@",
+            "@../asset-sync@v1",
+            "@dfinity/..@v1",
+            "@dfinity/.@v1",
+            "@dfinity/asset-sync@..",
+            "@dfinity/nested/name@v1",
+            "@dfinity/asset-sync@v1\\..",
+        ] {
+            assert!(v.parse::<RegistryRef>().is_err(), "accepted `{v}`");
+        }
+    }
+}
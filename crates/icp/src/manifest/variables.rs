@@ -0,0 +1,480 @@
+//! `${VAR}` references in manifests.
+//!
+//! String values in a manifest may reference variables, which are replaced
+//! before the manifest is deserialized:
+//!
+//! - `${NAME}` is the value of `NAME`, which must be set.
+//! - `${NAME:-default}` is `default` when `NAME` is unset or empty.
+//! - `${secret:NAME}` (or `${secret:NAME:-default}`) is the same, but the value
+//!   is redacted from `icp project show`.
+//! - `$${` is a literal `${`.
+//!
+//! A value that is a single reference takes the type of what it resolves to,
+//! as if the value had been written in its place: `port: ${PORT}` is a number
+//! and `enabled: ${FLAG}` a boolean. Secrets and environment variable values
+//! always stay strings.
+//!
+//! Script `command` and `commands` are left as written, since they run in a
+//! shell that expands its own `${VAR}` references, such as
+//! `${ICP_WASM_OUTPUT_PATH}`.
+//!
+//! Variables come from the process environment and from a `.env` file in the
+//! project root. Inside an environment's definition, a `.env.<environment>` file
+//! is read as well. The process environment takes precedence over either file,
+//! and `.env.<environment>` over `.env`.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use serde_yaml::Value;
+use snafu::prelude::*;
+
+use crate::{fs, prelude::*};
+
+/// The name of the file variables are read from, in the project root.
+pub const DOTENV_FILE: &str = ".env";
+
+#[derive(Debug, Snafu)]
+pub enum VariableError {
+    #[snafu(display("variable '{name}' is not set"))]
+    Unset { name: String },
+
+    #[snafu(display("malformed variable reference '{reference}'"))]
+    Malformed { reference: String },
+
+    #[snafu(display("failed to read variables file"))]
+    ReadFile { source: fs::IoError },
+
+    #[snafu(display("invalid line {line} in variables file '{path}': expected NAME=VALUE"))]
+    ParseFile { path: PathBuf, line: usize },
+}
+
+/// The variables `${VAR}` references in manifests resolve to.
+#[derive(Clone, Debug, Default)]
+pub struct Variables {
+    /// The directory `.env` files are read from, if any
+    dir: Option<PathBuf>,
+
+    /// The process environment
+    process: HashMap<String, String>,
+
+    /// The values read from `.env` files
+    files: HashMap<String, String>,
+
+    /// The values of the `${secret:NAME}` references resolved so far, shared by
+    /// every environment's view
+    secrets: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl Variables {
+    /// The process environment, and the `.env` file in `dir` if there is one.
+    pub fn load(dir: &Path) -> Result<Self, VariableError> {
+        Ok(Self {
+            dir: Some(dir.to_owned()),
+            process: std::env::vars().collect(),
+            files: read_dotenv(&dir.join(DOTENV_FILE))?,
+            secrets: Default::default(),
+        })
+    }
+
+    /// Just the given variables, without reading any file.
+    pub fn from_map(vars: HashMap<String, String>) -> Self {
+        Self {
+            process: vars,
+            ..Default::default()
+        }
+    }
+
+    /// The variables inside the definition of `environment`, with its
+    /// `.env.<environment>` file read in.
+    pub fn for_environment(&self, environment: &str) -> Result<Self, VariableError> {
+        let mut files = self.files.clone();
+        if let Some(dir) = &self.dir {
+            files.extend(read_dotenv(
+                &dir.join(format!("{DOTENV_FILE}.{environment}")),
+            )?);
+        }
+        Ok(Self {
+            files,
+            ..self.clone()
+        })
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.process
+            .get(name)
+            .or_else(|| self.files.get(name))
+            .map(String::as_str)
+    }
+
+    /// The values of the secret variables referenced so far.
+    pub fn secrets(&self) -> BTreeSet<String> {
+        self.secrets.lock().expect("poisoned").clone()
+    }
+
+    /// Replaces the references in every string of `doc`, including mapping
+    /// keys, except in script commands.
+    pub fn interpolate(&self, doc: &mut Value) -> Result<(), VariableError> {
+        self.interpolate_value(doc, true)
+    }
+
+    /// Replaces the references in `doc`; with `typed`, a value that is a single
+    /// reference takes the type of what it resolves to.
+    fn interpolate_value(&self, doc: &mut Value, typed: bool) -> Result<(), VariableError> {
+        match doc {
+            Value::String(s) => {
+                let typed =
+                    typed && single_reference(s).is_some_and(|expr| !expr.starts_with("secret:"));
+                let value = self.interpolate_str(s)?;
+                *doc = match typed {
+                    true => scalar(value),
+                    false => Value::String(value),
+                };
+            }
+            Value::Sequence(items) => {
+                for item in items {
+                    self.interpolate_value(item, typed)?;
+                }
+            }
+            Value::Mapping(m) => {
+                let entries = std::mem::take(m);
+                for (mut k, mut v) in entries {
+                    let key = k.as_str();
+                    let is_command = matches!(key, Some("command" | "commands"));
+                    // Environment variable values are strings, whatever they read as
+                    let typed = typed && key != Some("environment_variables");
+                    if let Value::String(k) = &mut k {
+                        *k = self.interpolate_str(k)?;
+                    }
+                    if !is_command {
+                        self.interpolate_value(&mut v, typed)?;
+                    }
+                    m.insert(k, v);
+                }
+            }
+            Value::Tagged(t) => self.interpolate_value(&mut t.value, typed)?,
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Replaces the references in `s`.
+    pub fn interpolate_str(&self, s: &str) -> Result<String, VariableError> {
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(i) = rest.find('$') {
+            out.push_str(&rest[..i]);
+            rest = &rest[i..];
+
+            if let Some(r) = rest.strip_prefix("$${") {
+                out.push_str("${");
+                rest = r;
+                continue;
+            }
+            let Some(r) = rest.strip_prefix("${") else {
+                out.push('$');
+                rest = &rest[1..];
+                continue;
+            };
+            let Some(end) = r.find('}') else {
+                return MalformedSnafu { reference: rest }.fail();
+            };
+            out.push_str(&self.resolve(&r[..end], &rest[..end + 3])?);
+            rest = &r[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// The value of the reference `${expr}`.
+    fn resolve(&self, expr: &str, reference: &str) -> Result<String, VariableError> {
+        let (secret, expr) = match expr.strip_prefix("secret:") {
+            Some(expr) => (true, expr),
+            None => (false, expr),
+        };
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        ensure!(is_name(name), MalformedSnafu { reference });
+
+        let value = match (self.get(name), default) {
+            (Some(""), Some(default)) => default.to_owned(),
+            (Some(v), _) => v.to_owned(),
+            (None, Some(default)) => default.to_owned(),
+            (None, None) => return UnsetSnafu { name }.fail(),
+        };
+        if secret && !value.is_empty() {
+            self.secrets.lock().expect("poisoned").insert(value.clone());
+        }
+        Ok(value)
+    }
+}
+
+/// The expression of `s` when all of `s` is one `${expr}` reference.
+fn single_reference(s: &str) -> Option<&str> {
+    let expr = s.strip_prefix("${")?.strip_suffix('}')?;
+    (!expr.contains('}')).then_some(expr)
+}
+
+/// `value` as the YAML scalar it reads as when it is a number or a boolean,
+/// otherwise as a string.
+fn scalar(value: String) -> Value {
+    match serde_yaml::from_str(&value) {
+        Ok(v @ (Value::Bool(_) | Value::Number(_))) => v,
+        _ => Value::String(value),
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Reads the `NAME=VALUE` lines of a `.env` file, if it exists.
+///
+/// Blank lines and lines starting with `#` are skipped, and a line may start
+/// with `export`. A value may be quoted; an unquoted value ends at ` #`.
+fn read_dotenv(path: &Path) -> Result<HashMap<String, String>, VariableError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(err).context(ReadFileSnafu),
+    };
+    parse_dotenv(&contents).map_err(|line| VariableError::ParseFile {
+        path: path.to_owned(),
+        line,
+    })
+}
+
+/// Parses `.env` contents, failing with the number of the first invalid line.
+fn parse_dotenv(contents: &str) -> Result<HashMap<String, String>, usize> {
+    let mut vars = HashMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line.split_once('=').ok_or(i + 1)?;
+        let name = name.trim();
+        if !is_name(name) {
+            return Err(i + 1);
+        }
+
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => {
+                let inner = value[1..].strip_suffix(q).ok_or(i + 1)?;
+                match q {
+                    '"' => inner
+                        .replace("\\n", "\n")
+                        .replace("\\\"", "\"")
+                        .replace("\\\\", "\\"),
+                    _ => inner.to_owned(),
+                }
+            }
+            _ => match value.find(" #") {
+                Some(comment) => value[..comment].trim_end().to_owned(),
+                None => value.to_owned(),
+            },
+        };
+        vars.insert(name.to_owned(), value);
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use camino_tempfile::Utf8TempDir;
+    use indoc::indoc;
+    use serde::Deserialize;
+
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Variables {
+        Variables::from_map(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn references_are_replaced() {
+        let v = vars(&[("DOMAIN", "example.org"), ("EMPTY", "")]);
+
+        assert_eq!(
+            v.interpolate_str("https://${DOMAIN}/api").unwrap(),
+            "https://example.org/api"
+        );
+        assert_eq!(
+            v.interpolate_str("${MISSING:-fallback}").unwrap(),
+            "fallback"
+        );
+        assert_eq!(v.interpolate_str("${EMPTY:-fallback}").unwrap(), "fallback");
+        assert_eq!(
+            v.interpolate_str("${DOMAIN:-fallback}").unwrap(),
+            "example.org"
+        );
+        assert_eq!(
+            v.interpolate_str("$HOME and $${DOMAIN}").unwrap(),
+            "$HOME and ${DOMAIN}"
+        );
+    }
+
+    #[test]
+    fn unset_and_malformed_references_are_rejected() {
+        let v = vars(&[]);
+
+        assert!(matches!(
+            v.interpolate_str("${MISSING}"),
+            Err(VariableError::Unset { name }) if name == "MISSING"
+        ));
+        assert!(matches!(
+            v.interpolate_str("${NOT CLOSED"),
+            Err(VariableError::Malformed { .. })
+        ));
+        assert!(matches!(
+            v.interpolate_str("${1ST}"),
+            Err(VariableError::Malformed { reference }) if reference == "${1ST}"
+        ));
+    }
+
+    #[test]
+    fn secret_values_are_recorded() {
+        let v = vars(&[("API_KEY", "s3cret"), ("LEVEL", "info")]);
+
+        v.interpolate_str("${secret:API_KEY} ${LEVEL}").unwrap();
+
+        assert_eq!(v.secrets(), BTreeSet::from(["s3cret".to_string()]));
+        // Shared with the views of each environment
+        v.for_environment("staging")
+            .unwrap()
+            .interpolate_str("${secret:MISSING:-fallback}")
+            .unwrap();
+        assert_eq!(v.secrets().len(), 2);
+    }
+
+    #[test]
+    fn documents_are_interpolated_throughout() {
+        let v = vars(&[("CONTROLLER", "aaaaa-aa")]);
+        let mut doc: Value = serde_yaml::from_str(indoc! {r#"
+            settings:
+              controllers: ["${CONTROLLER}"]
+              compute_allocation: 1
+        "#})
+        .unwrap();
+
+        v.interpolate(&mut doc).unwrap();
+
+        assert_eq!(doc["settings"]["controllers"][0], "aaaaa-aa");
+        assert_eq!(doc["settings"]["compute_allocation"], 1);
+    }
+
+    #[test]
+    fn single_references_take_the_type_of_their_value() {
+        let v = vars(&[
+            ("PORT", "8080"),
+            ("FLAG", "true"),
+            ("NAME", "null"),
+            ("KEY", "1234"),
+        ]);
+        let mut doc: Value = serde_yaml::from_str(indoc! {r#"
+            port: ${PORT}
+            enabled: ${FLAG}
+            name: ${NAME}
+            key: ${secret:KEY}
+            url: localhost:${PORT}
+            environment_variables:
+              PORT: ${PORT}
+        "#})
+        .unwrap();
+
+        v.interpolate(&mut doc).unwrap();
+
+        assert_eq!(doc["port"], 8080);
+        assert_eq!(doc["enabled"], true);
+        assert_eq!(doc["name"], "null");
+        assert_eq!(doc["key"], "1234");
+        assert_eq!(doc["url"], "localhost:8080");
+        assert_eq!(doc["environment_variables"]["PORT"], "8080");
+
+        #[derive(Deserialize)]
+        struct Gateway {
+            port: u16,
+        }
+        let gateway: Gateway = serde_yaml::from_value(doc).unwrap();
+        assert_eq!(gateway.port, 8080);
+    }
+
+    #[test]
+    fn script_commands_are_left_to_the_shell() {
+        let v = vars(&[("CANISTER", "backend")]);
+        let mut doc: Value = serde_yaml::from_str(indoc! {r#"
+            name: ${CANISTER}
+            build:
+              steps:
+                - type: script
+                  command: echo "${ICP_WASM_OUTPUT_PATH}"
+                - type: script
+                  commands:
+                    - mv "${ICP_WASM_OUTPUT_PATH}.gz" "$ICP_WASM_OUTPUT_PATH"
+        "#})
+        .unwrap();
+
+        v.interpolate(&mut doc).unwrap();
+
+        assert_eq!(doc["name"], "backend");
+        let steps = &doc["build"]["steps"];
+        assert_eq!(steps[0]["command"], r#"echo "${ICP_WASM_OUTPUT_PATH}""#);
+        assert_eq!(
+            steps[1]["commands"][0],
+            r#"mv "${ICP_WASM_OUTPUT_PATH}.gz" "$ICP_WASM_OUTPUT_PATH""#
+        );
+    }
+
+    #[test]
+    fn process_environment_wins_over_files() {
+        let tmp = Utf8TempDir::new().unwrap();
+        std::fs::write(tmp.path().join(".env"), "A=file\nB=file\nC=file\n").unwrap();
+        std::fs::write(tmp.path().join(".env.staging"), "B=staging\n").unwrap();
+        let mut v = Variables::load(tmp.path()).unwrap();
+        v.process = HashMap::from([("C".to_string(), "process".to_string())]);
+
+        let staging = v.for_environment("staging").unwrap();
+        assert_eq!(
+            staging.interpolate_str("${A} ${B} ${C}").unwrap(),
+            "file staging process"
+        );
+        assert_eq!(
+            v.interpolate_str("${A} ${B} ${C}").unwrap(),
+            "file file process"
+        );
+    }
+
+    #[test]
+    fn dotenv_syntax() {
+        let vars = parse_dotenv(indoc! {r#"
+            # A comment
+            export PLAIN=value # trailing comment
+            DOUBLE="two\nlines"
+            SINGLE='kept \n as is'
+            EMPTY=
+        "#})
+        .unwrap();
+
+        assert_eq!(vars["PLAIN"], "value");
+        assert_eq!(vars["DOUBLE"], "two\nlines");
+        assert_eq!(vars["SINGLE"], "kept \\n as is");
+        assert_eq!(vars["EMPTY"], "");
+        assert_eq!(parse_dotenv("A=1\nnot a pair\n"), Err(2));
+        assert_eq!(parse_dotenv("A=\"unterminated\n"), Err(1));
+    }
+}
@@ -45,6 +45,7 @@ pub fn get_cached_launcher_version(
     };
     let version_path = paths.launcher_version(&declared_version);
     if version_path.exists() {
+        _ = crate::fs::write(&paths.launcher_atime(&declared_version), b"");
         Ok(Some((declared_version, version_path.join(LAUNCHER_NAME))))
     } else {
         Ok(None)
@@ -187,6 +188,7 @@ pub async fn download_launcher_version(
         from: extracted_dir_path,
         to: &version_path,
     })?;
+    _ = crate::fs::write(&paths.launcher_atime(&pkg_version), b"");
     Ok((pkg_version, version_path.join(LAUNCHER_NAME)))
 }
 
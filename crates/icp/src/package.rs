@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::SystemTime,
+};
 
 use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
@@ -25,14 +28,25 @@ impl PackageCachePaths {
     pub fn wasms_dir(&self) -> PathBuf {
         self.root.join("wasms")
     }
+    pub fn plugins_dir(&self) -> PathBuf {
+        self.root.join("plugins")
+    }
     pub fn launcher_version(&self, version: &str) -> PathBuf {
         self.launcher_dir().join(version)
     }
+    pub fn launcher_atime(&self, version: &str) -> PathBuf {
+        self.launcher_version(version).join(".atime")
+    }
     pub fn wasm_sha(&self, sha: &str) -> WasmCache {
         WasmCache {
             dir: self.wasms_dir().join(sha),
         }
     }
+    pub fn plugin_sha(&self, sha: &str) -> PluginCache {
+        PluginCache {
+            dir: self.plugins_dir().join(sha),
+        }
+    }
     pub fn recipe_sha(&self, sha: &str) -> RecipeCache {
         RecipeCache {
             dir: self.recipes_dir().join(sha),
@@ -62,6 +76,30 @@ impl WasmCache {
     }
 }
 
+/// One sync plugin, keyed by the plugin's sha256: the wasm itself for plugins
+/// fetched from a registry, and precompiled artifacts for any plugin.
+/// A plugin can have several artifacts side by side, one per engine key
+/// (wasmtime version and configuration), so switching CLI versions back and
+/// forth does not evict each other's compilations.
+pub struct PluginCache {
+    dir: PathBuf,
+}
+
+impl PluginCache {
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+    pub fn wasm(&self) -> PathBuf {
+        self.dir.join("plugin.wasm")
+    }
+    pub fn component(&self, engine_key: &str) -> PathBuf {
+        self.dir.join(format!("{engine_key}.cwasm"))
+    }
+    pub fn atime(&self) -> PathBuf {
+        self.dir.join(".atime")
+    }
+}
+
 pub struct RecipeCache {
     dir: PathBuf,
 }
@@ -93,6 +131,103 @@ pub fn cache_wasm(
     Ok(())
 }
 
+/// Read a precompiled plugin component, if one was cached for this engine.
+pub fn read_cached_component(
+    cache: LRead<&PackageCachePaths>,
+    sha: &str,
+    engine_key: &str,
+) -> Result<Option<Vec<u8>>, crate::fs::IoError> {
+    let cache_path = cache.plugin_sha(sha);
+    let component_path = cache_path.component(engine_key);
+    if component_path.exists() {
+        let compiled = crate::fs::read(&component_path)?;
+        _ = crate::fs::write(&cache_path.atime(), b"");
+        Ok(Some(compiled))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Cache a precompiled plugin component. Unlike the other cache writers this
+/// overwrites an existing artifact: one is only rewritten after it failed to
+/// load, and leaving it in place would fail the same way on every run.
+pub fn cache_component(
+    cache: LWrite<&PackageCachePaths>,
+    sha: &str,
+    engine_key: &str,
+    compiled: &[u8],
+) -> Result<(), crate::fs::IoError> {
+    let cache_path = cache.plugin_sha(sha);
+    crate::fs::create_dir_all(cache_path.dir())?;
+    crate::fs::write(&cache_path.component(engine_key), compiled)?;
+    _ = crate::fs::write(&cache_path.atime(), b"");
+    Ok(())
+}
+
+/// Look up a cached registry plugin by package and version (e.g.,
+/// `@dfinity/asset-sync`, `v1.0.0`). Returns the path of its wasm in
+/// `plugins/{sha}/plugin.wasm` together with the sha256.
+pub fn read_cached_registry_plugin(
+    cache: LRead<&PackageCachePaths>,
+    package: &str,
+    version: &str,
+) -> Result<Option<(PathBuf, String)>, PluginCacheError> {
+    assert!(package.starts_with('@'));
+    let Some(sha) =
+        get_tag(cache, &format!("plugin{package}"), version).context(LoadPluginTagSnafu)?
+    else {
+        return Ok(None);
+    };
+    Ok(read_cached_plugin(cache, &sha).map(|path| (path, sha)))
+}
+
+/// The path of a cached plugin wasm by its sha256, if it is cached.
+pub fn read_cached_plugin(cache: LRead<&PackageCachePaths>, sha: &str) -> Option<PathBuf> {
+    let cache_path = cache.plugin_sha(sha);
+    let wasm_path = cache_path.wasm();
+    if wasm_path.exists() {
+        _ = crate::fs::write(&cache_path.atime(), b"");
+        Some(wasm_path)
+    } else {
+        None
+    }
+}
+
+/// Cache a registry plugin. `package` is the registry-qualified name (e.g.,
+/// `@dfinity/asset-sync`) and `sha2` the sha256 of the wasm. Stores the
+/// version→SHA mapping in the package manifest and writes the wasm to
+/// `plugins/{sha2}/plugin.wasm`.
+pub fn cache_registry_plugin(
+    cache: LWrite<&PackageCachePaths>,
+    package: &str,
+    version: &str,
+    sha2: &str,
+    wasm: &[u8],
+) -> Result<(), PluginCacheError> {
+    assert!(package.starts_with('@'));
+    set_tag(cache, &format!("plugin{package}"), sha2, version).context(SavePluginTagSnafu)?;
+    let cache_path = cache.plugin_sha(sha2);
+    let wasm_path = cache_path.wasm();
+    if !wasm_path.exists() {
+        crate::fs::create_dir_all(cache_path.dir()).context(PluginCacheIoSnafu)?;
+        crate::fs::write(&wasm_path, wasm).context(PluginCacheIoSnafu)?;
+        _ = crate::fs::write(&cache_path.atime(), b"");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Snafu)]
+pub enum PluginCacheError {
+    #[snafu(display("failed to load plugin cache tag"))]
+    LoadPluginTag { source: crate::fs::json::Error },
+
+    #[snafu(display("failed to save plugin cache tag"))]
+    SavePluginTag { source: crate::fs::json::Error },
+
+    #[snafu(display("failed to read or write plugin cache file"))]
+    PluginCacheIo { source: crate::fs::IoError },
+}
+
 /// Read a cached recipe template by recipe and version (e.g., `@dfinity/rust`, `v1.0.2`).
 /// Resolves the version to a git SHA via the package manifest, then reads
 /// the cached template from `recipes/{sha}/recipe.hbs`.
@@ -277,3 +412,225 @@ struct Manifest {
     #[serde(default)]
     updater_versions: HashMap<String, String>,
 }
+
+/// The kinds of artifacts kept in the package cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, strum::Display)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum CacheKind {
+    Launcher,
+    Recipe,
+    Wasm,
+    Plugin,
+    Template,
+}
+
+/// One artifact in the package cache, e.g. a launcher version or a wasm.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub kind: CacheKind,
+    /// The launcher version, or the sha256 of any other artifact.
+    pub key: String,
+    pub path: PathBuf,
+    /// Total size of the entry on disk, in bytes.
+    pub size: u64,
+    /// When the entry was last read, per its `.atime` marker, or when it was
+    /// written if it has none.
+    pub last_used: SystemTime,
+}
+
+#[derive(Debug, Snafu)]
+pub enum CacheRemoveError {
+    #[snafu(transparent)]
+    Io { source: crate::fs::IoError },
+
+    #[snafu(display("failed to update package cache tags"))]
+    UpdateTags { source: crate::fs::json::Error },
+}
+
+/// Every artifact in the package cache, sorted by kind and key.
+pub fn list_cache_entries(
+    cache: LRead<&PackageCachePaths>,
+) -> Result<Vec<CacheEntry>, crate::fs::IoError> {
+    let mut entries = vec![];
+    for (kind, dir) in [
+        (CacheKind::Launcher, cache.launcher_dir()),
+        (CacheKind::Recipe, cache.recipes_dir()),
+        (CacheKind::Wasm, cache.wasms_dir()),
+        (CacheKind::Plugin, cache.plugins_dir()),
+        (CacheKind::Template, cache.project_templates_dir()),
+    ] {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in crate::fs::read_dir(&dir)? {
+            let path = entry.path().to_owned();
+            // Launcher downloads are extracted into `tmp` before being moved
+            // into place; it is not an entry in its own right.
+            if !path.is_dir() || (kind == CacheKind::Launcher && entry.file_name() == "tmp") {
+                continue;
+            }
+            let marker = crate::fs::metadata(&path.join(".atime"))
+                .or_else(|_| crate::fs::metadata(&path))?;
+            entries.push(CacheEntry {
+                kind,
+                key: entry.file_name().to_owned(),
+                size: dir_size(&path)?,
+                last_used: marker.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                path,
+            });
+        }
+    }
+    entries.sort_by(|a, b| (a.kind, &a.key).cmp(&(b.kind, &b.key)));
+    Ok(entries)
+}
+
+fn dir_size(dir: &Path) -> Result<u64, crate::fs::IoError> {
+    let mut size = 0;
+    for entry in crate::fs::read_dir(dir)? {
+        let meta = crate::fs::metadata(entry.path())?;
+        size += if meta.is_dir() {
+            dir_size(entry.path())?
+        } else {
+            meta.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Remove entries from the package cache, along with any tags resolving to them.
+pub fn remove_cache_entries(
+    cache: LWrite<&PackageCachePaths>,
+    entries: &[CacheEntry],
+) -> Result<(), CacheRemoveError> {
+    for entry in entries {
+        crate::fs::remove_dir_all(&entry.path)?;
+    }
+    let removed: HashSet<_> = entries.iter().map(|e| e.key.as_str()).collect();
+    let mut manifest: Manifest =
+        crate::fs::json::load_or_default(&cache.manifest()).context(UpdateTagsSnafu)?;
+    let before = manifest.tags.len();
+    manifest
+        .tags
+        .retain(|_, value| !removed.contains(value.as_str()));
+    if manifest.tags.len() != before {
+        crate::fs::json::save(&cache.manifest(), &manifest).context(UpdateTagsSnafu)?;
+    }
+    Ok(())
+}
+
+/// Remove everything from the package cache.
+pub fn clear_cache(cache: LWrite<&PackageCachePaths>) -> Result<(), crate::fs::IoError> {
+    if !cache.root.exists() {
+        return Ok(());
+    }
+    let lock_file = cache.lock_file();
+    for entry in crate::fs::read_dir(&cache.root)? {
+        if entry.path() == lock_file {
+            continue;
+        } else if entry.path().is_dir() {
+            crate::fs::remove_dir_all(entry.path())?;
+        } else {
+            crate::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_in(dir: &Path) -> PackageCache {
+        PackageCache::new(dir.to_owned()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_entries_of_every_kind() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let cache = cache_in(tmp.path());
+        cache
+            .with_write(async |pkg| {
+                cache_wasm(pkg, "aaaa", b"wasm").unwrap();
+                cache_recipe(pkg, "bbbb", b"recipe").unwrap();
+                crate::fs::create_dir_all(&pkg.launcher_version("v1.0.0")).unwrap();
+                crate::fs::write(
+                    &pkg.launcher_version("v1.0.0")
+                        .join("icp-cli-network-launcher"),
+                    b"launcher",
+                )
+                .unwrap();
+                crate::fs::create_dir_all(&pkg.launcher_dir().join("tmp")).unwrap();
+            })
+            .await
+            .unwrap();
+
+        let entries = cache
+            .with_read(async |pkg| list_cache_entries(pkg))
+            .await
+            .unwrap()
+            .unwrap();
+        let listed: Vec<_> = entries
+            .iter()
+            .map(|e| (e.kind, e.key.as_str(), e.size))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (CacheKind::Launcher, "v1.0.0", 8),
+                (CacheKind::Recipe, "bbbb", 6),
+                (CacheKind::Wasm, "aaaa", 4),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn removing_entries_drops_their_tags() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let cache = cache_in(tmp.path());
+        cache
+            .with_write(async |pkg| {
+                cache_registry_recipe(pkg, "@dfinity/rust", "v1.0.0", "aaaa", b"old").unwrap();
+                cache_registry_recipe(pkg, "@dfinity/motoko", "v1.0.0", "bbbb", b"new").unwrap();
+                let entries = list_cache_entries(pkg.read()).unwrap();
+                let stale: Vec<_> = entries.into_iter().filter(|e| e.key == "aaaa").collect();
+                remove_cache_entries(pkg, &stale).unwrap();
+
+                assert!(!pkg.recipe_sha("aaaa").dir().exists());
+                assert!(pkg.recipe_sha("bbbb").template().exists());
+                assert_eq!(
+                    get_tag(pkg.read(), "recipe@dfinity/rust", "v1.0.0").unwrap(),
+                    None
+                );
+                assert_eq!(
+                    get_tag(pkg.read(), "recipe@dfinity/motoko", "v1.0.0").unwrap(),
+                    Some("bbbb".to_string())
+                );
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn clear_keeps_only_the_lock_file() {
+        let tmp = camino_tempfile::Utf8TempDir::new().unwrap();
+        let cache = cache_in(tmp.path());
+        cache
+            .with_write(async |pkg| {
+                cache_registry_recipe(pkg, "@dfinity/rust", "v1.0.0", "aaaa", b"recipe").unwrap();
+                cache_wasm(pkg, "bbbb", b"wasm").unwrap();
+                clear_cache(pkg).unwrap();
+
+                let remaining: Vec<_> = crate::fs::read_dir(tmp.path())
+                    .unwrap()
+                    .into_iter()
+                    .map(|e| e.file_name().to_owned())
+                    .collect();
+                assert_eq!(remaining, vec![".lock".to_string()]);
+                assert!(list_cache_entries(pkg.read()).unwrap().is_empty());
+            })
+            .await
+            .unwrap();
+    }
+}
@@ -5,14 +5,15 @@ use indexmap::{IndexMap, map::Entry as IndexEntry};
 use snafu::prelude::*;
 
 use crate::{
-    Canister, Environment, InitArgs, Network, Project,
-    canister::{ControllerRef, ManifestEnvVar, ManifestSettings, Settings, recipe},
+    Canister, Environment, EnvironmentDeclaration, InitArgs, Network, Project, RecipeDefaults,
+    RecipeSource,
+    canister::{ControllerRef, ManifestEnvVar, ManifestSettings, Settings, recipe, secret::Secret},
     fs,
     manifest::{
         ArgsFormat, CANISTER_MANIFEST, CanisterManifest, DependencyManifest, EnvironmentManifest,
         Item, LoadManifestFromPathError, ManifestInitArgs, NetworkManifest, PROJECT_MANIFEST,
-        ProjectManifest, ProjectRootLocateError,
-        canister::{Instructions, SyncSteps},
+        ProjectManifest, ProjectRootLocateError, Variables,
+        canister::{BuildSteps, Instructions, SyncSteps},
         environment::CanisterSelection,
         load_manifest_from_path,
         network::RootKeySpec,
@@ -22,6 +23,7 @@ use crate::{
         Configuration, Connected, Gateway, Managed, ManagedLauncherConfig, ManagedMode, Port,
     },
     prelude::*,
+    registry::{Registries, RegistryError},
 };
 
 pub const DEFAULT_LOCAL_NETWORK_BIND: &str = "127.0.0.1";
@@ -40,6 +42,12 @@ pub enum EnvironmentError {
         environment: String,
         canister: String,
     },
+
+    #[snafu(display("environment '{environment}' extends unknown environment '{base}'"))]
+    UnknownBase { environment: String, base: String },
+
+    #[snafu(display("environments extend each other in a cycle: {}", chain.join(" -> ")))]
+    ExtendsCycle { chain: Vec<String> },
 }
 
 #[derive(Debug, Snafu)]
@@ -75,6 +83,13 @@ pub enum ConsolidateManifestError {
         recipe_type: RecipeType,
     },
 
+    #[snafu(display(
+        "recipes are not in the package cache and cannot be downloaded in offline mode; \
+         run `icp project prefetch` while online:{}",
+        recipes.iter().map(|r| format!("\n  {r}")).collect::<String>()
+    ))]
+    RecipesNotCached { recipes: Vec<String> },
+
     #[snafu(display("failed to render canister recipe: {recipe_type:?}"))]
     RenderRecipe {
         #[snafu(source(from(recipe::RenderRecipeError, Box::new)))]
@@ -82,6 +97,16 @@ pub enum ConsolidateManifestError {
         recipe_type: RecipeType,
     },
 
+    #[snafu(display(
+        "failed to render canister recipe {recipe_type:?} for environment '{environment}'"
+    ))]
+    RenderEnvironmentRecipe {
+        #[snafu(source(from(recipe::RenderRecipeError, Box::new)))]
+        source: Box<recipe::RenderRecipeError>,
+        recipe_type: RecipeType,
+        environment: String,
+    },
+
     #[snafu(display("failed to cache canister recipe: {recipe_type:?}"))]
     CacheRecipe {
         #[snafu(source(from(recipe::ResolveError, Box::new)))]
@@ -119,6 +144,12 @@ pub enum ConsolidateManifestError {
     ))]
     BinFormatInlineContent { canister: String },
 
+    #[snafu(display(
+        "the recipe of canister '{canister}' sets environment variable '{variable}' from a \
+         secret; set it in the canister's manifest instead"
+    ))]
+    RecipeSecretEnvironmentVariable { canister: String, variable: String },
+
     #[snafu(display(
         "canister '{canister}' lists controller '{controller}', but no canister with that \
          name is declared in the project"
@@ -168,6 +199,9 @@ pub enum ConsolidateManifestError {
     #[snafu(display("dependency cycle detected: {chain}"))]
     CircularDependency { chain: String },
 
+    #[snafu(display("invalid recipe registries"))]
+    Registry { source: RegistryError },
+
     #[snafu(transparent)]
     Environment { source: EnvironmentError },
 }
@@ -208,18 +242,46 @@ fn resolve_manifest_init_args(
                 format: fmt.clone(),
             }),
         },
+        ManifestInitArgs::Secret { source, format } => match format {
+            ArgsFormat::Bin => BinFormatInlineContentSnafu { canister }.fail(),
+            fmt => Ok(InitArgs::Secret {
+                secret: Secret {
+                    source: source.clone(),
+                    dir: base_path.to_owned(),
+                },
+                format: fmt.clone(),
+            }),
+        },
+    }
+}
+
+/// A manifest's settings resolved, with what [`Canister`] keeps about their
+/// environment variables.
+struct ResolvedSettings {
+    settings: Settings,
+    /// See [`Canister::environment_variable_files`]
+    files: BTreeMap<String, PathBuf>,
+    /// See [`Canister::secret_environment_variables`]
+    secrets: BTreeMap<String, Secret>,
+}
+
+impl ResolvedSettings {
+    /// Replaces the settings of `canister` with these.
+    fn apply_to(self, canister: &mut Canister) {
+        canister.settings = self.settings;
+        canister.environment_variable_files = self.files;
+        canister.secret_environment_variables = self.secrets;
     }
 }
 
 /// Resolve a manifest's [`ManifestSettings`] into the model's [`Settings`] by
 /// reading any file-backed environment variable values relative to `base_path`.
-/// Also returns the file each such value came from, for
-/// [`Canister::environment_variable_files`].
+/// Secret values are not read: they are set aside to be read at deploy time.
 fn resolve_manifest_settings(
     manifest_settings: &ManifestSettings,
     base_path: &Path,
     canister: &str,
-) -> Result<(Settings, BTreeMap<String, PathBuf>), ConsolidateManifestError> {
+) -> Result<ResolvedSettings, ConsolidateManifestError> {
     let ManifestSettings {
         log_visibility,
         compute_allocation,
@@ -234,28 +296,38 @@ fn resolve_manifest_settings(
     } = manifest_settings;
 
     let mut files = BTreeMap::new();
+    let mut secrets = BTreeMap::new();
     let environment_variables = environment_variables
         .as_ref()
         .map(|vars| {
-            vars.iter()
-                .map(|(name, var)| {
-                    let value = match var {
-                        ManifestEnvVar::Value(value) => value.to_owned(),
-                        ManifestEnvVar::Path { path } => {
-                            let file = base_path.join(path);
-                            let contents = fs::read_to_string(&file).context(
-                                ReadEnvironmentVariableSnafu {
-                                    canister,
-                                    variable: name,
-                                },
-                            )?;
-                            files.insert(name.to_owned(), file);
-                            contents.trim().to_owned()
-                        }
-                    };
-                    Ok((name.to_owned(), value))
-                })
-                .collect::<Result<HashMap<_, _>, ConsolidateManifestError>>()
+            let mut values = HashMap::new();
+            for (name, var) in vars {
+                match var {
+                    ManifestEnvVar::Value(value) => {
+                        values.insert(name.to_owned(), value.to_owned());
+                    }
+                    ManifestEnvVar::Path { path } => {
+                        let file = base_path.join(path);
+                        let contents =
+                            fs::read_to_string(&file).context(ReadEnvironmentVariableSnafu {
+                                canister,
+                                variable: name,
+                            })?;
+                        files.insert(name.to_owned(), file);
+                        values.insert(name.to_owned(), contents.trim().to_owned());
+                    }
+                    ManifestEnvVar::Secret(source) => {
+                        secrets.insert(
+                            name.to_owned(),
+                            Secret {
+                                source: source.clone(),
+                                dir: base_path.to_owned(),
+                            },
+                        );
+                    }
+                }
+            }
+            Ok::<_, ConsolidateManifestError>(values)
         })
         .transpose()?;
 
@@ -271,7 +343,179 @@ fn resolve_manifest_settings(
         environment_variables,
         controllers: controllers.clone(),
     };
-    Ok((settings, files))
+    Ok(ResolvedSettings {
+        settings,
+        files,
+        secrets,
+    })
+}
+
+/// Fill in the settings the canister's recipe provides that are not set
+/// otherwise, recording each in [`Canister::from_recipe`].
+fn apply_recipe_settings(canister: &mut Canister) {
+    let Canister {
+        settings,
+        recipe_defaults,
+        from_recipe,
+        ..
+    } = canister;
+    let Settings {
+        log_visibility,
+        compute_allocation,
+        memory_allocation,
+        freezing_threshold,
+        reserved_cycles_limit,
+        wasm_memory_limit,
+        wasm_memory_threshold,
+        log_memory_limit,
+        environment_variables,
+        // Recipes cannot set controllers
+        controllers: _,
+    } = &recipe_defaults.settings;
+
+    from_recipe.retain(|key| !key.starts_with("settings."));
+    macro_rules! fill {
+        ($($field:ident),* $(,)?) => {$(
+            if settings.$field.is_none() && $field.is_some() {
+                settings.$field = $field.clone();
+                from_recipe.push(concat!("settings.", stringify!($field)).to_owned());
+            }
+        )*};
+    }
+    fill!(
+        log_visibility,
+        compute_allocation,
+        memory_allocation,
+        freezing_threshold,
+        reserved_cycles_limit,
+        wasm_memory_limit,
+        wasm_memory_threshold,
+        log_memory_limit,
+    );
+    for (name, value) in environment_variables.iter().flatten() {
+        let vars = settings.environment_variables.get_or_insert_default();
+        if !vars.contains_key(name) {
+            vars.insert(name.to_owned(), value.to_owned());
+            from_recipe.push(format!("settings.environment_variables.{name}"));
+        }
+    }
+    from_recipe.sort();
+}
+
+/// Use the init args the canister's recipe provides if none are set otherwise,
+/// recording it in [`Canister::from_recipe`].
+fn apply_recipe_init_args(canister: &mut Canister) {
+    canister.from_recipe.retain(|key| key != "init_args");
+    if canister.init_args.is_none()
+        && let Some(init_args) = &canister.recipe_defaults.init_args
+    {
+        canister.init_args = Some(init_args.clone());
+        canister.from_recipe.push("init_args".to_string());
+        canister.from_recipe.sort();
+    }
+}
+
+/// Render a canister's recipe, for `environment` or outside any environment,
+/// into its build and sync steps and the defaults it provides.
+fn render_canister_recipe(
+    source: &RecipeSource,
+    canister: &str,
+    cdir: &Path,
+    environment: Option<(&str, &Network)>,
+) -> Result<(BuildSteps, SyncSteps, RecipeDefaults), ConsolidateManifestError> {
+    let ctx = recipe::RecipeContext {
+        canister_name: canister.to_owned(),
+        project_root: source.project_root.clone(),
+        environment: environment
+            .map(|(name, network)| recipe::RecipeEnvironment::new(name, network)),
+    };
+    let rendered =
+        recipe::render_recipe(&source.template, &source.recipe, &ctx).map_err(|err| {
+            match environment {
+                None => ConsolidateManifestError::RenderRecipe {
+                    source: Box::new(err),
+                    recipe_type: source.recipe.recipe_type.clone(),
+                },
+                Some((name, _)) => ConsolidateManifestError::RenderEnvironmentRecipe {
+                    source: Box::new(err),
+                    recipe_type: source.recipe.recipe_type.clone(),
+                    environment: name.to_owned(),
+                },
+            }
+        })?;
+
+    // Recipes cannot reference files, so there are none to record
+    let ResolvedSettings {
+        settings, secrets, ..
+    } = resolve_manifest_settings(&rendered.settings, cdir, canister)?;
+    if let Some(variable) = secrets.into_keys().next() {
+        return RecipeSecretEnvironmentVariableSnafu { canister, variable }.fail();
+    }
+    let init_args = rendered
+        .init_args
+        .as_ref()
+        .map(|mia| resolve_manifest_init_args(mia, cdir, canister))
+        .transpose()?;
+
+    Ok((
+        rendered.build,
+        rendered.sync,
+        RecipeDefaults {
+            settings,
+            init_args,
+        },
+    ))
+}
+
+/// Render the canister's recipe again for the environment it is being embedded
+/// in, replacing the steps and defaults rendered outside any environment.
+fn render_recipe_for_environment(
+    canister: &mut Canister,
+    cdir: &Path,
+    env_name: &str,
+    network: &Network,
+) -> Result<(), ConsolidateManifestError> {
+    let Some(source) = &canister.recipe_source else {
+        return Ok(());
+    };
+    let (build, sync, recipe_defaults) =
+        render_canister_recipe(source, &canister.name, cdir, Some((env_name, network)))?;
+    unapply_recipe_defaults(canister);
+    canister.build = build;
+    canister.sync = sync;
+    canister.recipe_defaults = recipe_defaults;
+    apply_recipe_settings(canister);
+    apply_recipe_init_args(canister);
+    Ok(())
+}
+
+/// Remove the values [`Canister::from_recipe`] records as the recipe's, leaving
+/// only those set by a manifest.
+fn unapply_recipe_defaults(canister: &mut Canister) {
+    let settings = &mut canister.settings;
+    for key in std::mem::take(&mut canister.from_recipe) {
+        match key.as_str() {
+            "init_args" => canister.init_args = None,
+            "settings.log_visibility" => settings.log_visibility = None,
+            "settings.compute_allocation" => settings.compute_allocation = None,
+            "settings.memory_allocation" => settings.memory_allocation = None,
+            "settings.freezing_threshold" => settings.freezing_threshold = None,
+            "settings.reserved_cycles_limit" => settings.reserved_cycles_limit = None,
+            "settings.wasm_memory_limit" => settings.wasm_memory_limit = None,
+            "settings.wasm_memory_threshold" => settings.wasm_memory_threshold = None,
+            "settings.log_memory_limit" => settings.log_memory_limit = None,
+            key => {
+                if let Some(name) = key.strip_prefix("settings.environment_variables.")
+                    && let Some(vars) = &mut settings.environment_variables
+                {
+                    vars.remove(name);
+                    if vars.is_empty() {
+                        settings.environment_variables = None;
+                    }
+                }
+            }
+        }
+    }
 }
 
 fn is_glob(s: &str) -> bool {
@@ -302,9 +546,14 @@ async fn build_manifest_canisters(
     pdir: &Path,
     manifest_canisters: &[Item<CanisterManifest>],
     recipe_resolver: &dyn recipe::Resolve,
+    registries: &Registries,
+    vars: &Variables,
 ) -> Result<Vec<(String, PathBuf, Canister)>, ConsolidateManifestError> {
     let mut result: Vec<(String, PathBuf, Canister)> = Vec::new();
 
+    // Recipes missing from the package cache in offline mode, all reported together
+    let mut not_cached = Vec::new();
+
     for i in manifest_canisters {
         let ms = match i {
             Item::Path(pattern) => {
@@ -355,9 +604,12 @@ async fn build_manifest_canisters(
                 for p in paths {
                     ms.push((
                         p.to_owned(),
-                        load_manifest_from_path::<CanisterManifest>(&p.join(CANISTER_MANIFEST))
-                            .await
-                            .context(LoadCanisterSnafu)?,
+                        load_manifest_from_path::<CanisterManifest>(
+                            &p.join(CANISTER_MANIFEST),
+                            vars,
+                        )
+                        .await
+                        .context(LoadCanisterSnafu)?,
                     ));
                 }
                 ms
@@ -382,7 +634,7 @@ async fn build_manifest_canisters(
                 },
             };
 
-            let (build, sync) = match &m.instructions {
+            let (build, sync, recipe_defaults, recipe_source) = match &m.instructions {
                 // Build/Sync
                 Instructions::BuildSync { build, sync } => (
                     build.to_owned(),
@@ -390,25 +642,33 @@ async fn build_manifest_canisters(
                         Some(sync) => sync.to_owned(),
                         None => SyncSteps::default(),
                     },
+                    RecipeDefaults::default(),
+                    None,
                 ),
 
                 // Recipe
                 Instructions::Recipe { recipe } => {
-                    let fetched =
-                        recipe_resolver
-                            .resolve(recipe)
-                            .await
-                            .context(FetchRecipeSnafu {
+                    let fetched = match recipe_resolver.resolve(recipe, registries).await {
+                        Ok(fetched) => fetched,
+                        Err(recipe::ResolveError::Fetch {
+                            source: recipe::fetch::RecipeFetchError::NotCached { recipe },
+                        }) => {
+                            not_cached.push(recipe);
+                            continue;
+                        }
+                        Err(err) => {
+                            return Err(err).context(FetchRecipeSnafu {
                                 recipe_type: recipe.recipe_type.clone(),
-                            })?;
-                    let ctx = recipe::RecipeContext {
-                        canister_name: m.name.clone(),
+                            });
+                        }
                     };
-                    let steps = recipe::render_recipe(&fetched.template, recipe, &ctx).context(
-                        RenderRecipeSnafu {
-                            recipe_type: recipe.recipe_type.clone(),
-                        },
-                    )?;
+                    let source = RecipeSource {
+                        recipe: recipe.clone(),
+                        template: fetched.template.into(),
+                        project_root: pdir.to_owned(),
+                    };
+                    let (build, sync, recipe_defaults) =
+                        render_canister_recipe(&source, &m.name, &cdir, None)?;
 
                     // The template rendered, so an unpinned download is now known
                     // good and safe to cache. Committing only here is what keeps a
@@ -422,12 +682,15 @@ async fn build_manifest_canisters(
                             })?;
                     }
 
-                    steps
+                    (build, sync, recipe_defaults, Some(source))
                 }
             };
 
-            let (settings, environment_variable_files) =
-                resolve_manifest_settings(&m.settings, &cdir, &m.name)?;
+            let ResolvedSettings {
+                settings,
+                files: environment_variable_files,
+                secrets: secret_environment_variables,
+            } = resolve_manifest_settings(&m.settings, &cdir, &m.name)?;
 
             let init_args = m
                 .init_args
@@ -435,27 +698,38 @@ async fn build_manifest_canisters(
                 .map(|mia| resolve_manifest_init_args(mia, &cdir, &m.name))
                 .transpose()?;
 
-            result.push((
-                m.name.clone(),
-                cdir,
-                Canister {
-                    name: m.name.clone(),
-                    settings,
-                    build,
-                    sync,
-                    init_args,
-                    registry_recipe,
-                    bindings: BTreeMap::new(),
-                    // Default to the bare local name; overwritten with the
-                    // dot-nested alias form when the canister is imported as a
-                    // dependency (see `import_dependency`).
-                    friendly_names: vec![m.name.clone()],
-                    environment_variable_files,
-                },
-            ));
+            let mut canister = Canister {
+                name: m.name.clone(),
+                settings,
+                build,
+                sync,
+                init_args,
+                registry_recipe,
+                bindings: BTreeMap::new(),
+                // Default to the bare local name; overwritten with the
+                // dot-nested alias form when the canister is imported as a
+                // dependency (see `import_dependency`).
+                friendly_names: vec![m.name.clone()],
+                environment_variable_files,
+                secret_environment_variables,
+                recipe_defaults,
+                from_recipe: vec![],
+                recipe_source,
+            };
+            apply_recipe_settings(&mut canister);
+            apply_recipe_init_args(&mut canister);
+
+            result.push((m.name.clone(), cdir, canister));
         }
     }
 
+    ensure!(
+        not_cached.is_empty(),
+        RecipesNotCachedSnafu {
+            recipes: not_cached
+        }
+    );
+
     Ok(result)
 }
 
@@ -629,13 +903,14 @@ fn resolve_edges(
 /// first; the visited set here only keeps the walk finite.
 pub async fn workspace_instances(
     pdir: &Path,
+    vars: &Variables,
 ) -> Result<Vec<WorkspaceInstance>, WorkspaceInstancesError> {
     let root_manifest_path = pdir.join(PROJECT_MANIFEST);
-    let root_manifest: ProjectManifest = load_manifest_from_path(&root_manifest_path)
+    let root_manifest: ProjectManifest = load_manifest_from_path(&root_manifest_path, vars)
         .await
         .context(LoadWorkspaceRootSnafu {
-            path: &root_manifest_path,
-        })?;
+        path: &root_manifest_path,
+    })?;
 
     // Same fallback as `consolidate_manifest`, so prefixes agree with the store
     // keys even when the root directory cannot be canonicalized.
@@ -661,13 +936,12 @@ pub async fn workspace_instances(
         }
 
         let manifest_path = edge.dir.join(PROJECT_MANIFEST);
-        let manifest: ProjectManifest =
-            load_manifest_from_path(&manifest_path)
-                .await
-                .context(LoadInstanceSnafu {
-                    alias: &edge.alias,
-                    path: &manifest_path,
-                })?;
+        let manifest: ProjectManifest = load_manifest_from_path(&manifest_path, vars)
+            .await
+            .context(LoadInstanceSnafu {
+                alias: &edge.alias,
+                path: &manifest_path,
+            })?;
 
         let edges = resolve_edges(&edge.dir, &manifest, &app_root_canonical)?;
         let dependency_prefixes = edges.iter().map(|e| e.prefix.clone()).collect();
@@ -809,6 +1083,8 @@ async fn import_dependency(
     parent_dir: &Path,
     dep: &DependencyManifest,
     recipe_resolver: &dyn recipe::Resolve,
+    registries: &Registries,
+    vars: &Variables,
     canisters: &mut IndexMap<String, (PathBuf, Canister)>,
     registry: &mut HashMap<PathBuf, ImportedInstance>,
     stack: &mut Vec<PathBuf>,
@@ -865,18 +1141,26 @@ async fn import_dependency(
 
     let prefix = relative_prefix(app_root_canonical, &canonical);
 
-    let dep_manifest: ProjectManifest =
-        load_manifest_from_path(&manifest_path)
-            .await
-            .context(LoadDependencyManifestSnafu {
-                alias: dep.name.clone(),
-            })?;
+    let dep_manifest: ProjectManifest = load_manifest_from_path(&manifest_path, vars)
+        .await
+        .context(LoadDependencyManifestSnafu {
+            alias: dep.name.clone(),
+        })?;
 
     // Build the dependency's own canisters and key them under the prefix. All of
     // them are imported (deploy-all); the `canisters` exposure subset is applied
     // by the caller when wiring env vars.
-    let built =
-        build_manifest_canisters(&dep_root, &dep_manifest.canisters, recipe_resolver).await?;
+    let registries = registries
+        .with_project(&dep_manifest.registries, &dep_root)
+        .context(RegistrySnafu)?;
+    let built = build_manifest_canisters(
+        &dep_root,
+        &dep_manifest.canisters,
+        recipe_resolver,
+        &registries,
+        vars,
+    )
+    .await?;
 
     let mut own: Vec<(String, String)> = Vec::new();
     let mut local_to_key: BTreeMap<String, String> = BTreeMap::new();
@@ -915,8 +1199,9 @@ async fn import_dependency(
     // ignored; only overrides on the member's *own* canisters are
     // folded in — keys naming its dependencies are left to those dependencies.
     let mut defined_envs: HashSet<String> = HashSet::new();
+    let mut member_envs = vec![];
     for env_item in &dep_manifest.environments {
-        let em: EnvironmentManifest = match env_item {
+        member_envs.push(match env_item {
             Item::Manifest(m) => m.clone(),
             Item::Path(path) => {
                 let p = dep_root.join(path);
@@ -927,11 +1212,13 @@ async fn import_dependency(
                     }
                     .fail();
                 }
-                load_manifest_from_path::<EnvironmentManifest>(&p)
+                load_manifest_from_path::<EnvironmentManifest>(&p, vars)
                     .await
                     .context(LoadEnvironmentSnafu)?
             }
-        };
+        });
+    }
+    for em in resolve_environment_extends(&member_envs)? {
         defined_envs.insert(em.name.clone());
         if let Some(settings) = &em.settings {
             for (local, s) in settings {
@@ -989,6 +1276,8 @@ async fn import_dependency(
             &dep_root,
             nested,
             recipe_resolver,
+            &registries,
+            vars,
             canisters,
             registry,
             stack,
@@ -1070,6 +1359,7 @@ pub fn member_scoped_canisters(
 fn build_environment_canisters(
     canisters: &IndexMap<String, (PathBuf, Canister)>,
     env_name: &str,
+    network: &Network,
     selection: &CanisterSelection,
     member_overrides: Option<&HashMap<String, MemberCanisterOverride>>,
     root_settings: Option<&HashMap<String, ManifestSettings>>,
@@ -1094,16 +1384,22 @@ fn build_environment_canisters(
         }
     };
 
+    // Recipes render for the environment, beneath any override.
+    for (cpath, canister) in cs.values_mut() {
+        render_recipe_for_environment(canister, cpath, env_name, network)?;
+    }
+
     // Member overrides first (lower precedence than the root's own overrides).
     if let Some(overrides) = member_overrides {
         for (key, ov) in overrides {
             if let Some((cpath, canister)) = cs.get_mut(key) {
                 if let Some(s) = &ov.settings {
-                    (canister.settings, canister.environment_variable_files) =
-                        resolve_manifest_settings(s, cpath, key)?;
+                    resolve_manifest_settings(s, cpath, key)?.apply_to(canister);
+                    apply_recipe_settings(canister);
                 }
                 if let Some(ia) = &ov.init_args {
                     canister.init_args = Some(resolve_manifest_init_args(ia, cpath, key)?);
+                    apply_recipe_init_args(canister);
                 }
             }
         }
@@ -1113,8 +1409,8 @@ fn build_environment_canisters(
     if let Some(settings) = root_settings {
         for (name, s) in settings {
             if let Some((cpath, canister)) = cs.get_mut(name) {
-                (canister.settings, canister.environment_variable_files) =
-                    resolve_manifest_settings(s, cpath, name)?;
+                resolve_manifest_settings(s, cpath, name)?.apply_to(canister);
+                apply_recipe_settings(canister);
             }
         }
     }
@@ -1122,6 +1418,7 @@ fn build_environment_canisters(
         for (name, ia) in init_args {
             if let Some((cpath, canister)) = cs.get_mut(name) {
                 canister.init_args = Some(resolve_manifest_init_args(ia, cpath, name)?);
+                apply_recipe_init_args(canister);
             }
         }
     }
@@ -1142,7 +1439,17 @@ pub async fn consolidate_manifest(
     pdir: &Path,
     recipe_resolver: &dyn recipe::Resolve,
     m: &ProjectManifest,
+    user_registries: &Registries,
+    vars: &Variables,
 ) -> Result<Project, ConsolidateManifestError> {
+    // Registries the project's recipes are looked up in
+    let registries = user_registries
+        .with_project(&m.registries, pdir)
+        .context(RegistrySnafu)?;
+
+    // The identity of the environments that do not choose their own
+    let default_identity = &m.identity;
+
     // Canisters. IndexMap (not HashMap) so the order from the project manifest is preserved
     // through to consumers like `icp project bundle`, which needs reproducible output.
     let mut canisters: IndexMap<String, (PathBuf, Canister)> = IndexMap::new();
@@ -1153,7 +1460,8 @@ pub async fn consolidate_manifest(
         canonicalize_dep("<project>", pdir).unwrap_or_else(|_| pdir.to_owned());
 
     // This project's own canisters, keyed by their bare local names.
-    let app_built = build_manifest_canisters(pdir, &m.canisters, recipe_resolver).await?;
+    let app_built =
+        build_manifest_canisters(pdir, &m.canisters, recipe_resolver, &registries, vars).await?;
     let mut app_own: Vec<(String, String)> = Vec::new();
     for (local, cdir, canister) in app_built {
         app_own.push((local.clone(), local.clone()));
@@ -1190,6 +1498,8 @@ pub async fn consolidate_manifest(
             pdir,
             dep,
             recipe_resolver,
+            &registries,
+            vars,
             &mut canisters,
             &mut registry,
             &mut stack,
@@ -1267,7 +1577,7 @@ pub async fn consolidate_manifest(
                     }
                     .fail();
                 }
-                load_manifest_from_path::<NetworkManifest>(&path)
+                load_manifest_from_path::<NetworkManifest>(&path, vars)
                     .await
                     .context(LoadNetworkSnafu)?
             }
@@ -1336,8 +1646,9 @@ pub async fn consolidate_manifest(
     // Environments
     let mut environments: HashMap<String, Environment> = HashMap::new();
 
+    let mut declared = vec![];
     for i in &m.environments {
-        let m = match i {
+        declared.push(match i {
             Item::Path(path) => {
                 let path = pdir.join(path);
                 if !path.exists() || !path.is_file() {
@@ -1347,13 +1658,27 @@ pub async fn consolidate_manifest(
                     }
                     .fail();
                 }
-                load_manifest_from_path::<EnvironmentManifest>(&path)
+                load_manifest_from_path::<EnvironmentManifest>(&path, vars)
                     .await
                     .context(LoadEnvironmentSnafu)?
             }
             Item::Manifest(ms) => ms.clone(),
-        };
+        });
+    }
+    let resolved = resolve_environment_extends(&declared)?;
+    let declared_environments = declared
+        .into_iter()
+        .zip(resolved.iter().cloned())
+        .map(|(declared, resolved)| {
+            (
+                declared.name.clone(),
+                EnvironmentDeclaration { declared, resolved },
+            )
+        })
+        .collect();
 
+    for m in resolved {
+        let network_name = m.network.as_deref().unwrap_or(LOCAL);
         match environments.entry(m.name.to_owned()) {
             // Duplicate
             Entry::Occupied(e) => {
@@ -1366,32 +1691,34 @@ pub async fn consolidate_manifest(
 
             // Ok
             Entry::Vacant(e) => {
+                // Embed network in environment
+                let network = networks
+                    .get(network_name)
+                    .ok_or(
+                        InvalidNetworkSnafu {
+                            environment: m.name.to_owned(),
+                            network: network_name.to_owned(),
+                        }
+                        .build(),
+                    )?
+                    .to_owned();
+
                 e.insert(Environment {
                     name: m.name.to_owned(),
-
-                    // Embed network in environment
-                    network: {
-                        let v = networks.get(&m.network).ok_or(
-                            InvalidNetworkSnafu {
-                                environment: m.name.to_owned(),
-                                network: m.network.to_owned(),
-                            }
-                            .build(),
-                        )?;
-
-                        v.to_owned()
-                    },
+                    identity: m.identity.clone().or_else(|| default_identity.clone()),
 
                     // Embed canisters in environment, folding member overrides
                     // beneath the root's own settings/init_args overrides.
                     canisters: build_environment_canisters(
                         &canisters,
                         &m.name,
+                        &network,
                         &m.canisters,
                         member_env_overrides.get(&m.name),
                         m.settings.as_ref(),
                         m.init_args.as_ref(),
                     )?,
+                    network,
                 });
             }
         }
@@ -1412,15 +1739,17 @@ pub async fn consolidate_manifest(
             .to_owned();
         vacant_entry.insert(Environment {
             name: LOCAL.to_string(),
-            network,
+            identity: default_identity.clone(),
             canisters: build_environment_canisters(
                 &canisters,
                 LOCAL,
+                &network,
                 &CanisterSelection::Everything,
                 member_env_overrides.get(LOCAL),
                 None,
                 None,
             )?,
+            network,
         });
     }
     if let Entry::Vacant(vacant_entry) = environments.entry(IC.to_string()) {
@@ -1436,15 +1765,17 @@ pub async fn consolidate_manifest(
             .to_owned();
         vacant_entry.insert(Environment {
             name: IC.to_string(),
-            network,
+            identity: default_identity.clone(),
             canisters: build_environment_canisters(
                 &canisters,
                 IC,
+                &network,
                 &CanisterSelection::Everything,
                 member_env_overrides.get(IC),
                 None,
                 None,
             )?,
+            network,
         });
     }
 
@@ -1473,23 +1804,95 @@ pub async fn consolidate_manifest(
         canisters,
         networks,
         environments,
+        declared_environments,
         member_missing_envs,
+        registries,
+        secret_values: vars.secrets(),
     })
 }
 
+/// Resolve each environment's `extends` chain, in the order declared. An
+/// environment may extend the implicit `local` and `ic` environments, which
+/// only set their network.
+fn resolve_environment_extends(
+    declared: &[EnvironmentManifest],
+) -> Result<Vec<EnvironmentManifest>, ConsolidateManifestError> {
+    let mut by_name: HashMap<&str, &EnvironmentManifest> = HashMap::new();
+    for m in declared {
+        if by_name.insert(&m.name, m).is_some() {
+            return DuplicateSnafu {
+                kind: "environment".to_string(),
+                name: m.name.clone(),
+            }
+            .fail();
+        }
+    }
+    let implicit = |name: &str| EnvironmentManifest {
+        name: name.to_owned(),
+        extends: None,
+        network: Some(name.to_owned()),
+        identity: None,
+        canisters: CanisterSelection::Everything,
+        settings: None,
+        init_args: None,
+    };
+
+    let mut resolved = vec![];
+    for m in declared {
+        // The chain from `m` up to the environment extending nothing
+        let mut chain = vec![m.clone()];
+        while let Some(base) = &chain.last().expect("chain is never empty").extends {
+            if chain.iter().any(|c| &c.name == base) {
+                let mut names: Vec<String> = chain.iter().map(|c| c.name.clone()).collect();
+                names.push(base.clone());
+                return Err(EnvironmentError::ExtendsCycle { chain: names }.into());
+            }
+            let next = match by_name.get(base.as_str()) {
+                Some(b) => (*b).clone(),
+                None if base == LOCAL || base == IC => implicit(base),
+                None => {
+                    return Err(EnvironmentError::UnknownBase {
+                        environment: chain.last().expect("chain is never empty").name.clone(),
+                        base: base.clone(),
+                    }
+                    .into());
+                }
+            };
+            chain.push(next);
+        }
+
+        let root = chain.pop().expect("chain is never empty");
+        resolved.push(
+            chain
+                .into_iter()
+                .rev()
+                .fold(root, |base, m| m.extend(&base)),
+        );
+    }
+    Ok(resolved)
+}
+
 #[cfg(test)]
 mod dependency_tests {
     use super::*;
-    use crate::canister::recipe::{Fetched, Resolve, ResolveError};
+    use crate::canister::{
+        recipe::{Fetched, Resolve, ResolveError},
+        secret::SecretSource,
+    };
     use crate::manifest::recipe::Recipe;
     use camino_tempfile::Utf8TempDir;
+    use indoc::indoc;
 
     /// Recipes are never used in these tests; every canister is pre-built.
     struct PanicResolver;
 
     #[async_trait::async_trait]
     impl Resolve for PanicResolver {
-        async fn resolve(&self, _recipe: &Recipe) -> Result<Fetched, ResolveError> {
+        async fn resolve(
+            &self,
+            _recipe: &Recipe,
+            _registries: &Registries,
+        ) -> Result<Fetched, ResolveError> {
             panic!("recipe resolver should not be called in dependency tests");
         }
     }
@@ -1519,10 +1922,18 @@ mod dependency_tests {
     }
 
     async fn consolidate(pdir: &Path) -> Result<Project, ConsolidateManifestError> {
-        let m: ProjectManifest = load_manifest_from_path(&pdir.join(PROJECT_MANIFEST))
-            .await
-            .expect("failed to parse project manifest");
-        consolidate_manifest(pdir, &PanicResolver, &m).await
+        let m: ProjectManifest =
+            load_manifest_from_path(&pdir.join(PROJECT_MANIFEST), &Variables::default())
+                .await
+                .expect("failed to parse project manifest");
+        consolidate_manifest(
+            pdir,
+            &PanicResolver,
+            &m,
+            &Registries::default(),
+            &Variables::default(),
+        )
+        .await
     }
 
     fn bindings_of<'a>(p: &'a Project, key: &str) -> &'a BTreeMap<String, String> {
@@ -1572,6 +1983,35 @@ mod dependency_tests {
         assert_eq!(bindings_of(&p, "frontend"), &expected);
     }
 
+    #[tokio::test]
+    async fn project_identity_applies_to_environments_without_their_own() {
+        let tmp = Utf8TempDir::new().unwrap();
+        write(
+            tmp.path(),
+            "icp.yaml",
+            &manifest(
+                &["backend"],
+                indoc! {r#"
+                    identity: dev
+                    environments:
+                      - name: staging
+                        network: local
+                      - name: prod
+                        network: ic
+                        identity: deployer
+                "#},
+            ),
+        );
+
+        let p = consolidate(tmp.path()).await.unwrap();
+
+        let identity = |env: &str| p.environments[env].identity.as_deref();
+        assert_eq!(identity("staging"), Some("dev"));
+        assert_eq!(identity("prod"), Some("deployer"));
+        assert_eq!(identity(LOCAL), Some("dev"));
+        assert_eq!(identity(IC), Some("dev"));
+    }
+
     #[tokio::test]
     async fn dependency_import_and_exposure_subset() {
         let tmp = Utf8TempDir::new().unwrap();
@@ -2226,6 +2666,64 @@ canisters:
         );
     }
 
+    /// Secret environment variables and init args are recorded with their
+    /// source and the canister's directory, and nothing is read at load time.
+    #[tokio::test]
+    async fn secrets_are_kept_out_of_settings_until_deploy() {
+        let tmp = Utf8TempDir::new().unwrap();
+        write(
+            tmp.path(),
+            "icp.yaml",
+            r#"
+canisters:
+  - name: backend
+    settings:
+      environment_variables:
+        LOG_LEVEL: info
+        API_KEY:
+          env: ICP_TEST_SURELY_UNSET_API_KEY
+    init_args:
+      command: exit 1
+    build:
+      steps:
+        - type: pre-built
+          path: backend.wasm
+"#,
+        );
+
+        let p = consolidate(tmp.path()).await.unwrap();
+
+        let (_, backend) = &p.canisters["backend"];
+        assert_eq!(
+            env_vars_of(&p.canisters, "backend"),
+            &HashMap::from([("LOG_LEVEL".to_string(), "info".to_string())]),
+        );
+        assert_eq!(
+            backend.secret_environment_variables,
+            BTreeMap::from([(
+                "API_KEY".to_string(),
+                Secret {
+                    source: SecretSource::Env {
+                        env: "ICP_TEST_SURELY_UNSET_API_KEY".to_string(),
+                    },
+                    dir: tmp.path().to_owned(),
+                }
+            )]),
+        );
+        assert_eq!(
+            backend.init_args,
+            Some(InitArgs::Secret {
+                secret: Secret {
+                    source: SecretSource::Command {
+                        command: "exit 1".to_string(),
+                    },
+                    dir: tmp.path().to_owned(),
+                },
+                format: ArgsFormat::Candid,
+            })
+        );
+    }
+
     #[tokio::test]
     async fn friendly_names_are_bare_for_own_and_dotted_for_dependencies() {
         let tmp = Utf8TempDir::new().unwrap();
@@ -2432,3 +2930,100 @@ canisters:
         assert!(local.canisters.contains_key("openemail:backend"));
     }
 }
+
+#[cfg(test)]
+mod extends_tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    fn resolve(yaml: &str) -> Result<Vec<EnvironmentManifest>, ConsolidateManifestError> {
+        let declared: Vec<EnvironmentManifest> = serde_yaml::from_str(yaml).unwrap();
+        resolve_environment_extends(&declared)
+    }
+
+    #[test]
+    fn chain_is_merged_from_the_root() {
+        let resolved = resolve(indoc! {r#"
+            - name: staging
+              network: ic
+              canisters: [frontend, backend]
+              settings:
+                backend:
+                  compute_allocation: 1
+                  memory_allocation: 2
+            - name: prod
+              extends: staging
+              settings:
+                backend:
+                  compute_allocation: 5
+            - name: prod-eu
+              extends: prod
+              init_args:
+                backend: "(\"eu\")"
+        "#})
+        .unwrap();
+
+        let prod_eu = &resolved[2];
+        assert_eq!(prod_eu.name, "prod-eu");
+        assert_eq!(prod_eu.extends, None);
+        assert_eq!(prod_eu.network.as_deref(), Some("ic"));
+        assert_eq!(
+            prod_eu.canisters,
+            CanisterSelection::Named(vec!["frontend".to_string(), "backend".to_string()])
+        );
+        let backend = &prod_eu.settings.as_ref().unwrap()["backend"];
+        assert_eq!(backend.compute_allocation, Some(5));
+        assert!(backend.memory_allocation.is_some());
+        assert!(prod_eu.init_args.as_ref().unwrap().contains_key("backend"));
+    }
+
+    #[test]
+    fn implicit_environments_can_be_extended() {
+        let resolved = resolve(indoc! {r#"
+            - name: mainnet-canary
+              extends: ic
+              canisters: [backend]
+        "#})
+        .unwrap();
+
+        assert_eq!(resolved[0].network.as_deref(), Some("ic"));
+    }
+
+    #[test]
+    fn unknown_base_is_rejected() {
+        let err = resolve(indoc! {r#"
+            - name: prod
+              extends: staging
+        "#})
+        .unwrap_err();
+
+        assert!(
+            matches!(
+                &err,
+                ConsolidateManifestError::Environment {
+                    source: EnvironmentError::UnknownBase { environment, base }
+                } if environment == "prod" && base == "staging"
+            ),
+            "got {err:?}"
+        );
+    }
+
+    #[test]
+    fn cycle_is_detected() {
+        let err = resolve(indoc! {r#"
+            - name: a
+              extends: b
+            - name: b
+              extends: c
+            - name: c
+              extends: a
+        "#})
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "environments extend each other in a cycle: a -> b -> c -> a"
+        );
+    }
+}
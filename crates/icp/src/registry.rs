@@ -0,0 +1,763 @@
+//! Locating packages published to a registry.
+//!
+//! A registry publishes each package version's contents as assets:
+//! `recipe.hbs` for a recipe, `plugin.wasm` for a sync plugin. Manifests name
+//! them as `@<registry>/<name>@<version>` ([`RegistryRef`]).
+//!
+//! The `dfinity` registry is built in: its packages are the releases of the
+//! `dfinity/icp-cli-recipes` GitHub repository, tagged `<name>-<version>`. Other
+//! registries are declared in `icp.yaml` or the user settings
+//! ([`RegistryManifest`]) and served from a base URL or a local directory laid
+//! out as `<name>/<version>/<asset>`. Every registry lists its recipes in an
+//! `index.json` ([`RegistryIndex`]).
+//!
+//! A recipe reference may give a semver requirement such as `^3` instead of an
+//! exact version ([`version_requirement`]), resolved against the versions the
+//! index lists.
+
+use std::collections::BTreeMap;
+
+use reqwest::{Method, Request, Url, header::AUTHORIZATION};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+
+use crate::{
+    fs,
+    manifest::{RegistryManifest, RegistryRef},
+    prelude::*,
+};
+
+/// Release asset holding a recipe's Handlebars template.
+pub const RECIPE_ASSET: &str = "recipe.hbs";
+
+/// Release asset holding a sync plugin's wasm component.
+pub const PLUGIN_ASSET: &str = "plugin.wasm";
+
+/// The file listing a registry's recipes.
+pub const INDEX_FILE: &str = "index.json";
+
+/// The built-in registry.
+pub const DFINITY: &str = "dfinity";
+
+const DFINITY_RELEASES: &str = "https://github.com/dfinity/icp-cli-recipes/releases";
+const DFINITY_INDEX: &str =
+    "https://raw.githubusercontent.com/dfinity/icp-cli-recipes/main/index.json";
+
+/// Prefix required of the variable a registry declared in `icp.yaml` reads its
+/// auth header from, so a project cannot have arbitrary secrets sent to a URL
+/// of its choosing. The variable must also be bound to the registry's origin by
+/// a registry in the user settings.
+pub const PROJECT_AUTH_ENV_PREFIX: &str = "ICP_REGISTRY_";
+
+#[derive(Debug, Snafu)]
+pub enum RegistryError {
+    #[snafu(display(
+        "unknown registry '@{registry}': declare it under `registries` in icp.yaml \
+         or with `icp settings registry add`"
+    ))]
+    UnknownRegistry { registry: String },
+
+    #[snafu(display(
+        "unknown registry '@{registry}': sync plugins are only resolved from the `dfinity` registry"
+    ))]
+    UnknownPluginRegistry { registry: String },
+
+    #[snafu(display("registry '{name}' must set exactly one of `url` and `path`"))]
+    Location { name: String },
+
+    #[snafu(display("registry '{name}' has an invalid url"))]
+    InvalidUrl {
+        name: String,
+        source: url::ParseError,
+    },
+
+    #[snafu(display("registry '{name}' in the user settings must use an absolute path"))]
+    RelativePath { name: String },
+
+    #[snafu(display("registry '{name}' is declared twice"))]
+    Duplicate { name: String },
+
+    #[snafu(display(
+        "registry '{name}' in icp.yaml may only read its auth header from a variable \
+         starting with `{PROJECT_AUTH_ENV_PREFIX}`, not `{variable}`"
+    ))]
+    AuthEnvName { name: String, variable: String },
+
+    #[snafu(display(
+        "registry '{name}' in icp.yaml reads its auth header from `{variable}`, which no \
+         registry in the user settings sends to {origin}; to allow it, run \
+         `icp settings registry add <name> --url <url> --auth-header-env {variable}` \
+         with a url on {origin}"
+    ))]
+    UnboundAuthEnv {
+        name: String,
+        variable: String,
+        origin: String,
+    },
+
+    #[snafu(display("registry '{name}' needs the environment variable `{variable}` to be set"))]
+    MissingAuth { name: String, variable: String },
+
+    #[snafu(display("registry '{name}' only sends its auth header over https, not to '{url}'"))]
+    InsecureAuth { name: String, url: String },
+
+    #[snafu(display("'{asset}' is outside of registry '{name}'"))]
+    AssetOutsideRegistry { name: String, asset: String },
+
+    #[snafu(display("failed to request the index of registry '{name}'"))]
+    IndexRequest {
+        name: String,
+        source: reqwest::Error,
+    },
+
+    #[snafu(display("request for the index of registry '{name}' at '{url}' returned {status}"))]
+    IndexStatus {
+        name: String,
+        url: String,
+        status: reqwest::StatusCode,
+    },
+
+    #[snafu(display("failed to read the index of registry '{name}'"))]
+    ReadIndex { name: String, source: fs::IoError },
+
+    #[snafu(display("the index of registry '{name}' is not valid"))]
+    ParseIndex {
+        name: String,
+        source: serde_json::Error,
+    },
+}
+
+/// Where a registry is hosted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegistryLocation {
+    /// The built-in `dfinity` registry.
+    Dfinity,
+    Url(Url),
+    Dir(PathBuf),
+}
+
+/// A registry, resolved from its [`RegistryManifest`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Registry {
+    pub name: String,
+    pub location: RegistryLocation,
+    pub auth_header_env: Option<String>,
+}
+
+/// Where one asset of a registry is found.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Asset {
+    /// Download it, sending `authorization` as the `Authorization` header.
+    Url {
+        url: String,
+        authorization: Option<String>,
+    },
+    File(PathBuf),
+}
+
+impl Registry {
+    fn dfinity() -> Self {
+        Self {
+            name: DFINITY.to_owned(),
+            location: RegistryLocation::Dfinity,
+            auth_header_env: None,
+        }
+    }
+
+    /// Resolve a registry declared in a manifest; a `path` is relative to `dir`.
+    fn from_manifest(m: &RegistryManifest, dir: &Path) -> Result<Self, RegistryError> {
+        let location = match (&m.url, &m.path) {
+            (Some(url), None) => {
+                // A trailing slash makes `Url::join` append to the base path
+                let url = match url.ends_with('/') {
+                    true => url.to_owned(),
+                    false => format!("{url}/"),
+                };
+                RegistryLocation::Url(Url::parse(&url).context(InvalidUrlSnafu { name: &m.name })?)
+            }
+            (None, Some(path)) => RegistryLocation::Dir(dir.join(path)),
+            _ => return LocationSnafu { name: &m.name }.fail(),
+        };
+        Ok(Self {
+            name: m.name.clone(),
+            location,
+            auth_header_env: m.auth_header_env.clone(),
+        })
+    }
+
+    /// Where the `asset` of version `version` of package `name` is found.
+    pub fn asset(&self, name: &str, version: &str, asset: &str) -> Result<Asset, RegistryError> {
+        match &self.location {
+            RegistryLocation::Dfinity => Ok(Asset::Url {
+                url: format!("{DFINITY_RELEASES}/download/{name}-{version}/{asset}"),
+                authorization: None,
+            }),
+            location => self.file(location, &format!("{name}/{version}/{asset}")),
+        }
+    }
+
+    /// Where the registry's [`RegistryIndex`] is found.
+    pub fn index(&self) -> Result<Asset, RegistryError> {
+        match &self.location {
+            RegistryLocation::Dfinity => Ok(Asset::Url {
+                url: DFINITY_INDEX.to_owned(),
+                authorization: None,
+            }),
+            location => self.file(location, INDEX_FILE),
+        }
+    }
+
+    /// The file at `relative` within the registry, which must stay under its base.
+    fn file(&self, location: &RegistryLocation, relative: &str) -> Result<Asset, RegistryError> {
+        match location {
+            RegistryLocation::Dir(dir) => {
+                let path = dir.join(relative);
+                ensure!(
+                    Path::new(relative)
+                        .components()
+                        .all(|c| matches!(c, camino::Utf8Component::Normal(_)))
+                        && path.starts_with(dir),
+                    AssetOutsideRegistrySnafu {
+                        name: &self.name,
+                        asset: relative,
+                    }
+                );
+                Ok(Asset::File(path))
+            }
+            RegistryLocation::Url(base) => {
+                let url = base
+                    .join(relative)
+                    .context(InvalidUrlSnafu { name: &self.name })?;
+                ensure!(
+                    url.as_str().starts_with(base.as_str()),
+                    AssetOutsideRegistrySnafu {
+                        name: &self.name,
+                        asset: relative,
+                    }
+                );
+                Ok(Asset::Url {
+                    authorization: self.authorization(&url)?,
+                    url: url.to_string(),
+                })
+            }
+            RegistryLocation::Dfinity => unreachable!("the dfinity registry has its own layout"),
+        }
+    }
+
+    /// The auth header to send to `url`, which must be https unless it is on
+    /// this machine.
+    fn authorization(&self, url: &Url) -> Result<Option<String>, RegistryError> {
+        let Some(variable) = &self.auth_header_env else {
+            return Ok(None);
+        };
+        let loopback = match url.host() {
+            Some(url::Host::Domain(domain)) => domain == "localhost",
+            Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        };
+        ensure!(
+            url.scheme() == "https" || loopback,
+            InsecureAuthSnafu {
+                name: &self.name,
+                url: url.as_str(),
+            }
+        );
+        let value = std::env::var(variable).map_err(|_| RegistryError::MissingAuth {
+            name: self.name.clone(),
+            variable: variable.clone(),
+        })?;
+        Ok(Some(value))
+    }
+
+    /// Download or read the registry's index.
+    pub async fn fetch_index(
+        &self,
+        client: &reqwest::Client,
+    ) -> Result<RegistryIndex, RegistryError> {
+        let name = &self.name;
+        let bytes = match self.index()? {
+            Asset::File(path) => fs::read(&path).context(ReadIndexSnafu { name })?,
+            Asset::Url { url, authorization } => {
+                let mut request = Request::new(
+                    Method::GET,
+                    // Built from a parsed url or a constant
+                    Url::parse(&url).expect("valid index url"),
+                );
+                if let Some(value) = authorization
+                    && let Ok(value) = value.parse()
+                {
+                    request.headers_mut().insert(AUTHORIZATION, value);
+                }
+                let resp = client
+                    .execute(request)
+                    .await
+                    .context(IndexRequestSnafu { name })?;
+                let status = resp.status();
+                ensure!(status.is_success(), IndexStatusSnafu { name, url, status });
+                resp.bytes()
+                    .await
+                    .context(IndexRequestSnafu { name })?
+                    .to_vec()
+            }
+        };
+        serde_json::from_slice(&bytes).context(ParseIndexSnafu { name })
+    }
+}
+
+/// The registries packages can be referenced from, by name.
+///
+/// A registry declared in the user settings takes precedence over one of the
+/// same name in `icp.yaml`, which takes precedence over the built-in `dfinity`
+/// registry. A dependency project's registries only add to its parent's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Registries {
+    registries: BTreeMap<String, Registry>,
+    /// Names declared by the user settings or an enclosing project, which a
+    /// project cannot redefine.
+    fixed: Vec<String>,
+    /// The auth header variables the user settings send, with the origin they
+    /// are sent to. A project's registry may only send these, to the same origin.
+    auth_origins: Vec<(String, url::Origin)>,
+}
+
+impl Default for Registries {
+    fn default() -> Self {
+        Self {
+            registries: BTreeMap::from([(DFINITY.to_owned(), Registry::dfinity())]),
+            fixed: vec![],
+            auth_origins: vec![],
+        }
+    }
+}
+
+impl Registries {
+    /// The built-in registry plus those declared in the user settings.
+    pub fn from_settings(manifests: &[RegistryManifest]) -> Result<Self, RegistryError> {
+        let mut registries = Self::default();
+        for m in manifests {
+            ensure!(
+                m.path.as_deref().is_none_or(|p| Path::new(p).is_absolute()),
+                RelativePathSnafu { name: &m.name }
+            );
+            ensure!(
+                !registries.fixed.contains(&m.name),
+                DuplicateSnafu { name: &m.name }
+            );
+            let registry = Registry::from_manifest(m, Path::new("/"))?;
+            if let (Some(variable), RegistryLocation::Url(url)) =
+                (&registry.auth_header_env, &registry.location)
+            {
+                registries
+                    .auth_origins
+                    .push((variable.clone(), url.origin()));
+            }
+            registries.registries.insert(m.name.clone(), registry);
+            registries.fixed.push(m.name.clone());
+        }
+        Ok(registries)
+    }
+
+    /// These registries plus those a project at `dir` declares, for resolving
+    /// that project's recipes.
+    pub fn with_project(
+        &self,
+        manifests: &[RegistryManifest],
+        dir: &Path,
+    ) -> Result<Self, RegistryError> {
+        let mut registries = self.clone();
+        let mut declared = vec![];
+        for m in manifests {
+            ensure!(
+                !declared.contains(&m.name),
+                DuplicateSnafu { name: &m.name }
+            );
+            declared.push(m.name.clone());
+            if let Some(variable) = &m.auth_header_env {
+                ensure!(
+                    variable.starts_with(PROJECT_AUTH_ENV_PREFIX),
+                    AuthEnvNameSnafu {
+                        name: &m.name,
+                        variable,
+                    }
+                );
+            }
+            let registry = Registry::from_manifest(m, dir)?;
+            if let (Some(variable), RegistryLocation::Url(url)) =
+                (&registry.auth_header_env, &registry.location)
+            {
+                let origin = url.origin();
+                ensure!(
+                    self.auth_origins
+                        .iter()
+                        .any(|(v, o)| v == variable && *o == origin),
+                    UnboundAuthEnvSnafu {
+                        name: &m.name,
+                        variable,
+                        origin: origin.ascii_serialization(),
+                    }
+                );
+            }
+            if !self.fixed.contains(&m.name) {
+                registries.registries.insert(m.name.clone(), registry);
+            }
+        }
+        registries.fixed.extend(declared);
+        Ok(registries)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Registry, RegistryError> {
+        self.registries
+            .get(name)
+            .context(UnknownRegistrySnafu { registry: name })
+    }
+
+    /// Every registry, by name.
+    pub fn iter(&self) -> impl Iterator<Item = &Registry> {
+        self.registries.values()
+    }
+}
+
+/// A registry's `index.json`, listing the recipes it publishes.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct RegistryIndex {
+    #[serde(default)]
+    pub recipes: Vec<IndexedRecipe>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct IndexedRecipe {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Published versions, oldest first.
+    #[serde(default)]
+    pub versions: Vec<String>,
+}
+
+impl RegistryIndex {
+    /// The recipe named `name`.
+    pub fn recipe(&self, name: &str) -> Option<&IndexedRecipe> {
+        self.recipes.iter().find(|r| r.name == name)
+    }
+}
+
+impl IndexedRecipe {
+    /// The highest published version satisfying `requirement`, as published.
+    pub fn latest_matching(&self, requirement: &VersionReq) -> Option<&str> {
+        self.semver_versions()
+            .filter(|(v, _)| requirement.matches(v))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, published)| published)
+    }
+
+    /// The highest published stable version, as published.
+    pub fn latest(&self) -> Option<&str> {
+        self.semver_versions()
+            .filter(|(v, _)| v.pre.is_empty())
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, published)| published)
+    }
+
+    /// The published versions that are semver, skipping any other tags.
+    fn semver_versions(&self) -> impl Iterator<Item = (Version, &str)> {
+        self.versions
+            .iter()
+            .filter_map(|v| Some((parse_version(v)?, v.as_str())))
+    }
+}
+
+/// The semver of a published version, ignoring a leading `v` as in `v3.1.0`.
+pub fn parse_version(version: &str) -> Option<Version> {
+    Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+/// The requirement the version of a reference stands for, such as `^3` or
+/// `>=3.1, <4`, or `None` when it names one exact version or tag, such as
+/// `v3.0.0` or `v3`: only what parses as a semver requirement is one.
+pub fn version_requirement(version: &str) -> Option<VersionReq> {
+    if parse_version(version).is_some() {
+        return None;
+    }
+    VersionReq::parse(version).ok()
+}
+
+/// The download URL of `asset` in the release of `reference`, for sync plugins,
+/// which are only resolved from the built-in registry.
+pub fn asset_url(reference: &RegistryRef, asset: &str) -> Result<String, RegistryError> {
+    ensure!(
+        reference.registry == DFINITY,
+        UnknownPluginRegistrySnafu {
+            registry: &reference.registry,
+        }
+    );
+    match Registry::dfinity().asset(&reference.name, &reference.version, asset)? {
+        Asset::Url { url, .. } => Ok(url),
+        Asset::File(_) => unreachable!("the dfinity registry is remote"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dfinity_release_asset() {
+        let reference: RegistryRef = "@dfinity/asset-sync@v1.0.0".parse().unwrap();
+        assert_eq!(
+            asset_url(&reference, PLUGIN_ASSET).unwrap(),
+            "https://github.com/dfinity/icp-cli-recipes/releases/download/asset-sync-v1.0.0/plugin.wasm"
+        );
+    }
+
+    #[test]
+    fn unknown_registry_is_an_error() {
+        let reference: RegistryRef = "@acme/asset-sync@v1.0.0".parse().unwrap();
+        assert!(matches!(
+            asset_url(&reference, PLUGIN_ASSET),
+            Err(RegistryError::UnknownPluginRegistry { registry }) if registry == "acme"
+        ));
+    }
+
+    fn manifest(name: &str, url: Option<&str>, path: Option<&str>) -> RegistryManifest {
+        RegistryManifest {
+            name: name.to_owned(),
+            url: url.map(str::to_owned),
+            path: path.map(str::to_owned),
+            auth_header_env: None,
+        }
+    }
+
+    fn location(registries: &Registries, name: &str) -> RegistryLocation {
+        registries.get(name).unwrap().location.clone()
+    }
+
+    #[test]
+    fn custom_registry_layout() {
+        let registries =
+            Registries::from_settings(&[manifest("acme", Some("https://r.acme.dev/icp"), None)])
+                .unwrap();
+        let acme = registries.get("acme").unwrap();
+        assert_eq!(
+            acme.asset("rust", "v1.0.0", RECIPE_ASSET).unwrap(),
+            Asset::Url {
+                url: "https://r.acme.dev/icp/rust/v1.0.0/recipe.hbs".to_owned(),
+                authorization: None,
+            }
+        );
+        assert_eq!(
+            acme.index().unwrap(),
+            Asset::Url {
+                url: "https://r.acme.dev/icp/index.json".to_owned(),
+                authorization: None,
+            }
+        );
+    }
+
+    #[test]
+    fn project_paths_are_relative_to_the_project() {
+        let registries = Registries::default()
+            .with_project(
+                &[manifest("local", None, Some("registry"))],
+                Path::new("/work/app"),
+            )
+            .unwrap();
+        assert_eq!(
+            registries
+                .get("local")
+                .unwrap()
+                .asset("rust", "v1", RECIPE_ASSET)
+                .unwrap(),
+            Asset::File("/work/app/registry/rust/v1/recipe.hbs".into())
+        );
+    }
+
+    #[test]
+    fn settings_paths_must_be_absolute() {
+        assert!(matches!(
+            Registries::from_settings(&[manifest("local", None, Some("registry"))]),
+            Err(RegistryError::RelativePath { .. })
+        ));
+    }
+
+    #[test]
+    fn exactly_one_location() {
+        assert!(matches!(
+            Registries::from_settings(&[manifest("acme", Some("https://a"), Some("/b"))]),
+            Err(RegistryError::Location { .. })
+        ));
+        assert!(matches!(
+            Registries::from_settings(&[manifest("acme", None, None)]),
+            Err(RegistryError::Location { .. })
+        ));
+    }
+
+    #[test]
+    fn user_settings_take_precedence_over_projects() {
+        let user = Registries::from_settings(&[manifest("acme", None, Some("/mirror"))]).unwrap();
+        let project = user
+            .with_project(
+                &[
+                    manifest("acme", Some("https://r.acme.dev"), None),
+                    manifest("other", Some("https://r.other.dev"), None),
+                ],
+                Path::new("/work/app"),
+            )
+            .unwrap();
+        assert_eq!(
+            location(&project, "acme"),
+            RegistryLocation::Dir("/mirror".into())
+        );
+
+        // A dependency cannot redefine what its parent declared
+        let dependency = project
+            .with_project(
+                &[manifest("other", None, Some("elsewhere"))],
+                Path::new("/work/dep"),
+            )
+            .unwrap();
+        assert!(matches!(
+            location(&dependency, "other"),
+            RegistryLocation::Url(url) if url.as_str() == "https://r.other.dev/"
+        ));
+    }
+
+    #[test]
+    fn project_auth_variables_need_the_prefix() {
+        let mut m = manifest("acme", Some("https://r.acme.dev"), None);
+        m.auth_header_env = Some("AWS_SECRET_ACCESS_KEY".to_owned());
+        assert!(matches!(
+            Registries::default().with_project(std::slice::from_ref(&m), Path::new("/work")),
+            Err(RegistryError::AuthEnvName { .. })
+        ));
+
+        // The user settings may name any variable
+        assert!(Registries::from_settings(&[m]).is_ok());
+    }
+
+    #[test]
+    fn project_auth_variables_need_a_settings_registry_on_the_same_origin() {
+        let with_auth = |name: &str, url: &str| RegistryManifest {
+            auth_header_env: Some("ICP_REGISTRY_ACME".to_owned()),
+            ..manifest(name, Some(url), None)
+        };
+        let project = [with_auth("acme", "https://r.acme.dev/icp")];
+
+        // Not bound by the user settings
+        assert!(matches!(
+            Registries::default().with_project(&project, Path::new("/work")),
+            Err(RegistryError::UnboundAuthEnv { origin, .. }) if origin == "https://r.acme.dev"
+        ));
+        // Bound to another host
+        let user = Registries::from_settings(&[with_auth("mine", "https://evil.example")]).unwrap();
+        assert!(matches!(
+            user.with_project(&project, Path::new("/work")),
+            Err(RegistryError::UnboundAuthEnv { .. })
+        ));
+        // Bound to the same origin
+        let user = Registries::from_settings(&[with_auth("mine", "https://r.acme.dev")]).unwrap();
+        assert!(user.with_project(&project, Path::new("/work")).is_ok());
+    }
+
+    #[test]
+    fn auth_headers_are_only_sent_over_https() {
+        let m = RegistryManifest {
+            auth_header_env: Some("ACME_TOKEN".to_owned()),
+            ..manifest("acme", Some("http://r.acme.dev"), None)
+        };
+        let registries = Registries::from_settings(&[m]).unwrap();
+        assert!(matches!(
+            registries.get("acme").unwrap().index(),
+            Err(RegistryError::InsecureAuth { .. })
+        ));
+    }
+
+    #[test]
+    fn assets_stay_in_the_registry() {
+        let registries = Registries::from_settings(&[
+            manifest("web", Some("https://r.acme.dev/icp"), None),
+            manifest("dir", None, Some("/srv/registry")),
+        ])
+        .unwrap();
+        for name in ["web", "dir"] {
+            let registry = registries.get(name).unwrap();
+            assert!(registry.asset("rust", "v1", RECIPE_ASSET).is_ok());
+            assert!(matches!(
+                registry.asset("..", "..", "index.json"),
+                Err(RegistryError::AssetOutsideRegistry { .. })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn index_from_a_directory() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        fs::write(
+            &dir.path().join(INDEX_FILE),
+            br#"{"recipes": [{"name": "rust", "description": "Rust canisters", "versions": ["v1", "v2"]}]}"#,
+        )
+        .unwrap();
+        let registries =
+            Registries::from_settings(&[manifest("local", None, Some(dir.path().as_str()))])
+                .unwrap();
+        let index = registries
+            .get("local")
+            .unwrap()
+            .fetch_index(&reqwest::Client::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            index.recipes,
+            vec![IndexedRecipe {
+                name: "rust".to_owned(),
+                description: Some("Rust canisters".to_owned()),
+                versions: vec!["v1".to_owned(), "v2".to_owned()],
+            }]
+        );
+    }
+
+    #[test]
+    fn exact_versions_are_not_requirements() {
+        assert_eq!(version_requirement("v3.0.0"), None);
+        assert_eq!(version_requirement("3.0.0"), None);
+        assert_eq!(version_requirement("latest"), None);
+        assert_eq!(
+            version_requirement("^3"),
+            Some(VersionReq::parse("^3").unwrap())
+        );
+        // A tag that is not a semver requirement stays an exact tag
+        assert_eq!(version_requirement("v3"), None);
+        assert_eq!(version_requirement("v3.x"), None);
+        assert_eq!(
+            version_requirement("3"),
+            Some(VersionReq::parse("^3").unwrap())
+        );
+        assert!(version_requirement(">=3.1, <4").is_some());
+    }
+
+    #[test]
+    fn latest_matching_version() {
+        let recipe = IndexedRecipe {
+            name: "rust".to_owned(),
+            description: None,
+            versions: [
+                "v2.9.0",
+                "v3.0.0",
+                "v3.2.1",
+                "v3.10.0",
+                "v4.0.0-beta.1",
+                "nightly",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+        };
+        let req = |r: &str| VersionReq::parse(r).unwrap();
+        assert_eq!(recipe.latest_matching(&req("^3")), Some("v3.10.0"));
+        assert_eq!(recipe.latest_matching(&req("~3.2")), Some("v3.2.1"));
+        assert_eq!(recipe.latest_matching(&req("^5")), None);
+        assert_eq!(recipe.latest(), Some("v3.10.0"));
+    }
+}
@@ -12,6 +12,7 @@ use crate::{
         json,
         lock::{DirectoryStructureLock, LRead, LWrite, LockError, PathsAccess},
     },
+    manifest::RegistryManifest,
     prelude::*,
 };
 
@@ -62,6 +63,11 @@ pub struct Settings {
     #[serde(default = "default_telemetry_enabled")]
     pub telemetry_enabled: bool,
 
+    /// Keep telemetry records on this machine, with the project each command
+    /// ran in, and never send them.
+    #[serde(default)]
+    pub telemetry_local_only: bool,
+
     /// Whether the CLI update check is enabled.
     #[serde(default)]
     pub update_check: UpdateCheck,
@@ -72,6 +78,16 @@ pub struct Settings {
     /// `Some(5)` produces a 7-minute session. `None` disables session caching.
     #[serde(default = "default_session_length")]
     pub session_length: Option<u32>,
+
+    /// Serve recipes, wasms, plugins and the network launcher from the package
+    /// cache only, as if `--offline` were always passed.
+    #[serde(default)]
+    pub offline: bool,
+
+    /// Recipe registries, taking precedence over those of the same name in a
+    /// project's `icp.yaml`. Paths must be absolute.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<RegistryManifest>,
 }
 
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, strum::Display, PartialEq, Eq)]
@@ -101,8 +117,11 @@ impl Default for Settings {
             v: 1,
             autocontainerize: false,
             telemetry_enabled: true,
+            telemetry_local_only: false,
             update_check: UpdateCheck::default(),
             session_length: default_session_length(),
+            offline: false,
+            registries: vec![],
         }
     }
 }
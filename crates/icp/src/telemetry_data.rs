@@ -70,6 +70,8 @@ pub enum IdentityStorageType {
     Pem,
     Keyring,
     Hsm,
+    External,
+    SshAgent,
     Anonymous,
     InternetIdentity,
     PendingDelegation,
@@ -91,6 +93,8 @@ impl From<&IdentitySpec> for IdentityStorageType {
             IdentitySpec::Pem { .. } => Self::Pem,
             IdentitySpec::Keyring { .. } => Self::Keyring,
             IdentitySpec::Hsm { .. } => Self::Hsm,
+            IdentitySpec::External { .. } => Self::External,
+            IdentitySpec::SshAgent { .. } => Self::SshAgent,
             IdentitySpec::Anonymous => Self::Anonymous,
             IdentitySpec::WebAuth { .. } => Self::InternetIdentity,
             IdentitySpec::PendingDelegation { .. } => Self::PendingDelegation,